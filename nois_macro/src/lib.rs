@@ -0,0 +1,113 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Index, LitStr, Member};
+
+use nois::embed::Script;
+
+/// Parse nois script at compile time, failing compilation on syntax errors.
+/// Expands into a `&'static nois::embed::Script`, parsed once on first use.
+///
+/// Examples:
+///
+/// ```
+/// use nois::interpret::value::Value;
+/// use nois_macro::nois;
+///
+/// let script = nois!("add = (a, b) -> a + b");
+/// assert_eq!(script.call("add", vec![Value::I(1), Value::I(2)]), Ok(Value::I(3)));
+/// ```
+///
+#[proc_macro]
+pub fn nois(input: TokenStream) -> TokenStream {
+    let source = parse_macro_input!(input as LitStr);
+    if let Err(e) = Script::parse(&source.value()) {
+        return syn::Error::new(source.span(), format!("nois syntax error\n{}", e))
+            .to_compile_error()
+            .into();
+    }
+    quote! {
+        {
+            static SCRIPT: ::std::sync::OnceLock<::nois::embed::Script> =
+                ::std::sync::OnceLock::new();
+            SCRIPT.get_or_init(|| {
+                ::nois::embed::Script::parse(#source).expect("script is validated at compile time")
+            })
+        }
+    }
+    .into()
+}
+
+/// Derive `nois::convert::ToNois` for a struct, representing it as a list of field values
+/// in declaration order, the same way struct constructors take arguments
+#[proc_macro_derive(ToNois)]
+pub fn derive_to_nois(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let fields = match struct_fields(&input) {
+        Ok(f) => f,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let values = fields
+        .iter()
+        .map(|f| quote! { ::nois::convert::ToNois::to_nois(self.#f) });
+    quote! {
+        impl ::nois::convert::ToNois for #name {
+            fn to_nois(self) -> ::nois::interpret::value::Value {
+                ::nois::interpret::value::Value::list(vec![#(#values),*])
+            }
+        }
+    }
+    .into()
+}
+
+/// Derive `nois::convert::FromNois` for a struct from a list of field values
+#[proc_macro_derive(FromNois)]
+pub fn derive_from_nois(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let fields = match struct_fields(&input) {
+        Ok(f) => f,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let count = fields.len();
+    let values = fields.iter().map(|f| {
+        quote! { #f: ::nois::convert::FromNois::from_nois(items.next().unwrap())? }
+    });
+    quote! {
+        impl ::nois::convert::FromNois for #name {
+            fn from_nois(value: ::nois::interpret::value::Value) -> Result<Self, String> {
+                match value {
+                    ::nois::interpret::value::Value::List { items, .. } if items.len() == #count => {
+                        let mut items = items.into_iter();
+                        Ok(#name { #(#values),* })
+                    }
+                    v => Err(format!(
+                        "expected {} of {} fields, found {}",
+                        stringify!(#name),
+                        #count,
+                        v.value_type()
+                    )),
+                }
+            }
+        }
+    }
+    .into()
+}
+
+fn struct_fields(input: &DeriveInput) -> Result<Vec<Member>, syn::Error> {
+    match &input.data {
+        Data::Struct(s) => Ok(s
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| match &f.ident {
+                Some(id) => Member::Named(id.clone()),
+                None => Member::Unnamed(Index::from(i)),
+            })
+            .collect()),
+        _ => Err(syn::Error::new(
+            input.ident.span(),
+            "only structs can be converted to and from nois values",
+        )),
+    }
+}
@@ -0,0 +1,93 @@
+use std::cell::RefMut;
+
+use nois::ast::ast::AstPair;
+use nois::error::Error;
+use nois::interpret::context::Context;
+use nois::interpret::value::Value;
+use nois::stdlib::lib::{function_info, register_package, LibFunction, NativePackage, Package};
+use nois::typecheck::signature::{Param, Signature};
+use nois::typecheck::types::Type;
+use nois_macro::nois;
+
+struct Triple;
+
+impl LibFunction for Triple {
+    fn name() -> String {
+        "triple".to_string()
+    }
+
+    fn doc() -> String {
+        "Multiply integer by three".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("a", Type::Int)], Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[0].1 {
+            Value::I(i) => Ok(Value::I(i * 3)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+struct Arith;
+
+impl NativePackage for Arith {
+    fn package() -> Package {
+        Package::new("arith", vec![Triple::info()])
+    }
+}
+
+struct Map;
+
+impl LibFunction for Map {
+    fn name() -> String {
+        "map".to_string()
+    }
+
+    fn doc() -> String {
+        "Clashes with list.map".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Unit)
+    }
+
+    fn call(_args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(Value::Unit)
+    }
+}
+
+struct Clashing;
+
+impl NativePackage for Clashing {
+    fn package() -> Package {
+        Package::new("clashing", vec![Map::info()])
+    }
+}
+
+// single test, since stdlib is initialized once per process
+#[test]
+fn register_native_package() {
+    assert_eq!(
+        register_package::<Clashing>(),
+        Err(
+            "unable to register package 'clashing', function 'map' is already defined by package 'list'"
+                .to_string()
+        )
+    );
+    assert_eq!(register_package::<Arith>(), Ok(()));
+
+    let script = nois!("f = a -> triple(a) + 1");
+    assert_eq!(script.call("f", vec![Value::I(2)]), Ok(Value::I(7)));
+    assert_eq!(
+        function_info("triple").map(|f| f.qualified_name()),
+        Some("arith.triple".to_string())
+    );
+    assert_eq!(
+        register_package::<Arith>(),
+        Err("unable to register package 'arith', stdlib is already initialized".to_string())
+    );
+}
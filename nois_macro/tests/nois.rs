@@ -0,0 +1,52 @@
+use nois::convert::FromNois;
+use nois::interpret::value::Value;
+use nois_macro::{nois, FromNois, ToNois};
+
+#[test]
+fn call_embedded_script() {
+    let script = nois!(
+        r#"
+add = (a, b) -> a + b
+greet = name -> 'Hello, ' + name
+"#
+    );
+    assert_eq!(
+        script.call("add", vec![Value::I(2), Value::I(3)]),
+        Ok(Value::I(5))
+    );
+    assert_eq!(
+        script
+            .call(
+                "greet",
+                vec![Value::list("nois".chars().map(Value::C).collect())]
+            )
+            .map(|v| v.to_string()),
+        Ok("Hello, nois".to_string())
+    );
+}
+
+#[derive(Debug, PartialEq, ToNois, FromNois)]
+struct User {
+    name: String,
+    age: u32,
+    tags: Vec<String>,
+}
+
+#[test]
+fn convert_derived_struct() {
+    let script = nois!("older = ([name, age, tags]) -> [name, age + 1, tags]");
+    let user = User {
+        name: "Jack".to_string(),
+        age: 34,
+        tags: vec!["admin".to_string()],
+    };
+    assert_eq!(
+        script.invoke::<User>("older", (user,)),
+        Ok(User {
+            name: "Jack".to_string(),
+            age: 35,
+            tags: vec!["admin".to_string()],
+        })
+    );
+    assert!(User::from_nois(Value::I(1)).is_err());
+}
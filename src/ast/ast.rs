@@ -1,18 +1,21 @@
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::string::ToString;
+use std::sync::Arc;
 
 use pest::iterators::Pair;
+use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 use crate::parser::Rule;
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub statements: Vec<AstPair<Statement>>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Statement {
     Return(Option<AstPair<Expression>>),
     Assignment {
@@ -20,9 +23,11 @@ pub enum Statement {
         expression: AstPair<Expression>,
     },
     Expression(AstPair<Expression>),
+    /// Top-level definitions of another file, e.g. `import 'lib/math'`
+    Import(AstPair<String>),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Expression {
     Operand(Box<AstPair<Operand>>),
     Unary {
@@ -38,9 +43,17 @@ pub enum Expression {
         condition: Box<AstPair<Expression>>,
         match_clauses: Vec<AstPair<MatchClause>>,
     },
+    /// `if` expression, `else if` branch is an else block with a single if expression
+    IfExpression {
+        condition: Box<AstPair<Expression>>,
+        then_block: AstPair<Block>,
+        else_block: Option<AstPair<Block>>,
+    },
+    /// Parenthesized expression, e.g. `(a + b)`, spanning the parentheses
+    Group(Box<AstPair<Expression>>),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Operand {
     Hole,
     Integer(i128),
@@ -56,7 +69,7 @@ pub enum Operand {
     ValueType(ValueType),
 }
 
-#[derive(Debug, PartialOrd, Clone, Eq, Hash)]
+#[derive(Debug, PartialOrd, Clone, Eq, Serialize, Deserialize)]
 pub enum ValueType {
     // TODO: differentiation between unit type and unit value initialization
     Unit,
@@ -67,6 +80,10 @@ pub enum ValueType {
     Function,
     Any,
     Type,
+    Struct(String),
+    Enum(String),
+    Deque,
+    Seq,
 }
 
 impl PartialEq for ValueType {
@@ -78,6 +95,13 @@ impl PartialEq for ValueType {
     }
 }
 
+impl Hash for ValueType {
+    /// `Any` is equal to every type, so all types must hash the same to stay consistent with `eq`
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        0.hash(state);
+    }
+}
+
 impl Display for ValueType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -92,29 +116,38 @@ impl Display for ValueType {
                 ValueType::Function => "Fn".to_string(),
                 ValueType::Any => "*".to_string(),
                 ValueType::Type => "T".to_string(),
+                ValueType::Deque => "Deque".to_string(),
+                ValueType::Seq => "Seq".to_string(),
+                ValueType::Struct(name) | ValueType::Enum(name) => name.to_string(),
             }
         )
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub identifier: AstPair<Identifier>,
     pub arguments: Vec<AstPair<Expression>>,
+    /// Arguments matched to parameters by name, following positional ones, e.g. `f(1, b: 2)`
+    pub named_arguments: Vec<NamedArgument>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub type NamedArgument = (AstPair<Identifier>, AstPair<Expression>);
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionInit {
     pub parameters: Vec<AstPair<Assignee>>,
     pub block: AstPair<Block>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum UnaryOperator {
     Plus,
     Minus,
     Not,
     Spread,
+    /// Bitwise complement of an integer, e.g. `~5 == -6`
+    BitNot,
 }
 
 impl Display for UnaryOperator {
@@ -127,6 +160,7 @@ impl Display for UnaryOperator {
                 UnaryOperator::Minus => "-",
                 UnaryOperator::Not => "!",
                 UnaryOperator::Spread => "..",
+                UnaryOperator::BitNot => "~",
             }
         )
     }
@@ -141,6 +175,7 @@ impl TryFrom<Pair<'_, Rule>> for UnaryOperator {
             Rule::SUBTRACT_OP => Ok(Self::Minus),
             Rule::NOT_OP => Ok(Self::Not),
             Rule::SPREAD_OP => Ok(Self::Spread),
+            Rule::BIT_NOT_OP => Ok(Self::BitNot),
 
             _ => Err(Error::from_pair(
                 &pair,
@@ -150,12 +185,13 @@ impl TryFrom<Pair<'_, Rule>> for UnaryOperator {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum BinaryOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    /// Exponentiation, e.g. `2 ^ 10`. Exclusive OR is a function `xor(a, b)` instead
     Exponent,
     Remainder,
     Accessor,
@@ -167,6 +203,16 @@ pub enum BinaryOperator {
     LessOrEquals,
     And,
     Or,
+    /// Range with exclusive end, e.g. `1..10`
+    Range,
+    /// Range with inclusive end, e.g. `1..=10`
+    RangeInclusive,
+    /// Call of the right side with the left value as the first argument, e.g. `xs |> map(f)`
+    Pipe,
+    BitAnd,
+    BitOr,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl Display for BinaryOperator {
@@ -190,6 +236,13 @@ impl Display for BinaryOperator {
                 BinaryOperator::LessOrEquals => "<=",
                 BinaryOperator::And => "&&",
                 BinaryOperator::Or => "||",
+                BinaryOperator::Range => "..",
+                BinaryOperator::RangeInclusive => "..=",
+                BinaryOperator::Pipe => "|>",
+                BinaryOperator::BitAnd => "&",
+                BinaryOperator::BitOr => "|",
+                BinaryOperator::ShiftLeft => "<<",
+                BinaryOperator::ShiftRight => ">>",
             }
         )
     }
@@ -215,6 +268,13 @@ impl TryFrom<Pair<'_, Rule>> for BinaryOperator {
             Rule::LESS_OR_EQUALS_OP => Ok(Self::LessOrEquals),
             Rule::AND_OP => Ok(Self::And),
             Rule::OR_OP => Ok(Self::Or),
+            Rule::SPREAD_OP => Ok(Self::Range),
+            Rule::RANGE_INCLUSIVE_OP => Ok(Self::RangeInclusive),
+            Rule::PIPE_OP => Ok(Self::Pipe),
+            Rule::BIT_AND_OP => Ok(Self::BitAnd),
+            Rule::BIT_OR_OP => Ok(Self::BitOr),
+            Rule::SHIFT_LEFT_OP => Ok(Self::ShiftLeft),
+            Rule::SHIFT_RIGHT_OP => Ok(Self::ShiftRight),
             r => Err(Error::from_pair(
                 &pair,
                 format!("expected binary operator, found {:?}", r),
@@ -223,7 +283,7 @@ impl TryFrom<Pair<'_, Rule>> for BinaryOperator {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct Identifier(pub String);
 
 impl Identifier {
@@ -238,13 +298,13 @@ impl Display for Identifier {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MatchClause {
     pub pattern: AstPair<PatternItem>,
     pub block: AstPair<Block>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum PatternItem {
     Hole,
     Integer(i128),
@@ -256,19 +316,53 @@ pub enum PatternItem {
         spread: bool,
     },
     PatternList(Vec<AstPair<PatternItem>>),
+    /// String with the prefix and suffix, binding the rest of it unless it is a hole,
+    /// e.g. `'GET ' ++ path`
+    StringConcat {
+        prefix: String,
+        rest: Option<AstPair<Identifier>>,
+        suffix: String,
+    },
+    /// Value of user enum type, e.g. `Color.Red`
+    EnumVariant {
+        name: AstPair<Identifier>,
+        value: AstPair<Identifier>,
+    },
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Assignee {
     Hole,
     DestructureList(DestructureList),
-    Identifier(AstPair<Identifier>),
+    Identifier {
+        identifier: AstPair<Identifier>,
+        annotation: Option<AstPair<TypeAnnotation>>,
+    },
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+/// Expected type of assigned value, e.g. `I` in `a: I = 4`
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
+pub enum TypeAnnotation {
+    Type(ValueType),
+    /// User struct or enum type
+    Named(Identifier),
+    List(Box<AstPair<TypeAnnotation>>),
+}
+
+impl Display for TypeAnnotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeAnnotation::Type(vt) => write!(f, "{}", vt),
+            TypeAnnotation::Named(i) => write!(f, "{}", i),
+            TypeAnnotation::List(t) => write!(f, "[{}]", t.1),
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DestructureList(pub Vec<AstPair<DestructureItem>>);
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub enum DestructureItem {
     Hole,
     Identifier {
@@ -278,12 +372,75 @@ pub enum DestructureItem {
     List(DestructureList),
 }
 
+/// Source of a parsed file. Imported files get their own context, placed after the sources
+/// parsed before them, so that spans of all files loaded by a program do not overlap and are
+/// resolved through the context of the program source
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
 pub struct AstContext {
-    pub input: String,
+    /// Shared source, so that cloning the context does not copy it
+    pub input: Arc<str>,
+    /// Byte offsets of line starts
+    line_starts: Arc<[usize]>,
+    /// Path of the imported file, shown in errors pointing into it
+    pub path: Option<String>,
+    /// Offset of the source start in spans
+    pub offset: usize,
+    /// Contexts of imported files, in the order of their offsets
+    imports: Vec<AstContext>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+impl AstContext {
+    pub fn new(input: impl Into<Arc<str>>) -> AstContext {
+        let input = input.into();
+        let line_starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        AstContext {
+            input,
+            line_starts,
+            path: None,
+            offset: 0,
+            imports: vec![],
+        }
+    }
+
+    /// Add source of the imported file, returning the offset its spans have to be moved by
+    pub fn import(&mut self, path: String, input: impl Into<Arc<str>>) -> usize {
+        let offset = match self.imports.last() {
+            Some(c) => c.offset + c.input.len(),
+            None => self.input.len(),
+        } + 1;
+        self.imports.push(AstContext {
+            path: Some(path),
+            offset,
+            ..AstContext::new(input)
+        });
+        offset
+    }
+
+    /// Context of the file the offset belongs to
+    pub fn source(&self, offset: usize) -> &AstContext {
+        self.imports
+            .iter()
+            .rev()
+            .find(|c| c.offset <= offset)
+            .unwrap_or(self)
+    }
+
+    /// 1-based line and column of the byte offset, column is counted in chars
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let source = self.source(offset);
+        let offset = offset - source.offset;
+        let line = source.line_starts.partition_point(|s| *s <= offset);
+        let line_start = source.line_starts[line - 1];
+        let column = source.input[line_start..offset.min(source.input.len())]
+            .chars()
+            .count();
+        (line, column + 1)
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -291,8 +448,21 @@ pub struct Span {
 
 impl Span {
     pub fn as_span<'a>(&self, ctx: &'a AstContext) -> pest::Span<'a> {
-        pest::Span::new(&ctx.input, self.start, self.end)
-            .expect(format!("Failed to convert {:?}", self).as_str())
+        let source = ctx.source(self.start);
+        pest::Span::new(
+            &source.input,
+            self.start - source.offset,
+            self.end - source.offset,
+        )
+        .expect(format!("Failed to convert {:?}", self).as_str())
+    }
+
+    pub fn start_line_col(&self, ctx: &AstContext) -> (usize, usize) {
+        ctx.line_col(self.start)
+    }
+
+    pub fn end_line_col(&self, ctx: &AstContext) -> (usize, usize) {
+        ctx.line_col(self.end)
     }
 }
 
@@ -305,7 +475,7 @@ impl<'a> From<pest::Span<'a>> for Span {
     }
 }
 
-#[derive(PartialOrd, PartialEq, Clone)]
+#[derive(PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AstPair<A>(pub Span, pub A);
 
 impl<A> AstPair<A> {
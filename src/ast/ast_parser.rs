@@ -1,14 +1,20 @@
-use enquote::unquote;
+use std::fmt::{Display, Formatter};
+use std::num::IntErrorKind;
+
 use pest::iterators::{Pair, Pairs};
 
 use crate::ast::ast::{
     Assignee, AstPair, BinaryOperator, Block, DestructureItem, DestructureList, Expression,
-    FunctionCall, FunctionInit, Identifier, MatchClause, Operand, PatternItem, Statement,
-    ValueType,
+    FunctionCall, FunctionInit, Identifier, MatchClause, NamedArgument, Operand, PatternItem, Span,
+    Statement, TypeAnnotation, UnaryOperator, ValueType,
+};
+use crate::ast::expression::{
+    Associativity, OperatorAssociativity, OperatorPrecedence, UNARY_PRECEDENCE,
 };
-use crate::ast::expression::{Associativity, OperatorAssociativity, OperatorPrecedence};
 use crate::ast::util::{children, first_child, parse_children};
+use crate::ast::visit::walk_expressions;
 use crate::error::Error;
+use crate::escape::unescape;
 use crate::parser::Rule;
 
 pub fn parse_file(pairs: &Pairs<Rule>) -> Result<AstPair<Block>, Error> {
@@ -51,6 +57,11 @@ pub fn parse_statement(pair: &Pair<Rule>) -> Result<AstPair<Statement>, Error> {
             };
             Ok(AstPair::from_pair(pair, st))
         }
+        Rule::import_statement => {
+            let path = &children(pair)[0];
+            let import = Statement::Import(AstPair::from_pair(path, parse_string(path)?));
+            Ok(AstPair::from_pair(pair, import))
+        }
         Rule::assignment => {
             let ch = children(pair);
             Ok(AstPair::from_pair(
@@ -82,6 +93,13 @@ pub fn parse_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>, Error>
                 parse_complex_expression(pair)
             }
         }
+        Rule::group => {
+            let expression = parse_expression(&children(pair)[0])?;
+            Ok(AstPair::from_pair(
+                pair,
+                Expression::Group(Box::new(expression)),
+            ))
+        }
         Rule::unary_expression => {
             let ch = children(pair);
             let operator = parse_operator(&ch[0])?;
@@ -110,6 +128,33 @@ pub fn parse_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>, Error>
                 },
             ));
         }
+        Rule::if_expression => {
+            let ch = children(pair);
+            let condition = parse_expression(&ch[0])?;
+            let then_block = parse_block(&ch[1])?;
+            let else_block = match ch.get(2) {
+                Some(p) if p.as_rule() == Rule::if_expression => {
+                    let statement =
+                        AstPair::from_pair(p, Statement::Expression(parse_expression(p)?));
+                    Some(AstPair::from_pair(
+                        p,
+                        Block {
+                            statements: vec![statement],
+                        },
+                    ))
+                }
+                Some(p) => Some(parse_block(p)?),
+                None => None,
+            };
+            Ok(AstPair::from_pair(
+                pair,
+                Expression::IfExpression {
+                    condition: Box::new(condition),
+                    then_block,
+                    else_block,
+                },
+            ))
+        }
         _ => {
             let operand = parse_operand(pair)?;
             Ok(AstPair::from_pair(
@@ -125,21 +170,51 @@ pub fn parse_complex_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>
     enum Node {
         ValueNode(ValueNode),
         ExpNode(ExpNode),
+        UnaryNode(UnaryNode),
     }
     #[derive(Debug, PartialOrd, PartialEq, Clone)]
     struct ValueNode(AstPair<Expression>);
     #[derive(Debug, PartialOrd, PartialEq, Clone)]
     struct ExpNode(AstPair<BinaryOperator>, Box<Node>, Box<Node>);
-    let mut operator_stack: Vec<AstPair<BinaryOperator>> = vec![];
+    #[derive(Debug, PartialOrd, PartialEq, Clone)]
+    struct UnaryNode(AstPair<UnaryOperator>, Box<Node>);
+    /// Unary operators of operands are handled as prefix operators, so that they take
+    /// accessor chains as operand, e.g. `-a.b` is `-(a.b)`
+    #[derive(Debug, PartialOrd, PartialEq, Clone)]
+    enum StackOperator {
+        Binary(AstPair<BinaryOperator>),
+        Prefix(AstPair<UnaryOperator>),
+    }
+    fn reduce(o: StackOperator, operand_stack: &mut Vec<Node>) {
+        let node = match o {
+            StackOperator::Binary(o) => {
+                let l_op = operand_stack.pop().unwrap();
+                let r_op = operand_stack.pop().unwrap();
+                Node::ExpNode(ExpNode(o, Box::from(l_op), Box::from(r_op)))
+            }
+            StackOperator::Prefix(o) => {
+                let operand = operand_stack.pop().unwrap();
+                Node::UnaryNode(UnaryNode(o, Box::from(operand)))
+            }
+        };
+        operand_stack.push(node);
+    }
+    let mut operator_stack: Vec<StackOperator> = vec![];
     let mut operand_stack: Vec<Node> = vec![];
     let ch = children(pair);
     for c in ch {
         match c.as_rule() {
             Rule::binary_operator => {
                 let o1: AstPair<BinaryOperator> = parse_operator(&c)?;
-                let mut o2;
-                while !operator_stack.is_empty() {
-                    o2 = operator_stack.iter().cloned().last().unwrap();
+                while let Some(top) = operator_stack.last().cloned() {
+                    let o2 = match top {
+                        StackOperator::Prefix(_) if o1.1.precedence() < UNARY_PRECEDENCE => {
+                            reduce(operator_stack.pop().unwrap(), &mut operand_stack);
+                            continue;
+                        }
+                        StackOperator::Prefix(_) => break,
+                        StackOperator::Binary(o2) => o2,
+                    };
                     if o1.1.precedence() == o2.1.precedence()
                         && o1.1.associativity() == Associativity::None
                         && o2.1.associativity() == Associativity::None
@@ -153,19 +228,18 @@ pub fn parse_complex_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>
                         && o1.1.precedence() == o2.1.precedence())
                         || o1.1.precedence() < o2.1.precedence()
                     {
-                        operator_stack.pop();
-                        let l_op = operand_stack.pop().unwrap();
-                        let r_op = operand_stack.pop().unwrap();
-                        operand_stack.push(Node::ExpNode(ExpNode(
-                            o2,
-                            Box::from(l_op.clone()),
-                            Box::from(r_op.clone()),
-                        )));
+                        reduce(operator_stack.pop().unwrap(), &mut operand_stack);
                     } else {
                         break;
                     }
                 }
-                operator_stack.push(o1.clone());
+                operator_stack.push(StackOperator::Binary(o1));
+            }
+            Rule::unary_expression => {
+                let uch = children(&c);
+                operator_stack.push(StackOperator::Prefix(parse_operator(&uch[0])?));
+                let operand = parse_expression(&uch[1])?;
+                operand_stack.push(Node::ValueNode(ValueNode(operand)));
             }
             _ => {
                 let operand = parse_expression(&c)?;
@@ -173,14 +247,8 @@ pub fn parse_complex_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>
             }
         }
     }
-    while !operator_stack.is_empty() {
-        let l_op = operand_stack.pop().unwrap();
-        let r_op = operand_stack.pop().unwrap();
-        operand_stack.push(Node::ExpNode(ExpNode(
-            operator_stack.pop().unwrap(),
-            Box::from(l_op.clone()),
-            Box::from(r_op.clone()),
-        )));
+    while let Some(o) = operator_stack.pop() {
+        reduce(o, &mut operand_stack);
     }
     fn map_node(n: &Node) -> AstPair<Expression> {
         match n {
@@ -193,6 +261,18 @@ pub fn parse_complex_expression(pair: &Pair<Rule>) -> Result<AstPair<Expression>
                 };
                 AstPair::from_span(&op.0, exp)
             }
+            Node::UnaryNode(UnaryNode(op, operand)) => {
+                let operand = map_node(operand);
+                let span = Span {
+                    start: op.0.start,
+                    end: operand.0.end.max(op.0.end),
+                };
+                let exp = Expression::Unary {
+                    operator: Box::new(op.clone()),
+                    operand: Box::new(operand),
+                };
+                AstPair(span, exp)
+            }
         }
     }
     let exp = map_node(&operand_stack.pop().unwrap());
@@ -249,20 +329,97 @@ pub fn parse_operand(pair: &Pair<Rule>) -> Result<AstPair<Operand>, Error> {
     }
 }
 
+/// Number literal that cannot be represented by its type
+#[derive(Debug, PartialEq, Clone)]
+pub enum LiteralError {
+    IntegerOverflow {
+        literal: String,
+    },
+    FloatOverflow {
+        literal: String,
+    },
+    Malformed {
+        literal: String,
+        value_type: ValueType,
+    },
+}
+
+impl Display for LiteralError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralError::IntegerOverflow { literal } if radix(literal).is_some() => write!(
+                f,
+                "integer literal {} does not fit into I, valid range is {} to {}",
+                literal,
+                i128::MIN,
+                i128::MAX
+            ),
+            LiteralError::IntegerOverflow { literal } => write!(
+                f,
+                "integer literal {} does not fit into I, valid range is {} to {}\n  \
+                 = help: use float literal {}.0 if precision loss is acceptable",
+                literal,
+                i128::MIN,
+                i128::MAX,
+                literal
+            ),
+            LiteralError::FloatOverflow { literal } => write!(
+                f,
+                "float literal {} does not fit into F, valid range is {:e} to {:e}",
+                literal,
+                f64::MIN,
+                f64::MAX
+            ),
+            LiteralError::Malformed {
+                literal,
+                value_type,
+            } => write!(f, "unable to parse {} {}", value_type, literal),
+        }
+    }
+}
+
+/// Radix and digits of a hex, binary or octal literal, e.g. `0xFF`
+fn radix(literal: &str) -> Option<(u32, &str)> {
+    [("0x", 16), ("0b", 2), ("0o", 8)]
+        .into_iter()
+        .find_map(|(prefix, radix)| literal.strip_prefix(prefix).map(|digits| (radix, digits)))
+}
+
 pub fn parse_integer(pair: &Pair<Rule>) -> Result<i128, Error> {
     let num_s = pair.as_str();
-    match num_s.parse::<i128>() {
-        Ok(n) => Ok(n),
-        Err(_) => Err(Error::from_pair(pair, format!("unable to parse I {num_s}"))),
-    }
+    let parsed = match radix(num_s) {
+        Some((radix, digits)) => i128::from_str_radix(digits, radix),
+        None => num_s.parse::<i128>(),
+    };
+    parsed.map_err(|e| {
+        let error = match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                LiteralError::IntegerOverflow {
+                    literal: num_s.to_string(),
+                }
+            }
+            _ => LiteralError::Malformed {
+                literal: num_s.to_string(),
+                value_type: ValueType::Integer,
+            },
+        };
+        Error::from_pair(pair, error.to_string())
+    })
 }
 
 pub fn parse_float(pair: &Pair<Rule>) -> Result<f64, Error> {
     let num_s = pair.as_str();
-    match num_s.parse::<f64>() {
-        Ok(n) => Ok(n),
-        Err(_) => Err(Error::from_pair(pair, format!("unable to parse F {num_s}"))),
-    }
+    let error = match num_s.parse::<f64>() {
+        Ok(n) if n.is_finite() => return Ok(n),
+        Ok(_) => LiteralError::FloatOverflow {
+            literal: num_s.to_string(),
+        },
+        Err(_) => LiteralError::Malformed {
+            literal: num_s.to_string(),
+            value_type: ValueType::Float,
+        },
+    };
+    Err(Error::from_pair(pair, error.to_string()))
 }
 
 pub fn parse_boolean(pair: &Pair<Rule>) -> Result<bool, Error> {
@@ -278,36 +435,84 @@ pub fn parse_boolean(pair: &Pair<Rule>) -> Result<bool, Error> {
 
 pub fn parse_string(pair: &Pair<Rule>) -> Result<String, Error> {
     let raw_str = pair.as_str();
-    match unquote(raw_str) {
-        Ok(s) => Ok(s),
-        Err(_) => Err(Error::from_pair(
-            pair,
-            format!("unable to parse C[] {raw_str}"),
-        )),
+    // quotes are always single byte
+    let inner = &raw_str[1..raw_str.len() - 1];
+    if raw_str.starts_with('`') {
+        return Ok(inner.to_string());
     }
+    unescape(inner)
+        .map_err(|e| Error::from_pair_range(pair, e.range.start + 1..e.range.end + 1, e.message))
 }
 
 pub fn parse_function_call(pair: &Pair<Rule>) -> Result<AstPair<Operand>, Error> {
     let ch = children(pair);
+    let (arguments, named_arguments) = parse_argument_list(&ch[1])?;
     Ok(AstPair::from_pair(
         pair,
         Operand::FunctionCall(FunctionCall {
             identifier: parse_identifier(&ch[0])?,
-            arguments: parse_argument_list(&ch[1])?,
+            arguments,
+            named_arguments,
         }),
     ))
 }
 
+/// Parameter of a function without parameter list referring to it, e.g. `{ it * 2 }`
+pub const IMPLICIT_PARAMETER: &str = "it";
+
 pub fn parse_function_init(pair: &Pair<Rule>) -> Result<AstPair<Operand>, Error> {
     let ch = children(pair);
-    let parameters: Vec<AstPair<Assignee>> = parse_children(&ch[0], parse_assignee)?;
+    let mut parameters: Vec<AstPair<Assignee>> = parse_children(&ch[0], parse_assignee)?;
     let block = parse_block(&ch[1])?;
+    // parameter list is omitted, not empty like in `() -> 1`
+    if ch[0].as_str().is_empty() && references_implicit_parameter(&block) {
+        let identifier = AstPair::from_pair(&ch[0], Identifier::new(IMPLICIT_PARAMETER));
+        parameters.push(AstPair::from_pair(
+            &ch[0],
+            Assignee::Identifier {
+                identifier,
+                annotation: None,
+            },
+        ));
+    }
     Ok(AstPair::from_pair(
         pair,
         Operand::FunctionInit(FunctionInit { parameters, block }),
     ))
 }
 
+/// Whether block refers to implicit parameter outside of nested functions declaring their own
+fn references_implicit_parameter(block: &AstPair<Block>) -> bool {
+    let implicit = Identifier::new(IMPLICIT_PARAMETER);
+    let mut shadowed: Vec<Span> = vec![];
+    let mut references: Vec<Span> = vec![];
+    walk_expressions(block, &mut |e| {
+        let o = match &e.1 {
+            Expression::Operand(o) => o,
+            _ => return,
+        };
+        match &o.1 {
+            Operand::Identifier(i) if i.1 == implicit => references.push(i.0.clone()),
+            Operand::FunctionCall(fc) if fc.identifier.1 == implicit => {
+                references.push(fc.identifier.0.clone())
+            }
+            Operand::FunctionInit(fi)
+                if fi.parameters.iter().any(|p| {
+                    matches!(&p.1, Assignee::Identifier { identifier, .. } if identifier.1 == implicit)
+                }) =>
+            {
+                shadowed.push(o.0.clone())
+            }
+            _ => {}
+        }
+    });
+    references.iter().any(|r| {
+        !shadowed
+            .iter()
+            .any(|s| s.start <= r.start && r.end <= s.end)
+    })
+}
+
 pub fn parse_list_init(pair: &Pair<Rule>) -> Result<AstPair<Operand>, Error> {
     let items = parse_children(pair, parse_expression)?;
     Ok(AstPair::from_pair(pair, Operand::ListInit { items }))
@@ -362,9 +567,36 @@ pub fn parse_value_type(pair: &Pair<Rule>) -> Result<ValueType, Error> {
     })
 }
 
-pub fn parse_argument_list(pair: &Pair<Rule>) -> Result<Vec<AstPair<Expression>>, Error> {
+/// Positional and named arguments, named ones must follow positional ones and be unique
+pub fn parse_argument_list(
+    pair: &Pair<Rule>,
+) -> Result<(Vec<AstPair<Expression>>, Vec<NamedArgument>), Error> {
     match pair.as_rule() {
-        Rule::argument_list => parse_children(pair, parse_expression),
+        Rule::argument_list => {
+            let mut positional = vec![];
+            let mut named: Vec<NamedArgument> = vec![];
+            for a in children(pair) {
+                if a.as_rule() == Rule::named_argument {
+                    let ch = children(&a);
+                    let name = parse_identifier(&ch[0])?;
+                    if named.iter().any(|(n, _)| n.1 == name.1) {
+                        return Err(Error::from_pair(
+                            &ch[0],
+                            format!("argument '{}' is specified more than once", name.1),
+                        ));
+                    }
+                    named.push((name, parse_expression(&ch[1])?));
+                } else if !named.is_empty() {
+                    return Err(Error::from_pair(
+                        &a,
+                        "positional argument cannot follow named arguments".to_string(),
+                    ));
+                } else {
+                    positional.push(parse_expression(&a)?);
+                }
+            }
+            Ok((positional, named))
+        }
         _ => Err(Error::from_pair(
             pair,
             format!(
@@ -381,10 +613,13 @@ fn parse_assignee(pair: &Pair<Rule>) -> Result<AstPair<Assignee>, Error> {
         Rule::assignee => {
             let ch = &first_child(pair).unwrap();
             let assignee = match ch.as_rule() {
-                Rule::identifier => {
-                    let id = parse_identifier(ch)?;
-                    Assignee::Identifier(id)
-                }
+                Rule::identifier => Assignee::Identifier {
+                    identifier: parse_identifier(ch)?,
+                    annotation: children(pair)
+                        .get(1)
+                        .map(parse_type_annotation)
+                        .transpose()?,
+                },
                 Rule::HOLE_OP => Assignee::Hole,
                 Rule::destructure_list => Assignee::DestructureList(parse_destructure_list(ch)?),
                 _ => unreachable!(),
@@ -398,6 +633,17 @@ fn parse_assignee(pair: &Pair<Rule>) -> Result<AstPair<Assignee>, Error> {
     }
 }
 
+fn parse_type_annotation(pair: &Pair<Rule>) -> Result<AstPair<TypeAnnotation>, Error> {
+    let ch = &first_child(pair).unwrap();
+    let annotation = match ch.as_rule() {
+        Rule::value_type => TypeAnnotation::Type(parse_value_type(&children(ch)[0])?),
+        Rule::type_expression => TypeAnnotation::List(Box::new(parse_type_annotation(ch)?)),
+        Rule::identifier => TypeAnnotation::Named(parse_identifier(ch)?.1),
+        _ => unreachable!(),
+    };
+    Ok(AstPair::from_pair(pair, annotation))
+}
+
 fn parse_destructure_list(pair: &Pair<Rule>) -> Result<DestructureList, Error> {
     let items = children(pair)
         .iter()
@@ -480,6 +726,14 @@ fn parse_pattern_item(pair: &Pair<Rule>) -> Result<AstPair<PatternItem>, Error>
                     spread: false,
                 },
                 Rule::pattern_list => return parse_pattern_list(&ch[0]),
+                Rule::string_concat => parse_string_concat(&ch[0])?,
+                Rule::enum_variant => {
+                    let ids = children(&ch[0]);
+                    PatternItem::EnumVariant {
+                        name: parse_identifier(&ids[0])?,
+                        value: parse_identifier(&ids[2])?,
+                    }
+                }
                 r => unreachable!("{:?}", r),
             };
             Ok(AstPair::from_pair(&pair, item))
@@ -495,6 +749,21 @@ fn parse_pattern_item(pair: &Pair<Rule>) -> Result<AstPair<PatternItem>, Error>
     }
 }
 
+fn parse_string_concat(pair: &Pair<Rule>) -> Result<PatternItem, Error> {
+    let ch = children(pair);
+    let rest_index = ch.iter().position(|p| p.as_rule() != Rule::string).unwrap();
+    let rest = match ch[rest_index].as_rule() {
+        Rule::identifier => Some(parse_identifier(&ch[rest_index])?),
+        _ => None,
+    };
+    let string = |p: Option<&Pair<Rule>>| p.map_or(Ok(String::new()), parse_string);
+    Ok(PatternItem::StringConcat {
+        prefix: string(ch[..rest_index].first())?,
+        rest,
+        suffix: string(ch.get(rest_index + 1))?,
+    })
+}
+
 fn parse_pattern_list(pair: &Pair<Rule>) -> Result<AstPair<PatternItem>, Error> {
     match pair.as_rule() {
         Rule::pattern_list => {
@@ -517,8 +786,10 @@ fn parse_pattern_list(pair: &Pair<Rule>) -> Result<AstPair<PatternItem>, Error>
 
 #[cfg(test)]
 mod tests {
+    use pest::error::InputLocation;
     use pest::Parser;
 
+    use crate::ast::ast::AstContext;
     use crate::parser::NoisParser;
 
     use super::*;
@@ -621,6 +892,58 @@ False
         assert_eq!(strings[7], "hey 😎");
     }
 
+    #[test]
+    fn build_ast_import() {
+        let source = "import 'lib/math'\nimport`C:\\lib`";
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block = parse_file(file).unwrap().1;
+        let paths = block
+            .statements
+            .into_iter()
+            .map(|s| match_enum!(s.1, Statement::Import(p) => p.1))
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["lib/math", "C:\\lib"]);
+        assert!(NoisParser::parse(Rule::program, "import = 1").is_err());
+    }
+
+    #[test]
+    fn build_ast_raw_string() {
+        let source = r#"
+``
+`C:\Users\q`
+`\d+'"\n`
+`a
+b`
+"#;
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block = parse_file(file).unwrap().1;
+        let strings: Vec<String> = block
+            .statements
+            .into_iter()
+            .map(|s| {
+                let exp = match_enum!(s.1, Statement::Expression(e) => e);
+                let op = *match_enum!(exp.1, Expression::Operand(o) => o);
+                match_enum!(op.1, Operand::String(s) => s)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(strings, vec!["", "C:\\Users\\q", "\\d+'\"\\n", "a\nb"]);
+    }
+
+    #[test]
+    fn build_ast_string_invalid_escape() {
+        let source = r#"a = 'ok\q'"#;
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let error = match parse_file(file).unwrap_err() {
+            Error::Error(e) => e,
+            e => panic!("unexpected error {:?}", e),
+        };
+        assert_eq!(error.location, InputLocation::Span((7, 9)));
+        assert!(error
+            .variant
+            .message()
+            .starts_with("invalid escape sequence \\q"));
+    }
+
     #[test]
     fn build_ast_type_value() {
         let source = "[(), I, F, C, B, [C], [[C], I], Fn]";
@@ -959,23 +1282,25 @@ Block {
         Expression(
             Binary {
                 left_operand: Binary {
-                    left_operand: Binary {
-                        left_operand: Operand(
-                            Identifier(
+                    left_operand: Group(
+                        Binary {
+                            left_operand: Operand(
                                 Identifier(
-                                    "a",
+                                    Identifier(
+                                        "a",
+                                    ),
                                 ),
                             ),
-                        ),
-                        operator: Add,
-                        right_operand: Operand(
-                            Identifier(
+                            operator: Add,
+                            right_operand: Operand(
                                 Identifier(
-                                    "b",
+                                    Identifier(
+                                        "b",
+                                    ),
                                 ),
                             ),
-                        ),
-                    },
+                        },
+                    ),
                     operator: Multiply,
                     right_operand: Binary {
                         left_operand: Binary {
@@ -992,6 +1317,7 @@ Block {
                                             "len",
                                         ),
                                         arguments: [],
+                                        named_arguments: [],
                                     },
                                 ),
                             ),
@@ -1028,6 +1354,7 @@ Block {
                                     ),
                                 ),
                             ],
+                            named_arguments: [],
                         },
                     ),
                 ),
@@ -1065,6 +1392,7 @@ Block {
                                     "foo",
                                 ),
                                 arguments: [],
+                                named_arguments: [],
                             },
                         ),
                     ),
@@ -1085,6 +1413,7 @@ Block {
                                     ),
                                 ),
                             ],
+                            named_arguments: [],
                         },
                     ),
                 ),
@@ -1104,6 +1433,125 @@ Block {
         assert_eq!(err.message(), "operators <= and == cannot be chained");
     }
 
+    #[test]
+    fn build_ast_named_arguments_fail() {
+        let error = |source: &str| {
+            let file = &NoisParser::parse(Rule::program, source).unwrap();
+            parse_file(file).unwrap_err().message()
+        };
+        assert_eq!(
+            error("f(a: 1, 2)"),
+            "positional argument cannot follow named arguments"
+        );
+        assert_eq!(
+            error("f(a: 1, a: 2)"),
+            "argument 'a' is specified more than once"
+        );
+    }
+
+    #[test]
+    fn build_ast_radix_integers() {
+        let source = r#"
+0xFF
+0xff
+0b1010
+0o755
+0x7fffffffffffffffffffffffffffffff
+"#;
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block = parse_file(file).unwrap().1;
+        let numbers = block
+            .statements
+            .into_iter()
+            .map(|s| {
+                let exp = match_enum!(s.1, Statement::Expression(e) => e);
+                let op = *match_enum!(exp.1, Expression::Operand(o) => o);
+                match_enum!(op.1, Operand::Integer(n) => n)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![255, 255, 10, 493, i128::MAX]);
+    }
+
+    #[test]
+    fn build_ast_number_overflow() {
+        let error = |source: &str| {
+            let file = &NoisParser::parse(Rule::program, source).unwrap();
+            parse_file(file).unwrap_err().message()
+        };
+        assert_eq!(
+            error("170141183460469231731687303715884105728"),
+            LiteralError::IntegerOverflow {
+                literal: "170141183460469231731687303715884105728".to_string()
+            }
+            .to_string()
+        );
+        assert!(error("a = 1000000000000000000000000000000000000000").ends_with(
+            "help: use float literal 1000000000000000000000000000000000000000.0 if precision loss is acceptable"
+        ));
+        assert_eq!(
+            error("0x80000000000000000000000000000000"),
+            "integer literal 0x80000000000000000000000000000000 does not fit into I, valid range is \
+             -170141183460469231731687303715884105728 to 170141183460469231731687303715884105727"
+        );
+        assert_eq!(
+            error("f = 1e309"),
+            "float literal 1e309 does not fit into F, valid range is -1.7976931348623157e308 to 1.7976931348623157e308"
+        );
+    }
+
+    #[test]
+    fn build_ast_implicit_parameter() {
+        let source = r#"
+{ it * 2 }
+{ map(it, { it + 1 }) }
+{ map([], { it + 1 }) }
+-> it
+() -> it
+a -> it
+"#;
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block: AstPair<Block> = parse_file(file).unwrap();
+        let parameters = block
+            .1
+            .statements
+            .iter()
+            .map(|s| {
+                let e = match_enum!(&s.1, Statement::Expression(e) => e);
+                let o = match_enum!(&e.1, Expression::Operand(o) => o);
+                let fi = match_enum!(&o.1, Operand::FunctionInit(fi) => fi);
+                fi.parameters
+                    .iter()
+                    .map(|p| match_enum!(&p.1, Assignee::Identifier { identifier, .. } => identifier.1 .0.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parameters,
+            vec![
+                vec!["it"],
+                vec!["it"],
+                vec![],
+                vec!["it"],
+                vec![],
+                vec!["a"]
+            ]
+        );
+    }
+
+    #[test]
+    fn build_ast_group_span() {
+        let source = "a * (\n  b + c\n)";
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block: AstPair<Block> = parse_file(file).unwrap();
+        let group = match_enum!(
+            &block.1.statements[0].1,
+            Statement::Expression(AstPair(_, Expression::Binary { right_operand, .. })) => right_operand
+        );
+        assert_eq!(group.0, Span { start: 4, end: 15 });
+        let inner = match_enum!(&group.1, Expression::Group(e) => e);
+        assert_eq!(inner.0, Span { start: 10, end: 11 });
+    }
+
     #[test]
     fn build_ast_unary_expression() {
         let source = r#"
@@ -1127,35 +1575,10 @@ Block {
             },
         ),
         Expression(
-            Binary {
-                left_operand: Unary {
-                    operator: Plus,
-                    operand: Operand(
-                        Identifier(
-                            Identifier(
-                                "a",
-                            ),
-                        ),
-                    ),
-                },
-                operator: Add,
-                right_operand: Unary {
-                    operator: Minus,
-                    operand: Operand(
-                        Identifier(
-                            Identifier(
-                                "b",
-                            ),
-                        ),
-                    ),
-                },
-            },
-        ),
-        Expression(
-            Binary {
-                left_operand: Binary {
+            Group(
+                Binary {
                     left_operand: Unary {
-                        operator: Not,
+                        operator: Plus,
                         operand: Operand(
                             Identifier(
                                 Identifier(
@@ -1164,9 +1587,9 @@ Block {
                             ),
                         ),
                     },
-                    operator: Or,
+                    operator: Add,
                     right_operand: Unary {
-                        operator: Not,
+                        operator: Minus,
                         operand: Operand(
                             Identifier(
                                 Identifier(
@@ -1176,26 +1599,57 @@ Block {
                         ),
                     },
                 },
+            ),
+        ),
+        Expression(
+            Binary {
+                left_operand: Group(
+                    Binary {
+                        left_operand: Unary {
+                            operator: Not,
+                            operand: Operand(
+                                Identifier(
+                                    Identifier(
+                                        "a",
+                                    ),
+                                ),
+                            ),
+                        },
+                        operator: Or,
+                        right_operand: Unary {
+                            operator: Not,
+                            operand: Operand(
+                                Identifier(
+                                    Identifier(
+                                        "b",
+                                    ),
+                                ),
+                            ),
+                        },
+                    },
+                ),
                 operator: Equals,
                 right_operand: Unary {
                     operator: Not,
-                    operand: Binary {
-                        left_operand: Operand(
-                            Identifier(
+                    operand: Group(
+                        Binary {
+                            left_operand: Operand(
                                 Identifier(
-                                    "a",
+                                    Identifier(
+                                        "a",
+                                    ),
                                 ),
                             ),
-                        ),
-                        operator: And,
-                        right_operand: Operand(
-                            Identifier(
+                            operator: And,
+                            right_operand: Operand(
                                 Identifier(
-                                    "b",
+                                    Identifier(
+                                        "b",
+                                    ),
                                 ),
                             ),
-                        ),
-                    },
+                        },
+                    ),
                 },
             },
         ),
@@ -1222,11 +1676,12 @@ _ = []
 Block {
     statements: [
         Assignment {
-            assignee: Identifier(
-                Identifier(
+            assignee: Identifier {
+                identifier: Identifier(
                     "a",
                 ),
-            ),
+                annotation: None,
+            },
             expression: Operand(
                 ListInit {
                     items: [],
@@ -1379,6 +1834,44 @@ Block {
         assert_eq!(format!("{:#?}", block), expect.trim())
     }
 
+    #[test]
+    fn build_ast_type_annotation() {
+        let source = r#"
+a: I = 4
+b: [[C]] = []
+f = (p: Point, q) -> p
+"#;
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block = parse_file(file).unwrap().1;
+        let annotations = block
+            .statements
+            .into_iter()
+            .flat_map(|s| {
+                let (assignee, expression) =
+                    match_enum!(s.1, Statement::Assignment{assignee: a, expression: e} => (a, e));
+                let params = match *match_enum!(expression.1, Expression::Operand(o) => o) {
+                    AstPair(_, Operand::FunctionInit(fi)) => fi.parameters,
+                    _ => vec![],
+                };
+                vec![assignee].into_iter().chain(params)
+            })
+            .map(|a| {
+                match_enum!(a.1, Assignee::Identifier{annotation: an, ..} => an)
+                    .map(|an| an.1.to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            annotations,
+            vec![
+                Some("I".to_string()),
+                Some("[[C]]".to_string()),
+                None,
+                Some("Point".to_string()),
+                None
+            ]
+        );
+    }
+
     #[test]
     fn build_ast_match_expression_basic() {
         let source = r#"
@@ -1454,6 +1947,7 @@ Block {
                                                     "panic",
                                                 ),
                                                 arguments: [],
+                                                named_arguments: [],
                                             },
                                         ),
                                     ),
@@ -1470,6 +1964,69 @@ Block {
         assert_eq!(format!("{:#?}", block), expect.trim())
     }
 
+    #[test]
+    fn build_ast_match_string_concat() {
+        let source =
+            "match a {\n  'GET ' ++ p => x,\n  _ ++ '.no' => x,\n  'a' ++ b ++ 'c' => x,\n}";
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block: AstPair<Block> = parse_file(file).unwrap();
+        let patterns = match &block.1.statements[0].1 {
+            Statement::Expression(AstPair(
+                _,
+                Expression::MatchExpression { match_clauses, .. },
+            )) => match_clauses
+                .iter()
+                .map(|c| format!("{:?}", c.1.pattern))
+                .collect::<Vec<_>>(),
+            s => panic!("unexpected statement {:?}", s),
+        };
+        assert_eq!(
+            patterns,
+            vec![
+                r#"StringConcat { prefix: "GET ", rest: Some(Identifier("p")), suffix: "" }"#,
+                r#"StringConcat { prefix: "", rest: None, suffix: ".no" }"#,
+                r#"StringConcat { prefix: "a", rest: Some(Identifier("b")), suffix: "c" }"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn build_ast_if_expression() {
+        let source = "if a { 1 } else if b { 2 } else { 3 }";
+        let file = &NoisParser::parse(Rule::program, source).unwrap();
+        let block: AstPair<Block> = parse_file(file).unwrap();
+        let else_block = match &block.1.statements[0].1 {
+            Statement::Expression(AstPair(
+                _,
+                Expression::IfExpression {
+                    condition,
+                    else_block: Some(else_block),
+                    ..
+                },
+            )) => {
+                assert_eq!(
+                    format!("{:?}", condition.1),
+                    r#"Operand(Identifier(Identifier("a")))"#
+                );
+                else_block.clone()
+            }
+            s => panic!("unexpected statement {:?}", s),
+        };
+        match &else_block.1.statements[..] {
+            [AstPair(
+                _,
+                Statement::Expression(AstPair(
+                    _,
+                    Expression::IfExpression {
+                        else_block: Some(_),
+                        ..
+                    },
+                )),
+            )] => {}
+            s => panic!("unexpected else block {:?}", s),
+        }
+    }
+
     #[test]
     fn build_ast_match_expression_list() {
         let source = r#"
@@ -1620,4 +2177,17 @@ Block {
 "#;
         assert_eq!(format!("{:#?}", block), expect.trim())
     }
+
+    #[test]
+    fn span_line_col() {
+        let a_ctx = AstContext::new("a = 1\nb = 'é'\n\nc");
+        for offset in 0..=a_ctx.input.len() {
+            if let Some(p) = pest::Position::new(&a_ctx.input, offset) {
+                assert_eq!(a_ctx.line_col(offset), p.line_col(), "offset {}", offset);
+            }
+        }
+        let span = Span { start: 10, end: 17 };
+        assert_eq!(span.start_line_col(&a_ctx), (2, 5));
+        assert_eq!(span.end_line_col(&a_ctx), (4, 2));
+    }
 }
@@ -15,46 +15,232 @@ pub trait OperatorAssociativity {
     fn associativity(&self) -> Associativity;
 }
 
+/// Precedence and associativity of every binary operator, from the tightest binding to the
+/// loosest. Operators of the same precedence without associativity cannot be chained, e.g.
+/// `a == b == c` is an error.
+pub const PRECEDENCE_TABLE: [(BinaryOperator, i32, Associativity); 22] = [
+    (BinaryOperator::Accessor, 13, Associativity::Left),
+    (BinaryOperator::Exponent, 11, Associativity::Right),
+    (BinaryOperator::Multiply, 10, Associativity::Left),
+    (BinaryOperator::Divide, 10, Associativity::Left),
+    (BinaryOperator::Remainder, 10, Associativity::Left),
+    (BinaryOperator::Add, 9, Associativity::Left),
+    (BinaryOperator::Subtract, 9, Associativity::Left),
+    (BinaryOperator::ShiftLeft, 8, Associativity::Left),
+    (BinaryOperator::ShiftRight, 8, Associativity::Left),
+    (BinaryOperator::BitAnd, 7, Associativity::Left),
+    (BinaryOperator::BitOr, 6, Associativity::Left),
+    (BinaryOperator::Range, 5, Associativity::None),
+    (BinaryOperator::RangeInclusive, 5, Associativity::None),
+    (BinaryOperator::Equals, 4, Associativity::None),
+    (BinaryOperator::NotEquals, 4, Associativity::None),
+    (BinaryOperator::Greater, 4, Associativity::None),
+    (BinaryOperator::GreaterOrEquals, 4, Associativity::None),
+    (BinaryOperator::Less, 4, Associativity::None),
+    (BinaryOperator::LessOrEquals, 4, Associativity::None),
+    (BinaryOperator::And, 3, Associativity::Right),
+    (BinaryOperator::Or, 2, Associativity::Right),
+    (BinaryOperator::Pipe, 1, Associativity::Left),
+];
+
+/// Precedence of unary operators: they bind looser than accessor, so `-a.b` is `-(a.b)`, but
+/// tighter than any other binary operator, so `-a ^ b` is `(-a) ^ b`
+pub const UNARY_PRECEDENCE: i32 = 12;
+
+fn table_entry(operator: &BinaryOperator) -> &'static (BinaryOperator, i32, Associativity) {
+    PRECEDENCE_TABLE
+        .iter()
+        .find(|(o, _, _)| o == operator)
+        .expect("operator is missing from precedence table")
+}
+
 impl OperatorPrecedence for BinaryOperator {
     fn precedence(&self) -> i32 {
-        match self {
-            BinaryOperator::Add => 6,
-            BinaryOperator::Subtract => 6,
-            BinaryOperator::Multiply => 7,
-            BinaryOperator::Divide => 7,
-            BinaryOperator::Exponent => 8,
-            BinaryOperator::Remainder => 7,
-            BinaryOperator::Accessor => 9,
-            BinaryOperator::Equals => 4,
-            BinaryOperator::NotEquals => 4,
-            BinaryOperator::Greater => 4,
-            BinaryOperator::GreaterOrEquals => 4,
-            BinaryOperator::Less => 4,
-            BinaryOperator::LessOrEquals => 4,
-            BinaryOperator::And => 3,
-            BinaryOperator::Or => 2,
-        }
+        table_entry(self).1
     }
 }
 
 impl OperatorAssociativity for BinaryOperator {
     fn associativity(&self) -> Associativity {
-        match self {
-            BinaryOperator::Add => Associativity::Left,
-            BinaryOperator::Subtract => Associativity::Left,
-            BinaryOperator::Multiply => Associativity::Left,
-            BinaryOperator::Divide => Associativity::Left,
-            BinaryOperator::Exponent => Associativity::Right,
-            BinaryOperator::Remainder => Associativity::Left,
-            BinaryOperator::Accessor => Associativity::Left,
-            BinaryOperator::Equals => Associativity::None,
-            BinaryOperator::NotEquals => Associativity::None,
-            BinaryOperator::Greater => Associativity::None,
-            BinaryOperator::GreaterOrEquals => Associativity::None,
-            BinaryOperator::Less => Associativity::None,
-            BinaryOperator::LessOrEquals => Associativity::None,
-            BinaryOperator::And => Associativity::Right,
-            BinaryOperator::Or => Associativity::Right,
+        table_entry(self).2.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ast::{AstPair, Block, Expression, Operand, Statement, UnaryOperator};
+    use crate::ast::ast_parser::parse_block;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    fn parse(source: &str) -> Result<AstPair<Expression>, String> {
+        let block: AstPair<Block> = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .map_err(|e| e.message())?;
+        match &block.1.statements[..] {
+            [AstPair(_, Statement::Expression(e))] => Ok(e.clone()),
+            s => Err(format!("expected single expression, found {:?}", s)),
+        }
+    }
+
+    /// Print expression with every operation in parentheses, showing the tree structure
+    fn print_grouped(expression: &AstPair<Expression>) -> String {
+        match &expression.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::Identifier(i) => i.1 .0.clone(),
+                Operand::Integer(i) => i.to_string(),
+                o => format!("{:?}", o),
+            },
+            Expression::Group(e) => print_grouped(e),
+            Expression::Unary { operator, operand } => {
+                format!("({}{})", operator.1, print_grouped(operand))
+            }
+            Expression::Binary {
+                left_operand,
+                operator,
+                right_operand,
+            } => format!(
+                "({} {} {})",
+                print_grouped(left_operand),
+                operator.1,
+                print_grouped(right_operand)
+            ),
+            e => format!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn precedence_table_is_complete() {
+        for (i, (o, _, _)) in PRECEDENCE_TABLE.iter().enumerate() {
+            assert!(!PRECEDENCE_TABLE[..i].iter().any(|(p, _, _)| p == o));
+        }
+        assert!(PRECEDENCE_TABLE.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn operator_pairs_follow_table() {
+        for (o1, p1, a1) in &PRECEDENCE_TABLE {
+            for (o2, p2, a2) in &PRECEDENCE_TABLE {
+                let source = format!("a {} b {} c", o1, o2);
+                let expected = if p1 == p2 && a1 == &Associativity::None && a2 == a1 {
+                    Err(format!("operators {} and {} cannot be chained", o2, o1))
+                } else if p1 > p2 || (p1 == p2 && a1 == &Associativity::Left) {
+                    Ok(format!("((a {} b) {} c)", o1, o2))
+                } else {
+                    Ok(format!("(a {} (b {} c))", o1, o2))
+                };
+                assert_eq!(
+                    parse(&source).map(|e| print_grouped(&e)),
+                    expected,
+                    "{}",
+                    source
+                );
+            }
+        }
+    }
+
+    enum Tree {
+        Leaf(String),
+        Unary(UnaryOperator, Box<Tree>),
+        Binary(BinaryOperator, Box<Tree>, Box<Tree>),
+    }
+
+    impl Tree {
+        /// Random expression tree using a linear congruential generator, for reproducibility
+        fn generate(seed: &mut u64, depth: usize) -> Tree {
+            let mut next = |n: usize| {
+                *seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (*seed >> 33) as usize % n
+            };
+            let unary = [
+                UnaryOperator::Minus,
+                UnaryOperator::Not,
+                UnaryOperator::Spread,
+                UnaryOperator::BitNot,
+            ];
+            match next(if depth == 0 { 1 } else { 6 }) {
+                0 => Tree::Leaf(["a", "b", "x", "1"][next(4)].to_string()),
+                1 => {
+                    let op = unary[next(unary.len())].clone();
+                    Tree::Unary(op, Box::new(Tree::generate(seed, depth - 1)))
+                }
+                _ => {
+                    let op = PRECEDENCE_TABLE[next(PRECEDENCE_TABLE.len())].0.clone();
+                    let l = Tree::generate(seed, depth - 1);
+                    let r = Tree::generate(seed, depth - 1);
+                    Tree::Binary(op, Box::new(l), Box::new(r))
+                }
+            }
+        }
+
+        fn grouped(&self) -> String {
+            match self {
+                Tree::Leaf(l) => l.clone(),
+                Tree::Unary(o, t) => format!("({}{})", o, t.grouped()),
+                Tree::Binary(o, l, r) => format!("({} {} {})", l.grouped(), o, r.grouped()),
+            }
+        }
+
+        /// Print with parentheses only where precedence and associativity require them
+        fn minimal(&self) -> String {
+            match self {
+                Tree::Leaf(l) => l.clone(),
+                // unary operand is either an operand or a parenthesized expression
+                Tree::Unary(o, t) => match **t {
+                    Tree::Leaf(_) => format!("{}{}", o, t.minimal()),
+                    _ => format!("{}({})", o, t.minimal()),
+                },
+                Tree::Binary(o, l, r) => {
+                    let side = |t: &Tree, assoc: Associativity| match t {
+                        Tree::Unary(..) if o.precedence() > UNARY_PRECEDENCE => {
+                            format!("({})", t.minimal())
+                        }
+                        Tree::Binary(c, ..)
+                            if c.precedence() < o.precedence()
+                                || (c.precedence() == o.precedence()
+                                    && c.associativity() != assoc) =>
+                        {
+                            format!("({})", t.minimal())
+                        }
+                        _ => t.minimal(),
+                    };
+                    format!(
+                        "{} {} {}",
+                        side(l, Associativity::Left),
+                        o,
+                        side(r, Associativity::Right)
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unary_operator_precedence() {
+        let grouped = |source: &str| parse(source).map(|e| print_grouped(&e)).unwrap();
+        assert_eq!(grouped("-a.b"), "(-(a . b))");
+        assert_eq!(grouped("!a.b.c && b"), "((!((a . b) . c)) && b)");
+        assert_eq!(grouped("-a ^ b"), "((-a) ^ b)");
+        assert_eq!(grouped("a ^ -b.c"), "(a ^ (-(b . c)))");
+        assert_eq!(grouped("(-a).b"), "((-a) . b)");
+        assert_eq!(grouped("a * -b + 1"), "((a * (-b)) + 1)");
+    }
+
+    #[test]
+    fn random_expressions_round_trip() {
+        let mut seed = 42;
+        for _ in 0..1000 {
+            let tree = Tree::generate(&mut seed, 4);
+            let source = tree.minimal();
+            assert_eq!(
+                parse(&source).map(|e| print_grouped(&e)),
+                Ok(tree.grouped()),
+                "{}",
+                source
+            );
         }
     }
 }
@@ -1,4 +1,6 @@
 pub mod ast;
 pub mod ast_parser;
 pub mod expression;
+pub mod printer;
 pub mod util;
+pub mod visit;
@@ -0,0 +1,665 @@
+use crate::ast::ast::{
+    Assignee, AstPair, BinaryOperator, Block, DestructureItem, DestructureList, Expression,
+    FunctionInit, MatchClause, Operand, PatternItem, Statement,
+};
+use crate::escape::escape;
+
+const INDENT: &str = "    ";
+
+/// Print AST back to nois source. Parentheses are printed only for `Expression::Group`, so
+/// parsing the output gives the same AST.
+pub fn print_block(block: &Block) -> String {
+    print_statements(block, 0)
+}
+
+pub fn print_expression(expression: &Expression) -> String {
+    expression_to_string(expression, 0)
+}
+
+fn print_statements(block: &Block, indent: usize) -> String {
+    block
+        .statements
+        .iter()
+        .map(|s| {
+            format!(
+                "{}{}",
+                INDENT.repeat(indent),
+                statement_to_string(&s.1, indent)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn statement_to_string(statement: &Statement, indent: usize) -> String {
+    match statement {
+        Statement::Return(None) => "return".to_string(),
+        Statement::Import(path) => format!("import {}", string_to_string(&path.1)),
+        Statement::Return(Some(e)) => format!("return {}", expression_to_string(&e.1, indent)),
+        Statement::Assignment {
+            assignee,
+            expression,
+        } => format!(
+            "{} = {}",
+            assignee_to_string(&assignee.1),
+            expression_to_string(&expression.1, indent)
+        ),
+        Statement::Expression(e) => expression_to_string(&e.1, indent),
+    }
+}
+
+fn expression_to_string(expression: &Expression, indent: usize) -> String {
+    match expression {
+        Expression::Operand(o) => operand_to_string(&o.1, indent),
+        Expression::Unary { operator, operand } => {
+            format!("{}{}", operator.1, expression_to_string(&operand.1, indent))
+        }
+        Expression::Binary {
+            left_operand,
+            operator,
+            right_operand,
+        } => {
+            let left = expression_to_string(&left_operand.1, indent);
+            let right = expression_to_string(&right_operand.1, indent);
+            match operator.1 {
+                BinaryOperator::Accessor => format!("{}.{}", left, right),
+                BinaryOperator::Range | BinaryOperator::RangeInclusive => {
+                    format!("{}{}{}", left, operator.1, right)
+                }
+                _ => format!("{} {} {}", left, operator.1, right),
+            }
+        }
+        Expression::MatchExpression {
+            condition,
+            match_clauses,
+        } => {
+            let clauses = match_clauses
+                .iter()
+                .map(|c| match_clause_to_string(&c.1, indent + 1))
+                .collect::<Vec<_>>()
+                .join(",\n");
+            format!(
+                "match {} {{\n{}\n{}}}",
+                expression_to_string(&condition.1, indent),
+                clauses,
+                INDENT.repeat(indent)
+            )
+        }
+        Expression::IfExpression {
+            condition,
+            then_block,
+            else_block,
+        } => {
+            let mut res = format!(
+                "if {} {}",
+                expression_to_string(&condition.1, indent),
+                block_to_string(&then_block.1, indent)
+            );
+            match else_block.as_ref().map(|b| &b.1.statements[..]) {
+                Some([s]) if matches!(&s.1, Statement::Expression(e) if matches!(e.1, Expression::IfExpression { .. })) => {
+                    res.push_str(&format!(" else {}", statement_to_string(&s.1, indent)))
+                }
+                Some(_) => res.push_str(&format!(
+                    " else {}",
+                    block_to_string(&else_block.as_ref().unwrap().1, indent)
+                )),
+                None => {}
+            }
+            res
+        }
+        Expression::Group(e) => format!("({})", expression_to_string(&e.1, indent)),
+    }
+}
+
+fn block_to_string(block: &Block, indent: usize) -> String {
+    if block.statements.is_empty() {
+        return "{}".to_string();
+    }
+    format!(
+        "{{\n{}\n{}}}",
+        print_statements(block, indent + 1),
+        INDENT.repeat(indent)
+    )
+}
+
+fn match_clause_to_string(clause: &MatchClause, indent: usize) -> String {
+    let statements = clause
+        .block
+        .1
+        .statements
+        .iter()
+        .map(|s| statement_to_string(&s.1, indent))
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}", INDENT.repeat(indent)));
+    format!(
+        "{}{} => {}",
+        INDENT.repeat(indent),
+        pattern_to_string(&clause.pattern.1),
+        statements
+    )
+}
+
+fn operand_to_string(operand: &Operand, indent: usize) -> String {
+    match operand {
+        Operand::Hole => "_".to_string(),
+        Operand::Integer(i) => i.to_string(),
+        // debug format keeps the fraction of whole numbers, e.g. `1.0`
+        Operand::Float(f) => format!("{:?}", f),
+        Operand::Boolean(b) => boolean_to_string(*b),
+        Operand::StructDefinition { fields } => {
+            format!("#{{{}}}", join(fields, |f| f.1.to_string()))
+        }
+        Operand::EnumDefinition { values } => format!("|{{{}}}", join(values, |v| v.1.to_string())),
+        Operand::ListInit { items } => {
+            format!("[{}]", join(items, |i| expression_to_string(&i.1, indent)))
+        }
+        Operand::FunctionInit(fi) => function_init_to_string(fi, indent),
+        Operand::FunctionCall(fc) => {
+            let arguments = fc
+                .arguments
+                .iter()
+                .map(|a| expression_to_string(&a.1, indent))
+                .chain(fc.named_arguments.iter().map(|(n, a)| {
+                    format!("{}: {}", n.1, expression_to_string(&a.1, indent))
+                }))
+                .collect::<Vec<_>>();
+            format!("{}({})", fc.identifier.1, arguments.join(", "))
+        }
+        Operand::String(s) => string_to_string(s),
+        Operand::Identifier(i) => i.1.to_string(),
+        Operand::ValueType(vt) => vt.to_string(),
+    }
+}
+
+fn function_init_to_string(function_init: &FunctionInit, indent: usize) -> String {
+    let parameters = join(&function_init.parameters, |p| assignee_to_string(&p.1));
+    format!(
+        "({}) {}",
+        parameters,
+        block_to_string(&function_init.block.1, indent)
+    )
+}
+
+fn assignee_to_string(assignee: &Assignee) -> String {
+    match assignee {
+        Assignee::Hole => "_".to_string(),
+        Assignee::DestructureList(dl) => destructure_list_to_string(dl),
+        Assignee::Identifier {
+            identifier,
+            annotation: Some(a),
+        } => format!("{}: {}", identifier.1, a.1),
+        Assignee::Identifier { identifier, .. } => identifier.1.to_string(),
+    }
+}
+
+fn destructure_list_to_string(destructure_list: &DestructureList) -> String {
+    let items = join(&destructure_list.0, |i| match &i.1 {
+        DestructureItem::Hole => "_".to_string(),
+        DestructureItem::Identifier { identifier, spread } => spread_to_string(identifier, *spread),
+        DestructureItem::List(dl) => destructure_list_to_string(dl),
+    });
+    format!("[{}]", items)
+}
+
+fn pattern_to_string(pattern: &PatternItem) -> String {
+    match pattern {
+        PatternItem::Hole => "_".to_string(),
+        PatternItem::Integer(i) => i.to_string(),
+        PatternItem::Float(f) => format!("{:?}", f),
+        PatternItem::Boolean(b) => boolean_to_string(*b),
+        PatternItem::String(s) => string_to_string(s),
+        PatternItem::Identifier { identifier, spread } => spread_to_string(identifier, *spread),
+        PatternItem::PatternList(items) => {
+            format!("[{}]", join(items, |i| pattern_to_string(&i.1)))
+        }
+        PatternItem::StringConcat {
+            prefix,
+            rest,
+            suffix,
+        } => {
+            let mut parts = vec![];
+            // at least one of the strings is required
+            if !prefix.is_empty() || suffix.is_empty() {
+                parts.push(string_to_string(prefix));
+            }
+            parts.push(rest.as_ref().map_or("_".to_string(), |r| r.1.to_string()));
+            if !suffix.is_empty() {
+                parts.push(string_to_string(suffix));
+            }
+            parts.join(" ++ ")
+        }
+        PatternItem::EnumVariant { name, value } => format!("{}.{}", name.1, value.1),
+    }
+}
+
+fn spread_to_string<T: ToString>(item: &AstPair<T>, spread: bool) -> String {
+    format!("{}{}", if spread { ".." } else { "" }, item.1.to_string())
+}
+
+fn boolean_to_string(b: bool) -> String {
+    if b { "True" } else { "False" }.to_string()
+}
+
+fn string_to_string(s: &str) -> String {
+    format!("'{}'", escape(s))
+}
+
+fn join<T, F>(items: &[AstPair<T>], f: F) -> String
+where
+    F: Fn(&AstPair<T>) -> String,
+{
+    items.iter().map(f).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use proptest::prelude::*;
+
+    use crate::ast::ast::{
+        AstContext, FunctionCall, Identifier, Span, TypeAnnotation, UnaryOperator, ValueType,
+    };
+    use crate::ast::ast_parser::parse_block;
+    use crate::ast::expression::{
+        Associativity, OperatorAssociativity, OperatorPrecedence, PRECEDENCE_TABLE,
+        UNARY_PRECEDENCE,
+    };
+    use crate::interpret::context::Context;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    fn pair<T>(t: T) -> AstPair<T> {
+        AstPair(Span { start: 0, end: 0 }, t)
+    }
+
+    fn operand(o: Operand) -> Expression {
+        Expression::Operand(Box::new(pair(o)))
+    }
+
+    fn group(e: Expression) -> Expression {
+        Expression::Group(Box::new(pair(e)))
+    }
+
+    fn leaf() -> impl Strategy<Value = Expression> {
+        prop_oneof![
+            (0..1000i128).prop_map(Operand::Integer),
+            (0..400u32).prop_map(|f| Operand::Float(f as f64 / 4.)),
+            any::<bool>().prop_map(Operand::Boolean),
+            "[a-z '\"\\\\\n]{0,4}".prop_map(Operand::String),
+            (0..8usize).prop_map(|i| Operand::Identifier(pair(Identifier(format!("v{}", i))))),
+            Just(Operand::Hole),
+            prop::sample::select(vec![
+                ValueType::Unit,
+                ValueType::Integer,
+                ValueType::Float,
+                ValueType::Char,
+                ValueType::Boolean,
+                ValueType::Function,
+            ])
+            .prop_map(Operand::ValueType),
+            prop::collection::vec(prop::sample::select(vec!["x", "y"]), 0..3).prop_map(|fs| {
+                Operand::StructDefinition {
+                    fields: fs.into_iter().map(|f| pair(Identifier::new(f))).collect(),
+                }
+            }),
+            prop::collection::vec(prop::sample::select(vec!["A", "B"]), 0..3).prop_map(|vs| {
+                Operand::EnumDefinition {
+                    values: vs.into_iter().map(|v| pair(Identifier::new(v))).collect(),
+                }
+            }),
+        ]
+        .prop_map(operand)
+    }
+
+    fn pattern() -> impl Strategy<Value = PatternItem> {
+        let leaf = prop_oneof![
+            (0..4i128).prop_map(PatternItem::Integer),
+            any::<bool>().prop_map(PatternItem::Boolean),
+            "[ab]{0,2}".prop_map(PatternItem::String),
+            Just(PatternItem::Hole),
+            Just(PatternItem::EnumVariant {
+                name: pair(Identifier::new("Color")),
+                value: pair(Identifier::new("Red")),
+            }),
+            ("[ab]{0,2}", prop::option::of(Just("r")), "[ab]{0,2}").prop_map(|(p, r, s)| {
+                PatternItem::StringConcat {
+                    prefix: p,
+                    rest: r.map(|r| pair(Identifier::new(r))),
+                    suffix: s,
+                }
+            }),
+        ];
+        leaf.prop_recursive(2, 6, 3, |inner| {
+            prop::collection::vec(inner.prop_map(pair), 0..3).prop_map(PatternItem::PatternList)
+        })
+    }
+
+    fn expression() -> impl Strategy<Value = Expression> {
+        leaf().prop_recursive(4, 32, 3, |inner| {
+            let boxed = || inner.clone().prop_map(|e| Box::new(pair(e)));
+            let items = || prop::collection::vec(inner.clone().prop_map(pair), 0..3);
+            prop_oneof![
+                items().prop_map(|items| operand(Operand::ListInit { items })),
+                (
+                    prop::sample::select(vec![
+                        UnaryOperator::Plus,
+                        UnaryOperator::Minus,
+                        UnaryOperator::Not,
+                        UnaryOperator::Spread,
+                        UnaryOperator::BitNot,
+                    ]),
+                    boxed()
+                )
+                    .prop_map(|(o, operand)| Expression::Unary {
+                        operator: Box::new(pair(o)),
+                        operand,
+                    }),
+                (
+                    prop::sample::select(
+                        PRECEDENCE_TABLE
+                            .iter()
+                            .map(|(o, _, _)| o.clone())
+                            .collect::<Vec<_>>()
+                    ),
+                    boxed(),
+                    boxed()
+                )
+                    .prop_map(|(o, left_operand, right_operand)| {
+                        Expression::Binary {
+                            left_operand,
+                            operator: Box::new(pair(o)),
+                            right_operand,
+                        }
+                    }),
+                (0..3usize, items()).prop_map(|(n, statements)| {
+                    operand(Operand::FunctionInit(FunctionInit {
+                        parameters: (0..n)
+                            .map(|i| {
+                                pair(Assignee::Identifier {
+                                    identifier: pair(Identifier(format!("p{}", i))),
+                                    annotation: None,
+                                })
+                            })
+                            .collect(),
+                        block: pair(Block {
+                            statements: statements
+                                .into_iter()
+                                .map(|e| pair(Statement::Expression(e)))
+                                .collect(),
+                        }),
+                    }))
+                }),
+                (0..8usize, items()).prop_map(|(i, arguments)| {
+                    operand(Operand::FunctionCall(FunctionCall {
+                        identifier: pair(Identifier(format!("v{}", i))),
+                        arguments,
+                        named_arguments: vec![],
+                    }))
+                }),
+                (
+                    boxed(),
+                    prop::collection::vec((pattern(), inner.clone()), 1..3)
+                )
+                    .prop_map(|(condition, clauses)| Expression::MatchExpression {
+                        condition,
+                        match_clauses: clauses
+                            .into_iter()
+                            .map(|(p, e)| {
+                                pair(MatchClause {
+                                    pattern: pair(p),
+                                    block: pair(Block {
+                                        statements: vec![pair(Statement::Expression(pair(e)))],
+                                    }),
+                                })
+                            })
+                            .collect(),
+                    }),
+                // block functions are not allowed in if condition
+                (leaf(), inner.clone(), prop::option::of(inner.clone())).prop_map(
+                    |(condition, then, els)| {
+                        let block = |e| {
+                            pair(Block {
+                                statements: vec![pair(Statement::Expression(pair(e)))],
+                            })
+                        };
+                        Expression::IfExpression {
+                            condition: Box::new(pair(condition)),
+                            then_block: block(then),
+                            else_block: els.map(block),
+                        }
+                    }
+                ),
+                inner.clone().prop_map(group),
+            ]
+        })
+    }
+
+    /// Generated program: statements with an optional assignment type annotation
+    fn program() -> impl Strategy<Value = Block> {
+        let annotation = prop::option::of(prop::sample::select(vec![
+            TypeAnnotation::Type(ValueType::Integer),
+            TypeAnnotation::Type(ValueType::Boolean),
+            TypeAnnotation::List(Box::new(pair(TypeAnnotation::Type(ValueType::Char)))),
+        ]));
+        prop::collection::vec((any::<bool>(), annotation, expression()), 1..6).prop_map(
+            |statements| {
+                let mut defined = 0;
+                let statements = statements
+                    .into_iter()
+                    .map(|(assign, annotation, e)| {
+                        let e = pair(canonical(e, defined));
+                        if !assign {
+                            return pair(Statement::Expression(pair(statement_start(e.1))));
+                        }
+                        let identifier = pair(Identifier(format!("v{}", defined)));
+                        defined += 1;
+                        pair(Statement::Assignment {
+                            assignee: pair(Assignee::Identifier {
+                                identifier,
+                                annotation: annotation.map(pair),
+                            }),
+                            expression: e,
+                        })
+                    })
+                    .collect();
+                Block { statements }
+            },
+        )
+    }
+
+    /// Make generated expression one the parser could produce and one that terminates when
+    /// evaluated: operands are grouped according to operator precedence, identifiers refer only
+    /// to `defined` earlier definitions, and function bodies refer to none, so that no
+    /// definition can refer to itself
+    fn canonical(e: Expression, defined: usize) -> Expression {
+        let fix = |e: Box<AstPair<Expression>>, defined| Box::new(pair(canonical(e.1, defined)));
+        match e {
+            Expression::Operand(o) => match o.1 {
+                Operand::Identifier(i) if defined == 0 => {
+                    operand(Operand::Integer(index(&i.1) as i128))
+                }
+                Operand::Identifier(i) => operand(Operand::Identifier(rebind(i, defined))),
+                Operand::ListInit { items } => operand(Operand::ListInit {
+                    items: items
+                        .into_iter()
+                        .map(|i| pair(canonical(i.1, defined)))
+                        .collect(),
+                }),
+                Operand::FunctionCall(fc) => {
+                    let arguments = fc
+                        .arguments
+                        .into_iter()
+                        .map(|a| pair(canonical(a.1, defined)))
+                        .collect();
+                    if defined == 0 {
+                        operand(Operand::ListInit { items: arguments })
+                    } else {
+                        operand(Operand::FunctionCall(FunctionCall {
+                            identifier: rebind(fc.identifier, defined),
+                            arguments,
+                            named_arguments: vec![],
+                        }))
+                    }
+                }
+                Operand::FunctionInit(fi) => operand(Operand::FunctionInit(FunctionInit {
+                    parameters: fi.parameters,
+                    block: pair(Block {
+                        statements: fi
+                            .block
+                            .1
+                            .statements
+                            .into_iter()
+                            .map(|s| match s.1 {
+                                Statement::Expression(e) => pair(Statement::Expression(pair(
+                                    statement_start(canonical(e.1, 0)),
+                                ))),
+                                s => pair(s),
+                            })
+                            .collect(),
+                    }),
+                })),
+                o => operand(o),
+            },
+            Expression::Unary { operator, operand } => {
+                let operand = canonical(operand.1, defined);
+                let operand = match operand {
+                    Expression::Unary { .. } | Expression::Binary { .. } => group(operand),
+                    e => e,
+                };
+                Expression::Unary {
+                    operator,
+                    operand: Box::new(pair(operand)),
+                }
+            }
+            Expression::Binary {
+                left_operand,
+                operator,
+                right_operand,
+            } => {
+                let left = canonical(left_operand.1, defined);
+                let right = canonical(right_operand.1, defined);
+                let right = match (&operator.1, &right) {
+                    (BinaryOperator::Accessor, Expression::Operand(o))
+                        if matches!(o.1, Operand::Identifier(_) | Operand::FunctionCall(_)) =>
+                    {
+                        right
+                    }
+                    // only fields and methods can be accessed
+                    (BinaryOperator::Accessor, _) => {
+                        operand(Operand::Identifier(pair(Identifier::new("x"))))
+                    }
+                    _ => right,
+                };
+                let precedence = operator.1.precedence();
+                let associativity = operator.1.associativity();
+                let group_if = |e: Expression, side: Associativity| {
+                    let p = match &e {
+                        Expression::Binary { operator, .. } => operator.1.precedence(),
+                        Expression::Unary { .. } => UNARY_PRECEDENCE,
+                        _ => return e,
+                    };
+                    if p < precedence || (p == precedence && associativity != side) {
+                        group(e)
+                    } else {
+                        e
+                    }
+                };
+                Expression::Binary {
+                    left_operand: Box::new(pair(group_if(left, Associativity::Left))),
+                    operator,
+                    right_operand: Box::new(pair(group_if(right, Associativity::Right))),
+                }
+            }
+            Expression::MatchExpression {
+                condition,
+                match_clauses,
+            } => Expression::MatchExpression {
+                condition: fix(condition, defined),
+                match_clauses: match_clauses
+                    .into_iter()
+                    .map(|c| {
+                        let statements =
+                            c.1.block
+                                .1
+                                .statements
+                                .into_iter()
+                                .map(|s| match s.1 {
+                                    Statement::Expression(e) => pair(Statement::Expression(pair(
+                                        statement_start(canonical(e.1, defined)),
+                                    ))),
+                                    s => pair(s),
+                                })
+                                .collect();
+                        pair(MatchClause {
+                            pattern: c.1.pattern,
+                            block: pair(Block { statements }),
+                        })
+                    })
+                    .collect(),
+            },
+            Expression::IfExpression {
+                condition,
+                then_block,
+                else_block,
+            } => Expression::IfExpression {
+                condition: fix(condition, defined),
+                then_block: canonical_block(then_block, defined),
+                else_block: else_block.map(|b| canonical_block(b, defined)),
+            },
+            Expression::Group(e) => Expression::Group(fix(e, defined)),
+        }
+    }
+
+    fn canonical_block(block: AstPair<Block>, defined: usize) -> AstPair<Block> {
+        pair(Block {
+            statements: block
+                .1
+                .statements
+                .into_iter()
+                .map(|s| match s.1 {
+                    Statement::Expression(e) => pair(Statement::Expression(pair(statement_start(
+                        canonical(e.1, defined),
+                    )))),
+                    s => pair(s),
+                })
+                .collect(),
+        })
+    }
+
+    fn index(identifier: &Identifier) -> usize {
+        identifier.0[1..].parse().unwrap()
+    }
+
+    fn rebind(identifier: AstPair<Identifier>, defined: usize) -> AstPair<Identifier> {
+        pair(Identifier(format!("v{}", index(&identifier.1) % defined)))
+    }
+
+    /// Statement starting with `+`, `-` or `..` would continue the previous statement as a binary
+    /// expression
+    fn statement_start(e: Expression) -> Expression {
+        if matches!(print_expression(&e).chars().next(), Some('+' | '-' | '.')) {
+            group(e)
+        } else {
+            e
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn parse_printed_program(block in program()) {
+            let source = print_block(&block);
+            let parsed = NoisParser::parse_program(&source)
+                .and_then(|p| parse_block(&p))
+                .map_err(|e| TestCaseError::fail(format!("{}\n{}", source, e)))?;
+            prop_assert_eq!(format!("{:?}", parsed.1), format!("{:?}", block), "{}", source);
+
+            let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source.as_str())));
+            let result = catch_unwind(AssertUnwindSafe(|| parsed.eval(&mut ctx_cell.borrow_mut(), true)));
+            prop_assert!(result.is_ok(), "evaluation panicked:\n{}", source);
+        }
+    }
+}
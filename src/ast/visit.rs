@@ -0,0 +1,285 @@
+use crate::ast::ast::{
+    Assignee, AstPair, Block, DestructureItem, DestructureList, Expression, FunctionInit, Operand,
+    PatternItem, Span, Statement, TypeAnnotation,
+};
+
+/// Visit every statement of the block, including statements of nested function and match
+/// clause blocks, in source order
+pub fn walk_statements<F>(block: &AstPair<Block>, f: &mut F)
+where
+    F: FnMut(&AstPair<Statement>),
+{
+    walk_block(block, f, &mut |_| {});
+}
+
+/// Visit every expression of the block, outer expressions first
+pub fn walk_expressions<F>(block: &AstPair<Block>, f: &mut F)
+where
+    F: FnMut(&AstPair<Expression>),
+{
+    walk_block(block, &mut |_| {}, f);
+}
+
+fn walk_block<S, E>(block: &AstPair<Block>, s: &mut S, e: &mut E)
+where
+    S: FnMut(&AstPair<Statement>),
+    E: FnMut(&AstPair<Expression>),
+{
+    for statement in &block.1.statements {
+        s(statement);
+        match &statement.1 {
+            Statement::Return(Some(exp)) | Statement::Expression(exp) => walk_expression(exp, s, e),
+            Statement::Assignment { expression, .. } => walk_expression(expression, s, e),
+            Statement::Return(None) | Statement::Import(_) => {}
+        }
+    }
+}
+
+fn walk_expression<S, E>(expression: &AstPair<Expression>, s: &mut S, e: &mut E)
+where
+    S: FnMut(&AstPair<Statement>),
+    E: FnMut(&AstPair<Expression>),
+{
+    e(expression);
+    match &expression.1 {
+        Expression::Operand(o) => match &o.1 {
+            Operand::FunctionInit(fi) => walk_block(&fi.block, s, e),
+            Operand::FunctionCall(fc) => {
+                fc.arguments.iter().for_each(|a| walk_expression(a, s, e));
+                fc.named_arguments
+                    .iter()
+                    .for_each(|(_, a)| walk_expression(a, s, e));
+            }
+            Operand::ListInit { items } => items.iter().for_each(|i| walk_expression(i, s, e)),
+            _ => {}
+        },
+        Expression::Unary { operand, .. } | Expression::Group(operand) => {
+            walk_expression(operand, s, e)
+        }
+        Expression::Binary {
+            left_operand,
+            right_operand,
+            ..
+        } => {
+            walk_expression(left_operand, s, e);
+            walk_expression(right_operand, s, e);
+        }
+        Expression::MatchExpression {
+            condition,
+            match_clauses,
+        } => {
+            walk_expression(condition, s, e);
+            match_clauses
+                .iter()
+                .for_each(|c| walk_block(&c.1.block, s, e));
+        }
+        Expression::IfExpression {
+            condition,
+            then_block,
+            else_block,
+        } => {
+            walk_expression(condition, s, e);
+            walk_block(then_block, s, e);
+            if let Some(b) = else_block {
+                walk_block(b, s, e);
+            }
+        }
+    }
+}
+
+/// Move every span of the block by the offset, e.g. to place spans of an imported file after the
+/// sources loaded before it
+pub fn shift_spans(block: &mut AstPair<Block>, offset: usize) {
+    shift_block(block, offset);
+}
+
+fn shift(span: &mut Span, offset: usize) {
+    span.start += offset;
+    span.end += offset;
+}
+
+fn shift_block(block: &mut AstPair<Block>, offset: usize) {
+    shift(&mut block.0, offset);
+    for statement in &mut block.1.statements {
+        shift(&mut statement.0, offset);
+        match &mut statement.1 {
+            Statement::Return(Some(exp)) | Statement::Expression(exp) => {
+                shift_expression(exp, offset)
+            }
+            Statement::Assignment {
+                assignee,
+                expression,
+            } => {
+                shift_assignee(assignee, offset);
+                shift_expression(expression, offset);
+            }
+            Statement::Import(path) => shift(&mut path.0, offset),
+            Statement::Return(None) => {}
+        }
+    }
+}
+
+fn shift_expression(expression: &mut AstPair<Expression>, offset: usize) {
+    shift(&mut expression.0, offset);
+    match &mut expression.1 {
+        Expression::Operand(o) => shift_operand(o, offset),
+        Expression::Unary { operator, operand } => {
+            shift(&mut operator.0, offset);
+            shift_expression(operand, offset);
+        }
+        Expression::Binary {
+            left_operand,
+            operator,
+            right_operand,
+        } => {
+            shift_expression(left_operand, offset);
+            shift(&mut operator.0, offset);
+            shift_expression(right_operand, offset);
+        }
+        Expression::MatchExpression {
+            condition,
+            match_clauses,
+        } => {
+            shift_expression(condition, offset);
+            for clause in match_clauses {
+                shift(&mut clause.0, offset);
+                shift_pattern(&mut clause.1.pattern, offset);
+                shift_block(&mut clause.1.block, offset);
+            }
+        }
+        Expression::IfExpression {
+            condition,
+            then_block,
+            else_block,
+        } => {
+            shift_expression(condition, offset);
+            shift_block(then_block, offset);
+            if let Some(b) = else_block {
+                shift_block(b, offset);
+            }
+        }
+        Expression::Group(e) => shift_expression(e, offset),
+    }
+}
+
+fn shift_operand(operand: &mut AstPair<Operand>, offset: usize) {
+    shift(&mut operand.0, offset);
+    match &mut operand.1 {
+        Operand::StructDefinition { fields: ids } | Operand::EnumDefinition { values: ids } => {
+            ids.iter_mut().for_each(|i| shift(&mut i.0, offset))
+        }
+        Operand::ListInit { items } => items.iter_mut().for_each(|i| shift_expression(i, offset)),
+        Operand::FunctionInit(fi) => shift_function(fi, offset),
+        Operand::FunctionCall(fc) => {
+            shift(&mut fc.identifier.0, offset);
+            fc.arguments
+                .iter_mut()
+                .for_each(|a| shift_expression(a, offset));
+            for (name, a) in &mut fc.named_arguments {
+                shift(&mut name.0, offset);
+                shift_expression(a, offset);
+            }
+        }
+        Operand::Identifier(i) => shift(&mut i.0, offset),
+        Operand::Hole
+        | Operand::Integer(_)
+        | Operand::Float(_)
+        | Operand::Boolean(_)
+        | Operand::String(_)
+        | Operand::ValueType(_) => {}
+    }
+}
+
+fn shift_function(function: &mut FunctionInit, offset: usize) {
+    function
+        .parameters
+        .iter_mut()
+        .for_each(|p| shift_assignee(p, offset));
+    shift_block(&mut function.block, offset);
+}
+
+fn shift_assignee(assignee: &mut AstPair<Assignee>, offset: usize) {
+    shift(&mut assignee.0, offset);
+    match &mut assignee.1 {
+        Assignee::Hole => {}
+        Assignee::DestructureList(list) => shift_destructure(list, offset),
+        Assignee::Identifier {
+            identifier,
+            annotation,
+        } => {
+            shift(&mut identifier.0, offset);
+            if let Some(a) = annotation {
+                shift_annotation(a, offset);
+            }
+        }
+    }
+}
+
+fn shift_annotation(annotation: &mut AstPair<TypeAnnotation>, offset: usize) {
+    shift(&mut annotation.0, offset);
+    if let TypeAnnotation::List(item) = &mut annotation.1 {
+        shift_annotation(item, offset);
+    }
+}
+
+fn shift_destructure(list: &mut DestructureList, offset: usize) {
+    for item in &mut list.0 {
+        shift(&mut item.0, offset);
+        match &mut item.1 {
+            DestructureItem::Hole => {}
+            DestructureItem::Identifier { identifier, .. } => shift(&mut identifier.0, offset),
+            DestructureItem::List(l) => shift_destructure(l, offset),
+        }
+    }
+}
+
+fn shift_pattern(pattern: &mut AstPair<PatternItem>, offset: usize) {
+    shift(&mut pattern.0, offset);
+    match &mut pattern.1 {
+        PatternItem::Identifier { identifier, .. } => shift(&mut identifier.0, offset),
+        PatternItem::PatternList(items) => items.iter_mut().for_each(|i| shift_pattern(i, offset)),
+        PatternItem::StringConcat { rest, .. } => {
+            if let Some(r) = rest {
+                shift(&mut r.0, offset);
+            }
+        }
+        PatternItem::EnumVariant { name, value } => {
+            shift(&mut name.0, offset);
+            shift(&mut value.0, offset);
+        }
+        PatternItem::Hole
+        | PatternItem::Integer(_)
+        | PatternItem::Float(_)
+        | PatternItem::Boolean(_)
+        | PatternItem::String(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ast_parser::parse_block;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    #[test]
+    fn shift_spans_as_if_parsed_at_offset() {
+        let source = r#"import 'a'
+[a, ..b] = [1, 2]
+l: [[C]] = []
+f = (x: I, _) -> match x {
+  Color.Red => #{a, b},
+  [h, ..t] => 'a' ++ rest,
+  _ => if x { g(x, n: -x) } else { (|{Red}) }
+}"#;
+        let parse = |s: &str| {
+            NoisParser::parse_program(s)
+                .and_then(|p| parse_block(&p))
+                .unwrap()
+        };
+        let mut shifted = parse(source);
+        shift_spans(&mut shifted, 3);
+        // block of the padded source starts with the padding
+        assert_eq!(shifted.1, parse(&format!("\n\n\n{}", source)).1);
+    }
+}
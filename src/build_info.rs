@@ -0,0 +1,58 @@
+use std::cmp::Ordering;
+
+/// Interpreter version, e.g. `0.1.0`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short hash of the commit the interpreter is built from, `unknown` if built outside of git
+pub const GIT_HASH: &str = env!("NOIS_GIT_HASH");
+
+/// Version with commit hash, e.g. `0.1.0 (3c01c63)`
+pub const LONG_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("NOIS_GIT_HASH"), ")");
+
+/// Target triple, e.g. `x86_64-unknown-linux-gnu`
+pub const TARGET: &str = env!("NOIS_TARGET");
+
+/// Cargo profile, `debug` or `release`
+pub const PROFILE: &str = env!("NOIS_PROFILE");
+
+/// Cargo features of the interpreter with whether they are enabled
+pub const FEATURES: &[(&str, bool)] = &[("desktop", cfg!(feature = "desktop"))];
+
+/// Whether interpreter version is at least the required one, e.g. `0.3`.
+/// Missing version components are treated as zeros.
+pub fn satisfies(required: &str) -> bool {
+    compare_versions(VERSION, required) != Ordering::Less
+}
+
+/// Compare dot separated versions component by component, e.g. `0.10 > 0.9`
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let components = |v: &str| {
+        v.split('.')
+            .map(|c| c.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (components(a), components(b));
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+pub fn feature_enabled(name: &str) -> bool {
+    FEATURES.iter().any(|(f, enabled)| *f == name && *enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare() {
+        assert_eq!(compare_versions("0.10", "0.9"), Ordering::Greater);
+        assert_eq!(compare_versions("0.1", "0.1.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.1"), Ordering::Less);
+        assert!(satisfies(VERSION));
+        assert!(satisfies("0"));
+        assert!(!satisfies("99"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::ast::ast::Statement;
+use crate::ast::ast_parser::parse_block;
+use crate::ast::visit::walk_statements;
+use crate::interpret::module::resolve;
+use crate::parser::NoisParser;
+
+/// Produce a single self-contained source file from the entry file.
+/// Imported files are inlined before the files importing them, each one once, and import
+/// statements are commented out. Every source is validated by parsing it before being emitted.
+pub fn bundle(entry: &Path) -> Result<String, String> {
+    let mut bundler = Bundler {
+        root: entry
+            .canonicalize()
+            .ok()
+            .and_then(|e| e.parent().map(Path::to_path_buf))
+            .unwrap_or_default(),
+        importing: vec![],
+        inlined: HashSet::new(),
+        output: String::new(),
+    };
+    let source = bundler.inline(entry)?;
+    Ok(format!(
+        "// bundled from {}\n{}{}",
+        entry.file_name().unwrap_or_default().to_string_lossy(),
+        bundler.output,
+        source
+    ))
+}
+
+struct Bundler {
+    /// Directory of the entry file, inlined files are named relative to it
+    root: PathBuf,
+    importing: Vec<PathBuf>,
+    inlined: HashSet<PathBuf>,
+    /// Sources of inlined imports
+    output: String,
+}
+
+impl Bundler {
+    /// Inline imports of the file, returning its source with import statements commented out
+    fn inline(&mut self, file: &Path) -> Result<String, String> {
+        let source = read_to_string(file)
+            .map_err(|e| format!("Unable to read file {}: {}", file.display(), e))?;
+        let block = NoisParser::parse_program(source.as_str())
+            .and_then(|parsed| parse_block(&parsed))
+            .map_err(|e| e.with_path(&file.display().to_string()).to_string())?;
+        let mut imports = vec![];
+        walk_statements(&block, &mut |s| {
+            if let Statement::Import(path) = &s.1 {
+                imports.push((s.0.clone(), path.1.clone()));
+            }
+        });
+
+        let canonical = file.canonicalize().map_err(|e| e.to_string())?;
+        self.importing.push(canonical);
+        for (_, path) in &imports {
+            let imported = resolve(path, Some(file))
+                .map_err(|e| format!("unable to import '{}': {}", path, e))?;
+            if let Some(start) = self.importing.iter().position(|f| f == &imported) {
+                let cycle = self.importing[start..]
+                    .iter()
+                    .chain([&imported])
+                    .map(|f| f.file_name().unwrap_or_default().to_string_lossy())
+                    .collect::<Vec<_>>();
+                return Err(format!("circular import: {}", cycle.join(" -> ")));
+            }
+            if self.inlined.insert(imported.clone()) {
+                let inlined = self.inline(&imported)?;
+                let name = imported.strip_prefix(&self.root).unwrap_or(&imported);
+                self.output
+                    .push_str(&format!("// import '{}' from {}\n", path, name.display()));
+                self.output.push_str(&inlined);
+                if !inlined.ends_with('\n') {
+                    self.output.push('\n');
+                }
+            }
+        }
+        self.importing.pop();
+
+        let mut source = source;
+        // statement is the only one on its line, unless it is followed by the closing brace
+        for (span, _) in imports.iter().rev() {
+            let rest = source[span.end..].lines().next().unwrap_or_default().trim();
+            if rest.is_empty() || rest.starts_with("//") {
+                source.insert_str(span.start, "// ");
+            } else {
+                source.replace_range(span.start..span.end, &" ".repeat(span.end - span.start));
+            }
+        }
+        Ok(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn bundle_single_file() {
+        let entry = temp_dir().join("nois_bundle_single_file.no");
+        write(&entry, "main = -> println('hi')\n").unwrap();
+        assert_eq!(
+            bundle(&entry),
+            Ok("// bundled from nois_bundle_single_file.no\nmain = -> println('hi')\n".to_string())
+        );
+    }
+
+    #[test]
+    fn bundle_imports() {
+        let dir = temp_dir().join("nois_bundle_imports");
+        create_dir_all(dir.join("lib")).unwrap();
+        write(dir.join("lib/util.no"), "twice = (a, f) -> f(a)").unwrap();
+        write(
+            dir.join("lib/math.no"),
+            "import 'util'\ndouble = a -> twice(a, b -> b + b)\n",
+        )
+        .unwrap();
+        write(
+            dir.join("main.no"),
+            "import 'lib/math'\nimport 'lib/util' // again\nmain = -> { import 'lib/util' }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            bundle(&dir.join("main.no")),
+            Ok(format!(
+                r#"// bundled from main.no
+// import 'util' from lib/util.no
+twice = (a, f) -> f(a)
+// import 'lib/math' from lib/math.no
+// import 'util'
+double = a -> twice(a, b -> b + b)
+// import 'lib/math'
+// import 'lib/util' // again
+main = -> {{{}}}
+"#,
+                " ".repeat(19)
+            ))
+        );
+
+        write(dir.join("lib/util.no"), "import 'math'").unwrap();
+        assert_eq!(
+            bundle(&dir.join("main.no")),
+            Err("circular import: math.no -> util.no -> math.no".to_string())
+        );
+    }
+
+    #[test]
+    fn bundle_invalid_syntax() {
+        let entry = temp_dir().join("nois_bundle_invalid_syntax.no");
+        write(&entry, "main = ->").unwrap();
+        assert!(bundle(&entry).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs::{read, write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::ast::ast::{AstPair, Block};
+use crate::ast::ast_parser::parse_block;
+use crate::error::Error;
+use crate::parser::NoisParser;
+
+pub const CACHE_EXTENSION: &str = "noc";
+
+/// Version of the serialized AST, bumped on every change of AST structure or of how source is
+/// parsed into it, so that caches written by development builds of the same interpreter version
+/// are not misread
+pub const AST_FORMAT: u32 = 10;
+
+/// Binary AST cache stored next to the source file.
+/// Cache is valid only for the same source content and interpreter version.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AstCache {
+    pub version: String,
+    pub format: u32,
+    pub hash: u64,
+    pub ast: AstPair<Block>,
+}
+
+impl AstCache {
+    pub fn new(source: &str, ast: AstPair<Block>) -> AstCache {
+        AstCache {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            format: AST_FORMAT,
+            hash: content_hash(source),
+            ast,
+        }
+    }
+
+    pub fn is_valid(&self, source: &str) -> bool {
+        self.version == env!("CARGO_PKG_VERSION")
+            && self.format == AST_FORMAT
+            && self.hash == content_hash(source)
+    }
+}
+
+pub fn cache_path(source_path: &Path) -> PathBuf {
+    source_path.with_extension(CACHE_EXTENSION)
+}
+
+/// FNV-1a hash, stable across platforms and compiler versions
+pub fn content_hash(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn load(source_path: &Path, source: &str) -> Option<AstPair<Block>> {
+    let path = cache_path(source_path);
+    let bytes = read(&path).ok()?;
+    match bincode::deserialize::<AstCache>(&bytes) {
+        Ok(c) if c.is_valid(source) => {
+            info!("using ast cache {}", path.display());
+            Some(c.ast)
+        }
+        Ok(_) => {
+            debug!("ast cache {} is outdated", path.display());
+            None
+        }
+        Err(e) => {
+            debug!("unable to read ast cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Write cache file; failures are not critical since cache can always be rebuilt
+pub fn store(source_path: &Path, source: &str, ast: &AstPair<Block>) {
+    let path = cache_path(source_path);
+    let res = bincode::serialize(&AstCache::new(source, ast.clone()))
+        .map_err(|e| e.to_string())
+        .and_then(|b| write(&path, b).map_err(|e| e.to_string()));
+    match res {
+        Ok(_) => debug!("ast cache written to {}", path.display()),
+        Err(e) => debug!("unable to write ast cache {}: {}", path.display(), e),
+    }
+}
+
+/// Parse source, reusing cached AST if source is unchanged since the last run
+pub fn parse_cached(source_path: &Path, source: &str) -> Result<AstPair<Block>, Error> {
+    if let Some(ast) = load(source_path, source) {
+        return Ok(ast);
+    }
+    let ast = NoisParser::parse_program(source).and_then(|parsed| parse_block(&parsed))?;
+    store(source_path, source, &ast);
+    Ok(ast)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{remove_file, write};
+
+    use super::*;
+
+    #[test]
+    fn cache_roundtrip() {
+        let path = temp_dir().join("nois_cache_roundtrip.no");
+        let source = "main = -> println([1, 'a', 2.5, ..b])";
+        write(&path, source).unwrap();
+        remove_file(cache_path(&path)).ok();
+
+        assert_eq!(load(&path, source), None);
+        let ast = parse_cached(&path, source).unwrap();
+        assert_eq!(load(&path, source), Some(ast));
+    }
+
+    #[test]
+    fn cache_invalidation() {
+        let path = temp_dir().join("nois_cache_invalidation.no");
+        parse_cached(&path, "a = 1").unwrap();
+        assert_eq!(load(&path, "a = 2"), None);
+
+        let mut cache = AstCache::new("a = 1", parse_cached(&path, "a = 1").unwrap());
+        assert!(cache.is_valid("a = 1"));
+        cache.version = "0.0.0".to_string();
+        assert!(!cache.is_valid("a = 1"));
+
+        let mut cache = AstCache::new("a = 1", parse_cached(&path, "a = 1").unwrap());
+        cache.format = AST_FORMAT - 1;
+        assert!(!cache.is_valid("a = 1"));
+    }
+}
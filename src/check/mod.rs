@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use crate::ast::ast::{
+    Assignee, AstContext, AstPair, Block, Expression, Identifier, Operand, Span, Statement,
+    TypeAnnotation, ValueType,
+};
+use crate::ast::visit::{walk_expressions, walk_statements};
+use crate::check::purity::{analyze, expression_purity, Purity, IMPURE_BUILTINS};
+use crate::error::Error;
+use crate::stdlib::lib::{registry, FunctionInfo};
+use crate::typecheck::typecheck;
+
+pub mod purity;
+
+/// Stdlib functions that expect pure callbacks
+pub const PURE_CALLBACK_FUNCTIONS: [&str; 2] = ["map", "filter"];
+
+/// Static analysis finding that does not prevent program from running
+#[derive(Debug, PartialEq, Clone)]
+pub struct Warning {
+    pub code: String,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Warning {
+    /// Warning formatted the same way as errors, with source location
+    pub fn to_error(&self, a_ctx: &AstContext) -> Error {
+        Error::from_span(
+            &self.span,
+            a_ctx,
+            format!("{}: {}", self.code, self.message),
+        )
+    }
+}
+
+/// Run all checks on the program
+pub fn check(ast: &AstPair<Block>) -> Vec<Warning> {
+    let mut warnings = check_purity(ast, &analyze(ast));
+    warnings.extend(check_types(ast));
+    warnings.extend(check_deprecated(ast, registry()));
+    warnings
+}
+
+/// Report definite type errors found by type inference, see [typecheck]
+pub fn check_types(ast: &AstPair<Block>) -> Vec<Warning> {
+    typecheck(ast)
+        .into_iter()
+        .map(|e| Warning {
+            code: "TYPE".to_string(),
+            span: e.span,
+            message: e.message,
+        })
+        .collect()
+}
+
+/// Type of a literal expression, if it is obvious without evaluation
+fn literal_type(expression: &AstPair<Expression>) -> Option<TypeAnnotation> {
+    let vt = match &expression.1 {
+        Expression::Operand(o) => match &o.1 {
+            Operand::Integer(_) => ValueType::Integer,
+            Operand::Float(_) => ValueType::Float,
+            Operand::Boolean(_) => ValueType::Boolean,
+            Operand::FunctionInit(_) => ValueType::Function,
+            Operand::String(_) => {
+                return Some(TypeAnnotation::List(Box::new(AstPair::from_span(
+                    &o.0,
+                    TypeAnnotation::Type(ValueType::Char),
+                ))))
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(TypeAnnotation::Type(vt))
+}
+
+/// Whether literal type definitely does not match the annotation
+fn is_mismatch(literal: &TypeAnnotation, annotation: &TypeAnnotation) -> bool {
+    match (literal, annotation) {
+        (_, TypeAnnotation::Type(ValueType::Any)) => false,
+        (TypeAnnotation::Type(a), TypeAnnotation::Type(b)) => a != b,
+        (TypeAnnotation::List(a), TypeAnnotation::List(b)) => is_mismatch(&a.1, &b.1),
+        _ => true,
+    }
+}
+
+/// Warn about literals bound to annotated assignees of a different type, either directly,
+/// e.g. `a: I = 'a'`, or as arguments of top-level functions, e.g. `f = (a: I) -> a; f('a')`
+pub fn check_annotations(ast: &AstPair<Block>) -> Vec<Warning> {
+    let mut warnings = vec![];
+    let mut warn = |annotation: &AstPair<TypeAnnotation>, expression: &AstPair<Expression>| {
+        if let Some(t) = literal_type(expression) {
+            if is_mismatch(&t, &annotation.1) {
+                warnings.push(Warning {
+                    code: "TYPE".to_string(),
+                    span: expression.0.clone(),
+                    message: format!("expected {}, found {}", annotation.1, t),
+                });
+            }
+        }
+    };
+    let mut parameters = HashMap::new();
+    for s in &ast.1.statements {
+        if let Statement::Assignment {
+            assignee: AstPair(_, Assignee::Identifier { identifier, .. }),
+            expression,
+        } = &s.1
+        {
+            if let Expression::Operand(o) = &expression.1 {
+                if let Operand::FunctionInit(fi) = &o.1 {
+                    parameters.insert(identifier.1.clone(), fi.parameters.clone());
+                }
+            }
+        }
+    }
+    walk_statements(ast, &mut |s| {
+        if let Statement::Assignment {
+            assignee:
+                AstPair(
+                    _,
+                    Assignee::Identifier {
+                        annotation: Some(a),
+                        ..
+                    },
+                ),
+            expression,
+        } = &s.1
+        {
+            warn(a, expression);
+        }
+    });
+    walk_expressions(ast, &mut |e| {
+        if let Expression::Operand(o) = &e.1 {
+            if let Operand::FunctionCall(fc) = &o.1 {
+                for (p, arg) in parameters
+                    .get(&fc.identifier.1)
+                    .into_iter()
+                    .flatten()
+                    .zip(&fc.arguments)
+                {
+                    if let Assignee::Identifier {
+                        annotation: Some(a),
+                        ..
+                    } = &p.1
+                    {
+                        warn(a, arg);
+                    }
+                }
+            }
+        }
+    });
+    warnings
+}
+
+/// Warn about usages of deprecated stdlib functions not shadowed by program definitions
+pub fn check_deprecated(
+    ast: &AstPair<Block>,
+    functions: &HashMap<String, FunctionInfo>,
+) -> Vec<Warning> {
+    let mut defined = vec![];
+    walk_statements(ast, &mut |s| {
+        if let Statement::Assignment {
+            assignee: AstPair(_, Assignee::Identifier { identifier, .. }),
+            ..
+        } = &s.1
+        {
+            defined.push(identifier.1.clone());
+        }
+    });
+    let mut warnings = vec![];
+    walk_expressions(ast, &mut |e| {
+        let identifier = match &e.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::FunctionCall(fc) => &fc.identifier,
+                Operand::Identifier(i) => i,
+                _ => return,
+            },
+            _ => return,
+        };
+        if defined.contains(&identifier.1) {
+            return;
+        }
+        if let Some(d) = functions
+            .get(&identifier.1 .0)
+            .and_then(|f| f.deprecation.as_ref())
+        {
+            warnings.push(Warning {
+                code: "DEPRECATED".to_string(),
+                span: identifier.0.clone(),
+                message: d.message(&identifier.1 .0),
+            });
+        }
+    });
+    warnings
+}
+
+/// Warn about impure callbacks passed to functions expecting pure ones, e.g. `map(l, println)`
+pub fn check_purity(ast: &AstPair<Block>, purity: &HashMap<Identifier, Purity>) -> Vec<Warning> {
+    let mut warnings = vec![];
+    walk_expressions(ast, &mut |e| {
+        let fc = match &e.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::FunctionCall(fc) => fc,
+                _ => return,
+            },
+            _ => return,
+        };
+        if !PURE_CALLBACK_FUNCTIONS.contains(&fc.identifier.1 .0.as_str()) {
+            return;
+        }
+        for arg in &fc.arguments {
+            let p = match &arg.1 {
+                Expression::Operand(o) => match &o.1 {
+                    Operand::Identifier(i) if IMPURE_BUILTINS.contains(&i.1 .0.as_str()) => {
+                        Purity::Impure {
+                            reason: format!("'{}' has side effects", i.1),
+                        }
+                    }
+                    Operand::Identifier(i) => purity.get(&i.1).cloned().unwrap_or(Purity::Pure),
+                    Operand::FunctionInit(_) => expression_purity(arg, purity),
+                    _ => Purity::Pure,
+                },
+                _ => Purity::Pure,
+            };
+            if let Purity::Impure { reason } = p {
+                warnings.push(Warning {
+                    code: "PURITY".to_string(),
+                    span: arg.0.clone(),
+                    message: format!(
+                        "impure function passed to '{}': {}",
+                        fc.identifier.1, reason
+                    ),
+                });
+            }
+        }
+    });
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ast::Identifier;
+    use crate::ast::ast_parser::parse_block;
+    use crate::parser::NoisParser;
+    use crate::stdlib::lib::Deprecation;
+
+    use super::*;
+
+    fn parse(source: &str) -> AstPair<Block> {
+        NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap()
+    }
+
+    #[test]
+    fn analyze_purity() {
+        let ast = parse(
+            r#"
+log = a -> println(a)
+logTwice = a -> {
+    log(a)
+    log(a)
+}
+inc = a -> a + 1
+apply = (f, a) -> f(a)
+"#,
+        );
+        let purity = analyze(&ast);
+        assert_eq!(
+            purity[&Identifier::new("log")],
+            Purity::Impure {
+                reason: "calls 'println'".to_string()
+            }
+        );
+        assert_eq!(
+            purity[&Identifier::new("logTwice")],
+            Purity::Impure {
+                reason: "calls impure 'log'".to_string()
+            }
+        );
+        assert!(purity[&Identifier::new("inc")].is_pure());
+        assert!(purity[&Identifier::new("apply")].is_pure());
+    }
+
+    #[test]
+    fn check_impure_callbacks() {
+        let ast = parse(
+            r#"
+log = a -> println(a)
+main = -> {
+    map([1], log)
+    map([1], a -> a + 1)
+    [1].filter(a -> { debug(a) })
+    map([1], println)
+}
+"#,
+        );
+        let warnings = check(&ast);
+        assert_eq!(
+            warnings
+                .iter()
+                .filter(|w| w.code == "PURITY")
+                .map(|w| w.message.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                "impure function passed to 'map': calls 'println'",
+                "impure function passed to 'filter': calls 'debug'",
+                "impure function passed to 'map': 'println' has side effects",
+            ]
+        );
+        // `a -> { debug(a) }` returns a function instead of a boolean
+        assert_eq!(
+            warnings.last().map(|w| w.message.as_str()),
+            Some("expected (I) -> B, found (I) -> (() -> ())")
+        );
+    }
+
+    #[test]
+    fn check_deprecated_usages() {
+        let ast = parse(
+            r#"
+main = -> {
+    a = range(3)
+    map(a, range)
+    f = range
+}
+"#,
+        );
+        let mut functions = registry().clone();
+        functions.get_mut("range").unwrap().deprecation = Some(Deprecation {
+            since: "0.1".to_string(),
+            hint: "use `seq` instead".to_string(),
+        });
+        let warnings = check_deprecated(&ast, &functions);
+        assert_eq!(
+            warnings.iter().map(|w| w.span.start).collect::<Vec<_>>(),
+            vec![21, 41, 56]
+        );
+        assert_eq!(
+            warnings[0].message,
+            "'range' is deprecated since 0.1: use `seq` instead"
+        );
+        assert!(check_deprecated(&ast, registry()).is_empty());
+
+        let shadowed = parse("range = a -> a\nmain = -> range(1)");
+        assert!(check_deprecated(&shadowed, &functions).is_empty());
+    }
+
+    #[test]
+    fn check_annotated_literals() {
+        let ast = parse(
+            r#"
+a: I = 'a'
+b: [C] = 'b'
+c: [*] = 1
+f = (x: F, y: *) -> {
+    d: Fn = 4.2
+    x
+}
+main = -> f(1, 2)
+"#,
+        );
+        let expected = vec![
+            "expected I, found [C]",
+            "expected [*], found I",
+            "expected Fn, found F",
+            "expected F, found I",
+        ];
+        let warnings = check_annotations(&ast);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|w| w.message.clone())
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(warnings.iter().all(|w| w.code == "TYPE"));
+
+        let inferred = check_types(&ast);
+        assert_eq!(
+            inferred
+                .iter()
+                .map(|w| w.message.clone())
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
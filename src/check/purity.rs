@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::ast::ast::{Assignee, AstPair, Block, Expression, Identifier, Operand, Statement};
+use crate::ast::visit::walk_expressions;
+
+/// Stdlib functions with side effects
+pub const IMPURE_BUILTINS: [&str; 4] = ["println", "eprintln", "debug", "panic"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Purity {
+    Pure,
+    Impure { reason: String },
+}
+
+impl Purity {
+    pub fn is_pure(&self) -> bool {
+        matches!(self, Purity::Pure)
+    }
+}
+
+/// Classify top-level definitions of the program.
+/// Definition is impure if it calls impure builtin or other impure definition, directly or
+/// through nested functions. Calls of function parameters cannot be resolved statically and
+/// are considered pure.
+pub fn analyze(ast: &AstPair<Block>) -> HashMap<Identifier, Purity> {
+    let definitions = ast
+        .1
+        .statements
+        .iter()
+        .filter_map(|s| match &s.1 {
+            Statement::Assignment {
+                assignee:
+                    AstPair(
+                        _,
+                        Assignee::Identifier {
+                            identifier: AstPair(_, identifier),
+                            ..
+                        },
+                    ),
+                expression,
+            } => Some((identifier.clone(), expression)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut purity = definitions
+        .iter()
+        .map(|(i, _)| (i.clone(), Purity::Pure))
+        .collect::<HashMap<_, _>>();
+    // propagate impurity through the call graph until nothing changes
+    loop {
+        let mut changed = false;
+        for (identifier, expression) in &definitions {
+            if purity[identifier].is_pure() {
+                let p = expression_purity(expression, &purity);
+                if !p.is_pure() {
+                    purity.insert(identifier.clone(), p);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return purity;
+        }
+    }
+}
+
+/// Purity of an expression, given purity of known definitions
+pub fn expression_purity(
+    expression: &AstPair<Expression>,
+    known: &HashMap<Identifier, Purity>,
+) -> Purity {
+    let block = AstPair::from_span(
+        &expression.0,
+        Block {
+            statements: vec![expression.map(|_| Statement::Expression(expression.clone()))],
+        },
+    );
+    let mut purity = Purity::Pure;
+    walk_expressions(&block, &mut |e| {
+        if !purity.is_pure() {
+            return;
+        }
+        let name = match &e.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::FunctionCall(fc) => &fc.identifier.1,
+                _ => return,
+            },
+            _ => return,
+        };
+        if IMPURE_BUILTINS.contains(&name.0.as_str()) {
+            purity = Purity::Impure {
+                reason: format!("calls '{}'", name),
+            };
+        } else if let Some(Purity::Impure { .. }) = known.get(name) {
+            purity = Purity::Impure {
+                reason: format!("calls impure '{}'", name),
+            };
+        }
+    });
+    purity
+}
@@ -0,0 +1,144 @@
+use std::io::{self, Read};
+
+use md5::{Digest, Md5};
+use ring::digest;
+
+/// Hash algorithms of checksums published along with downloads
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name.to_lowercase().replace('-', "").as_str() {
+            "md5" => Some(Algorithm::Md5),
+            "sha1" => Some(Algorithm::Sha1),
+            "sha256" => Some(Algorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Algorithm producing hex digest of this length, e.g. 64 for SHA-256
+    pub fn from_hex_len(len: usize) -> Option<Algorithm> {
+        [Algorithm::Md5, Algorithm::Sha1, Algorithm::Sha256]
+            .into_iter()
+            .find(|a| a.digest_len() * 2 == len)
+    }
+
+    fn digest_len(&self) -> usize {
+        match self {
+            Algorithm::Md5 => 16,
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha256 => 32,
+        }
+    }
+}
+
+/// Incremental hasher, so that data does not have to be kept in memory
+pub enum Hasher {
+    Md5(Md5),
+    Ring(digest::Context),
+}
+
+impl Hasher {
+    pub fn new(algorithm: Algorithm) -> Hasher {
+        match algorithm {
+            Algorithm::Md5 => Hasher::Md5(Md5::new()),
+            Algorithm::Sha1 => {
+                Hasher::Ring(digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY))
+            }
+            Algorithm::Sha256 => Hasher::Ring(digest::Context::new(&digest::SHA256)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Ring(c) => c.update(data),
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        match self {
+            Hasher::Md5(h) => h.finalize().to_vec(),
+            Hasher::Ring(c) => c.finish().as_ref().to_vec(),
+        }
+    }
+}
+
+/// Hex digest of everything read from the reader
+pub fn hash_reader(algorithm: Algorithm, reader: &mut impl Read) -> io::Result<String> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = [0; 64 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hex(&hasher.finish()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(algorithm: Algorithm, data: &[u8]) -> String {
+        hash_reader(algorithm, &mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn known_digests() {
+        assert_eq!(
+            digest(Algorithm::Md5, b""),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_eq!(
+            digest(
+                Algorithm::Md5,
+                b"The quick brown fox jumps over the lazy dog"
+            ),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+        assert_eq!(
+            digest(Algorithm::Sha1, b"abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            digest(Algorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            digest(
+                Algorithm::Sha256,
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            ),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn streaming_matches_single_update() {
+        let data = (0..1000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        for algorithm in [Algorithm::Md5, Algorithm::Sha1, Algorithm::Sha256] {
+            let mut hasher = Hasher::new(algorithm);
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hex(&hasher.finish()), digest(algorithm, &data));
+        }
+        assert_eq!(
+            digest(Algorithm::Sha1, &vec![b'a'; 1_000_000]),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+}
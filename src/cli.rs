@@ -1,11 +1,26 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
-#[clap(version, about, long_about = None)]
+#[clap(version = nois::build_info::LONG_VERSION, about, long_about = None)]
 #[clap(propagate_version = true)]
 pub struct Cli {
     #[clap(subcommand)]
     pub command: Commands,
+
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value = "human",
+        help = "Format of errors and warnings printed to stderr, `json` prints one object per line"
+    )]
+    pub error_format: ErrorFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, PartialEq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
@@ -24,6 +39,11 @@ pub enum Commands {
         )]
         verbose: bool,
     },
+    #[clap(about = "Report warnings found by static analysis")]
+    Check {
+        #[clap(value_parser, help = "Path to source file")]
+        source: String,
+    },
     #[clap(about = "Run source file")]
     Run {
         #[clap(value_parser, help = "Path to source file")]
@@ -37,5 +57,97 @@ pub enum Commands {
             help = "Detailed output"
         )]
         verbose: bool,
+
+        #[clap(
+            long,
+            required = false,
+            takes_value = false,
+            help = "Do not read or write .noc AST cache next to the source and imported files"
+        )]
+        no_cache: bool,
+
+        #[clap(
+            long,
+            required = false,
+            takes_value = false,
+            help = "Print function calls and their results to stderr"
+        )]
+        trace: bool,
+
+        #[clap(
+            long,
+            value_name = "N",
+            help = "Print results of the last N statements of every scope to stderr on runtime error"
+        )]
+        history: Option<usize>,
+
+        #[clap(
+            long,
+            required = false,
+            takes_value = false,
+            help = "Validate values against type annotations, e.g. `a: I = 4`"
+        )]
+        check_types: bool,
+    },
+    #[clap(about = "Run test definitions, e.g. `testSum = -> assert(sum([1, 2]) == 3)`")]
+    Test {
+        #[clap(value_parser, help = "Path to source file")]
+        source: String,
+
+        #[clap(
+            long,
+            required = false,
+            takes_value = false,
+            help = "Report line coverage and write it in lcov format"
+        )]
+        coverage: bool,
+
+        #[clap(
+            long,
+            default_value = "lcov.info",
+            help = "Path to lcov coverage output"
+        )]
+        lcov: String,
+    },
+    #[clap(about = "Run examples and compare their output with the one in `// output:` comment")]
+    Examples {
+        #[clap(value_parser, help = "Path to directory with examples")]
+        dir: String,
+    },
+    #[clap(about = "Print stdlib reference, e.g. `nois doc list.map`")]
+    Doc {
+        #[clap(
+            value_parser,
+            default_value = "",
+            help = "Function name or name prefix, optionally qualified with package name"
+        )]
+        query: String,
+    },
+    #[clap(about = "Bundle source file into a single self-contained file")]
+    Bundle {
+        #[clap(value_parser, help = "Path to entry source file")]
+        source: String,
+
+        #[clap(short, long, help = "Path to output file, stdout by default")]
+        output: Option<String>,
+    },
+    #[clap(about = "Manage dependencies vendored into nois_modules")]
+    Pkg {
+        #[clap(subcommand)]
+        command: PkgCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PkgCommands {
+    #[clap(about = "Vendor dependency from git URL or path and add it to the manifest")]
+    Add {
+        #[clap(value_parser, help = "Git URL or path to dependency directory")]
+        source: String,
+
+        #[clap(short, long, help = "Dependency name, inferred from source by default")]
+        name: Option<String>,
     },
+    #[clap(about = "Vendor all dependencies listed in the manifest")]
+    Install,
 }
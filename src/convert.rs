@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::interpret::value::Value;
+
+/// Conversion of host values into nois values
+pub trait ToNois {
+    fn to_nois(self) -> Value;
+}
+
+/// Conversion of nois values into host values
+pub trait FromNois: Sized {
+    fn from_nois(value: Value) -> Result<Self, String>;
+}
+
+/// Function arguments convertible into nois values, implemented for tuples
+pub trait ToNoisArgs {
+    fn to_nois_args(self) -> Vec<Value>;
+}
+
+fn type_error<T>(expected: &str, value: &Value) -> Result<T, String> {
+    Err(format!(
+        "expected {}, found {}",
+        expected,
+        value.value_type()
+    ))
+}
+
+impl ToNois for Value {
+    fn to_nois(self) -> Value {
+        self
+    }
+}
+
+impl FromNois for Value {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        Ok(value)
+    }
+}
+
+impl ToNois for () {
+    fn to_nois(self) -> Value {
+        Value::Unit
+    }
+}
+
+impl FromNois for () {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Unit => Ok(()),
+            v => type_error("()", &v),
+        }
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToNois for $t {
+                fn to_nois(self) -> Value {
+                    Value::I(self as i128)
+                }
+            }
+
+            impl FromNois for $t {
+                fn from_nois(value: Value) -> Result<Self, String> {
+                    match value {
+                        Value::I(i) => <$t>::try_from(i)
+                            .map_err(|_| format!("{} is out of {} range", i, stringify!($t))),
+                        v => type_error("I", &v),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_float {
+    ($($t:ty),*) => {
+        $(
+            impl ToNois for $t {
+                fn to_nois(self) -> Value {
+                    Value::F(self as f64)
+                }
+            }
+
+            impl FromNois for $t {
+                fn from_nois(value: Value) -> Result<Self, String> {
+                    match value {
+                        Value::F(f) => Ok(f as $t),
+                        Value::I(i) => Ok(i as $t),
+                        v => type_error("F", &v),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+impl ToNois for bool {
+    fn to_nois(self) -> Value {
+        Value::B(self)
+    }
+}
+
+impl FromNois for bool {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::B(b) => Ok(b),
+            v => type_error("B", &v),
+        }
+    }
+}
+
+impl ToNois for char {
+    fn to_nois(self) -> Value {
+        Value::C(self)
+    }
+}
+
+impl FromNois for char {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::C(c) => Ok(c),
+            v => type_error("C", &v),
+        }
+    }
+}
+
+impl ToNois for &str {
+    fn to_nois(self) -> Value {
+        Value::list(self.chars().map(Value::C).collect())
+    }
+}
+
+impl ToNois for String {
+    fn to_nois(self) -> Value {
+        self.as_str().to_nois()
+    }
+}
+
+impl FromNois for String {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::List { items, .. } => items.into_iter().map(char::from_nois).collect(),
+            v => type_error("[C]", &v),
+        }
+    }
+}
+
+impl<T: ToNois> ToNois for Vec<T> {
+    fn to_nois(self) -> Value {
+        Value::list(self.into_iter().map(|i| i.to_nois()).collect())
+    }
+}
+
+impl<T: FromNois> FromNois for Vec<T> {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::List { items, .. } => items.into_iter().map(T::from_nois).collect(),
+            v => type_error("[*]", &v),
+        }
+    }
+}
+
+/// Option is represented the same way as `some()` and `none()` construct it: `[a]` or `[]`
+impl<T: ToNois> ToNois for Option<T> {
+    fn to_nois(self) -> Value {
+        Value::list(self.into_iter().map(|i| i.to_nois()).collect())
+    }
+}
+
+impl<T: FromNois> FromNois for Option<T> {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::List { items, .. } => match <[Value; 1]>::try_from(items) {
+                Ok([v]) => T::from_nois(v).map(Some),
+                Err(items) if items.is_empty() => Ok(None),
+                Err(items) => Err(format!(
+                    "expected option of at most one item, found {} items",
+                    items.len()
+                )),
+            },
+            v => type_error("[*]", &v),
+        }
+    }
+}
+
+/// Map is represented as a list of key-value pairs: `[[k, v], ..]`
+impl<K: ToNois, V: ToNois> ToNois for HashMap<K, V> {
+    fn to_nois(self) -> Value {
+        Value::list(
+            self.into_iter()
+                .map(|(k, v)| Value::list(vec![k.to_nois(), v.to_nois()]))
+                .collect(),
+        )
+    }
+}
+
+impl<K: FromNois + Eq + Hash, V: FromNois> FromNois for HashMap<K, V> {
+    fn from_nois(value: Value) -> Result<Self, String> {
+        match value {
+            Value::List { items, .. } => items
+                .into_iter()
+                .map(|p| match p {
+                    Value::List { items, .. } => match <[Value; 2]>::try_from(items) {
+                        Ok([k, v]) => Ok((K::from_nois(k)?, V::from_nois(v)?)),
+                        Err(items) => Err(format!(
+                            "expected key-value pair, found {} items",
+                            items.len()
+                        )),
+                    },
+                    v => type_error("[*]", &v),
+                })
+                .collect(),
+            v => type_error("[[*]]", &v),
+        }
+    }
+}
+
+macro_rules! impl_args {
+    ($($t:ident),*) => {
+        impl<$($t: ToNois),*> ToNoisArgs for ($($t,)*) {
+            #[allow(non_snake_case)]
+            fn to_nois_args(self) -> Vec<Value> {
+                let ($($t,)*) = self;
+                vec![$($t.to_nois()),*]
+            }
+        }
+    };
+}
+
+impl ToNoisArgs for () {
+    fn to_nois_args(self) -> Vec<Value> {
+        vec![]
+    }
+}
+
+impl_args!(A);
+impl_args!(A, B);
+impl_args!(A, B, C);
+impl_args!(A, B, C, D);
+impl_args!(A, B, C, D, E);
+impl_args!(A, B, C, D, E, F);
+
+impl ToNoisArgs for Vec<Value> {
+    fn to_nois_args(self) -> Vec<Value> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: ToNois + FromNois + Clone>(v: T) -> Result<T, String> {
+        T::from_nois(v.to_nois())
+    }
+
+    #[test]
+    fn convert_primitives() {
+        assert_eq!(roundtrip(42u8), Ok(42));
+        assert_eq!(roundtrip(-42i64), Ok(-42));
+        assert_eq!(roundtrip(2.5f64), Ok(2.5));
+        assert_eq!(roundtrip(true), Ok(true));
+        assert_eq!(roundtrip('x'), Ok('x'));
+        assert_eq!(roundtrip("nois".to_string()), Ok("nois".to_string()));
+        assert_eq!(
+            u8::from_nois(Value::I(256)),
+            Err("256 is out of u8 range".to_string())
+        );
+        assert_eq!(
+            bool::from_nois(Value::I(1)),
+            Err("expected B, found I".to_string())
+        );
+    }
+
+    #[test]
+    fn convert_collections() {
+        assert_eq!(roundtrip(vec![1, 2, 3]), Ok(vec![1, 2, 3]));
+        assert_eq!(roundtrip(Some(vec!['a'])), Ok(Some(vec!['a'])));
+        assert_eq!(roundtrip(Option::<i32>::None), Ok(None));
+        let map = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        assert_eq!(roundtrip(map.clone()), Ok(map));
+        assert!(Option::<i32>::from_nois(Value::list(vec![Value::I(1), Value::I(2)])).is_err());
+    }
+}
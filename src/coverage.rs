@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::ast::ast::{AstContext, AstPair, Block, Statement};
+use crate::ast::visit::walk_statements;
+use crate::interpret::context::Context;
+use crate::interpret::observer::EvalObserver;
+
+/// Observer counting how many times each statement was executed, keyed by statement start
+#[derive(Debug, Default)]
+pub struct Coverage {
+    pub hits: HashMap<usize, usize>,
+}
+
+impl EvalObserver for Coverage {
+    fn on_statement_enter(&mut self, statement: &AstPair<Statement>, _ctx: &Context) {
+        *self.hits.entry(statement.0.start).or_default() += 1;
+    }
+}
+
+/// Line coverage of a single source file: line number -> execution count
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileCoverage {
+    pub path: String,
+    pub lines: BTreeMap<usize, usize>,
+}
+
+impl Coverage {
+    /// Map recorded hits onto source lines.
+    /// Top-level statements are not instrumented since they are definitions, evaluated on
+    /// demand; statements inside of functions and match clauses are.
+    pub fn report(&self, path: &str, ast: &AstPair<Block>, a_ctx: &AstContext) -> FileCoverage {
+        let mut lines = BTreeMap::new();
+        for top in &ast.1.statements {
+            let nested = AstPair::from_span(
+                &top.0,
+                Block {
+                    statements: vec![top.clone()],
+                },
+            );
+            walk_statements(&nested, &mut |s| {
+                if s == top {
+                    return;
+                }
+                let line = s.0.start_line_col(a_ctx).0;
+                let hits = self.hits.get(&s.0.start).cloned().unwrap_or(0);
+                let e = lines.entry(line).or_insert(0);
+                *e = hits.max(*e);
+            });
+        }
+        FileCoverage {
+            path: path.to_string(),
+            lines,
+        }
+    }
+}
+
+impl FileCoverage {
+    pub fn covered(&self) -> usize {
+        self.lines.values().filter(|h| **h > 0).count()
+    }
+
+    pub fn percent(&self) -> f64 {
+        if self.lines.is_empty() {
+            return 100.;
+        }
+        self.covered() as f64 / self.lines.len() as f64 * 100.
+    }
+
+    /// Human readable summary listing uncovered lines
+    pub fn to_text(&self) -> String {
+        let uncovered = self
+            .lines
+            .iter()
+            .filter(|(_, h)| **h == 0)
+            .map(|(l, _)| l.to_string())
+            .collect::<Vec<_>>();
+        let mut text = format!(
+            "{}: {:.1}% ({}/{} lines)",
+            self.path,
+            self.percent(),
+            self.covered(),
+            self.lines.len()
+        );
+        if !uncovered.is_empty() {
+            write!(text, "\n  uncovered lines: {}", uncovered.join(", ")).unwrap();
+        }
+        text
+    }
+}
+
+/// Coverage in lcov tracefile format, supported by most coverage viewers
+pub fn to_lcov(files: &[FileCoverage]) -> String {
+    let mut lcov = String::new();
+    for f in files {
+        writeln!(lcov, "TN:\nSF:{}", f.path).unwrap();
+        for (line, hits) in &f.lines {
+            writeln!(lcov, "DA:{},{}", line, hits).unwrap();
+        }
+        writeln!(
+            lcov,
+            "LF:{}\nLH:{}\nend_of_record",
+            f.lines.len(),
+            f.covered()
+        )
+        .unwrap();
+    }
+    lcov
+}
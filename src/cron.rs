@@ -0,0 +1,178 @@
+/// Schedule in cron format with minute, hour, day of month, month and day of week fields, e.g.
+/// `*/15 9-17 * * 1-5`. Each field is a comma separated list of values, ranges (`1-5`), steps
+/// (`*/15`, `10-30/5`) or `*` for any value. Sunday is either 0 or 7. If both day fields are
+/// restricted, a day matching either of them matches. Times are in UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Cron matches are searched for this many days ahead, so that schedules matching impossible
+/// dates like `0 0 30 2 *` do not search forever
+const SEARCH_DAYS: u64 = 366 * 8;
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Cron, String> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let (minute, hour, day, month, weekday) = match fields[..] {
+            [minute, hour, day, month, weekday] => (minute, hour, day, month, weekday),
+            _ => {
+                return Err(format!(
+                    "expected 5 cron fields: minute, hour, day, month and weekday, found {}",
+                    fields.len()
+                ))
+            }
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, "weekday")?;
+        // both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// Start of the first matching minute after the time, both in seconds since unix epoch.
+    /// `None` if nothing matches in the next few years
+    pub fn next_after(&self, secs: u64) -> Option<u64> {
+        let mut minute = secs / 60 + 1;
+        let last = minute + SEARCH_DAYS * 24 * 60;
+        while minute < last {
+            let days = minute / (24 * 60);
+            if !self.matches_day(days) {
+                minute = (days + 1) * 24 * 60;
+                continue;
+            }
+            let hour = minute / 60 % 24;
+            if self.hours & (1 << hour) == 0 {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if self.minutes & (1 << (minute % 60)) != 0 {
+                return Some(minute * 60);
+            }
+            minute += 1;
+        }
+        None
+    }
+
+    fn matches_day(&self, days: u64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 is Thursday
+        let weekday = (days + 4) % 7;
+        let day_match = self.days & (1 << day) != 0;
+        let weekday_match = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_match || weekday_match,
+            _ => day_match && weekday_match,
+        }
+    }
+}
+
+/// Bitmask of field values in range `[min, max]`
+fn parse_field(field: &str, min: u64, max: u64, name: &str) -> Result<u64, String> {
+    let error = || format!("invalid cron {} field '{}'", name, field);
+    let number = |s: &str| match s.parse::<u64>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "cron {} '{}' is out of range {}-{}",
+            name, s, min, max
+        )),
+    };
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => match s.parse::<u64>() {
+                Ok(s) if s > 0 => (r, s),
+                _ => return Err(error()),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((s, e)) => (number(s)?, number(e)?),
+            // single value with step continues to the max, e.g. `5/10`
+            None if step > 1 => (number(range)?, max),
+            None => {
+                let n = number(range)?;
+                (n, n)
+            }
+        };
+        if start > end {
+            return Err(error());
+        }
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+/// Year, month and day of the date that is the number of days since unix epoch
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00 UTC, Monday
+    const JAN_1: u64 = 1704067200;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(JAN_1 / 86400), (2024, 1, 1));
+        assert_eq!(civil_from_days(JAN_1 / 86400 + 59), (2024, 2, 29));
+    }
+
+    #[test]
+    fn next_match() {
+        let next = |expr: &str, secs: u64| Cron::parse(expr).unwrap().next_after(secs);
+        assert_eq!(next("* * * * *", JAN_1), Some(JAN_1 + 60));
+        assert_eq!(next("*/15 * * * *", JAN_1 + 60), Some(JAN_1 + 15 * 60));
+        assert_eq!(next("30 9 * * *", JAN_1), Some(JAN_1 + 9 * 3600 + 30 * 60));
+        // first Sunday is 2024-01-07
+        assert_eq!(next("0 0 * * 7", JAN_1), Some(JAN_1 + 6 * 86400));
+        assert_eq!(next("0 0 1 3 *", JAN_1), Some(JAN_1 + 60 * 86400));
+        // either day of month or weekday
+        assert_eq!(next("0 0 15 * 3", JAN_1), Some(JAN_1 + 2 * 86400));
+        assert_eq!(next("0 0 30 2 *", JAN_1), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Cron::parse("* * * *").is_err());
+        assert!(Cron::parse("60 * * * *").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+        assert!(Cron::parse("5-1 * * * *").is_err());
+        assert!(Cron::parse("1,5-10/2 * * * 0").is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use pest::error::{InputLocation, LineColLocation};
+
+use crate::ast::ast::AstContext;
+use crate::check::Warning;
+use crate::error::Error;
+
+/// Machine readable error or warning, consumed by editors and CI tools
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stage that produced the diagnostic, e.g. `parse`, `check` or `runtime`
+    pub kind: String,
+    /// Warning code, e.g. `PURITY`
+    pub code: Option<String>,
+    pub message: String,
+    pub file: String,
+    pub span: Option<DiagnosticSpan>,
+    /// Call locations from the innermost one
+    pub backtrace: Vec<Frame>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Byte offsets and 1-based line and column numbers of the span ends
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiagnosticSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub location: String,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Diagnostic {
+    pub fn from_error(error: &Error, kind: &str, file: &str) -> Diagnostic {
+        let mut backtrace = vec![];
+        let mut error = error;
+        let e = loop {
+            match error {
+                Error::Error(e) => break e,
+                Error::Panic { error: cause, .. } => error = cause,
+                Error::Cause {
+                    error: cause,
+                    location,
+                    line_col,
+                    path,
+                } => {
+                    backtrace.push(Frame {
+                        location: location.clone(),
+                        file: path.as_deref().unwrap_or(file).to_string(),
+                        line: line_col.0,
+                        column: line_col.1,
+                    });
+                    error = cause;
+                }
+            }
+        };
+        // causes wrap inner calls, so the outermost one is the first
+        backtrace.reverse();
+        let (start, end) = match e.location {
+            InputLocation::Pos(p) => (p, p),
+            InputLocation::Span(s) => s,
+        };
+        let ((line, column), (end_line, end_column)) = match e.line_col {
+            LineColLocation::Pos(p) => (p, p),
+            LineColLocation::Span(s, e) => (s, e),
+        };
+        Diagnostic {
+            severity: Severity::Error,
+            kind: kind.to_string(),
+            code: None,
+            message: e.variant.message().to_string(),
+            file: e.path().unwrap_or(file).to_string(),
+            span: Some(DiagnosticSpan {
+                start,
+                end,
+                line,
+                column,
+                end_line,
+                end_column,
+            }),
+            backtrace,
+        }
+    }
+
+    pub fn from_warning(warning: &Warning, a_ctx: &AstContext, file: &str) -> Diagnostic {
+        let span = &warning.span;
+        let (line, column) = span.start_line_col(a_ctx);
+        let (end_line, end_column) = span.end_line_col(a_ctx);
+        Diagnostic {
+            severity: Severity::Warning,
+            kind: "check".to_string(),
+            code: Some(warning.code.clone()),
+            message: warning.message.clone(),
+            file: file.to_string(),
+            span: Some(DiagnosticSpan {
+                start: span.start,
+                end: span.end,
+                line,
+                column,
+                end_line,
+                end_column,
+            }),
+            backtrace: vec![],
+        }
+    }
+
+    /// Diagnostic without source location, e.g. failure to read a file
+    pub fn from_message(message: &str, kind: &str, file: &str) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            kind: kind.to_string(),
+            code: None,
+            message: message.to_string(),
+            file: file.to_string(),
+            span: None,
+            backtrace: vec![],
+        }
+    }
+
+    /// Single line JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"severity\":{},\"kind\":{},\"code\":{},\"message\":{},\"file\":{},\"span\":",
+            json_string(match self.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            }),
+            json_string(&self.kind),
+            self.code
+                .as_ref()
+                .map_or("null".to_string(), |c| json_string(c)),
+            json_string(&self.message),
+            json_string(&self.file),
+        );
+        match &self.span {
+            Some(s) => write!(
+                json,
+                "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}}}",
+                s.start, s.end, s.line, s.column, s.end_line, s.end_column
+            )
+            .unwrap(),
+            None => json.push_str("null"),
+        }
+        let frames = self
+            .backtrace
+            .iter()
+            .map(|f| {
+                format!(
+                    "{{\"location\":{},\"file\":{},\"line\":{},\"column\":{}}}",
+                    json_string(&f.location),
+                    json_string(&f.file),
+                    f.line,
+                    f.column
+                )
+            })
+            .collect::<Vec<_>>();
+        write!(json, ",\"backtrace\":[{}]}}", frames.join(",")).unwrap();
+        json
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::context::Context;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    #[test]
+    fn runtime_error_to_json() {
+        let source = r#"f = -> g("a")
+main = -> f()
+main()"#;
+        let a_ctx = AstContext::new(source);
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx_cell = RefCell::new(Context::stdlib(a_ctx));
+        let ctx = &mut ctx_cell.borrow_mut();
+        let error = ast.eval(ctx, true).unwrap_err();
+        let diagnostic = Diagnostic::from_error(&error, "runtime", "a.no");
+        assert_eq!(
+            diagnostic
+                .backtrace
+                .iter()
+                .map(|f| f.location.as_str())
+                .collect::<Vec<_>>(),
+            vec!["g", "f", "main"]
+        );
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"severity":"error","kind":"runtime","code":null,"message":"function 'g' not found","file":"a.no","span":{"start":7,"end":13,"line":1,"column":8,"end_line":1,"end_column":14},"backtrace":[{"location":"g","file":"a.no","line":1,"column":8},{"location":"f","file":"a.no","line":2,"column":11},{"location":"main","file":"a.no","line":3,"column":1}]}"#
+        );
+        assert_eq!(json_string("a \"b\"\n"), r#""a \"b\"\n""#);
+    }
+
+    #[test]
+    fn parse_error_to_json() {
+        let error = NoisParser::parse_program("a = ]").unwrap_err();
+        let diagnostic = Diagnostic::from_error(&error, "parse", "a.no");
+        assert_eq!(diagnostic.span.map(|s| (s.line, s.column)), Some((1, 5)));
+        assert!(diagnostic.backtrace.is_empty());
+    }
+}
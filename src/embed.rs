@@ -0,0 +1,422 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::ast::ast::{AstContext, AstPair, Block, FunctionInit, Identifier, Span, Statement};
+use crate::ast::ast_parser::parse_block;
+use crate::convert::{FromNois, ToNoisArgs};
+use crate::error::Error;
+use crate::interpret::context::{Context, Definition, DefinitionEntry, Scope};
+use crate::interpret::evaluate::{call_value, eval_memoized, Evaluate};
+use crate::interpret::interpreter::push_global_scope;
+use crate::interpret::value::Value;
+use crate::parser::NoisParser;
+use crate::stdlib::lib::pure_stdlib;
+
+/// Parsed nois program that can be used from Rust code.
+/// Usually constructed with `nois_macro::nois!`, validating the source at compile time.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub ast_context: AstContext,
+    pub ast: AstPair<Block>,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Script, Error> {
+        let ast_context = AstContext::new(source);
+        let ast = NoisParser::parse_program(source).and_then(|parsed| parse_block(&parsed))?;
+        Ok(Script { ast_context, ast })
+    }
+
+    /// Load script definitions into a new runtime
+    pub fn runtime(&self) -> Result<Runtime, Error> {
+        Runtime::new(self)
+    }
+
+    /// Call top-level definition by its name, e.g. `add = (a, b) -> a + b`
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        self.runtime()?.call(name, args)
+    }
+
+    /// Call top-level definition converting arguments and result from and into host types
+    ///
+    /// ```text
+    /// let sum: i64 = script.invoke("add", (1, 2))?;
+    /// ```
+    ///
+    pub fn invoke<R: FromNois>(&self, name: &str, args: impl ToNoisArgs) -> Result<R, Error> {
+        self.runtime()?.invoke(name, args)
+    }
+}
+
+/// Script definitions loaded into a context that is kept between calls
+#[derive(Debug)]
+pub struct Runtime {
+    ctx: RefCell<Context>,
+}
+
+/// Function value that host code can keep and call multiple times
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionHandle(FunctionInit);
+
+impl TryFrom<Value> for FunctionHandle {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Fn(f) => Ok(FunctionHandle(f)),
+            v => Err(format!("expected Fn, found {}", v.value_type())),
+        }
+    }
+}
+
+impl Runtime {
+    pub fn new(script: &Script) -> Result<Runtime, Error> {
+        Runtime::with_context(script, Context::stdlib(script.ast_context.clone()))
+    }
+
+    /// Load script definitions into a prepared context, e.g. one with registered observers
+    pub fn with_context(script: &Script, ctx: Context) -> Result<Runtime, Error> {
+        let ctx = RefCell::new(ctx);
+        push_global_scope(script.ast.clone(), &mut ctx.borrow_mut())?;
+        Ok(Runtime { ctx })
+    }
+
+    /// Names of top-level definitions, sorted
+    pub fn definitions(&self) -> Vec<String> {
+        let ctx = self.ctx.borrow();
+        let names = ctx
+            .scopes()
+            .next()
+            .map(|s| s.entries().into_iter().map(|e| e.name.0).collect())
+            .unwrap_or_default();
+        names
+    }
+
+    /// Definitions visible to the script, without stdlib ones
+    pub fn inspect(&self) -> Vec<DefinitionEntry> {
+        self.ctx.borrow().visible_definitions(false)
+    }
+
+    /// Call top-level definition by its name
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        let ctx = &mut self.ctx.borrow_mut();
+        let identifier = Identifier::new(name);
+        let (id, exp, memo) = match ctx.find_definition(&identifier) {
+            Some(Definition::User(id, exp, memo)) => (id, exp, memo),
+            _ => {
+                return Err(error(ctx, format!("definition '{}' not found", identifier)));
+            }
+        };
+        let depth = ctx.scope_stack.len();
+        ctx.scope_stack.push(
+            Scope::new(name.to_string())
+                .with_callee(Some(id.0.clone()))
+                .with_arguments(
+                    args.into_iter()
+                        .map(|a| AstPair::from_span(&id.0, a))
+                        .collect(),
+                ),
+        );
+        debug!("push scope @{}", name);
+        let res = eval_memoized(&exp, &memo, ctx).and_then(|v| v.eval(ctx, true));
+        ctx.scope_stack.truncate(depth);
+        res.map(|v| v.1)
+            .map_err(|e| Error::new_cause(e, name.to_string(), &id.0, &ctx.ast_context))
+    }
+
+    /// Call top-level definition converting arguments and result from and into host types
+    pub fn invoke<R: FromNois>(&self, name: &str, args: impl ToNoisArgs) -> Result<R, Error> {
+        let res = self.call(name, args.to_nois_args())?;
+        R::from_nois(res).map_err(|e| {
+            error(
+                &self.ctx.borrow_mut(),
+                format!("invalid '{}' result: {}", name, e),
+            )
+        })
+    }
+
+    /// Get handle to a top-level function definition, e.g. a hook registered by the script
+    pub fn function(&self, name: &str) -> Result<FunctionHandle, Error> {
+        let ctx = &mut self.ctx.borrow_mut();
+        let identifier = Identifier::new(name);
+        let value = match ctx.find_definition(&identifier) {
+            Some(Definition::User(_, exp, memo)) => eval_memoized(&exp, &memo, ctx)?,
+            _ => {
+                return Err(error(ctx, format!("definition '{}' not found", identifier)));
+            }
+        };
+        FunctionHandle::try_from(value.1)
+            .map_err(|e| Error::from_span(&value.0, &ctx.ast_context, e))
+    }
+
+    /// Call function value with host arguments
+    pub fn call_function(
+        &self,
+        function: &FunctionHandle,
+        args: Vec<Value>,
+    ) -> Result<Value, Error> {
+        let ctx = &mut self.ctx.borrow_mut();
+        let span = function.0.block.0.clone();
+        let depth = ctx.scope_stack.len();
+        let res = call_value(
+            &AstPair::from_span(&span, Value::Fn(function.0.clone())),
+            args.into_iter()
+                .map(|a| AstPair::from_span(&span, a))
+                .collect(),
+            &span,
+            ctx,
+        );
+        ctx.scope_stack.truncate(depth);
+        res.map(|v| v.1)
+            .map_err(|e| Error::new_cause(e, "<fn>".to_string(), &span, &ctx.ast_context))
+    }
+
+    /// Call function value converting arguments and result from and into host types
+    pub fn invoke_function<R: FromNois>(
+        &self,
+        function: &FunctionHandle,
+        args: impl ToNoisArgs,
+    ) -> Result<R, Error> {
+        let res = self.call_function(function, args.to_nois_args())?;
+        R::from_nois(res).map_err(|e| {
+            error(
+                &self.ctx.borrow_mut(),
+                format!("invalid function result: {}", e),
+            )
+        })
+    }
+}
+
+/// Evaluate a single expression with provided bindings as the only definitions besides stdlib
+/// packages that do not interact with the host, e.g. a filter or a formula from configuration
+///
+/// ```text
+/// let bindings = HashMap::from([("level".to_string(), Value::I(3))]);
+/// let alert = eval_expr("level == 3", bindings)?;
+/// ```
+///
+pub fn eval_expr(source: &str, bindings: HashMap<String, Value>) -> Result<Value, Error> {
+    let ast_context = AstContext::new(source);
+    eval_expr_in(Context::new(ast_context, pure_stdlib()), source, bindings)
+}
+
+/// Evaluate a single expression like `eval_expr`, but only the listed stdlib functions and
+/// operators can be used. Any other identifier that is not a binding fails with
+/// "permission denied"
+///
+/// ```text
+/// let bindings = HashMap::from([("price".to_string(), Value::F(9.5))]);
+/// let half = eval_expr_allowed("price / 2", bindings, &["/"])?;
+/// ```
+///
+pub fn eval_expr_allowed(
+    source: &str,
+    bindings: HashMap<String, Value>,
+    allowed: &[&str],
+) -> Result<Value, Error> {
+    let ast_context = AstContext::new(source);
+    let allowed = allowed.iter().map(|a| Identifier::new(a)).collect();
+    let ctx = Context::new(ast_context, pure_stdlib()).with_allowed(allowed);
+    eval_expr_in(ctx, source, bindings)
+}
+
+fn eval_expr_in(
+    ctx: Context,
+    source: &str,
+    bindings: HashMap<String, Value>,
+) -> Result<Value, Error> {
+    let block = NoisParser::parse_program(source).and_then(|parsed| parse_block(&parsed))?;
+    let ctx = &mut RefCell::new(ctx);
+    let ctx = &mut ctx.borrow_mut();
+    let expression = match &block.1.statements[..] {
+        [AstPair(_, Statement::Expression(e))] => e,
+        _ => return Err(error(ctx, "expected single expression".to_string())),
+    };
+    let definitions = bindings
+        .into_iter()
+        .map(|(name, v)| {
+            let value = AstPair::from_span(&block.0, v);
+            (Identifier::new(&name), Definition::Value(value))
+        })
+        .collect();
+    ctx.scope_stack
+        .push(Scope::new("bindings".to_string()).with_definitions(definitions));
+    expression.eval(ctx, true).map(|v| v.1)
+}
+
+fn error(ctx: &Context, message: String) -> Error {
+    Error::from_span(&Span { start: 0, end: 0 }, &ctx.ast_context, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::convert::ToNois;
+    use crate::interpret::context::DefinitionKind;
+
+    use super::*;
+
+    #[test]
+    fn script_call() {
+        let script = Script::parse("add = (a, b) -> a + b\nanswer = 42").unwrap();
+        assert_eq!(
+            script.call("add", vec![Value::I(1), Value::I(2)]),
+            Ok(Value::I(3))
+        );
+        assert_eq!(script.call("answer", vec![]), Ok(Value::I(42)));
+        assert_eq!(
+            script.call("missing", vec![]).map_err(|e| e.message()),
+            Err("definition 'missing' not found".to_string())
+        );
+    }
+
+    #[test]
+    fn runtime_function_handle() {
+        let script = Script::parse(
+            r#"
+onNumber = a -> a + 1
+onString = a -> a + 'b'
+failing = a -> a + (-> 1)
+answer = 42
+"#,
+        )
+        .unwrap();
+        let runtime = script.runtime().unwrap();
+        let on_number = runtime.function("onNumber").unwrap();
+        let on_string = runtime.function("onString").unwrap();
+        for i in 0..3 {
+            assert_eq!(runtime.invoke_function::<i32>(&on_number, (i,)), Ok(i + 1));
+            assert_eq!(
+                runtime.invoke_function::<String>(&on_string, ("a",)),
+                Ok("ab".to_string())
+            );
+        }
+
+        let failing = runtime.function("failing").unwrap();
+        assert!(runtime.call_function(&failing, vec![Value::I(1)]).is_err());
+        assert_eq!(runtime.invoke_function::<i32>(&on_number, (2,)), Ok(3));
+        assert_eq!(
+            runtime.function("answer").map_err(|e| e.message()),
+            Err("expected Fn, found I".to_string())
+        );
+        assert!(runtime.function("missing").is_err());
+    }
+
+    #[test]
+    fn runtime_inspect() {
+        let script = Script::parse("b = 1\na = -> b\nP = #{x}").unwrap();
+        let runtime = script.runtime().unwrap();
+        assert_eq!(runtime.definitions(), vec!["P", "a", "b"]);
+        let entries = runtime.inspect();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.name.0.as_str(), e.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("P", DefinitionKind::Value),
+                ("a", DefinitionKind::User),
+                ("b", DefinitionKind::User),
+            ]
+        );
+        let span = entries[2].span.as_ref().unwrap();
+        assert_eq!(&script.ast_context.input[span.start..span.end], "b");
+
+        let ctx = runtime.ctx.borrow();
+        let with_stdlib = ctx.visible_definitions(true);
+        assert!(with_stdlib
+            .iter()
+            .any(|e| e.name.0 == "map" && e.kind == DefinitionKind::System && e.span.is_none()));
+    }
+
+    #[test]
+    fn runtime_isolation() {
+        let script = Script::parse("a = 1\nget = -> a").unwrap();
+        let handles = (0..4)
+            .map(|i| {
+                let script = script.clone();
+                std::thread::spawn(move || {
+                    let runtime = script.runtime().unwrap();
+                    runtime.ctx.borrow_mut().scope_stack[1].definitions.insert(
+                        Identifier::new("a"),
+                        Definition::Value(AstPair::from_span(
+                            &Span { start: 0, end: 0 },
+                            Value::I(i),
+                        )),
+                    );
+                    runtime.invoke::<i128>("get", ()).unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![0, 1, 2, 3]);
+
+        let a = script.runtime().unwrap();
+        let b = script.runtime().unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &a.ctx.borrow().stdlib,
+            &b.ctx.borrow().stdlib
+        ));
+    }
+
+    #[test]
+    fn eval_expr_bindings() {
+        let bindings = HashMap::from([
+            ("level".to_string(), Value::I(3)),
+            ("tags".to_string(), vec!["db", "prod"].to_nois()),
+        ]);
+        assert_eq!(eval_expr("level + 1", bindings.clone()), Ok(Value::I(4)));
+        assert_eq!(
+            eval_expr("tags.map(t -> t + '!')", bindings.clone()).map(|v| v.to_string()),
+            Ok("[db!, prod!]".to_string())
+        );
+        assert_eq!(
+            eval_expr("a = 1\na", bindings.clone()).map_err(|e| e.message()),
+            Err("expected single expression".to_string())
+        );
+        assert_eq!(
+            eval_expr("println(level)", bindings.clone()).map_err(|e| e.message()),
+            Err("function 'println' not found".to_string())
+        );
+        assert!(eval_expr("missing", bindings).is_err());
+    }
+
+    #[test]
+    fn eval_expr_whitelist() {
+        let bindings = HashMap::from([("level".to_string(), Value::I(3))]);
+        let allowed = ["+", "==", "map"];
+        let eval = |source: &str| {
+            eval_expr_allowed(source, bindings.clone(), &allowed).map_err(|e| e.message())
+        };
+        assert_eq!(eval("level + 1 == 4"), Ok(Value::B(true)));
+        assert_eq!(
+            eval("[level].map(l -> l + 1)").map(|v| v.to_string()),
+            Ok("[4]".to_string())
+        );
+        let denied = |name: &str| Err(format!("permission denied: '{}' is not allowed", name));
+        assert_eq!(eval("level - 1"), denied("-"));
+        assert_eq!(eval("[level].filter(l -> l == 3)"), denied("filter"));
+        assert_eq!(eval("join"), denied("join"));
+        assert_eq!(eval("lvl + 1"), denied("lvl"));
+    }
+
+    #[test]
+    fn script_invoke() {
+        let script = Script::parse("add = (a, b) -> a + b\nwrap = a -> [a, a]").unwrap();
+        assert_eq!(script.invoke::<i64>("add", (1, 2)), Ok(3));
+        assert_eq!(
+            script.invoke::<Vec<String>>("wrap", ("a",)),
+            Ok(vec!["a".to_string(), "a".to_string()])
+        );
+        assert_eq!(
+            script
+                .invoke::<bool>("add", (1, 2))
+                .map_err(|e| e.message()),
+            Err("invalid 'add' result: expected B, found I".to_string())
+        );
+    }
+}
@@ -1,53 +1,86 @@
 use std::cell::RefMut;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
 
+use pest::error::Error as PError;
 use pest::error::ErrorVariant;
-use pest::error::{Error as PError, LineColLocation};
 use pest::iterators::Pair;
 
 use crate::ast::ast::{AstContext, Span};
 use crate::interpret::context::Context;
+use crate::interpret::value::Value;
 use crate::parser::Rule;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
-    Error(pest::error::Error<Rule>),
+    /// Boxed, so that `Result` of the interpreter stays small
+    Error(Box<pest::error::Error<Rule>>),
     Cause {
         error: Box<Error>,
         location: String,
         line_col: (usize, usize),
+        /// Path of the imported file the location is in
+        path: Option<String>,
+    },
+    /// Error raised by `panic`, carrying the value it was called with
+    Panic {
+        error: Box<Error>,
+        payload: Box<Value>,
     },
 }
 
 impl Error {
     pub fn new_cause(error: Error, location: String, span: &Span, ctx: &AstContext) -> Error {
-        let line_col = match Self::custom_error_span(span, ctx, String::new()).line_col {
-            LineColLocation::Pos(line_col) => line_col,
-            LineColLocation::Span(start_line_col, _) => start_line_col,
-        };
         Error::Cause {
             error: Box::new(error),
             location,
-            line_col,
+            line_col: span.start_line_col(ctx),
+            path: ctx.source(span.start).path.clone(),
         }
     }
 
     pub fn from_pair(pair: &Pair<Rule>, message: String) -> Error {
-        Error::Error(Self::custom_error(pair, message))
+        Error::Error(Box::new(Self::custom_error(pair, message)))
+    }
+
+    /// Error pointing at a part of the pair, range is relative to the pair start
+    pub fn from_pair_range(pair: &Pair<Rule>, range: Range<usize>, message: String) -> Error {
+        let span = pair.as_span().get(range).unwrap_or(pair.as_span());
+        Error::Error(Box::new(PError::new_from_span(
+            ErrorVariant::CustomError { message },
+            span,
+        )))
     }
 
     pub fn from_span(span: &Span, ctx: &AstContext, message: String) -> Error {
-        Error::Error(Self::custom_error_span(span, ctx, message))
+        Error::Error(Box::new(Self::custom_error_span(span, ctx, message)))
     }
 
     pub fn from_callee(ctx: &mut RefMut<Context>, message: String) -> Error {
-        Error::Error(Self::custom_error_callee(ctx, message))
+        Error::Error(Box::new(Self::custom_error_callee(ctx, message)))
+    }
+
+    /// Error of the imported file, parsed on its own
+    pub fn with_path(self, path: &str) -> Error {
+        match self {
+            Error::Error(e) => Error::Error(Box::new(e.with_path(path))),
+            e => e,
+        }
     }
 
     pub fn message(&self) -> String {
         match self {
             Error::Error(e) => e.variant.message().to_string(),
-            Error::Cause { error, .. } => error.message(),
+            Error::Cause { error, .. } | Error::Panic { error, .. } => error.message(),
+        }
+    }
+
+    /// Value passed to `panic`, if the error is raised by it
+    pub fn payload(&self) -> Option<&Value> {
+        match self {
+            Error::Error(_) => None,
+            Error::Cause { error, .. } => error.payload(),
+            Error::Panic { payload, .. } => Some(payload),
         }
     }
 
@@ -56,7 +89,11 @@ impl Error {
     }
 
     fn custom_error_span(span: &Span, ctx: &AstContext, message: String) -> PError<Rule> {
-        PError::new_from_span(ErrorVariant::CustomError { message }, span.as_span(ctx))
+        let error = PError::new_from_span(ErrorVariant::CustomError { message }, span.as_span(ctx));
+        match &ctx.source(span.start).path {
+            Some(path) => error.with_path(path),
+            None => error,
+        }
     }
 
     fn custom_error_callee(ctx: &mut RefMut<Context>, message: String) -> PError<Rule> {
@@ -74,7 +111,7 @@ impl Iterator for Error {
     fn next(&mut self) -> Option<Self::Item> {
         match self {
             Error::Error(_) => None,
-            Error::Cause { error, .. } => Some(*error.clone()),
+            Error::Cause { error, .. } | Error::Panic { error, .. } => Some(*error.clone()),
         }
     }
 }
@@ -83,14 +120,20 @@ impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Error(e) => write!(f, "{}", e.to_string()),
+            Error::Panic { error, .. } => write!(f, "{}", error),
             Error::Cause {
                 error,
                 location,
                 line_col,
+                path,
             } => write!(
                 f,
-                "{}\n\t@ {:<8} ({}:{})",
-                error, location, line_col.0, line_col.1,
+                "{}\n\t@ {:<8} ({}{}:{})",
+                error,
+                location,
+                path.as_ref().map(|p| format!("{}:", p)).unwrap_or_default(),
+                line_col.0,
+                line_col.1,
             ),
         }
     }
@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+/// Escape sequences supported in string literals
+pub const ESCAPE_SEQUENCES: &str = r#"\" \' \\ \/ \n \r \t \uXXXX"#;
+
+/// Invalid escape sequence, range is the byte range of the sequence within the input
+#[derive(Debug, PartialEq, Clone)]
+pub struct EscapeError {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+/// Replace escape sequences with characters they represent
+pub fn unescape(s: &str) -> Result<String, EscapeError> {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some((_, c @ ('"' | '\'' | '\\' | '/'))) => c,
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, 'u')) => {
+                let start = i + 2;
+                let mut end = start;
+                while end - start < 4 {
+                    match chars.peek() {
+                        Some((j, h)) if h.is_ascii_hexdigit() => {
+                            end = j + 1;
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                let code = &s[start..end];
+                if code.len() < 4 {
+                    return Err(EscapeError {
+                        range: i..end,
+                        message: format!(
+                            "unicode escape \\u{} must have 4 hex digits, e.g. \\u00e9",
+                            code
+                        ),
+                    });
+                }
+                match u32::from_str_radix(code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => {
+                        return Err(EscapeError {
+                            range: i..end,
+                            message: format!("\\u{} is not a valid unicode character", code),
+                        })
+                    }
+                }
+            }
+            Some((j, c)) => {
+                return Err(EscapeError {
+                    range: i..j + c.len_utf8(),
+                    message: format!(
+                        "invalid escape sequence \\{}, supported escape sequences: {}",
+                        c, ESCAPE_SEQUENCES
+                    ),
+                })
+            }
+            None => {
+                return Err(EscapeError {
+                    range: i..i + 1,
+                    message: format!(
+                        "incomplete escape sequence, supported escape sequences: {}",
+                        ESCAPE_SEQUENCES
+                    ),
+                })
+            }
+        };
+        res.push(escaped);
+    }
+    Ok(res)
+}
+
+/// Replace characters that cannot appear in string literals as is with escape sequences,
+/// inverse of `unescape`
+pub fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' | '\'' | '\\' => {
+                res.push('\\');
+                res.push(c);
+            }
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Quote string to be used as a single POSIX shell word
+pub fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r#"'"'"'"#))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_sequences() {
+        assert_eq!(
+            unescape(r#"a\tb\n\"c\" \'\\\/ é"#),
+            Ok("a\tb\n\"c\" '\\/ é".to_string())
+        );
+        assert_eq!(unescape("é\\q").unwrap_err().range, 2..4);
+        assert_eq!(
+            unescape("\\u00g").unwrap_err(),
+            EscapeError {
+                range: 0..4,
+                message: "unicode escape \\u00 must have 4 hex digits, e.g. \\u00e9".to_string()
+            }
+        );
+        assert_eq!(unescape("a\\ud800").unwrap_err().range, 1..7);
+        assert_eq!(unescape("a\\").unwrap_err().range, 1..2);
+    }
+
+    #[test]
+    fn escape_roundtrip() {
+        let s = "say \"hi\"\n\t'it\\s' \u{7} é";
+        assert_eq!(escape(s), r#"say \"hi\"\n\t\'it\\s\' \u0007 é"#);
+        assert_eq!(unescape(&escape(s)), Ok(s.to_string()));
+    }
+
+    #[test]
+    fn shell_quote_words() {
+        assert_eq!(shell_quote("a/b.txt"), "a/b.txt");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's $HOME"), r#"'it'"'"'s $HOME'"#);
+    }
+}
@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+use std::fs::{read_dir, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::embed::Script;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::interpreter::{run, RunError};
+
+/// Line starting the trailing comment block with expected output of an example, e.g.
+///
+/// ```text
+/// main = -> println('Hello, World!')
+///
+/// // output:
+/// // Hello, World!
+/// ```
+///
+pub const OUTPUT_MARKER: &str = "// output:";
+
+#[derive(Debug)]
+pub struct ExampleResult {
+    pub path: PathBuf,
+    pub expected: Vec<String>,
+    /// Printed lines, or error if the example failed to parse or run
+    pub actual: Result<Vec<String>, Error>,
+}
+
+impl ExampleResult {
+    pub fn passed(&self) -> bool {
+        self.actual.as_ref() == Ok(&self.expected)
+    }
+
+    /// Expected lines prefixed with `-` and actual lines prefixed with `+` where they differ
+    pub fn diff(&self) -> String {
+        let actual = match &self.actual {
+            Ok(a) => a,
+            Err(e) => return e.to_string(),
+        };
+        let mut diff = vec![];
+        for i in 0..self.expected.len().max(actual.len()) {
+            match (self.expected.get(i), actual.get(i)) {
+                (Some(e), Some(a)) if e == a => diff.push(format!("  {}", e)),
+                (e, a) => {
+                    diff.extend(e.map(|e| format!("- {}", e)));
+                    diff.extend(a.map(|a| format!("+ {}", a)));
+                }
+            }
+        }
+        diff.join("\n")
+    }
+}
+
+/// Lines of the comment block following the output marker, `None` if there is no marker
+pub fn expected_output(source: &str) -> Option<Vec<String>> {
+    let mut lines = source.lines().skip_while(|l| l.trim() != OUTPUT_MARKER);
+    lines.next()?;
+    Some(
+        lines
+            .map_while(|l| l.strip_prefix("//"))
+            .map(|l| l.strip_prefix(' ').unwrap_or(l).to_string())
+            .collect(),
+    )
+}
+
+/// Run example and compare printed lines with the expected output, `None` if the example
+/// has no expected output
+pub fn run_example(path: &Path) -> io::Result<Option<ExampleResult>> {
+    let source = read_to_string(path)?;
+    let expected = match expected_output(&source) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let actual = Script::parse(&source).and_then(|script| {
+        let output = Rc::new(RefCell::new(String::new()));
+        let ctx = Context::stdlib(script.ast_context).with_output(output.clone());
+        match run(script.ast, ctx) {
+            Ok(_) => Ok(output.borrow().lines().map(|l| l.to_string()).collect()),
+            Err(RunError::Program(e) | RunError::Internal(e)) => Err(e),
+        }
+    });
+    Ok(Some(ExampleResult {
+        path: path.to_path_buf(),
+        expected,
+        actual,
+    }))
+}
+
+/// Run every `.no` example of the directory that has expected output, sorted by path
+pub fn run_examples(dir: &Path) -> io::Result<Vec<ExampleResult>> {
+    let mut paths = read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.extension().is_some_and(|e| e == "no"));
+    paths.sort();
+    let mut results = vec![];
+    for path in paths {
+        results.extend(run_example(&path)?);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_expected_output() {
+        let source = "main = -> println('a')\n\n// output:\n// a\n//\n//  b\nc = 1";
+        assert_eq!(
+            expected_output(source),
+            Some(vec!["a".to_string(), "".to_string(), " b".to_string()])
+        );
+        assert_eq!(expected_output("// a"), None);
+    }
+
+    #[test]
+    fn data_examples() {
+        let results = run_examples(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data")).unwrap();
+        assert!(!results.is_empty());
+        for r in results {
+            assert!(r.passed(), "{}:\n{}", r.path.display(), r.diff());
+        }
+    }
+}
@@ -0,0 +1,106 @@
+/// Levenshtein distance: minimal number of char insertions, deletions and substitutions
+/// required to turn one string into the other
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // distances from the prefix of `a` processed so far to every prefix of `b`
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Score of the pattern matched against the string, `None` if pattern chars do not appear in
+/// the string in the same order. Chars are compared ignoring case and matched leftmost-first.
+/// Every matched char scores 1, with bonus 2 if it follows the previous matched char and
+/// bonus 3 if it starts a word, e.g. `fb` scores more for `fooBar` than for `fabric`.
+pub fn fuzzy_match(string: &str, pattern: &str) -> Option<usize> {
+    let chars = string.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut last_match = None;
+    let mut start = 0;
+    for p in pattern.chars() {
+        let i = start + chars[start..].iter().position(|c| eq_ignore_case(*c, p))?;
+        score += 1;
+        if i > 0 && last_match == Some(i - 1) {
+            score += 2;
+        }
+        if word_start(&chars, i) {
+            score += 3;
+        }
+        last_match = Some(i);
+        start = i + 1;
+    }
+    Some(score)
+}
+
+/// Candidates closest to the name, used for "did you mean" suggestions, at most `limit` of them
+/// ordered by distance. Candidates differing in more than a third of the name are not
+/// considered similar.
+pub fn suggest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    limit: usize,
+) -> Vec<&'a str> {
+    let max_distance = name.chars().count() / 3;
+    let mut similar = candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .collect::<Vec<_>>();
+    similar.sort();
+    similar.dedup();
+    similar.into_iter().take(limit).map(|(_, c)| c).collect()
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn word_start(chars: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|p| chars[p]) {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && chars[i].is_uppercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn fuzzy_scores() {
+        assert_eq!(fuzzy_match("fooBar", "fb"), Some(8));
+        assert_eq!(fuzzy_match("fabric", "fb"), Some(5));
+        assert_eq!(fuzzy_match("foo", "of"), None);
+        assert_eq!(fuzzy_match("abc", ""), Some(0));
+        assert_eq!(fuzzy_match("ABC", "ab"), Some(7));
+    }
+
+    #[test]
+    fn suggest_closest() {
+        let names = ["map", "filter", "println", "eprintln", "print", "map"];
+        assert_eq!(
+            suggest("printn", names, 3),
+            vec!["print", "println", "eprintln"]
+        );
+        assert_eq!(suggest("printn", names, 1), vec!["print"]);
+        assert_eq!(suggest("mapp", names, 3), vec!["map"]);
+        assert!(suggest("reduce", names, 3).is_empty());
+    }
+}
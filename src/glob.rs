@@ -0,0 +1,398 @@
+use std::fs::{read_dir, read_to_string, symlink_metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Files matching the glob pattern, sorted by path. Pattern segments are separated by `/`:
+/// `*` matches any characters within a segment, `?` matches a single character and `**`
+/// matches any number of nested directories, e.g. `src/**/*.rs`. If `gitignore` is set, ignored
+/// files are skipped, see [walk_dir]
+pub fn glob(pattern: &str, gitignore: bool) -> io::Result<Vec<PathBuf>> {
+    let segments = pattern.split('/').collect::<Vec<_>>();
+    // leading segments without wildcards form the directory the search starts from
+    let literal = segments
+        .iter()
+        .take_while(|s| !is_wildcard(s))
+        .count()
+        .min(segments.len() - 1);
+    let base = match segments[..literal].join("/") {
+        b if b.is_empty() && pattern.starts_with('/') => PathBuf::from("/"),
+        b if b.is_empty() => PathBuf::from("."),
+        b => PathBuf::from(b),
+    };
+    if !base.is_dir() {
+        return Ok(vec![]);
+    }
+    walk_dir(&base, Some(&segments[literal..].join("/")), gitignore)
+}
+
+/// Files under the directory and its subdirectories, sorted by path. If `pattern` is set, only
+/// paths relative to the directory matching it are listed, see [matches_path]. If `gitignore`
+/// is set, `.git` directory and files ignored by `.gitignore` files of the visited directories
+/// are skipped. Symbolic links to directories are not followed
+pub fn walk_dir(dir: &Path, pattern: Option<&str>, gitignore: bool) -> io::Result<Vec<PathBuf>> {
+    let pattern = pattern.map(|p| p.split('/').collect::<Vec<_>>());
+    let mut walk = Walk {
+        root: dir,
+        pattern: pattern.as_deref(),
+        gitignore,
+        ignores: vec![],
+        files: vec![],
+    };
+    walk.dir(dir)?;
+    let mut files = walk
+        .files
+        .into_iter()
+        .map(|f| match f.strip_prefix(".") {
+            Ok(relative) if dir == Path::new(".") => relative.to_path_buf(),
+            _ => f,
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+struct Walk<'a> {
+    root: &'a Path,
+    pattern: Option<&'a [&'a str]>,
+    gitignore: bool,
+    ignores: Vec<Gitignore>,
+    files: Vec<PathBuf>,
+}
+
+impl Walk<'_> {
+    fn dir(&mut self, dir: &Path) -> io::Result<()> {
+        let has_ignore = self.gitignore
+            && match read_to_string(dir.join(".gitignore")) {
+                Ok(s) => {
+                    self.ignores.push(Gitignore::parse(dir, &s));
+                    true
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+                Err(e) => return Err(e),
+            };
+        for entry in read_dir(dir)? {
+            let path = entry?.path();
+            let file_type = symlink_metadata(&path)?.file_type();
+            let is_dir = file_type.is_dir();
+            if file_type.is_symlink() && path.is_dir() {
+                // followed links could lead to a loop
+                continue;
+            }
+            if self.gitignore
+                && (path.file_name() == Some(".git".as_ref())
+                    || is_ignored(&self.ignores, &path, is_dir))
+            {
+                continue;
+            }
+            let relative = segments(path.strip_prefix(self.root).unwrap_or(&path));
+            let relative = relative.iter().map(String::as_str).collect::<Vec<_>>();
+            if is_dir {
+                if self.pattern.is_none_or(|p| matches_prefix(p, &relative)) {
+                    self.dir(&path)?;
+                }
+            } else if self
+                .pattern
+                .is_none_or(|p| matches_segments(p, &relative, false))
+            {
+                self.files.push(path);
+            }
+        }
+        if has_ignore {
+            self.ignores.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Rules of a `.gitignore` file, matched against paths relative to its directory
+struct Gitignore {
+    dir: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+struct IgnoreRule {
+    segments: Vec<String>,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Gitignore {
+    fn parse(dir: &Path, input: &str) -> Gitignore {
+        let rules = input
+            .lines()
+            .map(|l| l.trim_end())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let (negated, l) = match l.strip_prefix('!') {
+                    Some(l) => (true, l),
+                    None => (false, l),
+                };
+                let (dir_only, l) = match l.strip_suffix('/') {
+                    Some(l) => (true, l),
+                    None => (false, l),
+                };
+                // pattern without a slash matches a name at any depth
+                let mut segments = match l.contains('/') {
+                    true => vec![],
+                    false => vec!["**".to_string()],
+                };
+                segments.extend(l.trim_start_matches('/').split('/').map(str::to_string));
+                IgnoreRule {
+                    segments,
+                    negated,
+                    dir_only,
+                }
+            })
+            .collect();
+        Gitignore {
+            dir: dir.to_path_buf(),
+            rules,
+        }
+    }
+}
+
+/// Whether the path is ignored. The last matching rule wins and rules of nested directories
+/// come after rules of their parents, so negated rule re-includes the path
+fn is_ignored(ignores: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for ignore in ignores {
+        let relative = match path.strip_prefix(&ignore.dir) {
+            Ok(r) => segments(r),
+            Err(_) => continue,
+        };
+        let relative = relative.iter().map(String::as_str).collect::<Vec<_>>();
+        for rule in &ignore.rules {
+            let segments = rule.segments.iter().map(String::as_str).collect::<Vec<_>>();
+            if (is_dir || !rule.dir_only) && matches_segments(&segments, &relative, true) {
+                ignored = !rule.negated;
+            }
+        }
+    }
+    ignored
+}
+
+fn segments(path: &Path) -> Vec<String> {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Whether the path matches the glob pattern, e.g. `src/**/*.rs`
+pub fn matches_path(pattern: &str, path: &Path) -> bool {
+    let segments = segments(path);
+    let path = segments.iter().map(String::as_str).collect::<Vec<_>>();
+    matches_segments(&pattern.split('/').collect::<Vec<_>>(), &path, false)
+}
+
+/// Whether path segments match pattern segments, `**` matches any number of segments. Names
+/// starting with a dot are matched by wildcards only if `hidden` is set
+fn matches_segments(pattern: &[&str], path: &[&str], hidden: bool) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            matches_segments(rest, path, hidden)
+                || match path.split_first() {
+                    Some((name, path_rest)) if hidden || !name.starts_with('.') => {
+                        matches_segments(pattern, path_rest, hidden)
+                    }
+                    _ => false,
+                }
+        }
+        (Some((segment, rest)), Some((name, path_rest))) => {
+            let matched = match hidden {
+                true => matches_name(segment, name),
+                false => matches(segment, name),
+            };
+            matched && matches_segments(rest, path_rest, hidden)
+        }
+        _ => false,
+    }
+}
+
+/// Whether paths under the directory can match pattern segments, so that the directory has to
+/// be visited
+fn matches_prefix(pattern: &[&str], dir: &[&str]) -> bool {
+    match (pattern.split_first(), dir.split_first()) {
+        (_, None) => true,
+        (Some((&"**", rest)), Some((name, dir_rest))) => {
+            matches_prefix(rest, dir)
+                || (!name.starts_with('.') && matches_prefix(pattern, dir_rest))
+        }
+        (Some((segment, rest)), Some((name, dir_rest))) => {
+            matches(segment, name) && matches_prefix(rest, dir_rest)
+        }
+        (None, Some(_)) => false,
+    }
+}
+
+fn is_wildcard(segment: &str) -> bool {
+    segment.contains(['*', '?'])
+}
+
+/// Whether the name matches a single pattern segment. Names starting with a dot are matched
+/// only by segments starting with a dot
+pub fn matches(segment: &str, name: &str) -> bool {
+    if name.starts_with('.') && !segment.starts_with('.') {
+        return false;
+    }
+    matches_name(segment, name)
+}
+
+fn matches_name(segment: &str, name: &str) -> bool {
+    let (p, n) = (
+        segment.chars().collect::<Vec<_>>(),
+        name.chars().collect::<Vec<_>>(),
+    );
+    // backtracking to the position after the last `*`
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        match p.get(pi) {
+            Some('*') => {
+                star = Some((pi, ni));
+                pi += 1;
+            }
+            Some(c) if *c == '?' || *c == n[ni] => {
+                pi += 1;
+                ni += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    pi = sp + 1;
+                    ni = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn match_segment() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*", "main.rs"));
+        assert!(matches("m?in.*", "main.rs"));
+        assert!(matches("*a*b", "xaxxb"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(!matches("*", ".gitignore"));
+        assert!(matches(".*", ".gitignore"));
+    }
+
+    #[test]
+    fn glob_files() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/glob_test");
+        let _ = remove_dir_all(&dir);
+        for f in [
+            "a.txt",
+            "b.md",
+            "sub/c.txt",
+            "sub/deep/d.txt",
+            ".hidden/e.txt",
+        ] {
+            let path = dir.join(f);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, "").unwrap();
+        }
+        let found = |pattern: &str| {
+            glob(&format!("{}/{}", dir.display(), pattern), false)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.strip_prefix(&dir).unwrap().display().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("*.txt"), vec!["a.txt"]);
+        assert_eq!(
+            found("**/*.txt"),
+            vec!["a.txt", "sub/c.txt", "sub/deep/d.txt"]
+        );
+        assert_eq!(found("sub/*/*.txt"), vec!["sub/deep/d.txt"]);
+        assert_eq!(found("b.md"), vec!["b.md"]);
+        assert_eq!(found("missing/*"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn walk_gitignore() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/walk_gitignore_test");
+        let _ = remove_dir_all(&dir);
+        for (f, content) in [
+            (".gitignore", "# build output\n/build/\n*.log\n!keep.log\n"),
+            (".git/HEAD", ""),
+            (".env", ""),
+            ("a.no", ""),
+            ("a.log", ""),
+            ("keep.log", ""),
+            ("build/out.no", ""),
+            ("src/build/gen.no", ""),
+            ("src/.gitignore", "gen.no\n"),
+            ("src/main.no", ""),
+        ] {
+            let path = dir.join(f);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, content).unwrap();
+        }
+        let walked = |gitignore: bool| {
+            walk_dir(&dir, None, gitignore)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.strip_prefix(&dir).unwrap().display().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(walked(false).len(), 10);
+        assert_eq!(
+            walked(true),
+            vec![
+                ".env",
+                ".gitignore",
+                "a.no",
+                "keep.log",
+                "src/.gitignore",
+                "src/main.no"
+            ]
+        );
+
+        assert_eq!(
+            walk_dir(&dir, Some("**/*.no"), true).unwrap(),
+            vec![dir.join("a.no"), dir.join("src/main.no")]
+        );
+        assert_eq!(
+            glob(&format!("{}/**/*.no", dir.display()), true).unwrap(),
+            vec![dir.join("a.no"), dir.join("src/main.no")]
+        );
+
+        assert!(matches_path("**/*.no", Path::new("src/main.no")));
+        assert!(matches_path("**/*.no", Path::new("a.no")));
+        assert!(!matches_path("**/*.no", Path::new(".hidden/a.no")));
+        assert!(!matches_path("src/*.no", Path::new("src/lib/a.no")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/walk_symlinks_test");
+        let _ = remove_dir_all(&dir);
+        create_dir_all(dir.join("sub")).unwrap();
+        write(dir.join("sub/a.no"), "").unwrap();
+        // loops back to the walked directory
+        symlink(&dir, dir.join("sub/loop")).unwrap();
+        symlink(dir.join("sub/a.no"), dir.join("b.no")).unwrap();
+
+        assert_eq!(
+            walk_dir(&dir, None, false).unwrap(),
+            vec![dir.join("b.no"), dir.join("sub/a.no")]
+        );
+        assert_eq!(
+            glob(&format!("{}/**/*.no", dir.display()), false).unwrap(),
+            vec![dir.join("b.no"), dir.join("sub/a.no")]
+        );
+    }
+}
@@ -0,0 +1,591 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rustls::pki_types::ServerName;
+use rustls::{ClientConnection, StreamOwned};
+
+use crate::tls;
+use crate::url::{self, Url};
+
+/// Requests with larger headers or body are rejected, bodies declared larger with status 413
+const MAX_SIZE: usize = 1 << 20;
+
+/// Responses with larger body are rejected by `fetch`
+const MAX_RESPONSE_SIZE: usize = 64 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// Percent-encoded path without query
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn text(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/plain; charset=utf-8".to_string(),
+            )],
+            body: body.to_string(),
+        }
+    }
+
+    /// Write response closing the connection after it, `Content-Length` is set from the body.
+    /// Response with invalid headers is not written
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        check_headers(&self.headers)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (k, v) in &self.headers {
+            if !k.eq_ignore_ascii_case("content-length") && !k.eq_ignore_ascii_case("connection") {
+                head.push_str(&format!("{}: {}\r\n", k, v));
+            }
+        }
+        head.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            self.body.len()
+        ));
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(self.body.as_bytes())?;
+        stream.flush()
+    }
+}
+
+/// Check that header names are tokens and values do not contain line breaks, so that headers
+/// set by a script cannot split the message or inject other headers
+pub fn check_headers(headers: &[(String, String)]) -> Result<(), String> {
+    for (k, v) in headers {
+        if !is_token(k) {
+            return Err(format!("invalid header name '{}'", k.escape_debug()));
+        }
+        if v.contains(['\r', '\n', '\0']) {
+            return Err(format!("invalid value of header '{}'", k));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the string is a token as defined by RFC 7230, e.g. a header name or a method
+pub fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// Read HTTP/1.x request, the error is a response to send back.
+/// Request not sent within the stream read timeout is answered with status 408.
+pub fn read_request(stream: impl Read) -> Result<Request, Response> {
+    let bad_request = |msg: &str| Response::text(400, msg);
+    let read_error = |e: std::io::Error, msg: &str| match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => Response::text(408, "request timeout"),
+        _ => bad_request(msg),
+    };
+    let mut reader = BufReader::new(stream.take(MAX_SIZE as u64));
+    let mut line = String::new();
+    let mut read_line = |line: &mut String| {
+        line.clear();
+        match reader.read_line(line) {
+            Ok(0) => Err(bad_request("incomplete request")),
+            Err(e) => Err(read_error(e, "incomplete request")),
+            Ok(_) => Ok(line.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    };
+    let request_line = read_line(&mut line)?;
+    let (method, target) = match request_line.split(' ').collect::<Vec<_>>()[..] {
+        [method, target, version] if version.starts_with("HTTP/1.") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Err(bad_request("invalid request line")),
+    };
+    let mut headers = vec![];
+    loop {
+        let header = read_line(&mut line)?;
+        if header.is_empty() {
+            break;
+        }
+        match header.split_once(':') {
+            Some((k, v)) => headers.push((k.trim().to_string(), v.trim().to_string())),
+            None => return Err(bad_request("invalid header")),
+        }
+    }
+    let length = match header(&headers, "content-length") {
+        Some(l) => l
+            .parse::<usize>()
+            .map_err(|_| bad_request("invalid content length"))?,
+        None => 0,
+    };
+    if length > MAX_SIZE {
+        return Err(Response::text(413, "payload too large"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| read_error(e, "incomplete body"))?;
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query: url::parse_query(query).map_err(|e| bad_request(&e))?,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+/// Value of the first header with the name, ignoring case
+pub fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Cookie name-value pairs of the `Cookie` request header, e.g. `a=1; b=2`
+pub fn parse_cookies(cookie: &str) -> Vec<(String, String)> {
+    cookie
+        .split(';')
+        .filter_map(|c| c.trim().split_once('='))
+        .map(|(k, v)| (k.to_string(), v.trim_matches('"').to_string()))
+        .collect()
+}
+
+/// Part of a `multipart/form-data` body, a file upload if it has a file name
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormPart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub content: String,
+}
+
+/// `application/x-www-form-urlencoded` body, spaces are encoded as `+`
+pub fn form_encode(fields: &[(String, String)]) -> String {
+    let encode = |s: &str| url::encode(s).replace("%20", "+");
+    fields
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// `multipart/form-data` body with parts separated by the boundary. Boundary must not occur in
+/// part contents.
+pub fn multipart(parts: &[FormPart], boundary: &str) -> String {
+    // quotes and line breaks would end the header parameter
+    let quote = |s: &str| {
+        s.replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+    let mut body = String::new();
+    for part in parts {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            boundary,
+            quote(&part.name)
+        ));
+        if let Some(filename) = &part.filename {
+            body.push_str(&format!("; filename=\"{}\"", quote(filename)));
+        }
+        body.push_str("\r\n");
+        if let Some(content_type) = &part.content_type {
+            body.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        body.push_str(&format!("\r\n{}\r\n", part.content));
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body
+}
+
+/// Options of client requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientOptions {
+    /// Timeout of connecting and of every read and write
+    pub timeout: Option<Duration>,
+    /// Whether server certificates of HTTPS requests are verified
+    pub verify: bool,
+    /// PEM file with CA certificates trusted instead of the bundled root certificates
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for ClientOptions {
+    fn default() -> ClientOptions {
+        ClientOptions {
+            timeout: None,
+            verify: true,
+            ca_bundle: None,
+        }
+    }
+}
+
+/// Send HTTP/1.1 request to the `http` or `https` url and read the response. Connection is
+/// closed after the response, redirects are not followed. Method and headers are checked, so
+/// that they cannot split the request.
+pub fn fetch(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body: &str,
+    options: &ClientOptions,
+) -> Result<Response, String> {
+    if !is_token(method) {
+        return Err(format!("invalid method '{}'", method.escape_debug()));
+    }
+    check_headers(headers)?;
+    let tls = match url.scheme.as_str() {
+        "http" => false,
+        "https" => true,
+        s => {
+            return Err(format!(
+                "unsupported scheme '{}', expected http or https",
+                s
+            ))
+        }
+    };
+    let mut target = match url.path.as_str() {
+        "" => "/".to_string(),
+        p => p.to_string(),
+    };
+    if !url.query.is_empty() {
+        target.push_str(&format!("?{}", url::encode_query(&url.query)));
+    }
+    if format!("{}{}", url.host, target).contains(|c: char| c.is_whitespace() || c.is_control()) {
+        return Err(format!("invalid url '{}'", url.to_string().escape_debug()));
+    }
+
+    // IPv6 address is bracketed only in the url
+    let host = url.host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port.unwrap_or(if tls { 443 } else { 80 });
+    let tcp = connect(host, port, options.timeout)?;
+    tcp.set_read_timeout(options.timeout).ok();
+    tcp.set_write_timeout(options.timeout).ok();
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, target, url.host);
+    for (k, v) in headers {
+        if !k.eq_ignore_ascii_case("host")
+            && !k.eq_ignore_ascii_case("content-length")
+            && !k.eq_ignore_ascii_case("connection")
+        {
+            head.push_str(&format!("{}: {}\r\n", k, v));
+        }
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    ));
+    let error = |e: std::io::Error| match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => "request timed out".to_string(),
+        _ => e.to_string(),
+    };
+    if tls {
+        let config = tls::client_config(options.verify, options.ca_bundle.as_deref())?;
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|_| format!("invalid server name '{}'", host))?;
+        let connection = ClientConnection::new(config, name).map_err(|e| e.to_string())?;
+        let mut stream = StreamOwned::new(connection, tcp);
+        stream.write_all(head.as_bytes()).map_err(error)?;
+        read_response(stream).map_err(error)
+    } else {
+        let mut stream = tcp;
+        stream.write_all(head.as_bytes()).map_err(error)?;
+        read_response(stream).map_err(error)
+    }
+}
+
+fn connect(host: &str, port: u16, timeout: Option<Duration>) -> Result<TcpStream, String> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("unable to resolve {}: {}", host, e))?;
+    let mut last_error = format!("unable to resolve {}", host);
+    for addr in addrs {
+        let res = match timeout {
+            Some(t) => TcpStream::connect_timeout(&addr, t),
+            None => TcpStream::connect(addr),
+        };
+        match res {
+            Ok(s) => return Ok(s),
+            Err(e) => last_error = format!("unable to connect to {}: {}", addr, e),
+        }
+    }
+    Err(last_error)
+}
+
+/// Read HTTP/1.x response, body is delimited by `Content-Length`, chunked transfer encoding or
+/// the end of the stream. Response with headers or body larger than the limits is rejected
+pub fn read_response(stream: impl Read) -> std::io::Result<Response> {
+    let invalid = |msg: &str| std::io::Error::new(ErrorKind::InvalidData, msg.to_string());
+    let too_large = || invalid("response body is too large");
+    // head and chunk sizes are limited separately from the body
+    let mut reader = BufReader::new(stream.take((MAX_SIZE + MAX_RESPONSE_SIZE) as u64));
+    let read_line = |reader: &mut BufReader<_>| {
+        let mut line = String::new();
+        match reader.read_line(&mut line)? {
+            0 => Err(std::io::Error::from(ErrorKind::UnexpectedEof)),
+            _ => Ok(line.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    };
+    let status_line = read_line(&mut reader)?;
+    let status = match status_line.split(' ').collect::<Vec<_>>()[..] {
+        [version, status, ..] if version.starts_with("HTTP/1.") => status
+            .parse::<u16>()
+            .map_err(|_| invalid("invalid status code"))?,
+        _ => return Err(invalid("invalid status line")),
+    };
+    let mut headers = vec![];
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        match line.split_once(':') {
+            Some((k, v)) => headers.push((k.trim().to_string(), v.trim().to_string())),
+            None => return Err(invalid("invalid header")),
+        }
+    }
+    let mut body = vec![];
+    let chunked =
+        header(&headers, "transfer-encoding").is_some_and(|e| e.eq_ignore_ascii_case("chunked"));
+    if chunked {
+        loop {
+            let size = read_line(&mut reader)?;
+            let size = size.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk"))?;
+            if size == 0 {
+                break;
+            }
+            let start = body.len();
+            let end = start
+                .checked_add(size)
+                .filter(|e| *e <= MAX_RESPONSE_SIZE)
+                .ok_or_else(too_large)?;
+            body.resize(end, 0);
+            reader.read_exact(&mut body[start..])?;
+            read_line(&mut reader)?;
+        }
+    } else if let Some(length) = header(&headers, "content-length") {
+        let length = length
+            .parse::<u64>()
+            .map_err(|_| invalid("invalid content length"))?;
+        if length > MAX_RESPONSE_SIZE as u64 {
+            return Err(too_large());
+        }
+        reader.take(length).read_to_end(&mut body)?;
+    } else {
+        match (&mut reader)
+            .take(MAX_RESPONSE_SIZE as u64 + 1)
+            .read_to_end(&mut body)
+        {
+            // TLS servers often close the connection without notifying
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {}
+            res => {
+                res?;
+            }
+        }
+    }
+    if body.len() > MAX_RESPONSE_SIZE {
+        return Err(too_large());
+    }
+    Ok(Response {
+        status,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn read_and_respond() {
+        let raw =
+            "POST /hook?a=1&b=x+y HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello";
+        assert_eq!(
+            read_request(raw.as_bytes()),
+            Ok(Request {
+                method: "POST".to_string(),
+                path: "/hook".to_string(),
+                query: vec![
+                    ("a".to_string(), "1".to_string()),
+                    ("b".to_string(), "x y".to_string())
+                ],
+                headers: vec![
+                    ("Host".to_string(), "localhost".to_string()),
+                    ("Content-Length".to_string(), "5".to_string())
+                ],
+                body: "hello".to_string(),
+            })
+        );
+        assert_eq!(
+            read_request("GET /\r\n\r\n".as_bytes()).unwrap_err().status,
+            400
+        );
+        let huge = "POST / HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n";
+        assert_eq!(read_request(huge.as_bytes()).unwrap_err().status, 413);
+
+        let mut out = vec![];
+        Response::text(404, "no").write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 2\r\nConnection: close\r\n\r\nno"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let with_header = |k: &str, v: &str| Response {
+            status: 200,
+            headers: vec![(k.to_string(), v.to_string())],
+            body: String::new(),
+        };
+        let mut out = vec![];
+        let injected = with_header("X-Name", "a\r\nSet-Cookie: session=evil");
+        assert!(injected.write(&mut out).is_err());
+        assert!(with_header("X Name", "a").write(&mut out).is_err());
+        assert!(with_header("X-Name:", "a").write(&mut out).is_err());
+        assert!(with_header("X-Name", "a\0").write(&mut out).is_err());
+        assert!(out.is_empty());
+        assert!(with_header("X-Name", "a; b=\"c\"").write(&mut out).is_ok());
+    }
+
+    #[test]
+    fn reject_invalid_requests_and_responses() {
+        let options = ClientOptions {
+            timeout: None,
+            verify: true,
+            ca_bundle: None,
+        };
+        let url = Url::parse("http://localhost:1/").unwrap();
+        let fetch = |method: &str, headers: &[(String, String)]| {
+            fetch(method, &url, headers, "", &options).unwrap_err()
+        };
+        assert_eq!(
+            fetch("GET / HTTP/1.1\r\nX:", &[]),
+            "invalid method 'GET / HTTP/1.1\\r\\nX:'"
+        );
+        let injected = [("X-Name".to_string(), "a\r\nHost: evil".to_string())];
+        assert_eq!(fetch("GET", &injected), "invalid value of header 'X-Name'");
+        let url = Url::parse("http://localhost:1/a HTTP/1.1\r\nX: b").unwrap();
+        assert!(super::fetch("GET", &url, &[], "", &options)
+            .unwrap_err()
+            .starts_with("invalid url"));
+
+        let huge = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\n";
+        assert!(read_response(huge.as_bytes()).is_err());
+        let huge = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", u64::MAX);
+        assert!(read_response(huge.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn form_bodies() {
+        let fields = vec![
+            ("q".to_string(), "a b&c".to_string()),
+            ("é".to_string(), "".to_string()),
+        ];
+        assert_eq!(form_encode(&fields), "q=a+b%26c&%C3%A9=");
+
+        let parts = vec![
+            FormPart {
+                name: "title".to_string(),
+                filename: None,
+                content_type: None,
+                content: "hi".to_string(),
+            },
+            FormPart {
+                name: "file".to_string(),
+                filename: Some("a\"b.txt".to_string()),
+                content_type: Some("text/plain".to_string()),
+                content: "x\ny".to_string(),
+            },
+        ];
+        assert_eq!(
+            multipart(&parts, "XYZ"),
+            "--XYZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nhi\r\n\
+             --XYZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a%22b.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nx\ny\r\n--XYZ--\r\n"
+        );
+    }
+
+    #[test]
+    fn cookies() {
+        assert_eq!(
+            parse_cookies("a=1; b=\"two\";c"),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "two".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn fetch_and_read_response() {
+        let chunked = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=1\r\nde\r\n0\r\n\r\n";
+        let response = read_response(chunked.as_bytes()).unwrap();
+        assert_eq!((response.status, response.body.as_str()), (200, "abcde"));
+        let unframed = "HTTP/1.0 404 Not Found\r\n\r\ngone";
+        let response = read_response(unframed.as_bytes()).unwrap();
+        assert_eq!((response.status, response.body.as_str()), (404, "gone"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let request = read_request(&stream).unwrap();
+            Response::text(201, &format!("{} {}", request.path, request.body))
+                .write(&mut &stream)
+                .unwrap();
+            request
+        });
+        let url = Url::parse(&format!("http://localhost:{}/a?q=x+y", port)).unwrap();
+        let headers = vec![("X-Id".to_string(), "1".to_string())];
+        let response = fetch("POST", &url, &headers, "hi", &ClientOptions::default()).unwrap();
+        assert_eq!((response.status, response.body.as_str()), (201, "/a hi"));
+        let request = server.join().unwrap();
+        assert_eq!(request.query, vec![("q".to_string(), "x y".to_string())]);
+        assert_eq!(header(&request.headers, "x-id"), Some("1"));
+
+        let url = Url::parse("ftp://localhost").unwrap();
+        assert_eq!(
+            fetch("GET", &url, &[], "", &ClientOptions::default()),
+            Err("unsupported scheme 'ftp', expected http or https".to_string())
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::{Assignee, AstPair, TypeAnnotation, ValueType};
+use crate::error::Error;
+use crate::interpret::context::{Context, TypeDefinition};
+use crate::interpret::evaluate::Evaluate;
+use crate::interpret::value::Value;
+
+/// Validate value bound to an annotated assignee, e.g. `a: I = 4` or `(a: [C]) -> a`.
+/// Does nothing unless type checking is enabled with `--check-types`.
+pub fn check_assignee<T: Evaluate>(
+    assignee: &AstPair<Assignee>,
+    expression: &T,
+    ctx: &mut RefMut<Context>,
+) -> Result<(), Error> {
+    let (identifier, annotation) = match &assignee.1 {
+        Assignee::Identifier {
+            identifier,
+            annotation: Some(annotation),
+        } if ctx.check_types => (identifier, annotation),
+        _ => return Ok(()),
+    };
+    let value = expression.eval(ctx, false)?;
+    match matches_annotation(&value.1, &annotation.1, ctx) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Error::from_span(
+            &value.0,
+            &ctx.ast_context,
+            format!(
+                "type mismatch for '{}': expected {}, found {}",
+                identifier.1,
+                annotation.1,
+                value.1.value_type()
+            ),
+        )),
+        Err(e) => Err(Error::from_span(&annotation.0, &ctx.ast_context, e)),
+    }
+}
+
+/// Whether value is of annotated type; fails if annotation refers to an unknown user type
+pub fn matches_annotation(
+    value: &Value,
+    annotation: &TypeAnnotation,
+    ctx: &Context,
+) -> Result<bool, String> {
+    match annotation {
+        TypeAnnotation::Type(ValueType::Any) => Ok(true),
+        TypeAnnotation::Type(vt) => Ok(value.value_type() == Value::Type(vt.clone())),
+        TypeAnnotation::Named(name) => {
+            let vt = match ctx.types.get(&name.0) {
+                Some(TypeDefinition::Struct { .. }) => ValueType::Struct(name.0.clone()),
+                Some(TypeDefinition::Enum { .. }) => ValueType::Enum(name.0.clone()),
+                None => return Err(format!("unknown type {}", name)),
+            };
+            Ok(value.value_type() == Value::Type(vt))
+        }
+        TypeAnnotation::List(item) => match value {
+            Value::List { items, .. } => {
+                for i in items {
+                    if !matches_annotation(i, &item.1, ctx)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+    }
+}
@@ -1,19 +1,72 @@
-use std::cell::RefMut;
-use std::collections::HashMap;
+use std::cell::{RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
 use log::error;
 
-use crate::ast::ast::{AstContext, AstPair, Expression, Identifier, Span, Statement};
+use crate::ast::ast::{
+    Assignee, AstContext, AstPair, Expression, Identifier, Operand, Span, Statement, ValueType,
+};
 use crate::error::Error;
+use crate::fuzzy::suggest;
 use crate::interpret::destructure::assign_definitions;
+use crate::interpret::module::import;
+use crate::interpret::observer::{EvalObserver, Observers};
 use crate::interpret::value::Value;
-use crate::stdlib::lib::stdlib;
+use crate::random::Rng;
+use crate::stdlib::lib::shared_stdlib;
+use crate::stdlib::process::Process;
+use crate::stdlib::scheduler::Job;
+
+/// Immutable definitions shared between contexts
+pub type Definitions = Arc<HashMap<Identifier, Definition>>;
 
 #[derive(Debug, Clone)]
 pub struct Context {
     pub ast_context: AstContext,
+    pub stdlib: Definitions,
     pub scope_stack: Vec<Scope>,
+    pub observers: Observers,
+    pub types: HashMap<String, TypeDefinition>,
+    /// Validate values against type annotations at bind time
+    pub check_types: bool,
+    /// Deprecated stdlib functions already reported during this run
+    pub deprecation_warned: HashSet<String>,
+    /// Buffer printed lines are written to instead of stdout, e.g. to compare output of an
+    /// example with the expected one
+    pub output: Option<Rc<RefCell<String>>>,
+    /// Generator used by the random package, seeded with the current time unless `seed` is called
+    pub rng: Rng,
+    /// Last call times of functions wrapped by `throttle` or `debounce`, by start and end of the
+    /// wrapping call span, since values are immutable and the wrapper cannot keep its own state
+    pub call_times: HashMap<(usize, usize), Instant>,
+    /// Functions added by `every` or `schedule`, called by `run_scheduler`
+    pub scheduled: Vec<Job>,
+    /// Child processes started by `spawn`, by process id. Shared by context clones, since a
+    /// process and its pipes cannot be copied
+    pub processes: HashMap<u32, Rc<RefCell<Process>>>,
+    /// Stdlib definitions available to the program, all of them if not set. Used by embedders
+    /// to evaluate untrusted expressions with a fixed set of functions and operators
+    pub allowed: Option<Arc<HashSet<Identifier>>>,
+    /// Path of the program source file, imports are resolved relative to it
+    pub file: Option<PathBuf>,
+    /// Files being imported, the innermost last, used to detect circular imports
+    pub importing: Vec<PathBuf>,
+    /// Definitions of already imported files, by canonical path
+    pub modules: HashMap<PathBuf, Vec<(Identifier, Definition)>>,
+    /// Read and write .noc AST cache next to imported files
+    pub cache_ast: bool,
+}
+
+/// User type declared with struct or enum definition
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeDefinition {
+    Struct { fields: Vec<Identifier> },
+    Enum { values: Vec<Identifier> },
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +80,21 @@ pub struct Scope {
 }
 
 impl Scope {
+    /// Definitions made in this scope, sorted by name
+    pub fn entries(&self) -> Vec<DefinitionEntry> {
+        let mut entries = self
+            .definitions
+            .iter()
+            .map(|(name, d)| DefinitionEntry {
+                name: name.clone(),
+                kind: d.kind(),
+                span: d.span(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+        entries
+    }
+
     pub fn new(name: String) -> Scope {
         Scope {
             name,
@@ -69,17 +137,69 @@ impl Scope {
     }
 }
 
+/// System function taking the span of the call site, since it can be called outside of a
+/// function call scope, e.g. when referenced eagerly
+pub type SystemFn =
+    fn(&Span, Vec<AstPair<Value>>, &mut RefMut<Context>) -> Result<AstPair<Value>, Error>;
+
+/// Value of a user definition, set once it is evaluated
+pub type Memo = Arc<OnceLock<AstPair<Value>>>;
+
 #[derive(Clone)]
 pub enum Definition {
-    User(AstPair<Identifier>, AstPair<Expression>),
-    System(fn(Vec<AstPair<Value>>, &mut RefMut<Context>) -> Result<AstPair<Value>, Error>),
+    User(AstPair<Identifier>, AstPair<Expression>, Memo),
+    System(SystemFn),
     Value(AstPair<Value>),
 }
 
+impl Definition {
+    /// Definition evaluated on first use and memoized, so that side effects of the expression,
+    /// e.g. spawning a process, happen once however many times the definition is referenced
+    pub fn user(identifier: AstPair<Identifier>, expression: AstPair<Expression>) -> Definition {
+        Definition::User(identifier, expression, Arc::default())
+    }
+
+    pub fn kind(&self) -> DefinitionKind {
+        match self {
+            Definition::User(..) => DefinitionKind::User,
+            Definition::System(_) => DefinitionKind::System,
+            Definition::Value(_) => DefinitionKind::Value,
+        }
+    }
+
+    /// Where definition is made, none for stdlib functions
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Definition::User(i, ..) => Some(i.0.clone()),
+            Definition::System(_) => None,
+            Definition::Value(v) => Some(v.0.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DefinitionKind {
+    /// Stdlib function
+    System,
+    /// Assignment in the program, evaluated on first use
+    User,
+    /// Evaluated value, e.g. function argument or embedder binding
+    Value,
+}
+
+/// Description of a definition for inspection tools, e.g. REPL `:env` command or debugger
+/// variables view
+#[derive(Debug, PartialEq, Clone)]
+pub struct DefinitionEntry {
+    pub name: Identifier,
+    pub kind: DefinitionKind,
+    pub span: Option<Span>,
+}
+
 impl Debug for Definition {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Definition::User(i, exp) => write!(f, "{:?} = {:?}", i, exp),
+            Definition::User(i, exp, _) => write!(f, "{:?} = {:?}", i, exp),
             Definition::System(_) => write!(f, "<fn>"),
             Definition::Value(v) => write!(f, "{:?}", v),
         }
@@ -87,20 +207,188 @@ impl Debug for Definition {
 }
 
 impl Context {
+    /// Context with stdlib definitions, initialized once and shared by all contexts
     pub fn stdlib(a_ctx: AstContext) -> Context {
-        let defs = stdlib().into_iter().flat_map(|p| p.definitions).collect();
+        Context::new(a_ctx, shared_stdlib())
+    }
+
+    pub fn new(a_ctx: AstContext, stdlib: Definitions) -> Context {
         Context {
             ast_context: a_ctx,
-            scope_stack: vec![Scope::new("stdlib".to_string()).with_definitions(defs)],
+            stdlib,
+            scope_stack: vec![Scope::new("root".to_string())],
+            observers: Observers::default(),
+            types: HashMap::new(),
+            check_types: false,
+            deprecation_warned: HashSet::new(),
+            output: None,
+            rng: Rng::from_time(),
+            call_times: HashMap::new(),
+            scheduled: vec![],
+            processes: HashMap::new(),
+            allowed: None,
+            file: None,
+            importing: vec![],
+            modules: HashMap::new(),
+            cache_ast: false,
+        }
+    }
+
+    pub fn with_check_types(&self, check_types: bool) -> Self {
+        let mut new = self.clone();
+        new.check_types = check_types;
+        new
+    }
+
+    pub fn with_file(&self, file: PathBuf) -> Self {
+        let mut new = self.clone();
+        new.file = Some(file);
+        new
+    }
+
+    pub fn with_cache_ast(&self, cache_ast: bool) -> Self {
+        let mut new = self.clone();
+        new.cache_ast = cache_ast;
+        new
+    }
+
+    pub fn with_allowed(&self, allowed: HashSet<Identifier>) -> Self {
+        let mut new = self.clone();
+        new.allowed = Some(Arc::new(allowed));
+        new
+    }
+
+    /// Whether stdlib definition can be used, always true unless context has a whitelist
+    pub fn is_allowed(&self, identifier: &Identifier) -> bool {
+        match &self.allowed {
+            Some(allowed) => allowed.contains(identifier),
+            None => true,
         }
     }
 
+    pub fn with_output(&self, output: Rc<RefCell<String>>) -> Self {
+        let mut new = self.clone();
+        new.output = Some(output);
+        new
+    }
+
+    /// Print line to the output buffer if there is one, to stdout otherwise
+    pub fn println(&self, line: &str) {
+        match &self.output {
+            Some(o) => {
+                let mut o = o.borrow_mut();
+                o.push_str(line);
+                o.push('\n');
+            }
+            None => println!("{}", line),
+        }
+    }
+
+    pub fn with_observer(&self, observer: Rc<RefCell<dyn EvalObserver>>) -> Self {
+        let mut new = self.clone();
+        new.observers.0.push(observer);
+        new
+    }
+
+    /// Register user type if expression is a struct or enum definition, e.g. `Point = #{x, y}`.
+    /// Type name is defined as a type value, which also acts as a struct constructor.
+    pub fn register_type(
+        &mut self,
+        assignee: &AstPair<Assignee>,
+        expression: &AstPair<Expression>,
+    ) -> Option<(Identifier, Definition)> {
+        let identifier = match &assignee.1 {
+            Assignee::Identifier { identifier, .. } => identifier,
+            _ => return None,
+        };
+        let name = identifier.1 .0.clone();
+        let (definition, vt) = match &expression.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::StructDefinition { fields } => (
+                    TypeDefinition::Struct {
+                        fields: fields.iter().map(|f| f.1.clone()).collect(),
+                    },
+                    ValueType::Struct(name.clone()),
+                ),
+                Operand::EnumDefinition { values } => (
+                    TypeDefinition::Enum {
+                        values: values.iter().map(|v| v.1.clone()).collect(),
+                    },
+                    ValueType::Enum(name.clone()),
+                ),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        self.types.insert(name, definition);
+        Some((
+            identifier.1.clone(),
+            Definition::Value(AstPair::from_span(&identifier.0, Value::Type(vt))),
+        ))
+    }
+
+    /// Scopes from the innermost to the root one
+    pub fn scopes(&self) -> impl Iterator<Item = &Scope> {
+        self.scope_stack.iter().rev()
+    }
+
+    /// Definitions visible from the current scope, sorted by name. Shadowed definitions are
+    /// omitted, stdlib functions are only included if requested.
+    pub fn visible_definitions(&self, include_stdlib: bool) -> Vec<DefinitionEntry> {
+        let mut seen = HashSet::new();
+        let mut entries = self
+            .scopes()
+            .flat_map(|s| s.entries())
+            .chain(include_stdlib.then(|| self.stdlib_entries()).into_iter().flatten())
+            .filter(|e| seen.insert(e.name.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+        entries
+    }
+
+    fn stdlib_entries(&self) -> Vec<DefinitionEntry> {
+        self.stdlib
+            .iter()
+            .filter(|(name, _)| self.is_allowed(name))
+            .map(|(name, d)| DefinitionEntry {
+                name: name.clone(),
+                kind: d.kind(),
+                span: None,
+            })
+            .collect()
+    }
+
+    /// Names of definitions visible from the current scope, from the innermost scope to stdlib.
+    /// Shadowed names are repeated.
+    pub fn definition_names(&self) -> impl Iterator<Item = &Identifier> {
+        self.scope_stack
+            .iter()
+            .rev()
+            .flat_map(|s| s.definitions.keys())
+            .chain(self.stdlib.keys().filter(|i| self.is_allowed(i)))
+    }
+
+    /// Up to three names of definitions similar to the identifier, the closest first, used for
+    /// suggestions when identifier is not found
+    pub fn similar_definitions(&self, identifier: &Identifier) -> Vec<Identifier> {
+        let names = self.definition_names().map(|i| i.0.as_str());
+        suggest(&identifier.0, names, 3)
+            .into_iter()
+            .map(Identifier::new)
+            .collect()
+    }
+
     pub fn find_definition(&self, identifier: &Identifier) -> Option<Definition> {
         let r = self
             .scope_stack
             .iter()
             .rev()
             .filter_map(|s| s.definitions.get(&identifier))
+            .chain(
+                self.stdlib
+                    .get(identifier)
+                    .filter(|_| self.is_allowed(identifier)),
+            )
             .cloned()
             .next();
         if let None = r {
@@ -122,7 +410,11 @@ impl Statement {
             Statement::Assignment {
                 assignee,
                 expression,
-            } => assign_definitions(assignee, expression, ctx, |i, e| Definition::User(i, e)),
+            } => match ctx.register_type(&assignee, &expression) {
+                Some(d) => Ok(vec![d]),
+                None => assign_definitions(assignee, expression, ctx, Definition::user),
+            },
+            Statement::Import(path) => import(&path, ctx),
             _ => Ok(vec![]),
         }
     }
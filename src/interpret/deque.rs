@@ -0,0 +1,235 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::interpret::value::Value;
+
+/// Persistent double-ended queue. Copies share their items, so cloning is O(1) and pushing or
+/// popping at either end is amortized O(1) without affecting other copies.
+///
+/// Items are kept in two stacks: the front one starting with the first item and the back one
+/// starting with the last item. When one of them runs out, the other is split in half.
+#[derive(Clone, Default)]
+pub struct Deque {
+    front: Stack,
+    back: Stack,
+}
+
+impl Deque {
+    pub fn len(&self) -> usize {
+        self.front.len + self.back.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Items from the first to the last one
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        let back = self.back.iter().collect::<Vec<_>>();
+        self.front.iter().chain(back.into_iter().rev())
+    }
+
+    pub fn push_front(&self, value: Value) -> Deque {
+        Deque {
+            front: self.front.push(value),
+            back: self.back.clone(),
+        }
+    }
+
+    pub fn push_back(&self, value: Value) -> Deque {
+        Deque {
+            front: self.front.clone(),
+            back: self.back.push(value),
+        }
+    }
+
+    pub fn front(&self) -> Option<&Value> {
+        self.front.peek().or_else(|| self.back.iter().last())
+    }
+
+    pub fn back(&self) -> Option<&Value> {
+        self.back.peek().or_else(|| self.front.iter().last())
+    }
+
+    /// Deque without the first item, `None` if it is empty
+    pub fn pop_front(&self) -> Option<Deque> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.front.len == 0 {
+            let (back, front) = Self::split(&self.back);
+            return Deque { front, back }.pop_front();
+        }
+        Some(Deque {
+            front: self.front.pop()?,
+            back: self.back.clone(),
+        })
+    }
+
+    /// Deque without the last item, `None` if it is empty
+    pub fn pop_back(&self) -> Option<Deque> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.back.len == 0 {
+            let (front, back) = Self::split(&self.front);
+            return Deque { front, back }.pop_back();
+        }
+        Some(Deque {
+            front: self.front.clone(),
+            back: self.back.pop()?,
+        })
+    }
+
+    /// Split stack into its top half and the reversed bottom half, so that both halves start
+    /// at the ends of the deque
+    fn split(stack: &Stack) -> (Stack, Stack) {
+        if stack.len == 0 {
+            return (Stack::default(), Stack::default());
+        }
+        let items = stack.iter().cloned().collect::<Vec<_>>();
+        let (top, bottom) = items.split_at(items.len() / 2);
+        (
+            top.iter().rev().cloned().collect(),
+            bottom.iter().cloned().collect(),
+        )
+    }
+}
+
+impl FromIterator<Value> for Deque {
+    fn from_iter<T: IntoIterator<Item = Value>>(iter: T) -> Self {
+        Deque {
+            front: Stack::default(),
+            back: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Debug for Deque {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Deque {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl PartialOrd for Deque {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+/// Immutable linked stack sharing its tail with the stacks it was pushed onto
+#[derive(Clone, Default)]
+struct Stack {
+    head: Option<Arc<Node>>,
+    len: usize,
+}
+
+struct Node {
+    value: Value,
+    next: Option<Arc<Node>>,
+}
+
+impl Stack {
+    fn push(&self, value: Value) -> Stack {
+        Stack {
+            head: Some(Arc::new(Node {
+                value,
+                next: self.head.clone(),
+            })),
+            len: self.len + 1,
+        }
+    }
+
+    fn pop(&self) -> Option<Stack> {
+        self.head.as_ref().map(|n| Stack {
+            head: n.next.clone(),
+            len: self.len - 1,
+        })
+    }
+
+    fn peek(&self) -> Option<&Value> {
+        self.head.as_ref().map(|n| &n.value)
+    }
+
+    /// Items from the top one
+    fn iter(&self) -> impl Iterator<Item = &Value> {
+        let mut next = self.head.as_deref();
+        std::iter::from_fn(move || {
+            let node = next?;
+            next = node.next.as_deref();
+            Some(&node.value)
+        })
+    }
+}
+
+/// Last item of the iterator ends up on top
+impl FromIterator<Value> for Stack {
+    fn from_iter<T: IntoIterator<Item = Value>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Stack::default(), |stack, v| stack.push(v))
+    }
+}
+
+impl Drop for Stack {
+    /// Drop unshared nodes one by one, recursive drop of a long stack overflows
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(node) = next {
+            match Arc::try_unwrap(node) {
+                Ok(mut n) => next = n.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(d: &Deque) -> Vec<i128> {
+        d.iter()
+            .map(|v| match v {
+                Value::I(i) => *i,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn push_and_pop_both_ends() {
+        let d = (1..=4).map(Value::I).collect::<Deque>();
+        let d = d.push_front(Value::I(0)).push_back(Value::I(5));
+        assert_eq!(items(&d), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(d.front(), Some(&Value::I(0)));
+        assert_eq!(d.back(), Some(&Value::I(5)));
+
+        let popped = d
+            .pop_front()
+            .unwrap()
+            .pop_front()
+            .unwrap()
+            .pop_back()
+            .unwrap();
+        assert_eq!(items(&popped), vec![2, 3, 4]);
+        // popping does not affect the original deque
+        assert_eq!(items(&d), vec![0, 1, 2, 3, 4, 5]);
+
+        let single = Deque::default().push_back(Value::I(1));
+        assert_eq!(single.front(), Some(&Value::I(1)));
+        assert!(single.pop_back().unwrap().pop_front().is_none());
+    }
+
+    #[test]
+    fn drop_long_deque() {
+        let d = (0..1_000_000).map(Value::I).collect::<Deque>();
+        assert_eq!(d.pop_front().unwrap().len(), 999_999);
+    }
+}
@@ -21,7 +21,7 @@ where
     F: Fn(AstPair<Identifier>, T) -> Definition,
 {
     match assignee.clone().1 {
-        Assignee::Identifier(i) => Ok(vec![(i.clone().1, f(i, expression))]),
+        Assignee::Identifier { identifier: i, .. } => Ok(vec![(i.clone().1, f(i, expression))]),
         Assignee::Hole => Ok(vec![]),
         Assignee::DestructureList(dl) => destructure_list(dl, expression, assignee.clone().0, ctx),
     }
@@ -5,14 +5,19 @@ use std::ops::Deref;
 use log::debug;
 
 use crate::ast::ast::{
-    AstPair, BinaryOperator, Block, Expression, FunctionCall, FunctionInit, Identifier, Operand,
-    Statement,
+    Assignee, AstPair, BinaryOperator, Block, Expression, FunctionCall, FunctionInit, Identifier,
+    Operand, Span, Statement, ValueType,
 };
 use crate::error::Error;
-use crate::interpret::context::{Context, Definition, Scope};
+use crate::interpret::annotation::check_assignee;
+use crate::interpret::context::{Context, Definition, Memo, Scope, TypeDefinition};
 use crate::interpret::destructure::assign_definitions;
 use crate::interpret::matcher::match_expression;
+use crate::interpret::module::import;
+use crate::interpret::seq::Seq;
 use crate::interpret::value::Value;
+use crate::typecheck::signature::stdlib_signature;
+use crate::typecheck::types::Type;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum FunctionCallType {
@@ -57,16 +62,25 @@ impl Evaluate for AstPair<Statement> {
     fn eval(&self, ctx: &mut RefMut<Context>, eager: bool) -> Result<AstPair<Value>, Error> {
         let unit = Ok(self.map(|_| Value::Unit));
         debug!("eval {:?}, eager: {}", &self, eager);
-        match &self.1 {
+        ctx.observers
+            .clone()
+            .notify(ctx, |o, c| o.on_statement_enter(self, c));
+        let res = match &self.1 {
             Statement::Expression(exp) => exp.eval(ctx, eager),
             Statement::Assignment {
                 assignee,
                 expression,
             } => {
-                let defs =
-                    assign_definitions(assignee.clone(), expression.clone(), ctx, |i, e| {
-                        Definition::User(i, e)
-                    })?;
+                check_assignee(assignee, expression, ctx)?;
+                let defs = match ctx.register_type(assignee, expression) {
+                    Some(d) => vec![d],
+                    None => assign_definitions(
+                        assignee.clone(),
+                        expression.clone(),
+                        ctx,
+                        Definition::user,
+                    )?,
+                };
                 ctx.scope_stack.last_mut().unwrap().definitions.extend(defs);
                 unit
             }
@@ -79,7 +93,18 @@ impl Evaluate for AstPair<Statement> {
                 debug!("return value: {:?}", &return_value);
                 unit
             }
+            Statement::Import(path) => {
+                let defs = import(path, ctx)?;
+                ctx.scope_stack.last_mut().unwrap().definitions.extend(defs);
+                unit
+            }
+        };
+        if let Ok(r) = &res {
+            ctx.observers
+                .clone()
+                .notify(ctx, |o, c| o.on_statement_exit(self, r, c));
         }
+        res
     }
 }
 
@@ -88,10 +113,12 @@ impl Evaluate for AstPair<Expression> {
         debug!("eval {:?}, eager: {}", &self, eager);
         match &self.1 {
             Expression::Operand(op) => op.eval(ctx, eager),
+            Expression::Group(e) => e.eval(ctx, eager).map(|v| AstPair(self.0.clone(), v.1)),
             Expression::Unary { operator, operand } => {
                 let fc = FunctionCall {
                     identifier: operator.map(|o| Identifier(format!("{}", o))),
                     arguments: vec![operand.deref().clone()],
+                    named_arguments: vec![],
                 };
                 let a = self.map(|_| fc.clone());
                 function_call(&a, ctx, FunctionCallType::Operator)
@@ -103,8 +130,17 @@ impl Evaluate for AstPair<Expression> {
             } => {
                 if operator.1 == BinaryOperator::Accessor {
                     let l = left_operand.eval(ctx, true)?;
-                    ctx.scope_stack.last_mut().unwrap().method_callee = Some(l);
-                    right_operand.eval(ctx, eager)
+                    if let Some(v) = access_member(&l, right_operand, ctx)? {
+                        return Ok(v);
+                    }
+                    method_call(l, right_operand, ctx)
+                } else if operator.1 == BinaryOperator::Pipe {
+                    let l = left_operand.eval(ctx, true)?;
+                    pipe_call(l, right_operand, ctx)
+                } else if let BinaryOperator::Range | BinaryOperator::RangeInclusive = operator.1 {
+                    let l = left_operand.eval(ctx, true)?;
+                    let r = right_operand.eval(ctx, true)?;
+                    range(self, &operator.1, l, r, ctx)
                 } else {
                     let fc = FunctionCall {
                         identifier: operator.map(|o| Identifier(format!("{}", o))),
@@ -113,69 +149,141 @@ impl Evaluate for AstPair<Expression> {
                             .map(|p| p.deref())
                             .cloned()
                             .collect(),
+                        named_arguments: vec![],
                     };
                     let a = self.map(|_| fc.clone());
-                    function_call(&a, ctx, FunctionCallType::Function)
+                    function_call(&a, ctx, FunctionCallType::Operator)
                 }
             }
             Expression::MatchExpression { .. } => {
                 let p_match = match_expression(self.clone(), ctx)?;
                 match p_match {
-                    Some((clause, pm)) => {
-                        ctx.scope_stack.push(
-                            Scope::new("<match_predicate>".to_string())
-                                .with_definitions(pm.into_iter().collect())
-                                .with_callee(Some(clause.0.clone())),
-                        );
-                        debug!("push scope {:?}", &ctx.scope_stack.last().unwrap());
-
-                        let res = clause.1.block.eval(ctx, true);
-                        let rv = &ctx.scope_stack.last().unwrap().return_value.clone();
-
-                        debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
-                        ctx.scope_stack.pop();
-
-                        if let Some(v) = rv {
-                            debug!("propagating return from match clause, value: {:?}", v);
-                            ctx.scope_stack.last_mut().unwrap().return_value = rv.clone();
-                        }
-
-                        res.map_err(|e| {
-                            Error::new_cause(
-                                e,
-                                "<match clause>".to_string(),
-                                &clause.1.block.0,
-                                &ctx.ast_context,
-                            )
-                        })
-                    }
+                    Some((clause, pm)) => eval_branch(
+                        "<match_predicate>",
+                        "<match clause>",
+                        &clause.1.block,
+                        pm,
+                        &clause.0,
+                        ctx,
+                    ),
                     None => {
                         debug!("no matches in match expression {:?}", &self);
                         Ok(self.map(|_| Value::Unit))
                     }
                 }
             }
+            Expression::IfExpression {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                let c = condition.eval(ctx, true)?;
+                let block = match c.1 {
+                    Value::B(true) => then_block,
+                    Value::B(false) => match else_block {
+                        Some(b) => b,
+                        None => return Ok(self.map(|_| Value::Unit)),
+                    },
+                    v => {
+                        return Err(Error::from_span(
+                            &c.0,
+                            &ctx.ast_context,
+                            format!("expected B condition, found {}", v.value_type()),
+                        ))
+                    }
+                };
+                eval_branch("<if_branch>", "<if branch>", block, vec![], &block.0, ctx)
+            }
         }
     }
 }
 
+/// Lazy range of integers, its items are not produced until the range is walked
+fn range(
+    expression: &AstPair<Expression>,
+    operator: &BinaryOperator,
+    start: AstPair<Value>,
+    end: AstPair<Value>,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    match (&start.1, &end.1) {
+        (Value::I(s), Value::I(e)) => {
+            let end = match operator {
+                BinaryOperator::RangeInclusive => e.saturating_add(1),
+                _ => *e,
+            };
+            Ok(expression.map(|_| Value::Seq(Seq::Range { start: *s, end })))
+        }
+        (s, e) => Err(Error::from_span(
+            &expression.0,
+            &ctx.ast_context,
+            format!(
+                "incompatible operands: {} {} {}",
+                s.value_type(),
+                operator,
+                e.value_type()
+            ),
+        )),
+    }
+}
+
+/// Evaluate block of a match clause or if branch in its own scope, propagating return from it
+fn eval_branch(
+    scope_name: &str,
+    cause: &str,
+    block: &AstPair<Block>,
+    definitions: Vec<(Identifier, Definition)>,
+    callee: &Span,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    ctx.scope_stack.push(
+        Scope::new(scope_name.to_string())
+            .with_definitions(definitions.into_iter().collect())
+            .with_callee(Some(callee.clone())),
+    );
+    debug!("push scope {:?}", &ctx.scope_stack.last().unwrap());
+
+    let res = block.eval(ctx, true);
+    let rv = &ctx.scope_stack.last().unwrap().return_value.clone();
+
+    debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
+    ctx.scope_stack.pop();
+
+    if let Some(v) = rv {
+        debug!("propagating return from {}, value: {:?}", cause, v);
+        ctx.scope_stack.last_mut().unwrap().return_value = rv.clone();
+    }
+
+    res.map_err(|e| Error::new_cause(e, cause.to_string(), &block.0, &ctx.ast_context))
+}
+
 pub fn function_call(
     function_call: &AstPair<FunctionCall>,
     ctx: &mut RefMut<Context>,
     call_type: FunctionCallType,
 ) -> Result<AstPair<Value>, Error> {
     let mut args: Vec<AstPair<Value>> = vec![];
-    if let Some(mc) = ctx.scope_stack.last().unwrap().method_callee.clone() {
+    // method callee belongs to this call only, it must not leak into calls that follow
+    if let Some(mc) = ctx.scope_stack.last_mut().unwrap().method_callee.take() {
         args.push(mc);
     }
-    args.extend(
-        function_call
-            .1
-            .arguments
-            .iter()
-            .map(|a| a.eval(ctx, false))
-            .collect::<Result<Vec<_>, _>>()?,
-    );
+    for a in &function_call.1.arguments {
+        // argument value is located at the call site, e.g. for errors about its type
+        match a.eval(ctx, false)?.1 {
+            // spread argument is expanded into positional arguments, e.g. `f(..args)`, operators
+            // take spread operands as is, e.g. `[0] + ..[1, 2]`
+            Value::List {
+                items,
+                spread: true,
+            } if call_type == FunctionCallType::Function => {
+                args.extend(items.into_iter().map(|i| AstPair::from_span(&a.0, i)))
+            }
+            v => args.push(AstPair::from_span(&a.0, v)),
+        }
+    }
+    if !function_call.1.named_arguments.is_empty() {
+        args = bind_named_arguments(function_call, args, ctx)?;
+    }
     let name = function_call.1.identifier.1.clone().0;
     ctx.scope_stack.push(
         Scope::new(name.clone())
@@ -183,26 +291,373 @@ pub fn function_call(
             .with_arguments(args.clone()),
     );
     debug!("push scope @{}", name);
+    ctx.observers
+        .clone()
+        .notify(ctx, |o, c| o.on_call(&name, &function_call.0, &args, c));
 
     let id = &function_call.1.identifier;
     debug!("function call {:?}, args: {:?}", &function_call, &args);
     let res = match ctx.find_definition(&id.1) {
-        Some(Definition::User(_, exp)) => exp.eval(ctx, true),
-        Some(Definition::System(f)) => f(args.clone(), ctx),
-        Some(Definition::Value(v)) => Ok(v),
+        // definition might evaluate to a function value, e.g. `h = compose(f, g)`
+        Some(Definition::User(_, exp, memo)) => {
+            eval_memoized(&exp, &memo, ctx).and_then(|v| v.eval(ctx, true))
+        }
+        Some(Definition::System(f)) => f(&function_call.0, args.clone(), ctx),
+        Some(Definition::Value(AstPair(_, Value::Type(ValueType::Struct(name))))) => {
+            construct_struct(&name, args, &function_call.0, ctx)
+        }
+        Some(Definition::Value(v)) => v.eval(ctx, true),
+        None if ctx.allowed.is_some() => Err(permission_denied(&function_call.0, &id.1, ctx)),
         None => Err(Error::from_span(
             &function_call.0,
             &ctx.ast_context,
-            format!("{} '{}' not found", call_type, id.1),
+            format!(
+                "{} '{}' not found{}",
+                call_type,
+                id.1,
+                did_you_mean(&id.1, ctx)
+            ),
         )),
     };
     debug!("function {:?} result {:?}", &id, &res);
 
     debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
     ctx.scope_stack.pop();
+    notify_result(&name, &function_call.0, &res, ctx);
     res.map_err(|e| Error::new_cause(e, id.1.to_string(), &function_call.0, &ctx.ast_context))
 }
 
+/// Positional arguments with named ones placed at positions of parameters with the same name
+fn bind_named_arguments(
+    function_call: &AstPair<FunctionCall>,
+    positional: Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Vec<AstPair<Value>>, Error> {
+    let id = &function_call.1.identifier;
+    let params = match parameter_names(&id.1, ctx) {
+        Some(ps) => ps,
+        None => {
+            return Err(Error::from_span(
+                &function_call.0,
+                &ctx.ast_context,
+                format!("'{}' does not accept named arguments", id.1),
+            ))
+        }
+    };
+    let mut slots = positional.into_iter().map(Some).collect::<Vec<_>>();
+    for (name, a) in &function_call.1.named_arguments {
+        let i = match params.iter().position(|p| p.as_ref() == Some(&name.1)) {
+            Some(i) => i,
+            None => {
+                return Err(Error::from_span(
+                    &name.0,
+                    &ctx.ast_context,
+                    format!("'{}' has no parameter '{}'", id.1, name.1),
+                ))
+            }
+        };
+        if slots.len() <= i {
+            slots.resize(i + 1, None);
+        }
+        if slots[i].is_some() {
+            return Err(Error::from_span(
+                &name.0,
+                &ctx.ast_context,
+                format!("argument '{}' is specified more than once", name.1),
+            ));
+        }
+        let v = a.eval(ctx, false)?;
+        slots[i] = Some(AstPair::from_span(&a.0, v.1));
+    }
+    slots
+        .into_iter()
+        .zip(&params)
+        .map(|(a, p)| {
+            a.ok_or_else(|| {
+                let p = p.as_ref().map_or("_".to_string(), |p| p.to_string());
+                Error::from_span(
+                    &function_call.0,
+                    &ctx.ast_context,
+                    format!("missing argument '{}' of '{}'", p, id.1),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Names of the function parameters, `None` for parameters that cannot be named, e.g. holes
+/// and destructured ones. Only functions defined with a parameter list, struct constructors and
+/// stdlib functions have known parameters
+fn parameter_names(identifier: &Identifier, ctx: &Context) -> Option<Vec<Option<Identifier>>> {
+    let function_params = |fi: &FunctionInit| {
+        fi.parameters
+            .iter()
+            .map(|p| match &p.1 {
+                Assignee::Identifier { identifier, .. } => Some(identifier.1.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    match ctx.find_definition(identifier)? {
+        Definition::User(_, exp, _) => match &exp.1 {
+            Expression::Operand(o) => match &o.1 {
+                Operand::FunctionInit(fi) => Some(function_params(fi)),
+                _ => None,
+            },
+            _ => None,
+        },
+        Definition::Value(AstPair(_, Value::Fn(fi))) => Some(function_params(&fi)),
+        Definition::Value(AstPair(_, Value::Type(ValueType::Struct(name)))) => {
+            match ctx.types.get(&name) {
+                Some(TypeDefinition::Struct { fields }) => {
+                    Some(fields.iter().map(|f| Some(f.clone())).collect())
+                }
+                _ => None,
+            }
+        }
+        Definition::System(_) => {
+            let signature = stdlib_signature(&identifier.0, &mut || Type::Any)?;
+            Some(
+                signature
+                    .params
+                    .iter()
+                    .map(|p| Some(Identifier::new(&p.name)))
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Resolution failure of an identifier that is not whitelisted, whether it exists or not
+fn permission_denied(span: &Span, identifier: &Identifier, ctx: &Context) -> Error {
+    Error::from_span(
+        span,
+        &ctx.ast_context,
+        format!("permission denied: '{}' is not allowed", identifier),
+    )
+}
+
+/// Suggestions of similar definitions for not found identifier, empty if there are none
+fn did_you_mean(identifier: &Identifier, ctx: &Context) -> String {
+    let similar = ctx
+        .similar_definitions(identifier)
+        .iter()
+        .map(|s| format!("'{}'", s))
+        .collect::<Vec<_>>();
+    match similar.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(", did you mean {}?", last),
+        Some((last, init)) => format!(", did you mean {} or {}?", init.join(", "), last),
+    }
+}
+
+/// Struct field or enum value access, e.g. `p.x` or `Color.Red`
+fn access_member(
+    target: &AstPair<Value>,
+    member: &AstPair<Expression>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Option<AstPair<Value>>, Error> {
+    let identifier = match &member.1 {
+        Expression::Operand(o) => match &o.1 {
+            Operand::Identifier(i) => &i.1,
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let value = match &target.1 {
+        Value::Struct { name, fields } => fields
+            .iter()
+            .find(|(f, _)| f == identifier)
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| format!("struct {} has no field '{}'", name, identifier)),
+        Value::Type(ValueType::Enum(name)) => match ctx.types.get(name) {
+            Some(TypeDefinition::Enum { values }) if values.contains(identifier) => {
+                Ok(Value::Enum {
+                    name: name.clone(),
+                    value: identifier.clone(),
+                })
+            }
+            _ => Err(format!("enum {} has no value '{}'", name, identifier)),
+        },
+        _ => return Ok(None),
+    };
+    value
+        .map(|v| Some(AstPair::from_span(&member.0, v)))
+        .map_err(|e| Error::from_span(&member.0, &ctx.ast_context, e))
+}
+
+/// Method call `x.f(a)`, sugar for `f(x, a)`.
+/// `f` is resolved the same way as in a regular call: definitions in scope from the innermost
+/// one, then stdlib. Stdlib packages share one namespace, so the type of `x` does not take part
+/// in the resolution.
+/// If `x` is a struct with field `f`, the field value is called with `a` instead, and it is an
+/// error if function `f` is defined as well.
+fn method_call(
+    target: AstPair<Value>,
+    method: &AstPair<Expression>,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    let operand = match &method.1 {
+        Expression::Operand(o) => Some(&o.1),
+        _ => None,
+    };
+    let call = match operand {
+        Some(Operand::FunctionCall(fc)) => method.map(|_| fc.clone()),
+        Some(Operand::Identifier(i)) => {
+            return Err(Error::from_span(
+                &method.0,
+                &ctx.ast_context,
+                format!(
+                    "{} has no member '{}', method call requires parentheses: .{}()",
+                    target.1.value_type(),
+                    i.1,
+                    i.1
+                ),
+            ))
+        }
+        _ => {
+            return Err(Error::from_span(
+                &method.0,
+                &ctx.ast_context,
+                "expected member or method call after '.'".to_string(),
+            ))
+        }
+    };
+    let name = &call.1.identifier.1;
+    let field = match &target.1 {
+        Value::Struct { fields, .. } => fields.iter().find(|(f, _)| f == name).cloned(),
+        _ => None,
+    };
+    match field {
+        Some(_) if ctx.find_definition(name).is_some() => Err(Error::from_span(
+            &call.1.identifier.0,
+            &ctx.ast_context,
+            format!(
+                "ambiguous method call: '{}' is both a field of {} and a function in scope",
+                name,
+                target.1.value_type()
+            ),
+        )),
+        Some((_, f)) => {
+            let args = call
+                .1
+                .arguments
+                .iter()
+                .map(|a| a.eval(ctx, false).map(|v| AstPair::from_span(&a.0, v.1)))
+                .collect::<Result<Vec<_>, _>>()?;
+            call_value(&AstPair::from_span(&call.0, f), args, &call.0, ctx)
+        }
+        None => {
+            ctx.scope_stack.last_mut().unwrap().method_callee = Some(target);
+            function_call(&call, ctx, FunctionCallType::Function)
+        }
+    }
+}
+
+/// Call of the pipe target with the piped value as the first argument: `a |> f(b)` is `f(a, b)`,
+/// `a |> f` is `f(a)`. Any other target is evaluated and called with the piped value
+fn pipe_call(
+    value: AstPair<Value>,
+    target: &AstPair<Expression>,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    let operand = match &target.1 {
+        Expression::Operand(o) => Some(&o.1),
+        _ => None,
+    };
+    let call = match operand {
+        Some(Operand::FunctionCall(fc)) => target.map(|_| fc.clone()),
+        Some(Operand::Identifier(i)) => target.map(|_| FunctionCall {
+            identifier: i.clone(),
+            arguments: vec![],
+            named_arguments: vec![],
+        }),
+        _ => {
+            let f = target.eval(ctx, false)?;
+            return call_value(&f, vec![value], &target.0, ctx);
+        }
+    };
+    ctx.scope_stack.last_mut().unwrap().method_callee = Some(value);
+    function_call(&call, ctx, FunctionCallType::Function)
+}
+
+fn construct_struct(
+    name: &str,
+    args: Vec<AstPair<Value>>,
+    callee: &Span,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    let fields = match ctx.types.get(name) {
+        Some(TypeDefinition::Struct { fields }) => fields.clone(),
+        _ => {
+            return Err(Error::from_span(
+                callee,
+                &ctx.ast_context,
+                format!("struct {} is not defined", name),
+            ))
+        }
+    };
+    if fields.len() != args.len() {
+        return Err(Error::from_span(
+            callee,
+            &ctx.ast_context,
+            format!(
+                "struct {} expects {} fields, found {}",
+                name,
+                fields.len(),
+                args.len()
+            ),
+        ));
+    }
+    Ok(AstPair::from_span(
+        callee,
+        Value::Struct {
+            name: name.to_string(),
+            fields: fields
+                .into_iter()
+                .zip(args.into_iter().map(|a| a.1))
+                .collect(),
+        },
+    ))
+}
+
+/// Call function value with arguments in a new scope
+pub fn call_value(
+    function: &AstPair<Value>,
+    args: Vec<AstPair<Value>>,
+    callee: &Span,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    ctx.scope_stack.push(
+        Scope::new("<closure>".to_string())
+            .with_callee(Some(callee.clone()))
+            .with_arguments(args),
+    );
+    debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
+    let name = "<closure>";
+    ctx.observers.clone().notify(ctx, |o, c| {
+        o.on_call(name, callee, &ctx.scope_stack.last().unwrap().arguments, c)
+    });
+    let res = function.eval(ctx, true);
+    debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
+    ctx.scope_stack.pop();
+    notify_result(name, callee, &res, ctx);
+    res
+}
+
+fn notify_result(
+    name: &str,
+    callee: &Span,
+    res: &Result<AstPair<Value>, Error>,
+    ctx: &mut RefMut<Context>,
+) {
+    ctx.observers.clone().notify(ctx, |o, c| match res {
+        Ok(v) => o.on_return(name, callee, v, c),
+        Err(e) => o.on_error(name, callee, e, c),
+    });
+}
+
 impl Evaluate for AstPair<Operand> {
     fn eval(&self, ctx: &mut RefMut<Context>, eager: bool) -> Result<AstPair<Value>, Error> {
         debug!("eval {:?}, eager: {}", &self, eager);
@@ -270,6 +725,7 @@ impl Evaluate for AstPair<FunctionInit> {
         if eager {
             let scope = ctx.scope_stack.last().unwrap().clone();
             for (param, v) in self.1.parameters.iter().zip(scope.arguments.clone()) {
+                check_assignee(param, &v, ctx)?;
                 let defs = assign_definitions(param.clone(), v, ctx, |_, e| Definition::Value(e))?;
                 ctx.scope_stack.last_mut().unwrap().definitions.extend(defs);
             }
@@ -289,11 +745,16 @@ impl Evaluate for AstPair<Identifier> {
     fn eval(&self, ctx: &mut RefMut<Context>, eager: bool) -> Result<AstPair<Value>, Error> {
         debug!("eval {:?}, eager: {}", &self, eager);
         let res = match ctx.find_definition(&self.1) {
-            Some(res) => res.eval(ctx, eager),
+            Some(res) => AstPair(self.0.clone(), res).eval(ctx, eager),
+            None if ctx.allowed.is_some() => Err(permission_denied(&self.0, &self.1, ctx)),
             None => Err(Error::from_span(
                 &self.0,
                 &ctx.ast_context,
-                format!("identifier '{}' not found", self.1),
+                format!(
+                    "identifier '{}' not found{}",
+                    self.1,
+                    did_you_mean(&self.1, ctx)
+                ),
             )),
         };
         debug!("result {:?}: {:?}", &self, res);
@@ -310,18 +771,40 @@ impl Evaluate for AstPair<Value> {
         }
         match &self.1 {
             Value::Fn(f) => self.map(|_| f.deref().clone()).eval(ctx, eager),
+            Value::Native(n) => {
+                let args = ctx.scope_stack.last().unwrap().arguments.clone();
+                (n.call)(&n.captured, args, ctx).map(|v| AstPair(self.0.clone(), v))
+            }
             _ => Ok(self.clone()),
         }
     }
 }
 
-impl Evaluate for Definition {
+/// Value of the user definition, evaluated once on first use
+pub fn eval_memoized(
+    expression: &AstPair<Expression>,
+    memo: &Memo,
+    ctx: &mut RefMut<Context>,
+) -> Result<AstPair<Value>, Error> {
+    if let Some(v) = memo.get() {
+        return Ok(v.clone());
+    }
+    let value = expression.eval(ctx, false)?;
+    Ok(memo.get_or_init(|| value).clone())
+}
+
+/// Evaluate definition referenced at the span
+impl Evaluate for AstPair<Definition> {
     fn eval(&self, ctx: &mut RefMut<Context>, eager: bool) -> Result<AstPair<Value>, Error> {
         debug!("eval {:?}, eager: {}", &self, eager);
-        match self {
-            Definition::User(_, exp) => exp.eval(ctx, eager),
+        match &self.1 {
+            Definition::User(_, exp, memo) => eval_memoized(exp, memo, ctx)?.eval(ctx, eager),
             // TODO: check if it's ok to clone args since fn might want to modify them
-            Definition::System(f) => f(ctx.scope_stack.last().unwrap().clone().arguments, ctx),
+            Definition::System(f) => f(
+                &self.0,
+                ctx.scope_stack.last().unwrap().clone().arguments,
+                ctx,
+            ),
             Definition::Value(v) => Ok(v.clone()),
         }
     }
@@ -332,6 +815,9 @@ mod tests {
     use std::cell::RefCell;
     use std::vec;
 
+    use proptest::num::f64::{POSITIVE, ZERO};
+    use proptest::prelude::*;
+
     use crate::ast::ast::{AstContext, ValueType};
     use crate::ast::ast_parser::parse_block;
     use crate::error::Error;
@@ -341,10 +827,8 @@ mod tests {
     use crate::parser::NoisParser;
 
     fn evaluate(source: &str, eager: bool) -> Result<Value, Error> {
-        let a_ctx = AstContext {
-            input: source.to_string(),
-        };
-        let pt = NoisParser::parse_program(a_ctx.input.as_str());
+        let a_ctx = AstContext::new(source);
+        let pt = NoisParser::parse_program(&a_ctx.input);
         let ast = pt.and_then(|parsed| parse_block(&parsed))?;
         let ctx_cell = RefCell::new(Context::stdlib(a_ctx));
         let ctx = &mut ctx_cell.borrow_mut();
@@ -410,6 +894,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn evaluate_float_roundtrip() {
+        let floats = [
+            0.0,
+            1.0,
+            0.1,
+            0.1 + 0.2,
+            12.5,
+            1e16,
+            1e21,
+            1.5e-7,
+            123456789.123456789,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+            -2.5,
+        ];
+        for f in floats {
+            let s = Value::F(f).to_string();
+            assert_eq!(
+                evaluate_eager(&format!("'{}'.to(F)", s)),
+                Ok(Value::F(f)),
+                "{}",
+                s
+            );
+            if f.is_sign_positive() {
+                assert_eq!(evaluate_eager(&s), Ok(Value::F(f)), "{}", s);
+            }
+        }
+        assert_eq!(
+            evaluate_eager("1.0.to([C])").map(|v| v.to_string()),
+            Ok("1.0".to_string())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn float_literal_roundtrip(f in POSITIVE | ZERO) {
+            prop_assert_eq!(evaluate_eager(&Value::F(f).to_string()), Ok(Value::F(f)));
+        }
+    }
+
     #[test]
     fn evaluate_assignee_basic() {
         assert_eq!(evaluate_eager("a = 4\na"), Ok(Value::I(4)));
@@ -424,6 +950,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn evaluate_parameter_destructuring() {
+        let eval = |source: &str| evaluate_eager(source).map(|v| v.to_string());
+        assert_eq!(
+            eval("[[1, 2], [3, 4]].map(([k, v]) -> k + v)"),
+            Ok("[3, 7]".to_string())
+        );
+        assert_eq!(
+            eval("[[1, [2, 3]]].map(([_, [b, ..c]]) -> [b, c])"),
+            Ok("[[2, [3]]]".to_string())
+        );
+        assert!(eval("[[1]].map(([k, v]) -> k)").is_err());
+    }
+
     #[test]
     fn evaluate_value_equality() {
         assert_eq!(evaluate_eager("1 == 1"), Ok(Value::B(true)));
@@ -440,6 +980,210 @@ mod tests {
         assert_eq!(evaluate_eager("1 == [1]"), Ok(Value::B(false)));
     }
 
+    #[test]
+    fn evaluate_remainder() {
+        assert_eq!(evaluate_eager("7 % 3"), Ok(Value::I(1)));
+        assert_eq!(
+            evaluate_eager("0 % 0").map_err(|e| e.message()),
+            Err("division by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_division() {
+        assert_eq!(evaluate_eager("7 / 2"), Ok(Value::F(3.5)));
+        assert_eq!(evaluate_eager("1 / 0.0"), Ok(Value::F(f64::INFINITY)));
+        assert_eq!(
+            evaluate_eager("1 / 0").map_err(|e| e.message()),
+            Err("division by zero".to_string())
+        );
+        assert_eq!(
+            evaluate_eager("floor_div(7, 0)").map_err(|e| e.message()),
+            Err("division by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_string_patterns() {
+        let source = |value: &str| {
+            format!(
+                "match '{}' {{\n    'GET ' ++ p ++ '/' => p,\n    'GET ' ++ p => p,\n    _ ++ '.no' => 'source',\n    _ => 'none',\n}}",
+                value
+            )
+        };
+        let eval = |value: &str| evaluate_eager(&source(value)).map(|v| v.to_string());
+        assert_eq!(eval("GET /a/"), Ok("/a".to_string()));
+        assert_eq!(eval("GET /a"), Ok("/a".to_string()));
+        assert_eq!(eval("a.no"), Ok("source".to_string()));
+        assert_eq!(eval("GE"), Ok("none".to_string()));
+    }
+
+    #[test]
+    fn evaluate_if_expression() {
+        let source = |value: &str| {
+            format!(
+                "n = {}\nif n == 0 {{ 'zero' }} else if n % 2 == 0 {{ 'even' }} else {{ 'odd' }}",
+                value
+            )
+        };
+        let eval = |value: &str| evaluate_eager(&source(value)).map(|v| v.to_string());
+        assert_eq!(eval("0"), Ok("zero".to_string()));
+        assert_eq!(eval("4"), Ok("even".to_string()));
+        assert_eq!(eval("3"), Ok("odd".to_string()));
+        assert_eq!(evaluate_eager("if False { 1 }"), Ok(Value::Unit));
+        assert_eq!(
+            evaluate_eager(
+                "a = True\nf = () {\n    if a {\n        return 1\n    }\n    2\n}\nf()"
+            ),
+            Ok(Value::I(1))
+        );
+        assert!(evaluate_eager("if 1 { 2 }").is_err());
+    }
+
+    #[test]
+    fn evaluate_range() {
+        let eval = |source: &str| evaluate_eager(source).map(|v| v.to_string());
+        assert_eq!(eval("1..4"), Ok("1..4".to_string()));
+        assert_eq!(eval("1..=3"), Ok("1..4".to_string()));
+        assert_eq!(eval("[..(0..3)]"), Ok("[0, 1, 2]".to_string()));
+        assert_eq!(eval("[..(3..0)]"), Ok("[]".to_string()));
+        assert_eq!(
+            eval("take(0..1000000000000000000000, 2)"),
+            Ok("[0, 1]".to_string())
+        );
+        assert_eq!(
+            eval("n = 3\n(1..=n).map(a -> a + a)"),
+            Ok("[2, 4, 6]".to_string())
+        );
+        assert!(evaluate_eager("1..2.5").is_err());
+        assert!(evaluate_eager("[..repeat(1)]").is_err());
+    }
+
+    #[test]
+    fn evaluate_pipe() {
+        let eval = |source: &str| evaluate_eager(source).map(|v| v.to_string());
+        assert_eq!(
+            eval("[1, 2, 3] |> filter(a -> a == 2) |> map(a -> a + 1)"),
+            Ok("[3]".to_string())
+        );
+        assert_eq!(eval("[2, 1] |> sort"), Ok("[1, 2]".to_string()));
+        assert_eq!(eval("inc = a -> a + 1\n1 + 1 |> inc"), Ok("3".to_string()));
+        assert_eq!(eval("2 |> (a -> a + a)"), Ok("4".to_string()));
+        assert_eq!(
+            eval("[1, 2]\n    |> map(a -> a + 1)\n    |> sort()"),
+            Ok("[2, 3]".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_bitwise() {
+        let eval = |source: &str| evaluate_eager(source).map(|v| v.to_string());
+        assert_eq!(eval("[6 & 3, 6 | 3, 1 << 4, 16 >> 2]"), Ok("[2, 7, 16, 4]".to_string()));
+        assert_eq!(eval("[~5, ~(0 - 1)]"), Ok("[-6, 0]".to_string()));
+        assert_eq!(eval("(0 - 8) >> 1"), Ok("-4".to_string()));
+        // shifts bind weaker than arithmetic, `&` binds stronger than `|`
+        assert_eq!(eval("1 + 1 << 2"), Ok("8".to_string()));
+        assert_eq!(eval("1 | 6 & 3"), Ok("3".to_string()));
+        assert_eq!(eval("1 << 2 == 4"), Ok("True".to_string()));
+        assert!(eval("1 << 128").is_err());
+        assert!(eval("1 >> (0 - 1)").is_err());
+        assert!(eval("1.5 & 1").is_err());
+    }
+
+    #[test]
+    fn higher_order_error_span() {
+        let locations = |source: &str| {
+            let mut error = evaluate_eager(source).unwrap_err();
+            let mut locations = vec![];
+            while let Error::Cause {
+                error: cause,
+                location,
+                line_col,
+                ..
+            } = error
+            {
+                locations.push(format!("{} {}:{}", location, line_col.0, line_col.1));
+                error = *cause;
+            }
+            (error.to_string(), locations)
+        };
+        let (error, frames) = locations("f = a -> {\n  1 / a\n}\nmap([1, 0], f)");
+        // span points into the callback body
+        assert!(error.starts_with(" --> 2:5"), "{}", error);
+        assert_eq!(frames, vec!["map 4:1", "map[1] 4:13", "/ 2:5"]);
+
+        let (error, frames) = locations("filter([1, 2], a -> a)");
+        assert!(error.contains("expected B, found I"), "{}", error);
+        assert_eq!(frames, vec!["filter 1:1", "filter[0] 1:16"]);
+
+        let (_, frames) = locations("scan([1, 2, 0], 1, (acc, a) -> a / a)");
+        assert_eq!(frames, vec!["scan 1:1", "scan[2] 1:20", "/ 1:34"]);
+    }
+
+    #[test]
+    fn evaluate_spread_arguments() {
+        let eval = |source: &str| evaluate_eager(source).map(|v| v.to_string());
+        let source = |call: &str| format!("f = (a, b, c) -> [a, b, c]\n{}", call);
+        assert_eq!(eval(&source("f(..[1, 2, 3])")), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(eval(&source("f(1, ..[2], 3)")), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(
+            eval(&source("f(..[1], ..'ab')")),
+            Ok("[1, a, b]".to_string())
+        );
+        assert_eq!(eval(&source("f(..(1..4))")), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(eval("take(..[[1, 2, 3], 2])"), Ok("[1, 2]".to_string()));
+    }
+
+    #[test]
+    fn evaluate_named_arguments() {
+        let eval = |source: &str| {
+            evaluate_eager(&format!("f = (a, b, c) -> [a, b, c]\n{}", source))
+                .map(|v| v.to_string())
+                .map_err(|e| e.message())
+        };
+        assert_eq!(eval("f(c: 3, a: 1, b: 2)"), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(eval("f(1, c: 3, b: 2)"), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(eval("1.f(b: 2, c: 3)"), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(
+            eval("P = #{x, y}\nP(y: 2, x: 1)"),
+            Ok("P(x: 1, y: 2)".to_string())
+        );
+        assert_eq!(
+            eval("take(n: 2, items: [1, 2, 3])"),
+            Ok("[1, 2]".to_string())
+        );
+        assert_eq!(
+            eval("f(1, a: 2)"),
+            Err("argument 'a' is specified more than once".to_string())
+        );
+        assert_eq!(eval("f(d: 1)"), Err("'f' has no parameter 'd'".to_string()));
+        assert_eq!(
+            eval("f(1, c: 3)"),
+            Err("missing argument 'b' of 'f'".to_string())
+        );
+        assert_eq!(
+            eval("g = compose(f, f)\ng(a: 1)"),
+            Err("'g' does not accept named arguments".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_enum_patterns() {
+        let source = |value: &str| {
+            format!(
+                "Color = |{{Red, Green, Blue}}\nmatch Color.{} {{\n    Red => 'red',\n    Color.Green => 'green',\n    c => c,\n}}",
+                value
+            )
+        };
+        let eval = |value: &str| evaluate_eager(&source(value)).map(|v| v.to_string());
+        assert_eq!(eval("Red"), Ok("red".to_string()));
+        assert_eq!(eval("Green"), Ok("green".to_string()));
+        assert_eq!(eval("Blue"), Ok("Color.Blue".to_string()));
+        assert!(
+            evaluate_eager("Color = |{Red}\nmatch Color.Red { Color.Pink => 1, _ => 2 }").is_err()
+        );
+    }
+
     #[test]
     fn evaluate_value_type() {
         assert_eq!(
@@ -533,5 +1277,151 @@ mod tests {
         assert_eq!(evaluate_eager("I == [*]"), Ok(Value::B(false)));
     }
 
+    #[test]
+    fn evaluate_user_types() {
+        let point = "Point = #{x, y}\np = Point(1, 'a')\n";
+        assert_eq!(
+            evaluate_eager(&format!("{}p", point)).map(|v| v.to_string()),
+            Ok("Point(x: 1, y: a)".to_string())
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}p.y", point)),
+            Ok(Value::list(vec![Value::C('a')]))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}type(p)", point)),
+            Ok(Value::Type(ValueType::Struct("Point".to_string())))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}type(p) == Point", point)),
+            Ok(Value::B(true))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}p.z", point)).map_err(|e| e.message()),
+            Err("struct Point has no field 'z'".to_string())
+        );
+        assert_eq!(
+            evaluate_eager("Point = #{x, y}\nPoint(1)").map_err(|e| e.message()),
+            Err("struct Point expects 2 fields, found 1".to_string())
+        );
+        assert_eq!(
+            evaluate_eager("Point = #{x, y}\nPoint(1, 2) + Point(1, 2)").map_err(|e| e.message()),
+            Err("incompatible operands: Point + Point".to_string())
+        );
+
+        let suit = "Suit = |{Hearts, Spades}\n";
+        assert_eq!(
+            evaluate_eager(&format!("{}Suit.Hearts", suit)).map(|v| v.to_string()),
+            Ok("Suit.Hearts".to_string())
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}Suit.Hearts == Suit.Hearts", suit)),
+            Ok(Value::B(true))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}Suit.Hearts == Suit.Spades", suit)),
+            Ok(Value::B(false))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}type(Suit.Spades) == Suit", suit)),
+            Ok(Value::B(true))
+        );
+        assert_eq!(
+            evaluate_eager(&format!("{}Suit.Diamonds", suit)).map_err(|e| e.message()),
+            Err("enum Suit has no value 'Diamonds'".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_type_annotations() {
+        let check_types = |source: &str| {
+            let a_ctx = AstContext::new(source);
+            let ast = NoisParser::parse_program(source)
+                .and_then(|parsed| parse_block(&parsed))
+                .unwrap();
+            let ctx_cell = RefCell::new(Context::stdlib(a_ctx).with_check_types(true));
+            let ctx = &mut ctx_cell.borrow_mut();
+            ast.eval(ctx, true).map(|a| a.1).map_err(|e| e.message())
+        };
+        assert_eq!(
+            evaluate_eager("a: I = 'a'\na"),
+            Ok(Value::list(vec![Value::C('a')]))
+        );
+        assert_eq!(
+            check_types("a: [C] = 'a'\na"),
+            Ok(Value::list(vec![Value::C('a')]))
+        );
+        assert_eq!(
+            check_types("a: I = 'a'"),
+            Err("type mismatch for 'a': expected I, found [C]".to_string())
+        );
+        assert_eq!(
+            check_types("f = (a: [I], b) -> b\nf([1, 2], 3)"),
+            Ok(Value::I(3))
+        );
+        assert_eq!(
+            check_types("f = (a: [I], b) -> b\nf([1, 'a'], 3)"),
+            Err("type mismatch for 'a': expected [I], found [I, [C]]".to_string())
+        );
+        assert_eq!(
+            check_types("Point = #{x, y}\np: Point = Point(1, 2)\nq: Point = 1"),
+            Err("type mismatch for 'q': expected Point, found I".to_string())
+        );
+        assert_eq!(
+            check_types("a: Point = 1"),
+            Err("unknown type Point".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_method_calls() {
+        let eval = |source: &str| evaluate_eager(source).map_err(|e| e.message());
+        assert_eq!(
+            eval("inc = a -> a + 1\n[1].map(inc).map(inc)"),
+            Ok(Value::list(vec![Value::I(3)]))
+        );
+        assert_eq!(eval("map = (l, f) -> 0\n[1].map(a -> a)"), Ok(Value::I(0)));
+        assert_eq!(eval("P = #{f}\nP(a -> a + 1).f(1)"), Ok(Value::I(2)));
+        assert_eq!(
+            eval("f = a -> a\nP = #{f}\nP(f).f(1)"),
+            Err(
+                "ambiguous method call: 'f' is both a field of P and a function in scope"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            eval("inc = a -> a + 1\n1.inc"),
+            Err("I has no member 'inc', method call requires parentheses: .inc()".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_not_found_suggestion() {
+        let message = |source: &str| evaluate_eager(source).unwrap_err().message();
+        assert_eq!(
+            message("count = 1\ncounr"),
+            "identifier 'counr' not found, did you mean 'count'?"
+        );
+        assert_eq!(
+            message("fitler([1], a -> a)"),
+            "function 'fitler' not found, did you mean 'filter'?"
+        );
+        assert_eq!(
+            message("count = 1\ncounter = 2\ncounte"),
+            "identifier 'counte' not found, did you mean 'count' or 'counter'?"
+        );
+        assert_eq!(message("g()"), "function 'g' not found");
+    }
+
+    #[test]
+    fn evaluate_system_function_outside_call() {
+        let error = evaluate_eager("map").unwrap_err();
+        assert!(error.message().starts_with("expected ("), "{}", error);
+        assert_eq!(
+            evaluate_eager("l = [1]\nl.map(a -> a)\nmap([1, 2], a -> a)"),
+            Ok(Value::list(vec![Value::I(1), Value::I(2)]))
+        );
+    }
+
     // TODO: more tests
 }
@@ -1,47 +1,300 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell, RefMut};
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
 use std::process::exit;
+use std::sync::Once;
 
 use colored::Colorize;
 use log::debug;
 
-use crate::ast::ast::{AstContext, AstPair, Block, Identifier};
+use crate::ast::ast::{AstContext, AstPair, Block, Identifier, Span, Statement};
 use crate::error::Error;
+use crate::interpret::annotation::check_assignee;
 use crate::interpret::context::{Context, Definition, Scope};
-use crate::interpret::evaluate::Evaluate;
+use crate::interpret::evaluate::{eval_memoized, Evaluate};
 
 pub fn execute(block: AstPair<Block>, a_ctx: AstContext) {
-    let ctx_cell = RefCell::new(Context::stdlib(a_ctx));
-    let ctx = &mut ctx_cell.borrow_mut();
+    execute_context(block, Context::stdlib(a_ctx))
+}
+
+/// Exit code of the process if interpreter itself fails, see [RunError::Internal]
+pub const INTERNAL_ERROR_EXIT_CODE: i32 = 70;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RunError {
+    /// Error in the program, e.g. call of undefined function
+    Program(Error),
+    /// Unexpected interpreter failure, e.g. `unwrap` of `None` in evaluator or stdlib.
+    /// Error points at the call being evaluated and includes the call stack.
+    Internal(Error),
+}
+
+/// Execute program in a prepared context, e.g. one with registered observers
+pub fn execute_context(block: AstPair<Block>, ctx: Context) {
+    match run(block, ctx) {
+        Ok(_) => {}
+        Err(RunError::Program(e)) => {
+            eprintln!("{}", format!("{}", e).red());
+            exit(1)
+        }
+        Err(RunError::Internal(e)) => {
+            eprintln!("{}", format!("{}", e).red());
+            exit(INTERNAL_ERROR_EXIT_CODE)
+        }
+    }
+}
+
+thread_local! {
+    /// Whether panics of this thread are caught by [run]
+    static CATCH_PANIC: Cell<bool> = const { Cell::new(false) };
+    static PANIC_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Panic hook installed once per process. Panics caught by [run] are recorded instead of
+/// printed, other panics are passed to the previous hook.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCH_PANIC.with(|c| c.get()) {
+                return default_hook(info);
+            }
+            let message = match info.location() {
+                Some(l) => format!("{} at {}:{}", panic_message(info), l.file(), l.line()),
+                None => panic_message(info),
+            };
+            PANIC_MESSAGE.with(|m| *m.borrow_mut() = Some(message));
+        }));
+    });
+}
+
+/// Call `main` definition of the program.
+/// Interpreter panics are caught and reported as [RunError::Internal] instead of aborting.
+pub fn run(block: AstPair<Block>, ctx: Context) -> Result<(), RunError> {
+    install_panic_hook();
+    let ctx_cell = RefCell::new(ctx);
+    let catching = CATCH_PANIC.with(|c| c.replace(true));
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_main(block, &mut ctx_cell.borrow_mut())
+    }));
+    CATCH_PANIC.with(|c| c.set(catching));
+    match res {
+        Ok(r) => r.map_err(RunError::Program),
+        Err(_) => {
+            let message = PANIC_MESSAGE
+                .with(|m| m.borrow_mut().take())
+                .unwrap_or_default();
+            Err(RunError::Internal(internal_error(
+                &ctx_cell.borrow(),
+                message,
+            )))
+        }
+    }
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    match info.payload().downcast_ref::<&str>() {
+        Some(s) => s.to_string(),
+        None => info
+            .payload()
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+/// Error at the innermost call, wrapped into causes of calls that were being evaluated.
+/// Scopes are not popped during unwinding, so they represent the stack at the moment of panic.
+fn internal_error(ctx: &Context, message: String) -> Error {
+    let mut calls = ctx
+        .scope_stack
+        .iter()
+        .filter_map(|s| s.callee.as_ref().map(|c| (s.name.clone(), c.clone())))
+        .rev();
+    let message = format!(
+        "internal interpreter error: {}\n  = help: this is a bug in nois, please report it at {}/issues",
+        message,
+        env!("CARGO_PKG_REPOSITORY")
+    );
+    let innermost = match calls.next() {
+        Some((name, span)) => Error::new_cause(
+            Error::from_span(&span, &ctx.ast_context, message),
+            name,
+            &span,
+            &ctx.ast_context,
+        ),
+        None => {
+            let start = Span { start: 0, end: 0 };
+            Error::from_span(&start, &ctx.ast_context, message)
+        }
+    };
+    calls.fold(innermost, |e, (name, span)| {
+        Error::new_cause(e, name, &span, &ctx.ast_context)
+    })
+}
+
+fn run_main(block: AstPair<Block>, ctx: &mut RefMut<Context>) -> Result<(), Error> {
+    // missing definition is reported at the start of the program
+    let start = Span {
+        start: block.0.start,
+        end: block.0.start,
+    };
+    push_global_scope(block, ctx)?;
+    let identifier = Identifier::new("main");
+    let (main_id, main, memo) = match ctx.find_definition(&identifier) {
+        Some(Definition::User(id, exp, memo)) => (id, exp, memo),
+        _ => {
+            return Err(Error::from_span(
+                &start,
+                &ctx.ast_context,
+                format!("'{}' not found", identifier),
+            ))
+        }
+    };
+    ctx.scope_stack
+        .push(Scope::new(identifier.to_string()).with_callee(Some(main_id.0.clone())));
+    debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
+    let res = eval_memoized(&main, &memo, ctx)
+        .and_then(|v| v.eval(ctx, true))
+        .map(|_| ())
+        .map_err(|e| Error::new_cause(e, main_id.1 .0, &main_id.0, &ctx.ast_context));
+    debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
+    ctx.scope_stack.pop();
+    res
+}
+
+/// Push scope with top-level definitions of the program
+pub fn push_global_scope(block: AstPair<Block>, ctx: &mut RefMut<Context>) -> Result<(), Error> {
     let block_defs = block
         .1
         .statements
+        .iter()
+        .map(|s| s.1.as_definitions(ctx))
+        .collect::<Result<Vec<_>, _>>()?
         .into_iter()
-        // TODO: proper handling
-        .flat_map(|s| s.1.as_definitions(ctx).unwrap())
+        .flatten()
         .collect::<HashMap<_, _>>();
-    let identifier = Identifier::new("main");
     ctx.scope_stack
         .push(Scope::new("global".to_string()).with_definitions(block_defs));
     debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
-    ctx.scope_stack.push(Scope::new(identifier.to_string()));
-    debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
-    let (main_id, main) = match ctx.find_definition(&identifier) {
-        Some(Definition::User(id, exp)) => (id, exp),
-        _ => {
-            eprintln!("{}", format!("'{}' not found", identifier).red());
-            exit(1)
+    for s in &block.1.statements {
+        if let Statement::Assignment {
+            assignee,
+            expression,
+        } = &s.1
+        {
+            check_assignee(assignee, expression, ctx)?;
         }
-    };
-    let mut a = ctx.scope_stack.last_mut().unwrap();
-    a.callee = Some(main_id.clone().0);
-    match main.eval(ctx, true) {
-        Ok(_) => {}
-        Err(e) => {
-            let err = Error::new_cause(e, main_id.1 .0, &main_id.0, &ctx.ast_context);
-            eprintln!("{}", format!("{}", err).red())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::value::Value;
+    use crate::parser::NoisParser;
+    use crate::stdlib::lib::LibFunction;
+    use crate::typecheck::signature::Signature;
+    use crate::typecheck::types::Type;
+
+    use super::*;
+
+    struct Broken;
+
+    impl LibFunction for Broken {
+        fn name() -> String {
+            "broken".to_string()
         }
-    };
-    debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
-    ctx.scope_stack.pop();
+
+        fn doc() -> String {
+            "Function with a bug".to_string()
+        }
+
+        fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+            Signature::fixed(vec![], Type::Unit)
+        }
+
+        fn call(_args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+            let none: Option<Value> = None;
+            Ok(none.expect("value not found"))
+        }
+    }
+
+    fn run_broken() -> Error {
+        let source = "f = -> broken()\nmain = -> f()";
+        let a_ctx = AstContext::new(source);
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let stdlib = Arc::new(HashMap::from([Broken::definition()]));
+        match run(ast, Context::new(a_ctx, stdlib)) {
+            Err(RunError::Internal(e)) => e,
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn run_reports_internal_error() {
+        let error = run_broken();
+        assert!(error
+            .message()
+            .starts_with("internal interpreter error: value not found at src/interpret/"));
+        let text = error.to_string();
+        assert!(text.contains("@ broken   (1:8)\n\t@ f        (2:11)\n\t@ main     (2:1)"));
+    }
+
+    #[test]
+    fn concurrent_runs_report_own_panics() {
+        let runs = (0..8)
+            .map(|_| thread::spawn(run_broken))
+            .collect::<Vec<_>>();
+        let other = thread::spawn(|| panic!("not caught"));
+        for r in runs {
+            let error = r.join().unwrap();
+            assert!(error.message().contains("value not found"));
+        }
+        assert!(other.join().is_err());
+    }
+
+    #[test]
+    fn missing_main_pops_scope() {
+        let source = "a = 1";
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx = RefCell::new(Context::stdlib(AstContext::new(source)));
+        let error = run_main(ast, &mut ctx.borrow_mut()).unwrap_err();
+        assert_eq!(error.message(), "'main' not found");
+        let names = ctx
+            .borrow()
+            .scope_stack
+            .iter()
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names.last().map(|n| n.as_str()), Some("global"));
+    }
+
+    #[test]
+    fn definitions_are_evaluated_once_on_use() {
+        let source = r#"top = println('top')
+unused = panic('never used')
+main = -> {
+  block = println('block')
+  unused = panic('never used')
+  [top, top, block, block]
+}"#;
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let output = Rc::new(RefCell::new(String::new()));
+        let ctx = Context::stdlib(AstContext::new(source)).with_output(output.clone());
+        assert_eq!(run(ast, ctx), Ok(()));
+        assert_eq!(output.borrow().as_str(), "top\nblock\n");
+    }
 }
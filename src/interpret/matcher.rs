@@ -4,8 +4,9 @@ use std::iter::zip;
 use log::debug;
 
 use crate::ast::ast::{AstPair, Expression, Identifier, MatchClause, PatternItem};
+use crate::convert::{FromNois, ToNois};
 use crate::error::Error;
-use crate::interpret::context::{Context, Definition};
+use crate::interpret::context::{Context, Definition, TypeDefinition};
 use crate::interpret::evaluate::Evaluate;
 use crate::interpret::value::Value;
 
@@ -47,10 +48,33 @@ pub fn match_pattern_item(
             .map_err(|e| Error::from_span(&pattern_item.0, &ctx.ast_context, e))?
             .eq(&value.1)
             .then(|| vec![]),
+        // value of the matched enum is compared instead of bound, e.g. `Red => ...`
         PatternItem::Identifier {
             identifier: id,
             spread: false,
-        } => Some(vec![(id.1, Definition::Value(value))]),
+        } => match &value.1 {
+            Value::Enum { name, value: v } if enum_values(name, ctx).contains(&id.1) => {
+                (v == &id.1).then(Vec::new)
+            }
+            _ => Some(vec![(id.1, Definition::Value(value))]),
+        },
+        PatternItem::EnumVariant {
+            name,
+            value: variant,
+        } => {
+            if !enum_values(&name.1 .0, ctx).contains(&variant.1) {
+                return Err(Error::from_span(
+                    &pattern_item.0,
+                    &ctx.ast_context,
+                    format!("enum {} has no value '{}'", name.1, variant.1),
+                ));
+            }
+            let expected = Value::Enum {
+                name: name.1 .0.clone(),
+                value: variant.1,
+            };
+            (expected == value.1).then(Vec::new)
+        }
         PatternItem::Identifier {
             identifier: _,
             spread: true,
@@ -61,6 +85,17 @@ pub fn match_pattern_item(
                 format!("unexpected spread operator"),
             ));
         }
+        PatternItem::StringConcat {
+            prefix,
+            rest,
+            suffix,
+        } => String::from_nois(value.1.clone())
+            .ok()
+            .and_then(|s| Some(s.strip_prefix(&prefix)?.strip_suffix(&suffix)?.to_string()))
+            .map(|r| match rest {
+                Some(id) => vec![(id.1, Definition::Value(value.map(|_| r.clone().to_nois())))],
+                None => vec![],
+            }),
         PatternItem::PatternList(items) => {
             return match &value.1 {
                 Value::List { items: vs, .. } => {
@@ -165,3 +200,11 @@ fn match_list_with_spread(
         .collect::<Option<Vec<_>>>()
         .map(|l| l.into_iter().flatten().collect()))
 }
+
+/// Values of the user enum type, empty if there is no such enum
+fn enum_values(name: &str, ctx: &Context) -> Vec<Identifier> {
+    match ctx.types.get(name) {
+        Some(TypeDefinition::Enum { values }) => values.clone(),
+        _ => vec![],
+    }
+}
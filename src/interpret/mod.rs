@@ -1,6 +1,11 @@
+pub mod annotation;
 pub mod context;
+pub mod deque;
 pub mod destructure;
 pub mod evaluate;
 pub mod interpreter;
 pub mod matcher;
+pub mod module;
+pub mod observer;
+pub mod seq;
 pub mod value;
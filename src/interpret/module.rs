@@ -0,0 +1,236 @@
+use std::cell::RefMut;
+use std::env::current_dir;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::ast::ast_parser::parse_block;
+use crate::ast::visit::shift_spans;
+use crate::cache;
+use crate::error::Error;
+use crate::interpret::context::{Context, Definition};
+use crate::parser::NoisParser;
+use crate::pkg;
+
+pub const MODULE_EXTENSION: &str = "no";
+
+/// Top-level definitions of the imported file. Path is relative to the importing file, or to the
+/// working directory if program is not read from a file, or is a name of a vendored module.
+///
+/// Imported source gets its own AST context, placed after the sources loaded before it, so that
+/// errors point into the imported file. Every file is imported once per context, later imports
+/// reuse its definitions. AST of the file is cached the same way as of the program source.
+pub fn import(
+    path: &AstPair<String>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Vec<(Identifier, Definition)>, Error> {
+    let error = |ctx: &RefMut<Context>, message: String| {
+        Error::from_span(&path.0, &ctx.ast_context, message)
+    };
+    let importer = ctx.importing.last().or(ctx.file.as_ref()).cloned();
+    let file = resolve(&path.1, importer.as_deref())
+        .map_err(|e| error(ctx, format!("unable to import '{}': {}", path.1, e)))?;
+    if let Some(defs) = ctx.modules.get(&file) {
+        return Ok(defs.clone());
+    }
+    let chain = ctx
+        .file
+        .iter()
+        .filter_map(|f| f.canonicalize().ok())
+        .chain(ctx.importing.iter().cloned())
+        .collect::<Vec<_>>();
+    if let Some(start) = chain.iter().position(|f| f == &file) {
+        let cycle = chain[start..]
+            .iter()
+            .chain([&file])
+            .map(|f| f.file_name().unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>();
+        return Err(error(
+            ctx,
+            format!("circular import: {}", cycle.join(" -> ")),
+        ));
+    }
+    let source = read_to_string(&file)
+        .map_err(|e| error(ctx, format!("unable to import '{}': {}", path.1, e)))?;
+    let display = display_path(&file);
+    let parsed = match ctx.cache_ast {
+        true => cache::parse_cached(&file, &source),
+        false => NoisParser::parse_program(&source).and_then(|parsed| parse_block(&parsed)),
+    };
+    let mut block = parsed.map_err(|e| {
+        Error::new_cause(
+            e.with_path(&display),
+            format!("import '{}'", path.1),
+            &path.0,
+            &ctx.ast_context,
+        )
+    })?;
+    let offset = ctx.ast_context.import(display, source);
+    shift_spans(&mut block, offset);
+    debug!("import {}", file.display());
+
+    ctx.importing.push(file.clone());
+    let defs = block
+        .1
+        .statements
+        .iter()
+        .map(|s| s.1.as_definitions(ctx))
+        .collect::<Result<Vec<_>, _>>();
+    ctx.importing.pop();
+    let defs = defs?.into_iter().flatten().collect::<Vec<_>>();
+    ctx.modules.insert(file, defs.clone());
+    Ok(defs)
+}
+
+/// Canonical path of the imported file. Bare module name, e.g. `std`, not matching a file next to
+/// the importer is looked up in `nois_modules` vendored by `nois pkg`
+pub fn resolve(path: &str, importer: Option<&Path>) -> Result<PathBuf, String> {
+    let dir = importer.and_then(|f| f.parent()).unwrap_or(Path::new("."));
+    let mut file = dir.join(path);
+    if file.extension().is_none() {
+        file.set_extension(MODULE_EXTENSION);
+    }
+    let is_bare = !path.starts_with('.') && !path.contains(['/', '\\']);
+    if !file.exists() && is_bare {
+        let dir = dir.canonicalize().unwrap_or(dir.to_path_buf());
+        if let Some(module) = pkg::resolve_module(&dir, path) {
+            file = module;
+        }
+    }
+    file.canonicalize().map_err(|e| e.to_string())
+}
+
+/// Path shown in errors, relative to the working directory if the file is inside of it
+fn display_path(file: &Path) -> String {
+    current_dir()
+        .ok()
+        .and_then(|dir| file.strip_prefix(dir).ok())
+        .unwrap_or(file)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::fs::{create_dir_all, remove_file, write};
+    use std::rc::Rc;
+
+    use crate::ast::ast::AstContext;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::interpret::value::Value;
+
+    use super::*;
+
+    /// Write files into a fresh directory and evaluate the main source as if read from it
+    fn evaluate(dir: &str, files: &[(&str, &str)], source: &str) -> Result<Value, Error> {
+        evaluate_with_output(dir, files, source, Rc::default())
+    }
+
+    fn evaluate_with_output(
+        dir: &str,
+        files: &[(&str, &str)],
+        source: &str,
+        output: Rc<RefCell<String>>,
+    ) -> Result<Value, Error> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join(dir);
+        for (name, content) in files {
+            let path = dir.join(name);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, content).unwrap();
+        }
+        let ast = NoisParser::parse_program(source).and_then(|parsed| parse_block(&parsed))?;
+        let ctx = Context::stdlib(AstContext::new(source))
+            .with_file(dir.join("main.no"))
+            .with_cache_ast(true)
+            .with_output(output);
+        let ctx_cell = RefCell::new(ctx);
+        let ctx = &mut ctx_cell.borrow_mut();
+        ast.eval(ctx, true).map(|v| v.1)
+    }
+
+    #[test]
+    fn import_definitions() {
+        let files = [
+            (
+                "lib/math.no",
+                "import 'util.no'\ndouble = a -> twice(a, b -> b + b)",
+            ),
+            ("lib/util.no", "twice = (a, f) -> f(a)\nanswer = 42"),
+        ];
+        let source = "import 'lib/math'\nimport 'lib/util'\n[double(2), answer]";
+        assert_eq!(
+            evaluate("import_definitions", &files, source).map(|v| v.to_string()),
+            Ok("[4, 42]".to_string())
+        );
+    }
+
+    #[test]
+    fn imported_definitions_are_evaluated_once() {
+        let files = [("lib.no", "a = println('imported')\nb = panic('never used')")];
+        let output = Rc::new(RefCell::new(String::new()));
+        let source = "import 'lib'\n[a, a]";
+        let res = evaluate_with_output("imported_once", &files, source, output.clone());
+        assert_eq!(res.map(|v| v.to_string()), Ok("[(), ()]".to_string()));
+        assert_eq!(output.borrow().as_str(), "imported\n");
+    }
+
+    #[test]
+    fn import_cached_ast() {
+        let files = [("lib.no", "a = 1")];
+        let lib = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/import_cached_ast/lib.no");
+        remove_file(cache::cache_path(&lib)).ok();
+        assert_eq!(
+            evaluate("import_cached_ast", &files, "import 'lib'\na").map(|v| v.to_string()),
+            Ok("1".to_string())
+        );
+        assert!(cache::load(&lib, "a = 1").is_some());
+        assert_eq!(
+            evaluate("import_cached_ast", &files, "import 'lib'\na").map(|v| v.to_string()),
+            Ok("1".to_string())
+        );
+    }
+
+    #[test]
+    fn import_vendored_module() {
+        let files = [
+            ("nois_modules/std/lib.no", "import 'list'\nstd = list"),
+            ("nois_modules/std/list.no", "list = 'std list'"),
+        ];
+        let source = "import 'std'\nstd";
+        assert_eq!(
+            evaluate("import_vendored_module", &files, source).map(|v| v.to_string()),
+            Ok("std list".to_string())
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        let files = [
+            ("a.no", "import 'b'\na = 1"),
+            ("b.no", "import 'a'\nb = 1"),
+            ("invalid.no", "a = "),
+            ("failing.no", "f = -> {\n  1 + (-> 2)\n}"),
+        ];
+        let error =
+            |source: &str| evaluate("import_errors", &files, source).map_err(|e| e.message());
+        assert_eq!(
+            error("import 'a'"),
+            Err("circular import: a.no -> b.no -> a.no".to_string())
+        );
+        assert!(error("import 'missing'")
+            .unwrap_err()
+            .starts_with("unable to import 'missing': "));
+        let err = evaluate("import_errors", &files, "import 'invalid'").unwrap_err();
+        assert!(err.to_string().contains("invalid.no:1:5"), "{}", err);
+
+        let err = evaluate("import_errors", &files, "import 'failing'\nf()").unwrap_err();
+        // error points into the imported file
+        assert!(err.to_string().contains("failing.no:2:5"), "{}", err);
+        assert!(err.to_string().contains("1 + (-> 2)"), "{}", err);
+    }
+}
@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::ast::ast::{AstPair, Span, Statement};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+
+/// Evaluation hooks for tooling such as tracers, profilers and coverage tools.
+/// Every `on_call` is followed by either `on_return` or `on_error` of the same call.
+pub trait EvalObserver {
+    fn on_statement_enter(&mut self, _statement: &AstPair<Statement>, _ctx: &Context) {}
+
+    /// Statement evaluated successfully, result of assignment or return is unit
+    fn on_statement_exit(
+        &mut self,
+        _statement: &AstPair<Statement>,
+        _result: &AstPair<Value>,
+        _ctx: &Context,
+    ) {
+    }
+
+    fn on_call(&mut self, _name: &str, _callee: &Span, _args: &[AstPair<Value>], _ctx: &Context) {}
+
+    fn on_return(&mut self, _name: &str, _callee: &Span, _result: &AstPair<Value>, _ctx: &Context) {
+    }
+
+    fn on_error(&mut self, _name: &str, _callee: &Span, _error: &Error, _ctx: &Context) {}
+}
+
+/// Observers registered on a context, notified in registration order
+#[derive(Clone, Default)]
+pub struct Observers(pub Vec<Rc<RefCell<dyn EvalObserver>>>);
+
+impl Observers {
+    pub fn notify(&self, ctx: &Context, f: impl Fn(&mut dyn EvalObserver, &Context)) {
+        for o in &self.0 {
+            f(&mut *o.borrow_mut(), ctx);
+        }
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{} observers>", self.0.len())
+    }
+}
+
+/// Observer printing call tree with arguments and results, used by `nois run --trace`
+pub struct Tracer<W: Write> {
+    pub out: W,
+    depth: usize,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(out: W) -> Tracer<W> {
+        Tracer { out, depth: 0 }
+    }
+
+    fn line(&mut self, ctx: &Context, span: &Span, message: String) {
+        let (line, _) = span.start_line_col(&ctx.ast_context);
+        writeln!(
+            self.out,
+            "{:>4}: {}{}",
+            line,
+            "  ".repeat(self.depth),
+            message
+        )
+        .ok();
+    }
+}
+
+impl<W: Write> EvalObserver for Tracer<W> {
+    fn on_call(&mut self, name: &str, callee: &Span, args: &[AstPair<Value>], ctx: &Context) {
+        let args = args.iter().map(|a| a.1.to_string()).collect::<Vec<_>>();
+        self.line(ctx, callee, format!("{}({})", name, args.join(", ")));
+        self.depth += 1;
+    }
+
+    fn on_return(&mut self, name: &str, callee: &Span, result: &AstPair<Value>, ctx: &Context) {
+        self.depth = self.depth.saturating_sub(1);
+        self.line(ctx, callee, format!("{} -> {}", name, result.1));
+    }
+
+    fn on_error(&mut self, name: &str, callee: &Span, error: &Error, ctx: &Context) {
+        self.depth = self.depth.saturating_sub(1);
+        self.line(ctx, callee, format!("{} !! {}", name, error.message()));
+    }
+}
+
+/// Observer keeping results of the last statements of every scope on the stack, so that the
+/// evaluation history leading to an error can be shown. Used by `nois run --history`
+pub struct History {
+    capacity: usize,
+    /// Recorded statements by scope, from the root scope to the innermost one
+    scopes: Vec<ScopeHistory>,
+}
+
+struct ScopeHistory {
+    name: String,
+    /// Line, statement source and its result
+    statements: VecDeque<(usize, String, Value)>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            capacity,
+            scopes: vec![],
+        }
+    }
+
+    /// Recorded statements grouped by scope, e.g.
+    ///
+    /// ```text
+    /// @main
+    ///    2: a = [1, 2] -> ()
+    ///    3: b = a.map(f) -> [2, 3]
+    /// ```
+    ///
+    pub fn dump(&self) -> String {
+        self.scopes
+            .iter()
+            .filter(|s| !s.statements.is_empty())
+            .map(|s| {
+                let statements = s
+                    .statements
+                    .iter()
+                    .map(|(line, source, value)| format!("{:>4}: {} -> {}", line, source, value))
+                    .collect::<Vec<_>>();
+                format!("@{}\n{}", s.name, statements.join("\n"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl EvalObserver for History {
+    fn on_statement_exit(
+        &mut self,
+        statement: &AstPair<Statement>,
+        result: &AstPair<Value>,
+        ctx: &Context,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        let depth = ctx.scope_stack.len();
+        let name = &ctx.scope_stack[depth - 1].name;
+        // scope at the same depth is a different call once its name changes
+        self.scopes.truncate(depth);
+        if self.scopes.get(depth - 1).map(|s| &s.name) != Some(name) {
+            self.scopes.truncate(depth - 1);
+        }
+        while self.scopes.len() < depth {
+            let name = ctx.scope_stack[self.scopes.len()].name.clone();
+            self.scopes.push(ScopeHistory {
+                name,
+                statements: VecDeque::new(),
+            });
+        }
+        let statements = &mut self.scopes[depth - 1].statements;
+        if statements.len() == self.capacity {
+            statements.pop_front();
+        }
+        let (line, _) = statement.0.start_line_col(&ctx.ast_context);
+        let source = statement
+            .0
+            .as_span(&ctx.ast_context)
+            .as_str()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        statements.push_back((line, source, result.1.clone()));
+    }
+
+    fn on_return(&mut self, _name: &str, _callee: &Span, _result: &AstPair<Value>, ctx: &Context) {
+        // finished call is no longer relevant, unlike the failed one
+        self.scopes.truncate(ctx.scope_stack.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ast::AstContext;
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        statements: usize,
+        calls: Vec<String>,
+        errors: usize,
+    }
+
+    impl EvalObserver for Counter {
+        fn on_statement_enter(&mut self, _statement: &AstPair<Statement>, _ctx: &Context) {
+            self.statements += 1;
+        }
+
+        fn on_call(
+            &mut self,
+            name: &str,
+            _callee: &Span,
+            _args: &[AstPair<Value>],
+            _ctx: &Context,
+        ) {
+            self.calls.push(name.to_string());
+        }
+
+        fn on_error(&mut self, _name: &str, _callee: &Span, _error: &Error, _ctx: &Context) {
+            self.errors += 1;
+        }
+    }
+
+    fn observe(source: &str, observer: Rc<RefCell<dyn EvalObserver>>) -> Result<Value, Error> {
+        let a_ctx = AstContext::new(source);
+        let ast = NoisParser::parse_program(source).and_then(|parsed| parse_block(&parsed))?;
+        let ctx_cell = RefCell::new(Context::stdlib(a_ctx).with_observer(observer));
+        let ctx = &mut ctx_cell.borrow_mut();
+        ast.eval(ctx, true).map(|a| a.1)
+    }
+
+    #[test]
+    fn observe_calls() {
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        let res = observe("f = a -> a + 1\nf(1)", counter.clone());
+        assert_eq!(res, Ok(Value::I(2)));
+        let counter = counter.borrow();
+        assert_eq!(counter.statements, 3);
+        assert_eq!(counter.calls, vec!["f", "+"]);
+        assert_eq!(counter.errors, 0);
+
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        assert!(observe("f = a -> a + (-> 1)\nf(1)", counter.clone()).is_err());
+        assert_eq!(counter.borrow().errors, 2);
+    }
+
+    #[test]
+    fn history_on_error() {
+        let history = Rc::new(RefCell::new(History::new(2)));
+        let source = "inc = a -> a + 1
+f = a -> {
+  b = inc(a)
+  c = b + 1
+  d = c + 1
+  d + (-> 1)
+}
+x = 1
+f(x)";
+        assert!(observe(source, history.clone()).is_err());
+        assert_eq!(
+            history.borrow().dump(),
+            "@root\n   2: f = a -> { -> ()\n   8: x = 1 -> ()\n\
+             @f\n   4: c = b + 1 -> ()\n   5: d = c + 1 -> ()"
+        );
+    }
+
+    #[test]
+    fn trace_calls() {
+        let tracer = Rc::new(RefCell::new(Tracer::new(vec![])));
+        observe("f = a -> a + 2\nf(3)", tracer.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(tracer.borrow().out.clone()).unwrap(),
+            "   2: f(3)\n   1:   +(3, 2)\n   1:   + -> 5\n   2: f -> 5\n"
+        );
+    }
+}
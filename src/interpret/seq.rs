@@ -0,0 +1,90 @@
+use std::cell::RefMut;
+use std::fmt::{Display, Formatter};
+
+use crate::ast::ast::{AstPair, Span};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::Value;
+
+/// Lazy sequence, its items are produced one by one only when taken, so it can be infinite.
+/// Sequence is not a list and cannot be forced eagerly, a list of its items is made by `take`
+/// or `take_while`. Only finite sequences, see `Seq::items`, are taken as lists.
+#[derive(Debug, PartialOrd, Clone, PartialEq)]
+pub enum Seq {
+    /// `init`, `f(init)`, `f(f(init))`, ...
+    Iterate { init: Box<Value>, f: Box<Value> },
+    /// The same value forever
+    Repeat(Box<Value>),
+    /// List items over and over again, empty if the list is empty
+    Cycle(Vec<Value>),
+    /// Integers from start inclusive to end exclusive, e.g. `1..10` or `1..=9`
+    Range { start: i128, end: i128 },
+}
+
+impl Seq {
+    /// Name of the function creating the sequence
+    pub fn name(&self) -> &str {
+        match self {
+            Seq::Iterate { .. } => "iterate",
+            Seq::Repeat(_) => "repeat",
+            Seq::Cycle(_) => "cycle",
+            Seq::Range { .. } => "range",
+        }
+    }
+
+    /// All items of a finite sequence, `None` if the sequence is infinite.
+    /// Finite sequences are taken as lists by functions expecting one, e.g. `(1..4).map(f)`.
+    pub fn items(&self) -> Option<Vec<Value>> {
+        match self {
+            Seq::Range { start, end } => Some((*start..*end).map(Value::I).collect()),
+            _ => None,
+        }
+    }
+
+    /// Pass items to the function until it returns `false` or the sequence ends.
+    /// Functions producing items are called at the callee span.
+    pub fn walk(
+        &self,
+        callee: &Span,
+        ctx: &mut RefMut<Context>,
+        mut f: impl FnMut(&Value, &mut RefMut<Context>) -> Result<bool, Error>,
+    ) -> Result<(), Error> {
+        match self {
+            Seq::Iterate { init, f: next } => {
+                let next = AstPair::from_span(callee, *next.clone());
+                let mut item = *init.clone();
+                // next item is only produced if it is going to be taken
+                while f(&item, ctx)? {
+                    let arg = AstPair::from_span(callee, item);
+                    item = call_value(&next, vec![arg], callee, ctx)?.1;
+                }
+            }
+            Seq::Repeat(v) => while f(v, ctx)? {},
+            Seq::Cycle(items) => {
+                for item in items.iter().cycle() {
+                    if !f(item, ctx)? {
+                        break;
+                    }
+                }
+            }
+            Seq::Range { start, end } => {
+                for i in *start..*end {
+                    if !f(&Value::I(i), ctx)? {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Seq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Seq::Range { start, end } => write!(f, "{}..{}", start, end),
+            _ => write!(f, "<{}>", self.name()),
+        }
+    }
+}
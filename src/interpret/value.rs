@@ -1,3 +1,5 @@
+use std::cell::RefMut;
+use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
@@ -5,19 +7,44 @@ use std::ops;
 
 use num::NumCast;
 
-use crate::ast::ast::{AstPair, FunctionInit, PatternItem, UnaryOperator, ValueType};
+use crate::ast::ast::{AstPair, FunctionInit, Identifier, PatternItem, UnaryOperator, ValueType};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::deque::Deque;
+use crate::interpret::seq::Seq;
 
 #[derive(Debug, PartialOrd, Clone)]
 pub enum Value {
     Unit,
     I(i128),
+    /// IEEE 754 float, operations result in infinity or NaN instead of failing, e.g. `1.0 % 0.0`.
+    /// NaN equals NaN and `-0.0` equals `0.0`, so that every value equals itself.
     F(f64),
     C(char),
     B(bool),
-    List { items: Vec<Value>, spread: bool },
+    List {
+        items: Vec<Value>,
+        spread: bool,
+    },
+    /// Double-ended queue, e.g. `deque([1, 2])`
+    Deque(Deque),
+    /// Lazy, possibly infinite sequence, e.g. `repeat(1)`
+    Seq(Seq),
     // TODO: closures don't remember their scope
     Fn(FunctionInit),
+    /// Function value created by stdlib code, e.g. result of `compose(f, g)`
+    Native(NativeFn),
     Type(ValueType),
+    /// Instance of user struct type, e.g. `Point(1, 2)` where `Point = #{x, y}`
+    Struct {
+        name: String,
+        fields: Vec<(Identifier, Value)>,
+    },
+    /// Value of user enum type, e.g. `Color.Red` where `Color = |{Red, Green}`
+    Enum {
+        name: String,
+        value: Identifier,
+    },
 }
 
 impl Value {
@@ -28,8 +55,12 @@ impl Value {
             Value::F(_) => ValueType::Float,
             Value::C(_) => ValueType::Char,
             Value::B(_) => ValueType::Boolean,
-            Value::Fn(_) => ValueType::Function,
+            Value::Fn(_) | Value::Native(_) => ValueType::Function,
+            Value::Deque(_) => ValueType::Deque,
+            Value::Seq(_) => ValueType::Seq,
             Value::Type(_) => ValueType::Type,
+            Value::Struct { name, .. } => ValueType::Struct(name.clone()),
+            Value::Enum { name, .. } => ValueType::Enum(name.clone()),
             Value::List { items, .. } => {
                 if items.is_empty() {
                     return Value::List {
@@ -66,7 +97,7 @@ impl Value {
                     let str = match t {
                         ValueType::Char => match arg {
                             Value::I(a) => Some(format!("{a}")),
-                            Value::F(a) => Some(format!("{a}")),
+                            Value::F(a) => Some(format!("{a:?}")),
                             Value::C(a) => Some(format!("{a}")),
                             _ => None,
                         },
@@ -122,6 +153,15 @@ impl Value {
         }
     }
 
+    /// Parameter count of a function value
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            Value::Fn(f) => Some(f.parameters.len()),
+            Value::Native(n) => n.arity,
+            _ => None,
+        }
+    }
+
     pub fn list(vec: Vec<Value>) -> Value {
         Self::List {
             items: vec,
@@ -130,6 +170,58 @@ impl Value {
     }
 }
 
+/// Called with captured values and call arguments, current scope is the one of the call
+pub type NativeCall =
+    fn(&[Value], Vec<AstPair<Value>>, &mut RefMut<Context>) -> Result<Value, Error>;
+
+/// Function implemented in Rust together with values it was created with
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    /// Values passed to every call, e.g. functions being composed
+    pub captured: Vec<Value>,
+    /// Number of expected arguments, `None` if unknown
+    pub arity: Option<usize>,
+    pub call: NativeCall,
+}
+
+impl NativeFn {
+    pub fn new(name: &str, captured: Vec<Value>, call: NativeCall) -> NativeFn {
+        NativeFn {
+            name: name.to_string(),
+            captured,
+            arity: None,
+            call,
+        }
+    }
+
+    pub fn with_arity(mut self, arity: Option<usize>) -> NativeFn {
+        self.arity = arity;
+        self
+    }
+}
+
+impl Debug for NativeFn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFn")
+            .field("name", &self.name)
+            .field("captured", &self.captured)
+            .finish()
+    }
+}
+
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.captured == other.captured
+    }
+}
+
+impl PartialOrd for NativeFn {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (&self.name, &self.captured).partial_cmp(&(&other.name, &other.captured))
+    }
+}
+
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
         format!("{:?}", self).hash(state);
@@ -152,7 +244,11 @@ impl PartialEq for Value {
                     spread: sb,
                 },
             ) => ia == ib && sa == sb,
+            (Self::Deque(a), Self::Deque(b)) => a == b,
+            (Self::Seq(a), Self::Seq(b)) => a == b,
+            (Self::F(a), Self::F(b)) => a == b || (a.is_nan() && b.is_nan()),
             (Self::Fn(a), Self::Fn(b)) => a == b,
+            (Self::Native(a), Self::Native(b)) => a == b,
             _ => format!("{:?}", self) == format!("{:?}", other),
         }
     }
@@ -165,7 +261,9 @@ impl Display for Value {
         match &self {
             Value::Unit => write!(f, "()"),
             Value::I(i) => write!(f, "{i}"),
-            Value::F(fl) => write!(f, "{fl}"),
+            // shortest representation that parses back to the same float, always with fraction or
+            // exponent, e.g. `1.0` or `1e20`, so that it is not read as an integer
+            Value::F(fl) => write!(f, "{fl:?}"),
             Value::C(c) => write!(f, "{c}"),
             Value::B(b) => write!(f, "{}", if *b { "True" } else { "False" }),
             Value::List { items: l, spread } => {
@@ -182,8 +280,22 @@ impl Display for Value {
                     write!(f, "{}[{}]", spread_s, is.join(", "))
                 }
             }
+            Value::Deque(d) => {
+                let is = d.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+                write!(f, "{}[{}]", ValueType::Deque, is.join(", "))
+            }
+            Value::Seq(s) => write!(f, "{s}"),
             Value::Fn(_) => write!(f, "<fn>"),
+            Value::Native(n) => write!(f, "<{}>", n.name),
             Value::Type(vt) => write!(f, "{vt}"),
+            Value::Struct { name, fields } => {
+                let fs = fields
+                    .iter()
+                    .map(|(i, v)| format!("{}: {}", i, v))
+                    .collect::<Vec<_>>();
+                write!(f, "{}({})", name, fs.join(", "))
+            }
+            Value::Enum { name, value } => write!(f, "{}.{}", name, value),
         }
     }
 }
@@ -283,10 +395,12 @@ impl ops::Sub for Value {
                 (Value::I(i1), Value::I(i2)) => Some(Value::I(i1 - i2)),
                 (Value::F(f1), Value::F(f2)) => Some(Value::F(f1 - f2)),
                 (Value::I(i1), Value::F(f2)) => Some(Value::F(*i1 as f64 - f2)),
+                (Value::F(f1), Value::I(i2)) => Some(Value::F(f1 - *i2 as f64)),
                 _ => None,
             }
         }
-        match _sub(&self, &rhs).or(_sub(&rhs, &self)) {
+        // not commutative, so operands are not swapped like in addition
+        match _sub(&self, &rhs) {
             Some(r) => Ok(r),
             None => Err(format!(
                 "incompatible operands: {} - {}",
@@ -297,19 +411,48 @@ impl ops::Sub for Value {
     }
 }
 
+impl ops::Div for Value {
+    type Output = Result<Value, String>;
+
+    /// True division, result is float even for integer operands, e.g. `7 / 2 -> 3.5`
+    fn div(self, rhs: Self) -> Self::Output {
+        if let (Value::I(_), Value::I(0)) = (&self, &rhs) {
+            return Err("division by zero".to_string());
+        }
+        let float = |v: &Value| match v {
+            Value::I(i) => Some(*i as f64),
+            Value::F(f) => Some(*f),
+            _ => None,
+        };
+        match (float(&self), float(&rhs)) {
+            (Some(f1), Some(f2)) => Ok(Value::F(f1 / f2)),
+            _ => Err(format!(
+                "incompatible operands: {} / {}",
+                self.value_type(),
+                rhs.value_type()
+            )),
+        }
+    }
+}
+
 impl ops::Rem for Value {
     type Output = Result<Value, String>;
 
     fn rem(self, rhs: Self) -> Self::Output {
+        if let (Value::I(_), Value::I(0)) = (&self, &rhs) {
+            return Err("division by zero".to_string());
+        }
         fn _rem(a: &Value, b: &Value) -> Option<Value> {
             match (a, b) {
                 (Value::I(i1), Value::I(i2)) => Some(Value::I(i1 % i2)),
                 (Value::F(f1), Value::F(f2)) => Some(Value::F(f1 % f2)),
                 (Value::I(i1), Value::F(f2)) => Some(Value::F(*i1 as f64 % f2)),
+                (Value::F(f1), Value::I(i2)) => Some(Value::F(f1 % *i2 as f64)),
                 _ => None,
             }
         }
-        match _rem(&self, &rhs).or(_rem(&rhs, &self)) {
+        // not commutative, so operands are not swapped like in addition
+        match _rem(&self, &rhs) {
             Some(r) => Ok(r),
             None => Err(format!(
                 "incompatible operands: {} % {}",
@@ -319,3 +462,64 @@ impl ops::Rem for Value {
         }
     }
 }
+
+/// Operands of a bitwise operator, which applies to integers only
+fn integers(a: &Value, b: &Value, operator: &str) -> Result<(i128, i128), String> {
+    match (a, b) {
+        (Value::I(i1), Value::I(i2)) => Ok((*i1, *i2)),
+        _ => Err(format!(
+            "incompatible operands: {} {} {}",
+            a.value_type(),
+            operator,
+            b.value_type()
+        )),
+    }
+}
+
+/// Shift amount, the number of bits is limited by the integer width
+fn shift_amount(amount: i128) -> Result<u32, String> {
+    match u32::try_from(amount) {
+        Ok(a) if a < i128::BITS => Ok(a),
+        _ => Err(format!(
+            "shift amount must be from 0 to {}, found {}",
+            i128::BITS - 1,
+            amount
+        )),
+    }
+}
+
+impl ops::BitAnd for Value {
+    type Output = Result<Value, String>;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        integers(&self, &rhs, "&").map(|(i1, i2)| Value::I(i1 & i2))
+    }
+}
+
+impl ops::BitOr for Value {
+    type Output = Result<Value, String>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        integers(&self, &rhs, "|").map(|(i1, i2)| Value::I(i1 | i2))
+    }
+}
+
+impl ops::Shl for Value {
+    type Output = Result<Value, String>;
+
+    /// Bits shifted out are discarded, e.g. `1 << 127` is the minimal integer
+    fn shl(self, rhs: Self) -> Self::Output {
+        let (i1, i2) = integers(&self, &rhs, "<<")?;
+        Ok(Value::I(i1 << shift_amount(i2)?))
+    }
+}
+
+impl ops::Shr for Value {
+    type Output = Result<Value, String>;
+
+    /// Arithmetic shift, sign of the integer is kept, e.g. `-8 >> 1 == -4`
+    fn shr(self, rhs: Self) -> Self::Output {
+        let (i1, i2) = integers(&self, &rhs, ">>")?;
+        Ok(Value::I(i1 >> shift_amount(i2)?))
+    }
+}
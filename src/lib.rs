@@ -0,0 +1,36 @@
+extern crate core;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod ast;
+pub mod build_info;
+pub mod bundle;
+pub mod cache;
+pub mod check;
+pub mod checksum;
+pub mod convert;
+pub mod coverage;
+pub mod cron;
+pub mod diagnostic;
+pub mod embed;
+pub mod error;
+pub mod escape;
+pub mod examples;
+pub mod fuzzy;
+pub mod glob;
+pub mod http;
+pub mod interpret;
+pub mod logger;
+pub mod parser;
+pub mod pkg;
+pub mod plot;
+pub mod ppm;
+pub mod random;
+pub mod semver;
+pub mod stdlib;
+pub mod table;
+pub mod test_runner;
+pub mod tls;
+pub mod typecheck;
+pub mod url;
+pub mod util;
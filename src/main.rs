@@ -1,13 +1,10 @@
-extern crate core;
-#[macro_use]
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
-use std::fs::read_to_string;
+use std::cell::RefCell;
+use std::env::current_dir;
+use std::fs::{read_to_string, write};
 use std::io;
 use std::path::PathBuf;
 use std::process::exit;
+use std::rc::Rc;
 
 use atty::Stream;
 use clap::Parser as p;
@@ -16,32 +13,87 @@ use log::info;
 use log::LevelFilter::Trace;
 use shellexpand::tilde;
 
-use crate::ast::ast::{AstContext, AstPair, Block};
-use crate::ast::ast_parser::parse_block;
-use crate::cli::{Cli, Commands};
-use crate::interpret::interpreter::execute;
-use crate::parser::NoisParser;
+use nois::ast::ast::{AstContext, AstPair, Block};
+use nois::ast::ast_parser::parse_block;
+use nois::check::Warning;
+use nois::coverage::{to_lcov, Coverage};
+use nois::diagnostic::Diagnostic;
+use nois::embed::Script;
+use nois::error::Error;
+use nois::examples::run_examples;
+use nois::interpret::context::Context;
+use nois::interpret::interpreter::{execute, run, RunError, INTERNAL_ERROR_EXIT_CODE};
+use nois::interpret::observer::{EvalObserver, History, Tracer};
+use nois::parser::NoisParser;
+use nois::stdlib::lib::find_functions;
+use nois::test_runner::run_tests;
+use nois::{bundle, cache, check, logger, pkg};
+
+use crate::cli::{Cli, Commands, ErrorFormat, PkgCommands};
 
-pub mod ast;
 pub mod cli;
-pub mod error;
-pub mod interpret;
-pub mod logger;
-pub mod parser;
-pub mod stdlib;
-pub mod util;
+
+/// Prints errors and warnings of a source file in the format chosen by `--error-format`
+pub struct Reporter {
+    format: ErrorFormat,
+    file: String,
+}
+
+impl Reporter {
+    /// Print error, kind is the stage that produced it, e.g. `parse` or `runtime`
+    pub fn error(&self, error: &Error, kind: &str) {
+        match self.format {
+            ErrorFormat::Human => eprintln!("{}", format!("{}", error).red()),
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                Diagnostic::from_error(error, kind, &self.file).to_json()
+            ),
+        }
+    }
+
+    pub fn message(&self, message: &str, kind: &str) {
+        match self.format {
+            ErrorFormat::Human => eprintln!("{}", message.red()),
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                Diagnostic::from_message(message, kind, &self.file).to_json()
+            ),
+        }
+    }
+
+    pub fn warning(&self, warning: &Warning, a_ctx: &AstContext) {
+        match self.format {
+            ErrorFormat::Human => {
+                eprintln!("{}", format!("{}", warning.to_error(a_ctx)).yellow())
+            }
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                Diagnostic::from_warning(warning, a_ctx, &self.file).to_json()
+            ),
+        }
+    }
+}
 
 fn main() {
     if let Some(source) = piped_input() {
-        let a_ctx = AstContext { input: source };
-        let ast = parse_ast(&a_ctx);
+        let reporter = Reporter {
+            format: ErrorFormat::Human,
+            file: "<stdin>".to_string(),
+        };
+        let a_ctx = AstContext::new(source);
+        let ast = parse_ast(&a_ctx, &reporter);
         execute(ast, a_ctx);
         return;
     }
 
     let verbose_level = Trace;
 
-    let command = Cli::parse().command;
+    let cli = Cli::parse();
+    let command = cli.command;
+    let reporter = |file: &str| Reporter {
+        format: cli.error_format.clone(),
+        file: file.to_string(),
+    };
     match &command {
         Commands::Parse {
             source: path,
@@ -51,40 +103,218 @@ fn main() {
                 logger::init(verbose_level);
             }
             info!("executing command {:?}", &command);
-            let source = read_source(path);
-            let a_ctx = AstContext { input: source };
-            let ast = parse_ast(&a_ctx);
+            let reporter = reporter(path);
+            let source = read_source(path, &reporter);
+            let a_ctx = AstContext::new(source);
+            let ast = parse_ast(&a_ctx, &reporter);
             println!("{:#?}", ast);
         }
+        Commands::Check { source: path } => {
+            let reporter = reporter(path);
+            let source = read_source(path, &reporter);
+            let a_ctx = AstContext::new(source);
+            let warnings = check::check(&parse_ast(&a_ctx, &reporter));
+            for w in &warnings {
+                reporter.warning(w, &a_ctx);
+            }
+            println!("{} warnings", warnings.len());
+        }
         Commands::Run {
             source: path,
             verbose,
+            no_cache,
+            trace,
+            history,
+            check_types,
         } => {
             if *verbose {
                 logger::init(verbose_level);
             }
             info!("executing command {:?}", &command);
-            let source = read_source(path);
-            let a_ctx = AstContext { input: source };
-            let ast = parse_ast(&a_ctx);
-            execute(ast, a_ctx);
+            let reporter = reporter(path);
+            let source = read_source(path, &reporter);
+            let a_ctx = AstContext::new(source);
+            let ast = if *no_cache {
+                parse_ast(&a_ctx, &reporter)
+            } else {
+                parse_ast_cached(path, &a_ctx, &reporter)
+            };
+            let mut ctx = Context::stdlib(a_ctx)
+                .with_check_types(*check_types)
+                .with_cache_ast(!*no_cache)
+                .with_file(PathBuf::from(tilde(path).to_string()));
+            if *trace {
+                ctx = ctx.with_observer(Rc::new(RefCell::new(Tracer::new(io::stderr()))));
+            }
+            let history = history.map(|n| Rc::new(RefCell::new(History::new(n))));
+            if let Some(h) = &history {
+                ctx = ctx.with_observer(h.clone());
+            }
+            match run(ast, ctx) {
+                Ok(_) => {}
+                Err(RunError::Program(e)) => {
+                    reporter.error(&e, "runtime");
+                    if let Some(h) = history {
+                        eprintln!("evaluation history:\n{}", h.borrow().dump());
+                    }
+                    exit(1)
+                }
+                Err(RunError::Internal(e)) => {
+                    reporter.error(&e, "internal");
+                    exit(INTERNAL_ERROR_EXIT_CODE)
+                }
+            }
+        }
+        Commands::Test {
+            source: path,
+            coverage,
+            lcov,
+        } => {
+            let reporter = reporter(path);
+            let source = read_source(path, &reporter);
+            let a_ctx = AstContext::new(source);
+            let script = Script {
+                ast: parse_ast(&a_ctx, &reporter),
+                ast_context: a_ctx,
+            };
+            let cov = Rc::new(RefCell::new(Coverage::default()));
+            let observers: Vec<Rc<RefCell<dyn EvalObserver>>> = match coverage {
+                true => vec![cov.clone()],
+                false => vec![],
+            };
+            let results = match run_tests(&script, observers) {
+                Ok(r) => r,
+                Err(e) => {
+                    reporter.error(&e, "runtime");
+                    exit(1)
+                }
+            };
+            for r in &results {
+                match (&r.error, &reporter.format) {
+                    (None, _) => println!("{} {}", "ok".green(), r.name),
+                    (Some(e), ErrorFormat::Human) => {
+                        println!("{} {}\n{}", "FAIL".red(), r.name, e)
+                    }
+                    (Some(e), ErrorFormat::Json) => {
+                        println!("{} {}", "FAIL".red(), r.name);
+                        reporter.error(e, "test");
+                    }
+                }
+            }
+            let failed = results.iter().filter(|r| !r.passed()).count();
+            println!("\n{} passed, {} failed", results.len() - failed, failed);
+            if *coverage {
+                let report = cov.borrow().report(path, &script.ast, &script.ast_context);
+                println!("\n{}", report.to_text());
+                if let Err(e) = write(tilde(lcov).to_string(), to_lcov(&[report])) {
+                    eprintln!("{}", format!("Unable to write {}: {}", lcov, e).red());
+                    exit(1)
+                }
+            }
+            if failed > 0 {
+                exit(1)
+            }
+        }
+        Commands::Examples { dir } => {
+            let results = match run_examples(&PathBuf::from(tilde(dir).to_string())) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Unable to read examples from {}: {}", dir, e).red()
+                    );
+                    exit(1)
+                }
+            };
+            for r in &results {
+                match r.passed() {
+                    true => println!("{} {}", "ok".green(), r.path.display()),
+                    false => println!(
+                        "{} {}
+{}",
+                        "FAIL".red(),
+                        r.path.display(),
+                        r.diff()
+                    ),
+                }
+            }
+            let failed = results.iter().filter(|r| !r.passed()).count();
+            println!("\n{} passed, {} failed", results.len() - failed, failed);
+            if failed > 0 {
+                exit(1)
+            }
+        }
+        Commands::Doc { query } => {
+            let functions = find_functions(query);
+            if functions.is_empty() {
+                eprintln!(
+                    "{}",
+                    format!("No stdlib function matches '{}'", query).red()
+                );
+                exit(1)
+            }
+            let reference = functions.iter().map(|f| f.reference()).collect::<Vec<_>>();
+            println!("{}", reference.join("\n\n"));
+        }
+        Commands::Bundle { source, output } => {
+            let path = PathBuf::from(tilde(source).to_string());
+            let res = bundle::bundle(&path).and_then(|b| match output {
+                Some(o) => write(tilde(o).to_string(), b).map_err(|e| e.to_string()),
+                None => {
+                    print!("{}", b);
+                    Ok(())
+                }
+            });
+            if let Err(e) = res {
+                eprintln!("{}", e.red());
+                exit(1)
+            }
+        }
+        Commands::Pkg { command } => {
+            let root = current_dir().unwrap();
+            let res = match command {
+                PkgCommands::Add { source, name } => {
+                    pkg::add(&root, source, name.clone()).map(|d| vec![d])
+                }
+                PkgCommands::Install => pkg::install(&root),
+            };
+            match res {
+                Ok(deps) => deps
+                    .iter()
+                    .for_each(|d| println!("vendored {} from {}", d.name, d.source)),
+                Err(e) => {
+                    eprintln!("{}", e.red());
+                    exit(1)
+                }
+            }
         }
     }
 }
 
-pub fn parse_ast(a_ctx: &AstContext) -> AstPair<Block> {
-    let pt = NoisParser::parse_program(a_ctx.input.as_str());
+pub fn parse_ast(a_ctx: &AstContext, reporter: &Reporter) -> AstPair<Block> {
+    let pt = NoisParser::parse_program(&a_ctx.input);
     let ast = pt.and_then(|parsed| parse_block(&parsed));
     match ast {
         Ok(a) => a,
         Err(e) => {
-            eprintln!("{}", format!("{}", e).red());
+            reporter.error(&e, "parse");
+            exit(1);
+        }
+    }
+}
+
+pub fn parse_ast_cached(path: &String, a_ctx: &AstContext, reporter: &Reporter) -> AstPair<Block> {
+    let source_path = PathBuf::from(tilde(path).to_string());
+    match cache::parse_cached(&source_path, &a_ctx.input) {
+        Ok(a) => a,
+        Err(e) => {
+            reporter.error(&e, "parse");
             exit(1);
         }
     }
 }
 
-pub fn read_source(path: &String) -> String {
+pub fn read_source(path: &String, reporter: &Reporter) -> String {
     let source = PathBuf::from(tilde(path).to_string())
         .canonicalize()
         .map(|s| s.into_os_string())
@@ -93,7 +323,7 @@ pub fn read_source(path: &String) -> String {
     match source {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("{}", format!("Unable to read file {}: {}", path, e).red());
+            reporter.message(&format!("Unable to read file {}: {}", path, e), "io");
             exit(1)
         }
     }
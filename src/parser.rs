@@ -1,23 +1,86 @@
+use pest::error::{Error as PError, ErrorVariant, InputLocation};
 use pest::iterators::Pair;
-use pest::Parser;
+use pest::{Parser, Position};
 
+use crate::build_info;
 use crate::error::Error;
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct NoisParser;
 
+/// Words that cannot be used as identifiers, including ones reserved for future syntax
+pub const KEYWORDS: [&str; 12] = [
+    "True", "False", "return", "match", "if", "else", "for", "in", "while", "break", "continue",
+    "import",
+];
+
 impl NoisParser {
+    /// Parse program block, checking its `#requires` pragma against the interpreter version
     pub fn parse_program(input: &str) -> Result<Pair<Rule>, Error> {
-        Self::parse(Rule::program, input)
-            .map(|ps| ps.into_iter().next().unwrap())
-            .map_err(|e| Error::Error(e))
+        let mut pairs = Self::parse(Rule::program, input)
+            .map_err(|e| Error::Error(Box::new(keyword_error(input, &e).unwrap_or(e))))?;
+        let first = pairs.next().unwrap();
+        if first.as_rule() != Rule::requires {
+            return Ok(first);
+        }
+        let version = first.into_inner().next().unwrap();
+        if !build_info::satisfies(version.as_str()) {
+            return Err(Error::from_pair(
+                &version,
+                format!(
+                    "file requires nois {} or newer, but interpreter version is {}, upgrade the interpreter to run it",
+                    version.as_str(),
+                    build_info::VERSION
+                ),
+            ));
+        }
+        Ok(pairs.next().unwrap())
     }
 }
 
+/// Clear error for a keyword used as identifier, e.g. `if = 1`.
+/// Such errors are reported either at the keyword or right after it if the keyword starts a
+/// valid statement, e.g. `return = 1`.
+fn keyword_error(input: &str, error: &PError<Rule>) -> Option<PError<Rule>> {
+    let pos = match error.location {
+        InputLocation::Pos(p) => p,
+        InputLocation::Span((s, _)) => s,
+    };
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let word_end = input[pos..]
+        .find(|c| !is_word_char(c))
+        .map_or(input.len(), |i| pos + i);
+    let keyword = if KEYWORDS.contains(&&input[pos..word_end]) {
+        pos..word_end
+    } else {
+        let rest = &input[pos..];
+        if !rest.starts_with('=') || rest.starts_with("==") {
+            return None;
+        }
+        let end = input[..pos].trim_end_matches([' ', '\t']).len();
+        let start = input[..end]
+            .rfind(|c| !is_word_char(c))
+            .map_or(0, |i| i + 1);
+        if !KEYWORDS.contains(&&input[start..end]) {
+            return None;
+        }
+        start..end
+    };
+    Some(PError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!(
+                "'{}' is a reserved keyword and cannot be used as an identifier",
+                &input[keyword.clone()]
+            ),
+        },
+        Position::new(input, keyword.start)?.span(&Position::new(input, keyword.end)?),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
-    use pest::parses_to;
+    use pest::{consumes_to, parses_to};
 
     use crate::parser::*;
 
@@ -43,6 +106,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_requires() {
+        let source = "#requires 0.1\n\na";
+        parses_to! {
+            parser: NoisParser,
+            input: source,
+            rule: Rule::program,
+            tokens: [
+                requires(0, 13, [version(10, 13)]),
+                block(15, 16, [expression(15, 16, [identifier(15, 16)])])
+            ]
+        }
+        assert!(NoisParser::parse_program(source).is_ok());
+
+        let error = NoisParser::parse_program("#requires 99.1\na").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("file requires nois 99.1 or newer"));
+    }
+
     #[test]
     fn parse_number() {
         let source = r#"
@@ -379,27 +462,31 @@ a - (a / 12).foo(boo() / 6) * c
                     expression(9, 56, [
                         identifier(9, 10),
                         binary_operator(11, 13, [AND_OP(11, 13)]),
-                        expression(20, 54, [
-                            identifier(20, 21),
-                            binary_operator(22, 24, [EQUALS_OP(22, 24)]),
-                            integer(25, 26),
-                            binary_operator(31, 33, [OR_OP(31, 33)]),
-                            identifier(34, 35),
-                            binary_operator(36, 38, [EQUALS_OP(36, 38)]),
-                            identifier(39, 40),
-                            binary_operator(45, 47, [OR_OP(45, 47)]),
-                            identifier(48, 49),
-                            binary_operator(50, 52, [NOT_EQUALS_OP(50, 52)]),
-                            identifier(53, 54)
+                        group(14, 56, [
+                            expression(20, 54, [
+                                identifier(20, 21),
+                                binary_operator(22, 24, [EQUALS_OP(22, 24)]),
+                                integer(25, 26),
+                                binary_operator(31, 33, [OR_OP(31, 33)]),
+                                identifier(34, 35),
+                                binary_operator(36, 38, [EQUALS_OP(36, 38)]),
+                                identifier(39, 40),
+                                binary_operator(45, 47, [OR_OP(45, 47)]),
+                                identifier(48, 49),
+                                binary_operator(50, 52, [NOT_EQUALS_OP(50, 52)]),
+                                identifier(53, 54)
+                            ])
                         ])
                     ]),
                     expression(57, 88, [
                         identifier(57, 58),
                         binary_operator(59, 60, [SUBTRACT_OP(59, 60)]),
-                        expression(62, 68, [
-                            identifier(62, 63),
-                            binary_operator(64, 65, [DIVIDE_OP(64, 65)]),
-                            integer(66, 68),
+                        group(61, 69, [
+                            expression(62, 68, [
+                                identifier(62, 63),
+                                binary_operator(64, 65, [DIVIDE_OP(64, 65)]),
+                                integer(66, 68),
+                            ])
                         ]),
                         binary_operator(69, 70, [ACCESSOR_OP(69, 70)]),
                         function_call(70, 84, [
@@ -419,17 +506,21 @@ a - (a / 12).foo(boo() / 6) * c
                         identifier(87, 88)
                     ]),
                     expression(89, 103, [
-                        expression(90, 102, [
-                            unary_expression(90, 98, [
-                                unary_operator(90, 91, [SUBTRACT_OP(90, 91)]),
-                                expression(92, 97, [
-                                    integer(92, 93),
-                                    binary_operator(94, 95, [ADD_OP(94, 95)]),
-                                    identifier(96, 97)
-                                ])
-                            ]),
-                            binary_operator(99, 100, [DIVIDE_OP(99, 100)]),
-                            integer(101, 102)
+                        group(89, 103, [
+                            expression(90, 102, [
+                                unary_expression(90, 98, [
+                                    unary_operator(90, 91, [SUBTRACT_OP(90, 91)]),
+                                    group(91, 98, [
+                                        expression(92, 97, [
+                                            integer(92, 93),
+                                            binary_operator(94, 95, [ADD_OP(94, 95)]),
+                                            identifier(96, 97)
+                                        ])
+                                    ])
+                                ]),
+                                binary_operator(99, 100, [DIVIDE_OP(99, 100)]),
+                                integer(101, 102)
+                            ])
                         ])
                     ])
                 ])
@@ -549,4 +640,38 @@ a = []
             ]
         }
     }
+
+    #[test]
+    fn parse_keywords() {
+        for k in KEYWORDS {
+            let error = NoisParser::parse_program(&format!("{} = 1", k)).unwrap_err();
+            assert_eq!(
+                error.message(),
+                format!(
+                    "'{}' is a reserved keyword and cannot be used as an identifier",
+                    k
+                )
+            );
+        }
+        assert!(NoisParser::parse_program("a = if").is_err());
+        for source in [
+            "returnValue = 1",
+            "matches = a -> a",
+            "Count = 1",
+            "Trueish",
+            "Into",
+            "Bool",
+            "Fnord = 2",
+            "snake_case = 1",
+            "café = 1",
+            "a: I = 1",
+        ] {
+            assert!(
+                NoisParser::parse_program(source).is_ok(),
+                "failed to parse {}",
+                source
+            );
+        }
+        assert!(NoisParser::parse_program("return\nmatch a { _ => 1 }").is_ok());
+    }
 }
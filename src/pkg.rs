@@ -0,0 +1,351 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, rename, write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fs_extra::dir::{copy, CopyOptions};
+use log::info;
+
+pub const MODULES_DIR: &str = "nois_modules";
+pub const MANIFEST_FILE: &str = "nois.pkg";
+pub const MODULE_ENTRY: &str = "lib.no";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Source {
+    Git(String),
+    Path(String),
+}
+
+impl Source {
+    pub fn parse(source: &str) -> Source {
+        let is_git = ["http://", "https://", "ssh://", "git@", "git://"]
+            .iter()
+            .any(|p| source.starts_with(p))
+            || source.ends_with(".git");
+        if is_git {
+            Source::Git(source.to_string())
+        } else {
+            Source::Path(source.to_string())
+        }
+    }
+
+    /// Package name inferred from the last segment of the source, e.g. `https://host/foo.git` -> `foo`
+    pub fn default_name(&self) -> String {
+        let s = match self {
+            Source::Git(s) | Source::Path(s) => s,
+        };
+        let last = s
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or(s);
+        last.trim_end_matches(".git").to_string()
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Git(s) | Source::Path(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub source: Source,
+}
+
+/// Manifest file listing project dependencies, one per line:
+///
+/// ```text
+/// name = source
+/// ```
+///
+/// Lines starting with `//` are comments.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Manifest {
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Manifest {
+    pub fn parse(input: &str) -> Result<Manifest, String> {
+        let dependencies = input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i, l.trim()))
+            .filter(|(_, l)| !l.is_empty() && !l.starts_with("//"))
+            .map(|(i, l)| match l.split_once('=') {
+                Some((name, source)) if !name.trim().is_empty() && !source.trim().is_empty() => {
+                    validate_name(name.trim())
+                        .map_err(|e| format!("{} line {}: {}", MANIFEST_FILE, i + 1, e))?;
+                    Ok(Dependency {
+                        name: name.trim().to_string(),
+                        source: Source::parse(source.trim()),
+                    })
+                }
+                _ => Err(format!(
+                    "malformed {} line {}: expected `name = source`",
+                    MANIFEST_FILE,
+                    i + 1
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Manifest { dependencies })
+    }
+
+    pub fn read(root: &Path) -> Result<Manifest, String> {
+        let path = root.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| Manifest::parse(&s))
+    }
+
+    pub fn write(&self, root: &Path) -> Result<(), String> {
+        write(root.join(MANIFEST_FILE), self.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// Add dependency or replace existing one with the same name
+    pub fn add(&mut self, dependency: Dependency) {
+        match self
+            .dependencies
+            .iter_mut()
+            .find(|d| d.name == dependency.name)
+        {
+            Some(d) => *d = dependency,
+            None => self.dependencies.push(dependency),
+        }
+    }
+}
+
+impl Display for Manifest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for d in &self.dependencies {
+            writeln!(f, "{} = {}", d.name, d.source)?;
+        }
+        Ok(())
+    }
+}
+
+/// Dependency name is used as a directory name inside `nois_modules`, so it must not point
+/// outside of it
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':']) {
+        return Err(format!(
+            "invalid dependency name '{}': expected a single path segment",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Copy dependency sources into `nois_modules/<name>`, replacing previously vendored version.
+/// Sources are first copied into a staging directory, so that failed vendoring keeps the
+/// previous version in place
+pub fn vendor(dependency: &Dependency, root: &Path) -> Result<PathBuf, String> {
+    if dependency.source.to_string().starts_with('-') {
+        return Err(format!(
+            "invalid source '{}' of dependency {}",
+            dependency.source, dependency.name
+        ));
+    }
+    let modules = root.join(MODULES_DIR);
+    create_dir_all(&modules).map_err(|e| e.to_string())?;
+    let target = modules.join(&dependency.name);
+    let staging = modules.join(format!(".{}.tmp", dependency.name));
+    if staging.exists() {
+        remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+    info!("vendoring {} from {}", dependency.name, dependency.source);
+    let fetched = fetch_source(&dependency.source, root, &staging);
+    if fetched.is_err() {
+        remove_dir_all(&staging).ok();
+    }
+    fetched?;
+    if target.exists() {
+        remove_dir_all(&target).map_err(|e| e.to_string())?;
+    }
+    rename(&staging, &target).map_err(|e| e.to_string())?;
+    Ok(target)
+}
+
+fn fetch_source(source: &Source, root: &Path, target: &Path) -> Result<(), String> {
+    match source {
+        Source::Git(url) => {
+            let status = Command::new("git")
+                .args(["clone", "--depth", "1", "--quiet", "--", url])
+                .arg(target)
+                .status()
+                .map_err(|e| format!("unable to run git: {}", e))?;
+            if !status.success() {
+                return Err(format!("git clone {} failed: {}", url, status));
+            }
+            remove_dir_all(target.join(".git")).ok();
+        }
+        Source::Path(path) => {
+            let source = root.join(path);
+            if !source.is_dir() {
+                return Err(format!("{} is not a directory", source.display()));
+            }
+            create_dir_all(target).map_err(|e| e.to_string())?;
+            let options = CopyOptions {
+                content_only: true,
+                ..CopyOptions::new()
+            };
+            copy(&source, target, &options).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Add dependency to the manifest and vendor it
+pub fn add(root: &Path, source: &str, name: Option<String>) -> Result<Dependency, String> {
+    let source = Source::parse(source);
+    let dependency = Dependency {
+        name: name.unwrap_or_else(|| source.default_name()),
+        source,
+    };
+    validate_name(&dependency.name)?;
+    let mut manifest = Manifest::read(root)?;
+    vendor(&dependency, root)?;
+    manifest.add(dependency.clone());
+    manifest.write(root)?;
+    Ok(dependency)
+}
+
+/// Vendor every dependency listed in the manifest
+pub fn install(root: &Path) -> Result<Vec<Dependency>, String> {
+    let manifest = Manifest::read(root)?;
+    for d in &manifest.dependencies {
+        vendor(d, root)?;
+    }
+    Ok(manifest.dependencies)
+}
+
+/// Find entry file of a vendored module by its name, looking up `nois_modules` directories
+/// starting from `dir` towards the filesystem root
+pub fn resolve_module(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(MODULES_DIR).join(name).join(MODULE_ENTRY))
+        .find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn parse_source() {
+        assert_eq!(
+            Source::parse("https://github.com/nois-lang/std.git"),
+            Source::Git("https://github.com/nois-lang/std.git".to_string())
+        );
+        assert_eq!(
+            Source::parse("git@github.com:nois-lang/std"),
+            Source::Git("git@github.com:nois-lang/std".to_string())
+        );
+        assert_eq!(
+            Source::parse("../libs/std"),
+            Source::Path("../libs/std".to_string())
+        );
+        assert_eq!(
+            Source::parse("https://github.com/nois-lang/std.git").default_name(),
+            "std"
+        );
+        assert_eq!(Source::parse("git@host:foo/bar/").default_name(), "bar");
+        assert_eq!(Source::parse("libs/std").default_name(), "std");
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+// dependencies
+std = https://github.com/nois-lang/std.git
+util = ../util
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.dependencies.len(), 2);
+        assert_eq!(manifest.dependencies[1].name, "util");
+        assert_eq!(
+            Manifest::parse(&manifest.to_string()).unwrap(),
+            manifest.clone()
+        );
+        assert!(Manifest::parse("foo").is_err());
+        assert!(Manifest::parse("= foo").is_err());
+        assert_eq!(
+            Manifest::parse("std = a\n.. = b"),
+            Err(format!(
+                "{} line 2: invalid dependency name '..': expected a single path segment",
+                MANIFEST_FILE
+            ))
+        );
+    }
+
+    #[test]
+    fn add_path_dependency() {
+        let root = temp_dir().join("nois_pkg_add_path_dependency");
+        remove_dir_all(&root).ok();
+        create_dir_all(root.join("util")).unwrap();
+        write(root.join("util").join(MODULE_ENTRY), "a = 1").unwrap();
+
+        let dep = add(&root, "util", Some("u".to_string())).unwrap();
+        assert_eq!(dep.name, "u");
+        assert_eq!(
+            Manifest::read(&root).unwrap().dependencies,
+            vec![dep.clone()]
+        );
+        assert_eq!(
+            resolve_module(&root.join("src"), "u"),
+            Some(root.join(MODULES_DIR).join("u").join(MODULE_ENTRY))
+        );
+        assert_eq!(resolve_module(&root, "missing"), None);
+
+        for name in ["", ".", "..", "a/b", "a\\b", "c:"] {
+            assert!(
+                add(&root, "util", Some(name.to_string())).is_err(),
+                "{}",
+                name
+            );
+        }
+        assert!(add(&root, "util/..", None).is_err());
+        assert!(root.join(MANIFEST_FILE).is_file());
+
+        remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn failed_vendor_keeps_previous_version() {
+        let root = temp_dir().join("nois_pkg_failed_vendor_keeps_previous_version");
+        remove_dir_all(&root).ok();
+        create_dir_all(root.join("util")).unwrap();
+        write(root.join("util").join(MODULE_ENTRY), "a = 1").unwrap();
+        add(&root, "util", None).unwrap();
+        let entry = root.join(MODULES_DIR).join("util").join(MODULE_ENTRY);
+
+        for source in [
+            Source::Git("--upload-pack=touch pwned".to_string()),
+            Source::Git(root.join("missing.git").display().to_string()),
+            Source::Path("missing".to_string()),
+        ] {
+            let dependency = Dependency {
+                name: "util".to_string(),
+                source,
+            };
+            assert!(vendor(&dependency, &root).is_err());
+            assert_eq!(read_to_string(&entry).unwrap(), "a = 1");
+        }
+        assert!(!root.join(MODULES_DIR).join(".util.tmp").exists());
+        assert!(!root.join("pwned").exists());
+
+        remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,103 @@
+/// Block chars of increasing height, used by sparklines
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Block chars of increasing width in eighths, used by bar charts
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// One char per value with its height proportional to the value position between minimum and
+/// maximum, e.g. `▁▄█` for `[1, 2, 3]`. NaN values are shown as spaces.
+pub fn sparkline(values: &[f64]) -> String {
+    let finite = values.iter().filter(|v| !v.is_nan());
+    let min = finite.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| {
+            if v.is_nan() {
+                return ' ';
+            }
+            let position = if max > min {
+                (v - min) / (max - min)
+            } else {
+                0.
+            };
+            BARS[(position * (BARS.len() - 1) as f64).round() as usize]
+        })
+        .collect()
+}
+
+/// Lines of a horizontal bar chart, bar of the largest value is `width` chars long, e.g.
+///
+/// ```text
+/// a │████▌ 1.5
+/// b │█████████ 3
+/// ```
+///
+/// Values must not be negative.
+pub fn bar_chart(bars: &[(String, f64)], width: usize) -> Vec<String> {
+    let label_width = bars
+        .iter()
+        .map(|(l, _)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = bars.iter().map(|(_, v)| *v).fold(0., f64::max);
+    bars.iter()
+        .map(|(label, value)| {
+            let eighths = if max > 0. {
+                (value / max * width as f64 * 8.).round() as usize
+            } else {
+                0
+            };
+            let mut bar = "█".repeat(eighths / 8);
+            if eighths % 8 > 0 {
+                bar.push(EIGHTHS[eighths % 8]);
+            }
+            format!(
+                "{:<w$} │{} {}",
+                label,
+                bar,
+                format_value(*value),
+                w = label_width
+            )
+        })
+        .collect()
+}
+
+/// Integral values are shown without fraction
+fn format_value(value: f64) -> String {
+    if value.fract() == 0. && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_scales_values() {
+        assert_eq!(sparkline(&[1., 2., 3.]), "▁▅█");
+        assert_eq!(sparkline(&[0., 7., 14., f64::NAN]), "▁▅█ ");
+        assert_eq!(sparkline(&[5., 5.]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn bar_chart_lines() {
+        let bars = vec![
+            ("a".to_string(), 1.5),
+            ("bb".to_string(), 3.),
+            ("c".to_string(), 0.),
+        ];
+        assert_eq!(
+            bar_chart(&bars, 4),
+            vec!["a  │██ 1.5", "bb │████ 3", "c  │ 0"]
+        );
+        assert_eq!(
+            bar_chart(&[("a".to_string(), 1.), ("b".to_string(), 16.)], 2),
+            vec!["a │▏ 1", "b │██ 16"]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+/// Pixel color channels: red, green and blue
+pub type Rgb = [u8; 3];
+
+/// Binary PPM (`P6`) image with 8 bit channels
+pub fn encode(width: usize, height: usize, pixels: &[Rgb]) -> Vec<u8> {
+    let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    bytes.extend(pixels.iter().flatten());
+    bytes
+}
+
+/// Rows of pixels of a binary (`P6`) or plain text (`P3`) PPM image.
+/// Channels of images with max value other than 255 are scaled to 8 bits.
+pub fn decode(bytes: &[u8]) -> Result<Vec<Vec<Rgb>>, String> {
+    let mut header = Header { bytes, pos: 0 };
+    let format = header.token()?;
+    let width = header.number()?;
+    let height = header.number()?;
+    let max = header.number()?;
+    if max == 0 || max > 65535 {
+        return Err(format!("invalid max channel value {}", max));
+    }
+    let count = width * height * 3;
+    let channels: Vec<usize> = match format.as_str() {
+        "P3" => (0..count)
+            .map(|_| header.number())
+            .collect::<Result<_, _>>()?,
+        "P6" => {
+            // single whitespace char separates header from the raster
+            let raster = &bytes[(header.pos + 1).min(bytes.len())..];
+            let size = if max < 256 { 1 } else { 2 };
+            if raster.len() < count * size {
+                return Err("unexpected end of image data".to_string());
+            }
+            raster
+                .chunks(size)
+                .take(count)
+                .map(|c| c.iter().fold(0, |acc, b| acc * 256 + *b as usize))
+                .collect()
+        }
+        f => {
+            return Err(format!(
+                "unsupported image format '{}', expected P3 or P6",
+                f
+            ))
+        }
+    };
+    if let Some(c) = channels.iter().find(|c| **c > max) {
+        return Err(format!("channel value {} exceeds max value {}", c, max));
+    }
+    let scale = |c: usize| (c * 255 / max) as u8;
+    Ok(channels
+        .chunks(3)
+        .map(|p| [scale(p[0]), scale(p[1]), scale(p[2])])
+        .collect::<Vec<_>>()
+        .chunks(width.max(1))
+        .map(|r| r.to_vec())
+        .collect())
+}
+
+/// Reader of whitespace separated header tokens, skipping `#` comments
+struct Header<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Header<'_> {
+    fn token(&mut self) -> Result<String, String> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while self.bytes.get(self.pos).is_some_and(|b| *b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err("unexpected end of image header".to_string()),
+            }
+        }
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string())
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| format!("expected number in image header, found '{}'", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let pixels = vec![[255, 0, 0], [0, 255, 0], [0, 0, 255], [1, 2, 3]];
+        let bytes = encode(2, 2, &pixels);
+        assert!(bytes.starts_with(b"P6\n2 2\n255\n"));
+        assert_eq!(
+            decode(&bytes),
+            Ok(vec![pixels[..2].to_vec(), pixels[2..].to_vec()])
+        );
+    }
+
+    #[test]
+    fn decode_plain() {
+        let plain = b"P3\n# comment\n2 1\n15\n15 0 0  0 15 15\n";
+        assert_eq!(decode(plain), Ok(vec![vec![[255, 0, 0], [0, 255, 255]]]));
+        assert!(decode(b"P5\n1 1\n255\n0").is_err());
+        assert!(decode(b"P6\n2 2\n255\n").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Pseudo random generator (SplitMix64), the same seed always produces the same numbers.
+/// It is not suitable for cryptography.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Generator seeded with the current time
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Float in range `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Integer in range `[0, n)`, every integer is equally likely
+    pub fn below(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        // reject the incomplete last cycle of values so that the remainder is not biased
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let v = self.next_u64();
+            if v < zone {
+                return (v % n) as usize;
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// Indices of `n` distinct items out of `len` in random order, `n` must not exceed `len`
+    pub fn sample(&mut self, len: usize, n: usize) -> Vec<usize> {
+        let mut indices = (0..len).collect::<Vec<_>>();
+        for i in 0..n {
+            indices.swap(i, i + self.below(len - i));
+        }
+        indices.truncate(n);
+        indices
+    }
+
+    /// Index chosen with probability proportional to its weight, `None` if weights sum to zero.
+    /// Weights must not be negative.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> Option<usize> {
+        let total = weights.iter().sum::<f64>();
+        if total <= 0. {
+            return None;
+        }
+        let mut target = self.next_f64() * total;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                return Some(i);
+            }
+            target -= w;
+        }
+        // float error might leave target past the last weight
+        weights.iter().rposition(|w| *w > 0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert_eq!(
+            (0..10).map(|_| a.next_u64()).collect::<Vec<_>>(),
+            (0..10).map(|_| b.next_u64()).collect::<Vec<_>>()
+        );
+        assert_ne!(a.next_u64(), Rng::new(43).next_u64());
+    }
+
+    #[test]
+    fn uniform_below() {
+        let mut rng = Rng::new(1);
+        let mut counts = [0; 4];
+        for _ in 0..40_000 {
+            counts[rng.below(4)] += 1;
+        }
+        assert!(
+            counts.iter().all(|c| (9_500..10_500).contains(c)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn sample_distinct() {
+        let mut rng = Rng::new(2);
+        let mut counts = [0; 5];
+        for _ in 0..10_000 {
+            let mut sample = rng.sample(5, 2);
+            counts[sample[0]] += 1;
+            sample.dedup();
+            assert_eq!(sample.len(), 2);
+        }
+        assert!(
+            counts.iter().all(|c| (1_800..2_200).contains(c)),
+            "{:?}",
+            counts
+        );
+        assert_eq!(rng.sample(3, 0), vec![]);
+    }
+
+    #[test]
+    fn weighted_proportions() {
+        let mut rng = Rng::new(3);
+        let weights = [1., 0., 3.];
+        let mut counts = [0; 3];
+        for _ in 0..40_000 {
+            counts[rng.weighted_index(&weights).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((9_500..10_500).contains(&counts[0]), "{:?}", counts);
+        assert_eq!(rng.weighted_index(&[0., 0.]), None);
+    }
+}
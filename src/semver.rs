@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+/// Semantic version `MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]`, see https://semver.org
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Dot separated pre-release identifiers, e.g. `rc.1`
+    pub pre: String,
+    /// Build metadata, ignored in comparisons
+    pub build: String,
+}
+
+impl SemVer {
+    /// Parse version, optionally prefixed with `v` as in release tags, e.g. `v1.2.3-rc.1`
+    pub fn parse(version: &str) -> Result<SemVer, String> {
+        let error = || {
+            format!(
+                "invalid semantic version '{}', expected MAJOR.MINOR.PATCH",
+                version
+            )
+        };
+        let v = version.strip_prefix('v').unwrap_or(version);
+        let (v, build) = v.split_once('+').map_or((v, None), |(v, b)| (v, Some(b)));
+        let (v, pre) = v.split_once('-').map_or((v, None), |(v, p)| (v, Some(p)));
+        let identifiers_valid = |s: Option<&str>| {
+            s.unwrap_or("a")
+                .split('.')
+                .all(|i| !i.is_empty() && i.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        };
+        if !identifiers_valid(pre) || !identifiers_valid(build) {
+            return Err(error());
+        }
+        let numbers = v
+            .split('.')
+            .map(|n| match n.parse::<u64>() {
+                // leading zeros are not allowed
+                Ok(i) if n.len() == 1 || !n.starts_with('0') => Ok(i),
+                _ => Err(error()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match numbers[..] {
+            [major, minor, patch] => Ok(SemVer {
+                major,
+                minor,
+                patch,
+                pre: pre.unwrap_or_default().to_string(),
+                build: build.unwrap_or_default().to_string(),
+            }),
+            _ => Err(error()),
+        }
+    }
+
+    /// Version precedence: numbers first, then pre-release versions before the release
+    pub fn precedence(&self, other: &SemVer) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre(&self.pre, &other.pre),
+            })
+    }
+}
+
+/// Compare pre-release identifiers one by one: numeric ones numerically and lower than
+/// alphanumeric ones, which are compared as strings. Longer list is greater if all preceding
+/// identifiers are equal
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ord = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ord.is_ne() {
+            return ord;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            SemVer::parse("v1.2.3-rc.1+build.5"),
+            Ok(SemVer {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                pre: "rc.1".to_string(),
+                build: "build.5".to_string(),
+            })
+        );
+        assert!(SemVer::parse("1.2").is_err());
+        assert!(SemVer::parse("1.2.x").is_err());
+        assert!(SemVer::parse("1.02.3").is_err());
+        assert!(SemVer::parse("1.2.3-").is_err());
+        assert!(SemVer::parse("1.2.3-a..b").is_err());
+    }
+
+    #[test]
+    fn precedence() {
+        // example from the spec
+        let versions = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.10.0",
+        ]
+        .map(|v| SemVer::parse(v).unwrap());
+        for pair in versions.windows(2) {
+            assert_eq!(pair[0].precedence(&pair[1]), Ordering::Less, "{:?}", pair);
+        }
+        assert_eq!(
+            SemVer::parse("1.0.0+a")
+                .unwrap()
+                .precedence(&SemVer::parse("1.0.0+b").unwrap()),
+            Ordering::Equal
+        );
+    }
+}
@@ -1,23 +1,29 @@
 use std::cell::RefMut;
-use std::collections::HashMap;
 
 use crate::ast::ast::{AstPair, BinaryOperator};
 use crate::error::Error;
 use crate::interpret::context::Context;
 use crate::interpret::value::Value;
 use crate::stdlib::lib::{LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 
 // TODO: dub every operator as callable function e.g. add() and eq()
 pub fn package() -> Package {
-    Package {
-        name: "binary_operator".to_string(),
-        definitions: HashMap::from([
-            Add::definition(),
-            Subtract::definition(),
-            Remainder::definition(),
-            Equals::definition(),
-        ]),
-    }
+    Package::new(
+        "binary_operator",
+        vec![
+            Add::info(),
+            Subtract::info(),
+            Divide::info(),
+            Remainder::info(),
+            Equals::info(),
+            BitAnd::info(),
+            BitOr::info(),
+            ShiftLeft::info(),
+            ShiftRight::info(),
+        ],
+    )
 }
 
 pub struct Add;
@@ -27,6 +33,14 @@ impl LibFunction for Add {
         BinaryOperator::Add.to_string()
     }
 
+    fn doc() -> String {
+        "Add numbers or concatenate lists, e.g. `1 + 2`, `[1] + [2]`, `[1] + 2`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Any)
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         (args[0].1.clone() + args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
     }
@@ -39,11 +53,39 @@ impl LibFunction for Subtract {
         BinaryOperator::Subtract.to_string()
     }
 
+    fn doc() -> String {
+        "Subtract numbers, e.g. `3 - 1`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Any)
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         (args[0].1.clone() - args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
     }
 }
 
+pub struct Divide;
+
+impl LibFunction for Divide {
+    fn name() -> String {
+        BinaryOperator::Divide.to_string()
+    }
+
+    fn doc() -> String {
+        "Divide numbers, result is float even for integers, e.g. `7 / 2 -> 3.5`; use `floor_div` for integer division".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Float)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        (args[0].1.clone() / args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
+    }
+}
+
 pub struct Remainder;
 
 impl LibFunction for Remainder {
@@ -51,6 +93,14 @@ impl LibFunction for Remainder {
         BinaryOperator::Remainder.to_string()
     }
 
+    fn doc() -> String {
+        "Remainder of the division, e.g. `5 % 2`, fails for integer zero divisor and is NaN for float one".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Any)
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         (args[0].1.clone() % args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
     }
@@ -63,7 +113,102 @@ impl LibFunction for Equals {
         BinaryOperator::Equals.to_string()
     }
 
+    fn doc() -> String {
+        "Whether values are equal, e.g. `1 == 1`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Bool)
+    }
+
     fn call(args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         Ok(Value::B(args[0].1 == args[1].1))
     }
 }
+
+pub struct BitAnd;
+
+impl LibFunction for BitAnd {
+    fn name() -> String {
+        BinaryOperator::BitAnd.to_string()
+    }
+
+    fn doc() -> String {
+        "Bitwise AND of integers, e.g. `6 & 3 -> 2`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        (args[0].1.clone() & args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
+    }
+}
+
+pub struct BitOr;
+
+impl LibFunction for BitOr {
+    fn name() -> String {
+        BinaryOperator::BitOr.to_string()
+    }
+
+    fn doc() -> String {
+        "Bitwise OR of integers, e.g. `6 | 3 -> 7`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        (args[0].1.clone() | args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
+    }
+}
+
+pub struct ShiftLeft;
+
+impl LibFunction for ShiftLeft {
+    fn name() -> String {
+        BinaryOperator::ShiftLeft.to_string()
+    }
+
+    fn doc() -> String {
+        "Shift integer bits to the left, e.g. `1 << 4 -> 16`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        (args[0].1.clone() << args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
+    }
+}
+
+pub struct ShiftRight;
+
+impl LibFunction for ShiftRight {
+    fn name() -> String {
+        BinaryOperator::ShiftRight.to_string()
+    }
+
+    fn doc() -> String {
+        "Arithmetic shift of integer bits to the right, e.g. `16 >> 2 -> 4`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        (args[0].1.clone() >> args[1].1.clone()).map_err(|s| Error::from_callee(ctx, s))
+    }
+}
+
+fn binary(ret: Type) -> Signature {
+    Signature::fixed(
+        vec![Param::new("a", Type::Any), Param::new("b", Type::Any)],
+        ret,
+    )
+}
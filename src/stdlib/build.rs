@@ -0,0 +1,142 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::build_info;
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "build",
+        vec![Version::info(), BuildInfo::info(), FeatureEnabled::info()],
+    )
+}
+
+/// Interpreter version
+///
+/// ```text
+/// version() -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// version() -> '0.1.0'
+/// ```
+///
+pub struct Version;
+
+impl LibFunction for Version {
+    fn name() -> String {
+        "version".to_string()
+    }
+
+    fn doc() -> String {
+        "Interpreter version".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::list(Type::Char))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        Ok(build_info::VERSION.to_nois())
+    }
+}
+
+/// Details of the interpreter build: version, git hash, target, profile and enabled features
+///
+/// ```text
+/// build_info() -> BuildInfo
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// build_info().git_hash -> '3c01c63'
+/// ```
+///
+pub struct BuildInfo;
+
+impl LibFunction for BuildInfo {
+    fn name() -> String {
+        "build_info".to_string()
+    }
+
+    fn doc() -> String {
+        "Details of the interpreter build".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Named("BuildInfo".to_string()))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        let features = build_info::FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(f, _)| *f)
+            .collect::<Vec<_>>();
+        let fields = vec![
+            ("version", build_info::VERSION.to_nois()),
+            ("git_hash", build_info::GIT_HASH.to_nois()),
+            ("target", build_info::TARGET.to_nois()),
+            ("profile", build_info::PROFILE.to_nois()),
+            ("features", features.to_nois()),
+        ];
+        Ok(Value::Struct {
+            name: "BuildInfo".to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(f, v)| (Identifier::new(f), v))
+                .collect(),
+        })
+    }
+}
+
+/// Check whether interpreter is built with the cargo feature, e.g. `desktop`
+///
+/// ```text
+/// feature_enabled([C]) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// feature_enabled('desktop') -> True
+/// ```
+///
+pub struct FeatureEnabled;
+
+impl LibFunction for FeatureEnabled {
+    fn name() -> String {
+        "feature_enabled".to_string()
+    }
+
+    fn doc() -> String {
+        "Check whether interpreter is built with the cargo feature".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("name", Type::list(Type::Char))], Type::Bool)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let name = match &args[..] {
+            [a] => String::from_nois(a.1.clone()).map_err(|_| arg_error("([C])", args, ctx))?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        Ok(Value::B(build_info::feature_enabled(&name)))
+    }
+}
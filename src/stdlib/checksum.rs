@@ -0,0 +1,153 @@
+use std::cell::RefMut;
+use std::fs::File;
+
+use crate::ast::ast::AstPair;
+use crate::checksum::{hash_reader, Algorithm};
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("checksum", vec![HashFile::info(), VerifyChecksum::info()])
+}
+
+/// Hex digest of the file contents. File is read in chunks, so it can be larger than memory.
+/// Supported algorithms are `md5`, `sha1` and `sha256`
+///
+/// ```text
+/// hash_file([C], [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// hash_file('empty.txt', 'sha256') -> e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+/// hash_file('empty.txt', 'md5') -> d41d8cd98f00b204e9800998ecf8427e
+/// ```
+///
+pub struct HashFile;
+
+impl LibFunction for HashFile {
+    fn name() -> String {
+        "hash_file".to_string()
+    }
+
+    fn doc() -> String {
+        "Hex digest of the file contents, algorithm is one of `md5`, `sha1`, `sha256`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("path", Type::list(Type::Char)),
+                Param::new("algorithm", Type::list(Type::Char)),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (path, name) = match &args[..] {
+            [p, a] => match (
+                String::from_nois(p.1.clone()),
+                String::from_nois(a.1.clone()),
+            ) {
+                (Ok(p), Ok(a)) => (p, a),
+                _ => return Err(arg_error("([C], [C])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [C])", args, ctx)),
+        };
+        let algorithm = Algorithm::from_name(&name).ok_or_else(|| {
+            Error::from_callee(
+                ctx,
+                format!(
+                    "unknown hash algorithm '{}', expected md5, sha1 or sha256",
+                    name
+                ),
+            )
+        })?;
+        Ok(hash(&path, algorithm, ctx)?.to_nois())
+    }
+}
+
+/// Whether the file matches expected hex digest, compared case-insensitively. Algorithm is
+/// detected by digest length or taken from the `algorithm:` prefix, e.g. `sha256:e3b0...`
+///
+/// ```text
+/// verify_checksum([C], [C]) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// verify_checksum('empty.txt', 'd41d8cd98f00b204e9800998ecf8427e') -> True
+/// verify_checksum('empty.txt', 'sha256:E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855') -> True
+/// ```
+///
+pub struct VerifyChecksum;
+
+impl LibFunction for VerifyChecksum {
+    fn name() -> String {
+        "verify_checksum".to_string()
+    }
+
+    fn doc() -> String {
+        "Whether the file matches expected hex digest, algorithm is detected by its length"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("path", Type::list(Type::Char)),
+                Param::new("expected", Type::list(Type::Char)),
+            ],
+            Type::Bool,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (path, expected) = match &args[..] {
+            [p, e] => match (
+                String::from_nois(p.1.clone()),
+                String::from_nois(e.1.clone()),
+            ) {
+                (Ok(p), Ok(e)) => (p, e),
+                _ => return Err(arg_error("([C], [C])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [C])", args, ctx)),
+        };
+        let (algorithm, digest) = match expected.trim().split_once(':') {
+            Some((name, digest)) => (Algorithm::from_name(name), digest),
+            None => (
+                Algorithm::from_hex_len(expected.trim().len()),
+                expected.trim(),
+            ),
+        };
+        let algorithm = match algorithm {
+            Some(a) if digest.chars().all(|c| c.is_ascii_hexdigit()) => a,
+            _ => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!(
+                        "invalid checksum '{}', expected md5, sha1 or sha256 hex digest",
+                        expected
+                    ),
+                ))
+            }
+        };
+        Ok(Value::B(
+            hash(&path, algorithm, ctx)?.eq_ignore_ascii_case(digest),
+        ))
+    }
+}
+
+fn hash(path: &str, algorithm: Algorithm, ctx: &mut RefMut<Context>) -> Result<String, Error> {
+    File::open(path)
+        .and_then(|mut f| hash_reader(algorithm, &mut f))
+        .map_err(|e| Error::from_callee(ctx, format!("unable to read {}: {}", path, e)))
+}
@@ -0,0 +1,360 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::AstPair;
+use crate::convert::ToNois;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::deque::Deque as DequeValue;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "deque",
+        vec![
+            Deque::info(),
+            ToList::info(),
+            PushFront::info(),
+            PushBack::info(),
+            PopFront::info(),
+            PopBack::info(),
+            Front::info(),
+            Back::info(),
+        ],
+    )
+}
+
+fn deque_arg<'a>(
+    args: &'a Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<&'a DequeValue, Error> {
+    match &args[..] {
+        [AstPair(_, Value::Deque(d))] => Ok(d),
+        _ => Err(arg_error("(Deque)", args, ctx)),
+    }
+}
+
+fn empty_error(ctx: &mut RefMut<Context>) -> Error {
+    Error::from_callee(ctx, "pop from empty deque".to_string())
+}
+
+/// Create double-ended queue from list items
+///
+/// ```text
+/// deque([*]) -> Deque[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// deque([1, 2]) -> Deque[1, 2]
+/// deque([]) -> Deque[]
+/// ```
+///
+pub struct Deque;
+
+impl LibFunction for Deque {
+    fn name() -> String {
+        "deque".to_string()
+    }
+
+    fn doc() -> String {
+        "Create double-ended queue from list items".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::deque(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => {
+                Ok(Value::Deque(items.iter().cloned().collect()))
+            }
+            _ => Err(arg_error("([*])", args, ctx)),
+        }
+    }
+}
+
+/// List of deque items from the first to the last one
+///
+/// ```text
+/// to_list(Deque[*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// to_list(deque([1, 2])) -> [1, 2]
+/// ```
+///
+pub struct ToList;
+
+impl LibFunction for ToList {
+    fn name() -> String {
+        "to_list".to_string()
+    }
+
+    fn doc() -> String {
+        "List of deque items from the first to the last one".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("deque", Type::deque(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let deque = deque_arg(args, ctx)?;
+        Ok(Value::list(deque.iter().cloned().collect()))
+    }
+}
+
+/// Deque with the value added before the first item
+///
+/// ```text
+/// push_front(Deque[*], *) -> Deque[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// push_front(deque([1, 2]), 0) -> Deque[0, 1, 2]
+/// ```
+///
+pub struct PushFront;
+
+impl LibFunction for PushFront {
+    fn name() -> String {
+        "push_front".to_string()
+    }
+
+    fn doc() -> String {
+        "Deque with the value added before the first item".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("deque", Type::deque(a.clone())),
+                Param::new("value", a.clone()),
+            ],
+            Type::deque(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::Deque(d)), AstPair(_, v)] => {
+                Ok(Value::Deque(d.push_front(v.clone())))
+            }
+            _ => Err(arg_error("(Deque, *)", args, ctx)),
+        }
+    }
+}
+
+/// Deque with the value added after the last item
+///
+/// ```text
+/// push_back(Deque[*], *) -> Deque[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// push_back(deque([1, 2]), 3) -> Deque[1, 2, 3]
+/// ```
+///
+pub struct PushBack;
+
+impl LibFunction for PushBack {
+    fn name() -> String {
+        "push_back".to_string()
+    }
+
+    fn doc() -> String {
+        "Deque with the value added after the last item".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("deque", Type::deque(a.clone())),
+                Param::new("value", a.clone()),
+            ],
+            Type::deque(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::Deque(d)), AstPair(_, v)] => {
+                Ok(Value::Deque(d.push_back(v.clone())))
+            }
+            _ => Err(arg_error("(Deque, *)", args, ctx)),
+        }
+    }
+}
+
+/// Deque without the first item, fails if the deque is empty
+///
+/// ```text
+/// pop_front(Deque[*]) -> Deque[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pop_front(deque([1, 2])) -> Deque[2]
+/// ```
+///
+pub struct PopFront;
+
+impl LibFunction for PopFront {
+    fn name() -> String {
+        "pop_front".to_string()
+    }
+
+    fn doc() -> String {
+        "Deque without the first item, fails if the deque is empty".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("deque", Type::deque(a.clone()))],
+            Type::deque(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match deque_arg(args, ctx)?.pop_front() {
+            Some(d) => Ok(Value::Deque(d)),
+            None => Err(empty_error(ctx)),
+        }
+    }
+}
+
+/// Deque without the last item, fails if the deque is empty
+///
+/// ```text
+/// pop_back(Deque[*]) -> Deque[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pop_back(deque([1, 2])) -> Deque[1]
+/// ```
+///
+pub struct PopBack;
+
+impl LibFunction for PopBack {
+    fn name() -> String {
+        "pop_back".to_string()
+    }
+
+    fn doc() -> String {
+        "Deque without the last item, fails if the deque is empty".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("deque", Type::deque(a.clone()))],
+            Type::deque(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match deque_arg(args, ctx)?.pop_back() {
+            Some(d) => Ok(Value::Deque(d)),
+            None => Err(empty_error(ctx)),
+        }
+    }
+}
+
+/// First item of the deque as an option
+///
+/// ```text
+/// front(Deque[*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// front(deque([1, 2])) -> [1]
+/// front(deque([])) -> []
+/// ```
+///
+pub struct Front;
+
+impl LibFunction for Front {
+    fn name() -> String {
+        "front".to_string()
+    }
+
+    fn doc() -> String {
+        "First item of the deque as an option".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("deque", Type::deque(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(deque_arg(args, ctx)?.front().cloned().to_nois())
+    }
+}
+
+/// Last item of the deque as an option
+///
+/// ```text
+/// back(Deque[*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// back(deque([1, 2])) -> [2]
+/// back(deque([])) -> []
+/// ```
+///
+pub struct Back;
+
+impl LibFunction for Back {
+    fn name() -> String {
+        "back".to_string()
+    }
+
+    fn doc() -> String {
+        "Last item of the deque as an option".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("deque", Type::deque(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(deque_arg(args, ctx)?.back().cloned().to_nois())
+    }
+}
@@ -0,0 +1,200 @@
+use std::cell::RefMut;
+use std::io::{stderr, Write};
+use std::process::{Command, Stdio};
+
+use crate::ast::ast::AstPair;
+use crate::convert::FromNois;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("desktop", vec![Notify::info(), Beep::info()])
+}
+
+/// Command showing desktop notification, `None` if platform is not supported
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+fn notification_command(title: &str, message: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--", title, message]);
+    Some(command)
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, message: &str) -> Option<Command> {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        &format!(
+            "display notification {} with title {}",
+            apple_script_string(message),
+            apple_script_string(title)
+        ),
+    ]);
+    Some(command)
+}
+
+#[cfg(target_os = "windows")]
+fn notification_command(title: &str, message: &str) -> Option<Command> {
+    // message box of Windows Script Host closes itself after 10 seconds
+    let script = format!(
+        "(New-Object -ComObject WScript.Shell).Popup({}, 10, {}, 64)",
+        power_shell_string(message),
+        power_shell_string(title)
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    Some(command)
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "windows"
+)))]
+fn notification_command(_title: &str, _message: &str) -> Option<Command> {
+    None
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn apple_script_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn power_shell_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Show desktop notification with the title and message
+///
+/// ```text
+/// notify([C], [C]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// notify('build', 'done in 5s')
+/// ```
+///
+pub struct Notify;
+
+impl LibFunction for Notify {
+    fn name() -> String {
+        "notify".to_string()
+    }
+
+    fn doc() -> String {
+        "Show desktop notification with the title and message".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("title", Type::list(Type::Char)),
+                Param::new("message", Type::list(Type::Char)),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (title, message) = match &args[..] {
+            [t, m] => match (
+                String::from_nois(t.1.clone()),
+                String::from_nois(m.1.clone()),
+            ) {
+                (Ok(t), Ok(m)) => (t, m),
+                _ => return Err(arg_error("([C], [C])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [C])", args, ctx)),
+        };
+        let mut command = match notification_command(&title, &message) {
+            Some(c) => c,
+            None => {
+                return Err(Error::from_callee(
+                    ctx,
+                    "notifications are not supported on this platform".to_string(),
+                ))
+            }
+        };
+        let status = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+        match status {
+            Ok(s) if s.success() => Ok(Value::Unit),
+            Ok(s) => Err(Error::from_callee(
+                ctx,
+                format!("unable to show notification: {}", s),
+            )),
+            Err(e) => Err(Error::from_callee(
+                ctx,
+                format!("unable to show notification: {}", e),
+            )),
+        }
+    }
+}
+
+/// Ring terminal bell
+///
+/// ```text
+/// beep() -> ()
+/// ```
+///
+pub struct Beep;
+
+impl LibFunction for Beep {
+    fn name() -> String {
+        "beep".to_string()
+    }
+
+    fn doc() -> String {
+        "Ring terminal bell".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        // written to stderr, so that it does not end up in redirected output
+        let mut err = stderr();
+        err.write_all(b"\x07").and_then(|_| err.flush()).ok();
+        Ok(Value::Unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_script_strings() {
+        assert_eq!(
+            apple_script_string(r#"say "hi" \o/"#),
+            r#""say \"hi\" \\o/""#
+        );
+        assert_eq!(power_shell_string("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn beep_signature() {
+        // beep is not called in tests, since it rings the bell of the terminal running them
+        let beep = package()
+            .functions
+            .into_iter()
+            .find(|f| f.name == "beep")
+            .unwrap();
+        assert_eq!(
+            (beep.signature)(&mut || Type::Any),
+            Signature::fixed(vec![], Type::Unit)
+        );
+    }
+}
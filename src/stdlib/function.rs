@@ -0,0 +1,890 @@
+use std::cell::RefMut;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::ast::ast::AstPair;
+use crate::convert::ToNois;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::{NativeCall, NativeFn, Value};
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "function",
+        vec![
+            Compose::info(),
+            Pipe::info(),
+            Curry::info(),
+            Uncurry::info(),
+            Apply::info(),
+            Arity::info(),
+            With::info(),
+            Retry::info(),
+            Catch::info(),
+            Throttle::info(),
+            Debounce::info(),
+        ],
+    )
+}
+
+/// Call captured functions one after another, passing result of each to the next one
+fn chain(
+    functions: &[Value],
+    args: Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Value, Error> {
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let mut args = args;
+    for f in functions {
+        let res = call_value(&AstPair::from_span(&callee, f.clone()), args, &callee, ctx)?;
+        args = vec![res];
+    }
+    Ok(args.remove(0).1)
+}
+
+/// Compose two functions, result function calls `g` first and `f` with its result
+///
+/// ```text
+/// compose((B) -> C, (A) -> B) -> (A) -> C
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// h = compose(a -> a + 1, a -> a + a)
+/// h(5) -> 11
+/// ```
+///
+pub struct Compose;
+
+impl LibFunction for Compose {
+    fn name() -> String {
+        "compose".to_string()
+    }
+
+    fn doc() -> String {
+        "Compose two functions, result function calls `g` first and `f` with its result".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let (a, b, c) = (fresh(), fresh(), fresh());
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![b.clone()], c.clone())),
+                Param::new("g", Type::function(vec![a.clone()], b)),
+            ],
+            Type::function(vec![a], c),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args.iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
+            [f, g] => Ok(Value::Native(
+                NativeFn::new("compose", vec![g.clone(), f.clone()], chain).with_arity(g.arity()),
+            )),
+            _ => Err(arg_error("(Fn, Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Chain two functions, result function calls `f` first and `g` with its result
+///
+/// ```text
+/// pipe((A) -> B, (B) -> C) -> (A) -> C
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// h = pipe(a -> a + 1, a -> a + a)
+/// h(5) -> 12
+/// ```
+///
+pub struct Pipe;
+
+impl LibFunction for Pipe {
+    fn name() -> String {
+        "pipe".to_string()
+    }
+
+    fn doc() -> String {
+        "Chain two functions, result function calls `f` first and `g` with its result".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let (a, b, c) = (fresh(), fresh(), fresh());
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![a.clone()], b.clone())),
+                Param::new("g", Type::function(vec![b], c.clone())),
+            ],
+            Type::function(vec![a], c),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args.iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
+            [f, g] => Ok(Value::Native(
+                NativeFn::new("pipe", vec![f.clone(), g.clone()], chain).with_arity(f.arity()),
+            )),
+            _ => Err(arg_error("(Fn, Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Collect arguments of curried function until there are enough to call the original one
+fn curried(
+    captured: &[Value],
+    args: Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Value, Error> {
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let (f, collected) = captured.split_first().unwrap();
+    let collected = collected
+        .iter()
+        .cloned()
+        .chain(args.into_iter().map(|a| a.1))
+        .collect::<Vec<_>>();
+    let arity = f.arity().unwrap();
+    if collected.len() < arity {
+        return Ok(Value::Native(
+            NativeFn::new(
+                "curry",
+                vec![f.clone()].into_iter().chain(collected).collect(),
+                curried,
+            )
+            .with_arity(Some(1)),
+        ));
+    }
+    let args = collected
+        .into_iter()
+        .map(|a| AstPair::from_span(&callee, a))
+        .collect();
+    call_value(&AstPair::from_span(&callee, f.clone()), args, &callee, ctx).map(|r| r.1)
+}
+
+/// Call curried function with arguments one by one
+fn uncurried(
+    captured: &[Value],
+    args: Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Value, Error> {
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let mut f = AstPair::from_span(&callee, captured[0].clone());
+    for a in args {
+        f = call_value(&f, vec![a], &callee, ctx)?;
+    }
+    Ok(f.1)
+}
+
+/// Convert function of multiple parameters into a chain of single parameter functions
+///
+/// ```text
+/// curry(Fn) -> Fn
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// add = curry((a, b) -> a + b)
+/// inc = add(1)
+/// inc(2) -> 3
+/// ```
+///
+pub struct Curry;
+
+impl LibFunction for Curry {
+    fn name() -> String {
+        "curry".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert function of multiple parameters into a chain of single parameter functions"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("f", Type::function(vec![], Type::Any))],
+            Type::Any,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args.iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
+            [f] => match f.arity() {
+                Some(0 | 1) => Ok(f.clone()),
+                Some(_) => Ok(Value::Native(
+                    NativeFn::new("curry", vec![f.clone()], curried).with_arity(Some(1)),
+                )),
+                None => Err(Error::from_callee(
+                    ctx,
+                    "unable to curry function with unknown number of parameters".to_string(),
+                )),
+            },
+            _ => Err(arg_error("(Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Convert a chain of single parameter functions into a function of multiple parameters
+///
+/// ```text
+/// uncurry(Fn) -> Fn
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// add = uncurry(curry((a, b) -> a + b))
+/// add(1, 2) -> 3
+/// ```
+///
+pub struct Uncurry;
+
+impl LibFunction for Uncurry {
+    fn name() -> String {
+        "uncurry".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert a chain of single parameter functions into a function of multiple parameters"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("f", Type::function(vec![], Type::Any))],
+            Type::Any,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args.iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
+            [f] => Ok(Value::Native(NativeFn::new(
+                "uncurry",
+                vec![f.clone()],
+                uncurried,
+            ))),
+            _ => Err(arg_error("(Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Call function with arguments from a list
+///
+/// ```text
+/// apply(Fn, [*]) -> *
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// apply((a, b) -> a + b, [1, 2]) -> 3
+/// ```
+///
+pub struct Apply;
+
+impl LibFunction for Apply {
+    fn name() -> String {
+        "apply".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function with arguments from a list".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![], Type::Any)),
+                Param::new("args", Type::list(fresh())),
+            ],
+            Type::Any,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [f, AstPair(_, Value::List { items, .. })] => {
+                let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+                let f_args = items
+                    .iter()
+                    .map(|i| AstPair::from_span(&args[1].0, i.clone()))
+                    .collect();
+                call_value(f, f_args, &callee, ctx).map(|r| r.1)
+            }
+            _ => Err(arg_error("(Fn, [*])", args, ctx)),
+        }
+    }
+}
+
+/// Number of parameters of a function
+///
+/// ```text
+/// arity(Fn) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// arity((a, b) -> a + b) -> 2
+/// arity(-> 1) -> 0
+/// ```
+///
+pub struct Arity;
+
+impl LibFunction for Arity {
+    fn name() -> String {
+        "arity".to_string()
+    }
+
+    fn doc() -> String {
+        "Number of parameters of a function".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("f", Type::function(vec![], Type::Any))],
+            Type::Int,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args.iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
+            [f @ (Value::Fn(_) | Value::Native(_))] => match f.arity() {
+                Some(a) => Ok(Value::I(a as i128)),
+                None => Err(Error::from_callee(
+                    ctx,
+                    format!("number of parameters of {} is unknown", f),
+                )),
+            },
+            _ => Err(arg_error("(Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Call `body` with the resource and then `cleanup` with it, even if `body` fails.
+/// Error of `body` takes precedence over the one of `cleanup`
+///
+/// ```text
+/// with(A, (A) -> *, (A) -> B) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// with(spawn('echo', ['hi']), p -> kill(p), p -> read_stdout_line(p)) -> ['hi']
+/// ```
+///
+pub struct With;
+
+impl LibFunction for With {
+    fn name() -> String {
+        "with".to_string()
+    }
+
+    fn doc() -> String {
+        "Call `body` with the resource and then `cleanup` with it, even if `body` fails, e.g. \
+        `with(spawn('echo', ['hi']), p -> kill(p), p -> read_stdout_line(p)) -> ['hi']`"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let (a, b) = (fresh(), fresh());
+        Signature::fixed(
+            vec![
+                Param::new("resource", a.clone()),
+                Param::new("cleanup", Type::function(vec![a.clone()], Type::Any)),
+                Param::new("body", Type::function(vec![a], b.clone())),
+            ],
+            b,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [resource, cleanup, body] => {
+                let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+                let res = call_value(body, vec![resource.clone()], &callee, ctx);
+                let cleaned = call_value(cleanup, vec![resource.clone()], &callee, ctx);
+                let res = res?;
+                cleaned?;
+                Ok(res.1)
+            }
+            _ => Err(arg_error("(*, Fn, Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Call function until it succeeds, at most `attempts` times, returning the first result or the
+/// last error. Waits `backoff_ms` before the second attempt, doubling the wait after each failure
+///
+/// ```text
+/// retry(() -> A, I, I) -> A
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// retry(-> fetch(url), 5, 100)
+/// ```
+///
+pub struct Retry;
+
+impl LibFunction for Retry {
+    fn name() -> String {
+        "retry".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function until it succeeds, at most `attempts` times, with exponential backoff"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![], a.clone())),
+                Param::new("attempts", Type::Int),
+                Param::new("backoff_ms", Type::Int),
+            ],
+            a,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (f, attempts, backoff) = match &args[..] {
+            [f, AstPair(_, Value::I(a)), AstPair(_, Value::I(b))] => (f, *a, *b),
+            _ => return Err(arg_error("(Fn, I, I)", args, ctx)),
+        };
+        if attempts < 1 || backoff < 0 {
+            return Err(Error::from_callee(
+                ctx,
+                format!(
+                    "expected positive attempts and non-negative backoff, found {} and {}",
+                    attempts, backoff
+                ),
+            ));
+        }
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let mut delay = backoff as u64;
+        let mut attempt = 1;
+        loop {
+            match call_value(f, vec![], &callee, ctx) {
+                Ok(res) => return Ok(res.1),
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(_) => {
+                    sleep(Duration::from_millis(delay));
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Call function, calling `handler` with the error if it fails. Handler receives the value passed
+/// to `panic`, or the error message for other errors
+///
+/// ```text
+/// catch(() -> A, (*) -> A) -> A
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// catch(-> panic(403), code -> code + 1) -> 404
+/// catch(-> 1 / 0, e -> e) -> 'division by zero'
+/// ```
+///
+pub struct Catch;
+
+impl LibFunction for Catch {
+    fn name() -> String {
+        "catch".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function, calling `handler` with the error if it fails".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![], a.clone())),
+                Param::new("handler", Type::function(vec![Type::Any], a.clone())),
+            ],
+            a,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (f, handler) = match &args[..] {
+            [f, h] => (f, h),
+            _ => return Err(arg_error("(Fn, Fn)", args, ctx)),
+        };
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        match call_value(f, vec![], &callee, ctx) {
+            Ok(res) => Ok(res.1),
+            Err(e) => {
+                let value = match e.payload() {
+                    Some(p) => p.clone(),
+                    None => e.message().to_nois(),
+                };
+                let arg = AstPair::from_span(&callee, value);
+                call_value(handler, vec![arg], &callee, ctx).map(|r| r.1)
+            }
+        }
+    }
+}
+
+/// Wrap function so that it is called only if `ms` passed since the last call time. Wrappers
+/// created by the same call share their call time
+fn limited(
+    name: &str,
+    args: &Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+    call: NativeCall,
+) -> Result<Value, Error> {
+    match &args[..] {
+        [f, AstPair(_, Value::I(ms))] if *ms >= 0 => {
+            let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+            Ok(Value::Native(
+                NativeFn::new(
+                    name,
+                    vec![
+                        f.1.clone(),
+                        Value::I(*ms),
+                        Value::I(callee.start as i128),
+                        Value::I(callee.end as i128),
+                    ],
+                    call,
+                )
+                .with_arity(f.1.arity()),
+            ))
+        }
+        _ => Err(arg_error("(Fn, I)", args, ctx)),
+    }
+}
+
+/// Call wrapped function if the wait since the last call time is over, returning its result as
+/// an option. Debounced calls update the call time even if skipped
+fn call_limited(
+    captured: &[Value],
+    args: Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+    debounce: bool,
+) -> Result<Value, Error> {
+    let (f, ms, key) = match captured {
+        [f, Value::I(ms), Value::I(start), Value::I(end)] => {
+            (f, *ms as u64, (*start as usize, *end as usize))
+        }
+        _ => unreachable!(),
+    };
+    let now = Instant::now();
+    let ready = ctx
+        .call_times
+        .get(&key)
+        .is_none_or(|t| now.duration_since(*t) >= Duration::from_millis(ms));
+    if ready || debounce {
+        ctx.call_times.insert(key, now);
+    }
+    if !ready {
+        return Ok(Value::list(vec![]));
+    }
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let res = call_value(&AstPair::from_span(&callee, f.clone()), args, &callee, ctx)?;
+    Ok(Value::list(vec![res.1]))
+}
+
+/// Wrap function so that it is called at most once per `ms` milliseconds, other calls are
+/// skipped. Wrapper returns result of the function as an option, empty if the call was skipped
+///
+/// ```text
+/// throttle((*) -> A, I) -> (*) -> [A]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// rebuild = throttle(-> run('make'), 1000)
+/// rebuild() -> ['ok']
+/// rebuild() -> []
+/// ```
+///
+pub struct Throttle;
+
+impl LibFunction for Throttle {
+    fn name() -> String {
+        "throttle".to_string()
+    }
+
+    fn doc() -> String {
+        "Wrap function so that it is called at most once per `ms` milliseconds".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        limited_signature(fresh)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        limited("throttle", args, ctx, |c, a, ctx| {
+            call_limited(c, a, ctx, false)
+        })
+    }
+}
+
+/// Wrap function so that it is called only after `ms` milliseconds without calls, so that a
+/// burst of calls results in a single one. Wrapper returns result of the function as an option,
+/// empty if the call was skipped
+///
+/// ```text
+/// debounce((*) -> A, I) -> (*) -> [A]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// on_change = debounce(path -> println(path), 200)
+/// on_change('a.no') -> [()]
+/// on_change('a.no') -> []
+/// ```
+///
+pub struct Debounce;
+
+impl LibFunction for Debounce {
+    fn name() -> String {
+        "debounce".to_string()
+    }
+
+    fn doc() -> String {
+        "Wrap function so that it is called only after `ms` milliseconds without calls".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        limited_signature(fresh)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        limited("debounce", args, ctx, |c, a, ctx| {
+            call_limited(c, a, ctx, true)
+        })
+    }
+}
+
+fn limited_signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+    let a = fresh();
+    Signature::fixed(
+        vec![
+            Param::new("f", Type::function(vec![], a.clone())),
+            Param::new("ms", Type::Int),
+        ],
+        Type::function(vec![], Type::list(a)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::ast::ast::AstContext;
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    fn evaluate(source: &str) -> Result<Value, Error> {
+        let a_ctx = AstContext::new(source);
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx_cell = RefCell::new(Context::stdlib(a_ctx));
+        let ctx = &mut ctx_cell.borrow_mut();
+        ast.eval(ctx, true).map(|a| a.1)
+    }
+
+    #[test]
+    fn compose_functions() {
+        let source = r#"
+inc = a -> a + 1
+double = a -> a + a
+main = -> {
+    h = compose(inc, double)
+    p = pipe(inc, double)
+    k = compose(h, inc)
+    [h(5), p(5), k(0), map([1, 2], pipe(double, inc))]
+}
+main()
+"#;
+        assert_eq!(
+            evaluate(source),
+            Ok(Value::list(vec![
+                Value::I(11),
+                Value::I(12),
+                Value::I(3),
+                Value::list(vec![Value::I(3), Value::I(5)]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn curry_functions() {
+        let source = r#"
+sum3 = (a, b, c) -> a + b + c
+main = -> {
+    add = curry(sum3)
+    inc = add(1)
+    inc2 = inc(1)
+    sum = uncurry(add)
+    [inc2(5), sum(1, 2, 3), add(1, 2, 3)]
+}
+main()
+"#;
+        assert_eq!(
+            evaluate(source),
+            Ok(Value::list(vec![Value::I(7), Value::I(6), Value::I(6)]))
+        );
+    }
+
+    #[test]
+    fn apply_and_arity() {
+        let source = r#"
+sum3 = (a, b, c) -> a + b + c
+main = -> {
+    args = [1, 2] + 3
+    [apply(sum3, args), arity(sum3), arity(-> 1), arity(curry(sum3)), arity(it -> it)]
+}
+main()
+"#;
+        assert_eq!(
+            evaluate(source),
+            Ok(Value::list(vec![
+                Value::I(6),
+                Value::I(3),
+                Value::I(0),
+                Value::I(1),
+                Value::I(1),
+            ]))
+        );
+        assert!(evaluate("arity(uncurry(a -> a))")
+            .unwrap_err()
+            .to_string()
+            .contains("number of parameters of <uncurry> is unknown"));
+    }
+
+    #[test]
+    fn with_cleanup() {
+        let source = r#"
+close = r -> println('close', r)
+main = -> {
+    println(with('a', close, r -> r + 'b'))
+    with('c', close, r -> fail(r))
+}
+main()
+"#;
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let output = Rc::new(RefCell::new(String::new()));
+        let ctx_cell =
+            RefCell::new(Context::stdlib(AstContext::new(source)).with_output(output.clone()));
+        let ctx = &mut ctx_cell.borrow_mut();
+        let error = ast.eval(ctx, true).unwrap_err();
+        assert!(error.to_string().contains("function 'fail' not found"));
+        assert_eq!(output.borrow().as_str(), "close a\nab\nclose c\n");
+    }
+
+    #[test]
+    fn retry_failing_function() {
+        let source = r#"
+succeed = -> {
+    println('try')
+    1
+}
+fail = -> {
+    println('try')
+    assert(False)
+}
+main = -> {
+    println(retry(succeed, 3, 100))
+    retry(fail, 3, 1)
+}
+main()
+"#;
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let output = Rc::new(RefCell::new(String::new()));
+        let ctx_cell =
+            RefCell::new(Context::stdlib(AstContext::new(source)).with_output(output.clone()));
+        let ctx = &mut ctx_cell.borrow_mut();
+        let error = ast.eval(ctx, true).unwrap_err();
+        assert!(error.to_string().contains("assertion failed"));
+        assert_eq!(output.borrow().as_str(), "try\n1\ntry\ntry\ntry\n");
+    }
+
+    #[test]
+    fn catch_panic_payload() {
+        let source = r#"
+Forbidden = #{code, reason}
+check = a -> match a {
+    0 => panic(Forbidden(403, 'denied')),
+    _ => a
+}
+main = -> {
+    [
+        catch(-> check(0), e -> e.code),
+        catch(-> map([1, 0], check), e -> e.code + 1),
+        catch(-> check(5), e -> 0),
+        catch(-> panic(), e -> e),
+        catch(-> 1 / 0, e -> e),
+    ]
+}
+main()
+"#;
+        assert_eq!(
+            evaluate(source).map(|v| v.to_string()),
+            Ok("[403, 404, 5, (), division by zero]".to_string())
+        );
+        let error = evaluate(
+            "f = -> panic(1, 'a')
+f()",
+        )
+        .unwrap_err();
+        assert_eq!(error.message(), "panic: 1 a");
+        assert_eq!(
+            error.payload(),
+            Some(&Value::list(vec![Value::I(1), "a".to_nois()]))
+        );
+    }
+
+    #[test]
+    fn throttle_and_debounce() {
+        let source = r#"
+main = -> {
+    t = throttle(a -> a + 1, 10000)
+    d = debounce(a -> a + 1, 0)
+    [t(1), t(2), d(1), d(2)]
+}
+main()
+"#;
+        assert_eq!(
+            evaluate(source),
+            Ok(Value::list(vec![
+                Value::list(vec![Value::I(2)]),
+                Value::list(vec![]),
+                Value::list(vec![Value::I(2)]),
+                Value::list(vec![Value::I(3)]),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,512 @@
+use std::cell::RefMut;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use crate::ast::ast::AstPair;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "graph",
+        vec![
+            Adjacency::info(),
+            Bfs::info(),
+            Dfs::info(),
+            ShortestPath::info(),
+            TopologicalSort::info(),
+            ConnectedComponents::info(),
+        ],
+    )
+}
+
+/// Directed graph built from a list of edges `[from, to]` or `[from, to, weight]`.
+/// Nodes can be any values and are numbered in order of their first appearance.
+struct Graph {
+    nodes: Vec<Value>,
+    /// Node index by its debug representation
+    index: HashMap<String, usize>,
+    /// Successors of every node with edge weights
+    edges: Vec<Vec<(usize, f64)>>,
+}
+
+impl Graph {
+    fn from_edges(edges: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Result<Graph, Error> {
+        let mut graph = Graph {
+            nodes: vec![],
+            index: HashMap::new(),
+            edges: vec![],
+        };
+        let edges = match &edges.1 {
+            Value::List { items, .. } => items,
+            v => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!("expected list of edges, found {}", v.value_type()),
+                ))
+            }
+        };
+        for edge in edges {
+            let (from, to, weight) = match edge {
+                Value::List { items, .. } => match &items[..] {
+                    [from, to] => (from, to, 1.),
+                    [from, to, Value::I(w)] => (from, to, *w as f64),
+                    [from, to, Value::F(w)] => (from, to, *w),
+                    _ => return Err(edge_error(edge, ctx)),
+                },
+                _ => return Err(edge_error(edge, ctx)),
+            };
+            let from = graph.insert(from);
+            let to = graph.insert(to);
+            graph.edges[from].push((to, weight));
+        }
+        Ok(graph)
+    }
+
+    fn insert(&mut self, node: &Value) -> usize {
+        let key = format!("{:?}", node);
+        if let Some(i) = self.index.get(&key) {
+            return *i;
+        }
+        self.nodes.push(node.clone());
+        self.edges.push(vec![]);
+        self.index.insert(key, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn node(&self, node: &Value, ctx: &mut RefMut<Context>) -> Result<usize, Error> {
+        match self.index.get(&format!("{:?}", node)) {
+            Some(i) => Ok(*i),
+            None => Err(Error::from_callee(
+                ctx,
+                format!("node {} is not in the graph", node),
+            )),
+        }
+    }
+
+    fn values(&self, indices: Vec<usize>) -> Value {
+        Value::list(indices.into_iter().map(|i| self.nodes[i].clone()).collect())
+    }
+
+    /// Node indices of the lightest path, empty if `to` is not reachable from `from`
+    fn dijkstra(&self, from: usize, to: usize) -> Vec<usize> {
+        let mut distance = vec![f64::INFINITY; self.nodes.len()];
+        let mut previous = vec![None; self.nodes.len()];
+        let mut heap = BinaryHeap::from([Reverse(Visit(0., from))]);
+        distance[from] = 0.;
+        while let Some(Reverse(Visit(d, i))) = heap.pop() {
+            if i == to {
+                break;
+            }
+            if d > distance[i] {
+                continue;
+            }
+            for (j, w) in &self.edges[i] {
+                if d + w < distance[*j] {
+                    distance[*j] = d + w;
+                    previous[*j] = Some(i);
+                    heap.push(Reverse(Visit(d + w, *j)));
+                }
+            }
+        }
+        if distance[to].is_infinite() {
+            return vec![];
+        }
+        let mut path = vec![to];
+        while let Some(p) = previous[*path.last().unwrap()] {
+            path.push(p);
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Node reached at the distance, ordered by distance
+struct Visit(f64, usize);
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+fn edge_error(edge: &Value, ctx: &mut RefMut<Context>) -> Error {
+    Error::from_callee(
+        ctx,
+        format!(
+            "expected edge [from, to] or [from, to, weight], found {}",
+            edge
+        ),
+    )
+}
+
+fn graph_arg(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Graph, Error> {
+    match &args[..] {
+        [edges] => Graph::from_edges(edges, ctx),
+        _ => Err(arg_error("([[*]])", args, ctx)),
+    }
+}
+
+fn graph_start_args(
+    args: &Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<(Graph, usize), Error> {
+    match &args[..] {
+        [edges, start] => {
+            let graph = Graph::from_edges(edges, ctx)?;
+            let start = graph.node(&start.1, ctx)?;
+            Ok((graph, start))
+        }
+        _ => Err(arg_error("([[*]], *)", args, ctx)),
+    }
+}
+
+/// Adjacency list of a directed graph given by edges, as pairs of node and its successors
+///
+/// ```text
+/// adjacency([[*]]) -> [[*]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// adjacency([[1, 2], [1, 3], [2, 3]]) -> [[1, [2, 3]], [2, [3]], [3, []]]
+/// ```
+///
+pub struct Adjacency;
+
+impl LibFunction for Adjacency {
+    fn name() -> String {
+        "adjacency".to_string()
+    }
+
+    fn doc() -> String {
+        "Adjacency list of a directed graph given by edges, as pairs of node and its successors"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("edges", Type::list(Type::list(a.clone())))],
+            Type::list(Type::list(a)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let graph = graph_arg(args, ctx)?;
+        Ok(Value::list(
+            (0..graph.nodes.len())
+                .map(|i| {
+                    let successors = graph.edges[i].iter().map(|(j, _)| graph.nodes[*j].clone());
+                    Value::list(vec![
+                        graph.nodes[i].clone(),
+                        Value::list(successors.collect()),
+                    ])
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Nodes reachable from the start node in breadth-first order
+///
+/// ```text
+/// bfs([[*]], *) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// bfs([[1, 2], [1, 3], [2, 4]], 1) -> [1, 2, 3, 4]
+/// ```
+///
+pub struct Bfs;
+
+impl LibFunction for Bfs {
+    fn name() -> String {
+        "bfs".to_string()
+    }
+
+    fn doc() -> String {
+        "Nodes reachable from the start node in breadth-first order".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("edges", Type::list(Type::list(a.clone()))),
+                Param::new("start", a.clone()),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (graph, start) = graph_start_args(args, ctx)?;
+        let mut visited = vec![false; graph.nodes.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut order = vec![];
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for (j, _) in &graph.edges[i] {
+                if !visited[*j] {
+                    visited[*j] = true;
+                    queue.push_back(*j);
+                }
+            }
+        }
+        Ok(graph.values(order))
+    }
+}
+
+/// Nodes reachable from the start node in depth-first preorder
+///
+/// ```text
+/// dfs([[*]], *) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// dfs([[1, 2], [1, 3], [2, 4]], 1) -> [1, 2, 4, 3]
+/// ```
+///
+pub struct Dfs;
+
+impl LibFunction for Dfs {
+    fn name() -> String {
+        "dfs".to_string()
+    }
+
+    fn doc() -> String {
+        "Nodes reachable from the start node in depth-first preorder".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("edges", Type::list(Type::list(a.clone()))),
+                Param::new("start", a.clone()),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (graph, start) = graph_start_args(args, ctx)?;
+        let mut visited = vec![false; graph.nodes.len()];
+        let mut stack = vec![start];
+        let mut order = vec![];
+        while let Some(i) = stack.pop() {
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+            order.push(i);
+            // pushed in reverse, so that successors are visited in edge order
+            stack.extend(graph.edges[i].iter().rev().map(|(j, _)| *j));
+        }
+        Ok(graph.values(order))
+    }
+}
+
+/// Lightest path between two nodes, empty if there is none. Edge weight is its third item, 1 if omitted
+///
+/// ```text
+/// shortest_path([[*]], *, *) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// shortest_path([[1, 2, 5], [1, 3, 1], [3, 2, 1]], 1, 2) -> [1, 3, 2]
+/// ```
+///
+pub struct ShortestPath;
+
+impl LibFunction for ShortestPath {
+    fn name() -> String {
+        "shortest_path".to_string()
+    }
+
+    fn doc() -> String {
+        "Lightest path between two nodes, empty if there is none".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("edges", Type::list(Type::list(a.clone()))),
+                Param::new("from", a.clone()),
+                Param::new("to", a.clone()),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (graph, from, to) = match &args[..] {
+            [edges, from, to] => {
+                let graph = Graph::from_edges(edges, ctx)?;
+                let from = graph.node(&from.1, ctx)?;
+                let to = graph.node(&to.1, ctx)?;
+                (graph, from, to)
+            }
+            _ => return Err(arg_error("([[*]], *, *)", args, ctx)),
+        };
+        if let Some((_, w)) = graph.edges.iter().flatten().find(|(_, w)| *w < 0.) {
+            return Err(Error::from_callee(
+                ctx,
+                format!("negative edge weight {}", w),
+            ));
+        }
+        Ok(graph.values(graph.dijkstra(from, to)))
+    }
+}
+
+/// Nodes of a directed acyclic graph ordered so that every edge goes forward, fails if there is a cycle
+///
+/// ```text
+/// topological_sort([[*]]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// topological_sort([[3, 1], [1, 2], [3, 2]]) -> [3, 1, 2]
+/// ```
+///
+pub struct TopologicalSort;
+
+impl LibFunction for TopologicalSort {
+    fn name() -> String {
+        "topological_sort".to_string()
+    }
+
+    fn doc() -> String {
+        "Nodes of a directed acyclic graph ordered so that every edge goes forward".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("edges", Type::list(Type::list(a.clone())))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let graph = graph_arg(args, ctx)?;
+        let mut in_degree = vec![0; graph.nodes.len()];
+        for (j, _) in graph.edges.iter().flatten() {
+            in_degree[*j] += 1;
+        }
+        let mut queue = (0..graph.nodes.len())
+            .filter(|i| in_degree[*i] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = vec![];
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for (j, _) in &graph.edges[i] {
+                in_degree[*j] -= 1;
+                if in_degree[*j] == 0 {
+                    queue.push_back(*j);
+                }
+            }
+        }
+        if order.len() < graph.nodes.len() {
+            return Err(Error::from_callee(ctx, "graph has a cycle".to_string()));
+        }
+        Ok(graph.values(order))
+    }
+}
+
+/// Groups of nodes connected by edges in any direction, in order of node appearance
+///
+/// ```text
+/// connected_components([[*]]) -> [[*]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// connected_components([[1, 2], [3, 4], [2, 5]]) -> [[1, 2, 5], [3, 4]]
+/// ```
+///
+pub struct ConnectedComponents;
+
+impl LibFunction for ConnectedComponents {
+    fn name() -> String {
+        "connected_components".to_string()
+    }
+
+    fn doc() -> String {
+        "Groups of nodes connected by edges in any direction, in order of node appearance"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("edges", Type::list(Type::list(a.clone())))],
+            Type::list(Type::list(a)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let graph = graph_arg(args, ctx)?;
+        let mut neighbours = graph.edges.clone();
+        for (i, es) in graph.edges.iter().enumerate() {
+            for (j, w) in es {
+                neighbours[*j].push((i, *w));
+            }
+        }
+        let mut component = vec![None; graph.nodes.len()];
+        let mut components: Vec<Vec<usize>> = vec![];
+        for start in 0..graph.nodes.len() {
+            if component[start].is_some() {
+                continue;
+            }
+            component[start] = Some(components.len());
+            let mut stack = vec![start];
+            let mut nodes = vec![];
+            while let Some(i) = stack.pop() {
+                nodes.push(i);
+                for (j, _) in &neighbours[i] {
+                    if component[*j].is_none() {
+                        component[*j] = Some(components.len());
+                        stack.push(*j);
+                    }
+                }
+            }
+            nodes.sort();
+            components.push(nodes);
+        }
+        Ok(Value::list(
+            components.into_iter().map(|c| graph.values(c)).collect(),
+        ))
+    }
+}
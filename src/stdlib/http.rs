@@ -0,0 +1,736 @@
+use std::cell::RefMut;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use colored::Colorize;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::http::{self, ClientOptions, FormPart, Request, Response};
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, parse_options, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+use crate::url::Url;
+
+pub fn package() -> Package {
+    Package::new(
+        "http",
+        vec![
+            Serve::info(),
+            Fetch::info(),
+            Header::info(),
+            ParseCookies::info(),
+            FormEncode::info(),
+            Multipart::info(),
+        ],
+    )
+}
+
+fn pairs_to_nois(pairs: Vec<(String, String)>) -> Value {
+    pairs
+        .into_iter()
+        .map(|(k, v)| vec![k, v])
+        .collect::<Vec<_>>()
+        .to_nois()
+}
+
+fn pairs_from_nois(value: Value) -> Result<Vec<(String, String)>, String> {
+    Vec::<Vec<String>>::from_nois(value)?
+        .into_iter()
+        .map(|p| match &p[..] {
+            [k, v] => Ok((k.clone(), v.clone())),
+            _ => Err(format!("expected key-value pair, found {} items", p.len())),
+        })
+        .collect()
+}
+
+fn request_to_nois(request: Request) -> Value {
+    let fields = vec![
+        ("method", request.method.to_nois()),
+        ("path", request.path.to_nois()),
+        ("query", pairs_to_nois(request.query)),
+        ("headers", pairs_to_nois(request.headers)),
+        ("body", request.body.to_nois()),
+    ];
+    Value::Struct {
+        name: "Request".to_string(),
+        fields: fields
+            .into_iter()
+            .map(|(f, v)| (Identifier::new(f), v))
+            .collect(),
+    }
+}
+
+/// Response from the handler result: a struct with optional `status`, `headers` and `body`
+/// fields or a string sent as plain text body
+fn response_from_nois(value: Value) -> Result<Response, String> {
+    let fields = match value {
+        Value::Struct { fields, .. } => fields,
+        v => {
+            return String::from_nois(v.clone())
+                .map(|body| Response::text(200, &body))
+                .map_err(|_| {
+                    format!(
+                        "expected handler to return response struct or [C], found {}",
+                        v.value_type()
+                    )
+                })
+        }
+    };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(f, _)| f.0 == name)
+            .map(|f| f.1.clone())
+    };
+    Ok(Response {
+        status: field("status").map_or(Ok(200), u16::from_nois)?,
+        headers: field("headers").map_or(Ok(vec![]), pairs_from_nois)?,
+        body: field("body").map_or(Ok(String::new()), String::from_nois)?,
+    })
+}
+
+/// Serve HTTP requests on the port, calling handler with each request one by one. Handler gets
+/// a `Request` struct with method, path, query, headers and body fields and returns either
+/// a string or a struct with status, headers and body fields. Errors of a request, including
+/// header names that are not tokens and header values with line breaks, are reported and
+/// answered with status 500 without stopping the server. If the number of requests is
+/// specified, server stops after handling them. Server listens on the loopback interface
+/// unless another host is specified. Options are name-value pairs:
+///
+///   - `requests`: number of requests to handle before stopping
+///   - `host`: address to listen on, e.g. `0.0.0.0` to accept connections from other hosts
+///   - `read_timeout_ms`: time to wait for a request, slower ones are answered with status 408,
+///     10 seconds by default
+///   - `write_timeout_ms`: time to wait for a response to be sent, 10 seconds by default
+///
+/// ```text
+/// serve(I, (Request) -> *) -> ()
+/// serve(I, (Request) -> *, I) -> ()
+/// serve(I, (Request) -> *, [[*]]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// Response = #{status, headers, body}
+/// serve(8080, req -> Response(200, [['Content-Type', 'application/json']], '{"ok": true}'))
+/// serve(8080, req -> 'hello ' + req.path, 1)
+/// serve(8080, req -> 'hello', [['read_timeout_ms', 500], ['host', '0.0.0.0']])
+/// ```
+///
+pub struct Serve;
+
+impl LibFunction for Serve {
+    fn name() -> String {
+        "serve".to_string()
+    }
+
+    fn doc() -> String {
+        "Serve HTTP requests on the port, calling handler with each request one by one".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("port", Type::Int),
+                Param::new(
+                    "handler",
+                    Type::function(vec![Type::Named("Request".to_string())], Type::Any),
+                ),
+                Param::new("options", Type::Any),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (port, handler, options) = match &args[..] {
+            [p, h] => (p, h, HashMap::new()),
+            [p, h, AstPair(_, Value::I(n))] => (
+                p,
+                h,
+                HashMap::from([("requests".to_string(), Value::I(*n))]),
+            ),
+            [p, h, o @ AstPair(_, Value::List { .. })] => (
+                p,
+                h,
+                parse_options(o.1.clone(), &serve_options())
+                    .map_err(|e| Error::from_span(&o.0, &ctx.ast_context, e))?,
+            ),
+            _ => return Err(arg_error("(I, Fn)", args, ctx)),
+        };
+        let limit = match options.get("requests") {
+            Some(Value::I(n)) => Some(*n),
+            _ => None,
+        };
+        let timeouts = timeout(&options, "read_timeout_ms")
+            .and_then(|r| Ok((r, timeout(&options, "write_timeout_ms")?)))
+            .map_err(|e| Error::from_span(&args[2].0, &ctx.ast_context, e))?;
+        let host = match options.get("host") {
+            Some(h) => String::from_nois(h.clone())
+                .map_err(|e| Error::from_span(&args[2].0, &ctx.ast_context, e))?,
+            None => DEFAULT_HOST.to_string(),
+        };
+        let port = u16::from_nois(port.1.clone())
+            .map_err(|e| Error::from_span(&port.0, &ctx.ast_context, e))?;
+        let listener = TcpListener::bind((host.as_str(), port)).map_err(|e| {
+            Error::from_callee(ctx, format!("unable to listen on {}:{}: {}", host, port, e))
+        })?;
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        if limit.is_some_and(|l| l < 1) {
+            return Ok(Value::Unit);
+        }
+        let mut handled = 0;
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            handled += 1;
+            // a client that sends nothing would block the server, which handles one at a time
+            stream
+                .set_read_timeout(Some(timeouts.0.unwrap_or(DEFAULT_TIMEOUT)))
+                .ok();
+            stream
+                .set_write_timeout(Some(timeouts.1.unwrap_or(DEFAULT_TIMEOUT)))
+                .ok();
+            let response = match http::read_request(&stream) {
+                Ok(request) => {
+                    let arg = AstPair::from_span(&callee, request_to_nois(request));
+                    call_value(handler, vec![arg], &callee, ctx)
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| response_from_nois(r.1))
+                        .and_then(|r| http::check_headers(&r.headers).map(|_| r))
+                        .unwrap_or_else(|e| {
+                            eprintln!("{}", e);
+                            Response::text(500, "internal server error")
+                        })
+                }
+                Err(response) => response,
+            };
+            // client might be gone already, this does not affect other requests
+            response.write(&mut stream).ok();
+            if limit.is_some_and(|l| handled >= l) {
+                break;
+            }
+        }
+        Ok(Value::Unit)
+    }
+}
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn serve_options() -> Vec<Param> {
+    vec![
+        Param::new("requests", Type::Int),
+        Param::new("host", Type::list(Type::Char)),
+        Param::new("read_timeout_ms", Type::Int),
+        Param::new("write_timeout_ms", Type::Int),
+    ]
+}
+
+/// Timeout option in milliseconds, it must be positive if specified
+fn timeout(options: &HashMap<String, Value>, name: &str) -> Result<Option<Duration>, String> {
+    match options.get(name) {
+        Some(Value::I(ms)) if *ms > 0 => Ok(Some(Duration::from_millis(*ms as u64))),
+        Some(v) => Err(format!("option '{}' must be positive, found {}", name, v)),
+        None => Ok(None),
+    }
+}
+
+fn response_to_nois(response: Response) -> Value {
+    let fields = vec![
+        ("status", Value::I(response.status as i128)),
+        ("headers", pairs_to_nois(response.headers)),
+        ("body", response.body.to_nois()),
+    ];
+    Value::Struct {
+        name: "Response".to_string(),
+        fields: fields
+            .into_iter()
+            .map(|(f, v)| (Identifier::new(f), v))
+            .collect(),
+    }
+}
+
+/// Send HTTP request to the `http` or `https` url and return a `Response` struct with status,
+/// headers and body fields. Request fails if the server cannot be reached, responses with error
+/// status are returned as is. Options are name-value pairs:
+///
+///   - `method`: request method, `GET` by default
+///   - `headers`: request headers as name-value pairs
+///   - `body`: request body
+///   - `timeout_ms`: time to wait for the connection and for every read and write
+///   - `verify`: whether the server certificate is verified, disabling it is insecure and
+///     reported with a warning
+///   - `ca_bundle`: path to a PEM file with CA certificates to trust instead of the bundled ones
+///
+/// ```text
+/// fetch([C]) -> Response
+/// fetch([C], [[*]]) -> Response
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// fetch('https://example.com').status -> 200
+/// fetch('http://localhost:8080/hook', [['method', 'POST'], ['body', '{}']])
+/// fetch('https://localhost:8443', [['ca_bundle', 'certs/dev.pem'], ['timeout_ms', 500]])
+/// ```
+///
+pub struct Fetch;
+
+impl LibFunction for Fetch {
+    fn name() -> String {
+        "fetch".to_string()
+    }
+
+    fn doc() -> String {
+        "Send HTTP request to the `http` or `https` url and return a `Response` struct".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("url", Type::list(Type::Char)),
+                Param::new("options", Type::Any),
+            ],
+            Type::Named("Response".to_string()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (url, options) = match &args[..] {
+            [u] => (u, HashMap::new()),
+            [u, o @ AstPair(_, Value::List { .. })] => (
+                u,
+                parse_options(o.1.clone(), &fetch_options())
+                    .map_err(|e| Error::from_span(&o.0, &ctx.ast_context, e))?,
+            ),
+            _ => return Err(arg_error("([C]) | ([C], [[*]])", args, ctx)),
+        };
+        let url = String::from_nois(url.1.clone())
+            .and_then(|u| Url::parse(&u))
+            .map_err(|e| Error::from_span(&url.0, &ctx.ast_context, e))?;
+        let option_error =
+            |ctx: &RefMut<Context>, e: String| Error::from_span(&args[1].0, &ctx.ast_context, e);
+        let string = |name: &str| {
+            options
+                .get(name)
+                .cloned()
+                .map(String::from_nois)
+                .transpose()
+        };
+        let method = string("method")
+            .map_err(|e| option_error(ctx, e))?
+            .unwrap_or_else(|| "GET".to_string());
+        let body = string("body").map_err(|e| option_error(ctx, e))?;
+        let headers = options
+            .get("headers")
+            .map(|h| pairs_from_nois(h.clone()))
+            .transpose()
+            .map_err(|e| option_error(ctx, e))?
+            .unwrap_or_default();
+        let client_options = ClientOptions {
+            timeout: timeout(&options, "timeout_ms").map_err(|e| option_error(ctx, e))?,
+            verify: !matches!(options.get("verify"), Some(Value::B(false))),
+            ca_bundle: string("ca_bundle")
+                .map_err(|e| option_error(ctx, e))?
+                .map(PathBuf::from),
+        };
+        if !client_options.verify && url.scheme == "https" {
+            eprintln!(
+                "{}",
+                format!(
+                    "warning: certificate verification is disabled, connection to {} is insecure",
+                    url.host
+                )
+                .yellow()
+            );
+        }
+        let response = http::fetch(
+            &method,
+            &url,
+            &headers,
+            &body.unwrap_or_default(),
+            &client_options,
+        )
+        .map_err(|e| Error::from_callee(ctx, format!("request to {} failed: {}", url, e)))?;
+        Ok(response_to_nois(response))
+    }
+}
+
+fn fetch_options() -> Vec<Param> {
+    vec![
+        Param::new("method", Type::list(Type::Char)),
+        Param::new("headers", Type::list(Type::list(Type::list(Type::Char)))),
+        Param::new("body", Type::list(Type::Char)),
+        Param::new("timeout_ms", Type::Int),
+        Param::new("verify", Type::Bool),
+        Param::new("ca_bundle", Type::list(Type::Char)),
+    ]
+}
+
+/// Value of the first header with the name ignoring case, as an option
+///
+/// ```text
+/// header([[C]], [C]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// header(req.headers, 'content-type') -> ['application/json']
+/// ```
+///
+pub struct Header;
+
+impl LibFunction for Header {
+    fn name() -> String {
+        "header".to_string()
+    }
+
+    fn doc() -> String {
+        "Value of the first header with the name ignoring case, as an option".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("headers", Type::list(Type::list(Type::list(Type::Char)))),
+                Param::new("name", Type::list(Type::Char)),
+            ],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (headers, name) = match &args[..] {
+            [h, n] => (
+                pairs_from_nois(h.1.clone())
+                    .map_err(|e| Error::from_span(&h.0, &ctx.ast_context, e))?,
+                String::from_nois(n.1.clone()).map_err(|_| arg_error("([[C]], [C])", args, ctx))?,
+            ),
+            _ => return Err(arg_error("([[C]], [C])", args, ctx)),
+        };
+        Ok(http::header(&headers, &name).to_nois())
+    }
+}
+
+/// Name-value pairs of the `Cookie` header
+///
+/// ```text
+/// parse_cookies([C]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// parse_cookies('session=abc; theme=dark') -> [['session', 'abc'], ['theme', 'dark']]
+/// ```
+///
+pub struct ParseCookies;
+
+impl LibFunction for ParseCookies {
+    fn name() -> String {
+        "parse_cookies".to_string()
+    }
+
+    fn doc() -> String {
+        "Name-value pairs of the `Cookie` header".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("cookie", Type::list(Type::Char))],
+            Type::list(Type::list(Type::list(Type::Char))),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let cookie = match &args[..] {
+            [c] => String::from_nois(c.1.clone()).map_err(|_| arg_error("([C])", args, ctx))?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        Ok(pairs_to_nois(http::parse_cookies(&cookie)))
+    }
+}
+
+/// `application/x-www-form-urlencoded` body of the name-value pairs
+///
+/// ```text
+/// form_encode([[C]]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// form_encode([['q', 'nois lang'], ['page', '2']]) -> 'q=nois+lang&page=2'
+/// ```
+///
+pub struct FormEncode;
+
+impl LibFunction for FormEncode {
+    fn name() -> String {
+        "form_encode".to_string()
+    }
+
+    fn doc() -> String {
+        "`application/x-www-form-urlencoded` body of the name-value pairs".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new(
+                "fields",
+                Type::list(Type::list(Type::list(Type::Char))),
+            )],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let fields = match &args[..] {
+            [f] => pairs_from_nois(f.1.clone())
+                .map_err(|e| Error::from_span(&f.0, &ctx.ast_context, e))?,
+            _ => return Err(arg_error("([[C]])", args, ctx)),
+        };
+        Ok(http::form_encode(&fields).to_nois())
+    }
+}
+
+/// `multipart/form-data` body of the parts, with the `Content-Type` header value carrying its
+/// boundary. Part is a form field `[name, value]` or a file `[name, filename, content]`, with
+/// optional content type `[name, filename, content_type, content]`
+///
+/// ```text
+/// multipart([[C]]) -> Multipart
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// form = multipart([['title', 'notes'], ['file', 'a.txt', 'text/plain', 'hello']])
+/// form.content_type -> 'multipart/form-data; boundary=nois-...'
+/// form.body -> '--nois-...'
+/// ```
+///
+pub struct Multipart;
+
+impl LibFunction for Multipart {
+    fn name() -> String {
+        "multipart".to_string()
+    }
+
+    fn doc() -> String {
+        "`multipart/form-data` body of the parts, with the `Content-Type` header value carrying its boundary".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new(
+                "parts",
+                Type::list(Type::list(Type::list(Type::Char))),
+            )],
+            Type::Named("Multipart".to_string()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let parts = match &args[..] {
+            [p] => Vec::<Vec<String>>::from_nois(p.1.clone())
+                .and_then(|ps| ps.into_iter().map(form_part).collect::<Result<Vec<_>, _>>())
+                .map_err(|e| Error::from_span(&p.0, &ctx.ast_context, e))?,
+            _ => return Err(arg_error("([[C]])", args, ctx)),
+        };
+        let boundary = loop {
+            let boundary = format!("nois-{:016x}", ctx.rng.next_u64());
+            if !parts.iter().any(|p| p.content.contains(&boundary)) {
+                break boundary;
+            }
+        };
+        let fields = vec![
+            (
+                "content_type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ),
+            ("body", http::multipart(&parts, &boundary)),
+        ];
+        Ok(Value::Struct {
+            name: "Multipart".to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(f, v)| (Identifier::new(f), v.to_nois()))
+                .collect(),
+        })
+    }
+}
+
+fn form_part(part: Vec<String>) -> Result<FormPart, String> {
+    let file = |name: &str, filename: &str, content_type: &str, content: &str| FormPart {
+        name: name.to_string(),
+        filename: Some(filename.to_string()),
+        content_type: Some(content_type.to_string()),
+        content: content.to_string(),
+    };
+    match &part[..] {
+        [name, content] => Ok(FormPart {
+            name: name.clone(),
+            filename: None,
+            content_type: None,
+            content: content.clone(),
+        }),
+        [name, filename, content] => Ok(file(name, filename, "application/octet-stream", content)),
+        [name, filename, content_type, content] => Ok(file(name, filename, content_type, content)),
+        _ => Err(format!(
+            "expected form part of 2 to 4 items, found {} items",
+            part.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::ast::ast::AstContext;
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    fn request(port: u16, raw: &str) -> String {
+        for _ in 0..50 {
+            if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+                stream.write_all(raw.as_bytes()).unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                return response;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        panic!("server is not listening on port {}", port)
+    }
+
+    #[test]
+    fn serve_requests() {
+        let source = r#"
+Response = #{status, headers, body}
+handle = req -> {
+    assert(req.path == '/ok')
+    Response(201, [['X-Id', '1']], 'hi ' + req.body)
+}
+serve(38457, handle, 2)
+"#;
+        let client = thread::spawn(|| {
+            [
+                request(
+                    38457,
+                    "POST /ok?a=b HTTP/1.1\r\nContent-Length: 3\r\n\r\nyou",
+                ),
+                request(38457, "GET /fail HTTP/1.1\r\n\r\n"),
+            ]
+        });
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+        let ctx = &mut ctx_cell.borrow_mut();
+        assert_eq!(ast.eval(ctx, true).map(|a| a.1), Ok(Value::Unit));
+        let [ok, fail] = client.join().unwrap();
+        assert!(
+            ok.starts_with("HTTP/1.1 201 Created\r\nX-Id: 1\r\n"),
+            "{}",
+            ok
+        );
+        assert!(ok.ends_with("\r\n\r\nhi you"), "{}", ok);
+        assert!(
+            fail.starts_with("HTTP/1.1 500 Internal Server Error"),
+            "{}",
+            fail
+        );
+    }
+
+    #[test]
+    fn serve_options() {
+        let eval = |source: &str| {
+            let ast = NoisParser::parse_program(source)
+                .and_then(|parsed| parse_block(&parsed))
+                .unwrap();
+            let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+            let ctx = &mut ctx_cell.borrow_mut();
+            ast.eval(ctx, true).map(|a| a.1)
+        };
+        let client = thread::spawn(|| request(38458, ""));
+        assert_eq!(
+            eval("serve(38458, req -> 'hi', [['requests', 1], ['read_timeout_ms', 50]])"),
+            Ok(Value::Unit)
+        );
+        let response = client.join().unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 408 Request Timeout"),
+            "{}",
+            response
+        );
+
+        let error = |source| eval(source).unwrap_err().to_string();
+        assert!(error("serve(38458, req -> 'hi', [['verify', False]])")
+            .contains("unknown option 'verify', expected one of: requests, host, read_timeout_ms, write_timeout_ms"));
+        assert!(
+            error("serve(38458, req -> 'hi', [['read_timeout_ms', 'a']])")
+                .contains("option 'read_timeout_ms' expects I, found [C]")
+        );
+        assert!(
+            error("serve(38458, req -> 'hi', [['write_timeout_ms', 0]])")
+                .contains("option 'write_timeout_ms' must be positive, found 0")
+        );
+    }
+
+    #[test]
+    fn fetch_with_options() {
+        let eval = |source: &str| {
+            let ast = NoisParser::parse_program(source)
+                .and_then(|parsed| parse_block(&parsed))
+                .unwrap();
+            let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+            let ctx = &mut ctx_cell.borrow_mut();
+            ast.eval(ctx, true).map(|a| a.1.to_string())
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let request = http::read_request(&stream).unwrap();
+            let body = format!("{} {}", request.method, request.body);
+            Response::text(200, &body).write(&mut &stream).unwrap();
+        });
+        let source = format!(
+            "r = fetch('http://127.0.0.1:{}', [['method', 'PUT'], ['body', 'a'], ['timeout_ms', 1000]])\n[r.status, r.body]",
+            port
+        );
+        assert_eq!(eval(&source), Ok("[200, PUT a]".to_string()));
+        server.join().unwrap();
+
+        let error = |source| eval(source).unwrap_err().to_string();
+        assert!(error("fetch('http://localhost', [['insecure', True]])").contains(
+            "unknown option 'insecure', expected one of: method, headers, body, timeout_ms, verify, ca_bundle"
+        ));
+        assert!(error("fetch('http://localhost', [['verify', 'no']])")
+            .contains("option 'verify' expects B, found [C]"));
+        assert!(error("fetch('localhost')").contains("invalid url 'localhost'"));
+    }
+}
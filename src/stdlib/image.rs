@@ -0,0 +1,144 @@
+use std::cell::RefMut;
+use std::fs;
+
+use crate::ast::ast::AstPair;
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::Value;
+use crate::ppm;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("image", vec![WritePpm::info(), ReadPpm::info()])
+}
+
+/// Write PPM image file, color of every pixel is `[r, g, b]` with channels from 0 to 255
+/// returned by the function called with pixel column and row
+///
+/// ```text
+/// write_ppm([C], I, I, (I, I) -> [I]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// write_ppm('gradient.ppm', 256, 256, (x, y) -> [x, y, 0])
+/// ```
+///
+pub struct WritePpm;
+
+impl LibFunction for WritePpm {
+    fn name() -> String {
+        "write_ppm".to_string()
+    }
+
+    fn doc() -> String {
+        "Write PPM image file, color of every pixel is returned by the function".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("path", Type::list(Type::Char)),
+                Param::new("width", Type::Int),
+                Param::new("height", Type::Int),
+                Param::new(
+                    "pixel",
+                    Type::function(vec![Type::Int, Type::Int], Type::list(Type::Int)),
+                ),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (path, width, height, pixel) = match &args[..] {
+            [path, width, height, pixel] => match (
+                String::from_nois(path.1.clone()),
+                usize::from_nois(width.1.clone()),
+                usize::from_nois(height.1.clone()),
+            ) {
+                (Ok(p), Ok(w), Ok(h)) => (p, w, h, pixel),
+                _ => return Err(arg_error("([C], I, I, Fn)", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], I, I, Fn)", args, ctx)),
+        };
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let coordinates = vec![
+                    AstPair::from_span(&callee, x.to_nois()),
+                    AstPair::from_span(&callee, y.to_nois()),
+                ];
+                let color = call_value(pixel, coordinates, &callee, ctx)?.1;
+                match <[u8; 3]>::try_from(Vec::<u8>::from_nois(color.clone()).unwrap_or_default()) {
+                    Ok(rgb) => pixels.push(rgb),
+                    Err(_) => {
+                        return Err(Error::from_callee(
+                            ctx,
+                            format!(
+                                "expected pixel color [r, g, b] with channels from 0 to 255, found {} at ({}, {})",
+                                color, x, y
+                            ),
+                        ))
+                    }
+                }
+            }
+        }
+        fs::write(&path, ppm::encode(width, height, &pixels))
+            .map_err(|e| Error::from_callee(ctx, format!("unable to write {}: {}", path, e)))?;
+        Ok(Value::Unit)
+    }
+}
+
+/// Read PPM image file as rows of pixels `[r, g, b]` with channels from 0 to 255
+///
+/// ```text
+/// read_ppm([C]) -> [[[I]]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// read_ppm('red.ppm') -> [[[255, 0, 0]]]
+/// ```
+///
+pub struct ReadPpm;
+
+impl LibFunction for ReadPpm {
+    fn name() -> String {
+        "read_ppm".to_string()
+    }
+
+    fn doc() -> String {
+        "Read PPM image file as rows of pixels".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("path", Type::list(Type::Char))],
+            Type::list(Type::list(Type::list(Type::Int))),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let path = match &args[..] {
+            [a] => String::from_nois(a.1.clone()).map_err(|_| arg_error("([C])", args, ctx))?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        let rows = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| ppm::decode(&bytes))
+            .map_err(|e| Error::from_callee(ctx, format!("unable to read {}: {}", path, e)))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| r.into_iter().map(|p| p.to_vec()).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+            .to_nois())
+    }
+}
@@ -1,25 +1,41 @@
 use std::cell::RefMut;
 use std::collections::HashMap;
-use std::process::exit;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process;
 
 use colored::Colorize;
 
 use crate::ast::ast::AstPair;
+use crate::convert::{FromNois, ToNois};
 use crate::error::Error;
+use crate::glob::{glob, walk_dir};
 use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
 use crate::interpret::value::Value;
-use crate::stdlib::lib::{LibFunction, Package};
+use crate::stdlib::lib::{arg_error, parse_options, LibFunction, Package};
+use crate::table::{format_table, Cell};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 
 pub fn package() -> Package {
-    Package {
-        name: "io".to_string(),
-        definitions: HashMap::from([
-            Println::definition(),
-            Eprintln::definition(),
-            Debug::definition(),
-            Panic::definition(),
-        ]),
-    }
+    Package::new(
+        "io",
+        vec![
+            Println::info(),
+            Eprintln::info(),
+            Debug::info(),
+            Panic::info(),
+            Assert::info(),
+            PrintTable::info(),
+            WriteFileAtomic::info(),
+            WithFileLock::info(),
+            EditFiles::info(),
+            WalkDir::info(),
+        ],
+    )
 }
 
 /// Print passed parameters in display mode
@@ -31,13 +47,21 @@ impl LibFunction for Println {
         "println".to_string()
     }
 
-    fn call(args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
-        println!(
-            "{}",
-            args.into_iter()
+    fn doc() -> String {
+        "Print passed parameters in display mode".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(vec![Param::new("values", Type::Any)], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        ctx.println(
+            &args
+                .iter()
                 .map(|a| a.1.to_string())
                 .collect::<Vec<_>>()
-                .join(" ")
+                .join(" "),
         );
         Ok(Value::Unit)
     }
@@ -45,7 +69,9 @@ impl LibFunction for Println {
 
 /// Print passed parameters in display mode in stderr in red color
 ///
-///     println(**) -> ()
+/// ```text
+/// println(**) -> ()
+/// ```
 ///
 pub struct Eprintln;
 
@@ -54,6 +80,14 @@ impl LibFunction for Eprintln {
         "eprintln".to_string()
     }
 
+    fn doc() -> String {
+        "Print passed parameters in display mode in stderr in red color".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(vec![Param::new("values", Type::Any)], Type::Unit)
+    }
+
     fn call(args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         eprintln!(
             "{}",
@@ -69,7 +103,9 @@ impl LibFunction for Eprintln {
 
 /// Print passed parameters in debug mode
 ///
-///     debug(**) -> ()
+/// ```text
+/// debug(**) -> ()
+/// ```
 ///
 pub struct Debug;
 
@@ -78,21 +114,39 @@ impl LibFunction for Debug {
         "debug".to_string()
     }
 
-    fn call(args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
-        println!(
-            "{}",
-            args.into_iter()
+    fn doc() -> String {
+        "Print passed parameters in debug mode".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(vec![Param::new("values", Type::Any)], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        ctx.println(
+            &args
+                .iter()
                 .map(|a| format!("{:?}", a.1))
                 .collect::<Vec<_>>()
-                .join(" ")
+                .join(" "),
         );
         Ok(Value::Unit)
     }
 }
 
-/// Eprint passed parameters and exit with code 1
+/// Fail with an error carrying the passed value. Error can be handled by `catch`, which receives
+/// the value itself. Several values are carried as a list, no values as `()`
+///
+/// ```text
+/// panic(**) -> !
+/// ```
 ///
-///     println(**) -> !
+/// Examples:
+///
+/// ```text
+/// panic('no access')
+/// catch(-> panic(403, 'forbidden'), e -> e) -> [403, 'forbidden']
+/// ```
 ///
 pub struct Panic;
 
@@ -101,10 +155,495 @@ impl LibFunction for Panic {
         "panic".to_string()
     }
 
+    fn doc() -> String {
+        "Fail with an error carrying the passed value".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(vec![Param::new("values", Type::Any)], Type::Any)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let values = args.iter().map(|a| a.1.clone()).collect::<Vec<_>>();
+        let message = match &values[..] {
+            [] => "panic".to_string(),
+            vs => format!(
+                "panic: {}",
+                vs.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        };
+        let payload = match values.len() {
+            0 => Value::Unit,
+            1 => values.into_iter().next().unwrap(),
+            _ => Value::list(values),
+        };
+        Err(Error::Panic {
+            error: Box::new(Error::from_callee(ctx, message)),
+            payload: Box::new(payload),
+        })
+    }
+}
+
+/// Fail with an error if condition is false, optionally with a message
+///
+/// ```text
+/// assert(B) -> ()
+/// assert(B, [C]) -> ()
+/// ```
+///
+pub struct Assert;
+
+impl LibFunction for Assert {
+    fn name() -> String {
+        "assert".to_string()
+    }
+
+    fn doc() -> String {
+        "Fail with an error if condition is false, optionally with a message".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("condition", Type::Bool),
+                Param::new("message", Type::Any),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let message = match &args[..] {
+            [AstPair(_, Value::B(true)), ..] => return Ok(Value::Unit),
+            [AstPair(_, Value::B(false))] => "assertion failed".to_string(),
+            [AstPair(_, Value::B(false)), m] => format!("assertion failed: {}", m.1),
+            _ => return Err(arg_error("(B) | (B, [C])", args, ctx)),
+        };
+        Err(Error::from_callee(ctx, message))
+    }
+}
+
+/// Print rows as a table with aligned columns. Row is either a list or a struct, headers of a
+/// struct table default to its field names. Table printed to a terminal is truncated to its
+/// width, taken from `COLUMNS` environment variable.
+///
+/// ```text
+/// print_table([*]) -> ()
+/// print_table([*], [[C]]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// print_table([['Alice', 30], ['Bob', 4]], ['name', 'age'])
+/// ```
+///
+pub struct PrintTable;
+
+impl LibFunction for PrintTable {
+    fn name() -> String {
+        "print_table".to_string()
+    }
+
+    fn doc() -> String {
+        "Print rows as a table with aligned columns".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("rows", Type::list(fresh())),
+                Param::new("headers", Type::list(Type::list(Type::Char))),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (rows, headers) = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => (items, vec![]),
+            [AstPair(_, Value::List { items, .. }), AstPair(_, Value::List { items: hs, .. })] => {
+                (items, hs.iter().map(|h| h.to_string()).collect())
+            }
+            _ => return Err(arg_error("([*], [[C]]?)", args, ctx)),
+        };
+        let headers = match (headers.is_empty(), rows.first()) {
+            (true, Some(Value::Struct { fields, .. })) => {
+                fields.iter().map(|(f, _)| f.to_string()).collect()
+            }
+            _ => headers,
+        };
+        let cell = |v: &Value| Cell::new(&v.to_string(), matches!(v, Value::I(_) | Value::F(_)));
+        let rows = rows
+            .iter()
+            .map(|row| match row {
+                Value::List { items, .. } => Ok(items.iter().map(cell).collect()),
+                Value::Struct { fields, .. } => Ok(fields.iter().map(|(_, v)| cell(v)).collect()),
+                v => Err(Error::from_callee(
+                    ctx,
+                    format!("expected list or struct row, found {}", v.value_type()),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let width = match ctx.output {
+            None if atty::is(atty::Stream::Stdout) => {
+                env::var("COLUMNS").ok().and_then(|c| c.parse().ok())
+            }
+            _ => None,
+        };
+        for line in format_table(&headers, &rows, width) {
+            ctx.println(&line);
+        }
+        Ok(Value::Unit)
+    }
+}
+
+/// Write contents to a temporary file next to `path` and rename it over `path`, so that readers
+/// never observe a partially written file, even if the script is interrupted
+///
+/// ```text
+/// write_file_atomic([C], [C]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// write_file_atomic('state.txt', 'done')
+/// ```
+///
+pub struct WriteFileAtomic;
+
+impl LibFunction for WriteFileAtomic {
+    fn name() -> String {
+        "write_file_atomic".to_string()
+    }
+
+    fn doc() -> String {
+        "Write contents to a temporary file next to `path` and rename it over `path`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("path", Type::list(Type::Char)),
+                Param::new("contents", Type::list(Type::Char)),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (path, contents) = match &args[..] {
+            [p, c] => match (
+                String::from_nois(p.1.clone()),
+                String::from_nois(c.1.clone()),
+            ) {
+                (Ok(p), Ok(c)) => (p, c),
+                _ => return Err(arg_error("([C], [C])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [C])", args, ctx)),
+        };
+        write_atomic(Path::new(&path), contents.as_bytes())
+            .map_err(|e| Error::from_callee(ctx, format!("unable to write {}: {}", path, e)))?;
+        Ok(Value::Unit)
+    }
+}
+
+/// Temporary file is created in the same directory, since rename is atomic only within a
+/// filesystem. It is removed if writing fails. Permissions of the replaced file are kept
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::other("path is not a file"))?;
+    let permissions = match fs::metadata(path) {
+        Ok(m) => Some(m.permissions()),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+    let written = File::create(&tmp).and_then(|mut f| {
+        if let Some(p) = permissions {
+            f.set_permissions(p)?;
+        }
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/// Call function while holding an exclusive lock of the file at `path`, creating the file if
+/// it does not exist. Blocks until the lock is released by other processes. Lock is released
+/// after the call, even if the function fails
+///
+/// ```text
+/// with_file_lock([C], () -> A) -> A
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// with_file_lock('state.lock', -> write_file_atomic('state.txt', 'done'))
+/// ```
+///
+pub struct WithFileLock;
+
+impl LibFunction for WithFileLock {
+    fn name() -> String {
+        "with_file_lock".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function while holding an exclusive lock of the file at `path`".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("path", Type::list(Type::Char)),
+                Param::new("body", Type::function(vec![], a.clone())),
+            ],
+            a,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (path, body) = match &args[..] {
+            [p, body] => match String::from_nois(p.1.clone()) {
+                Ok(p) => (p, body),
+                _ => return Err(arg_error("([C], Fn)", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], Fn)", args, ctx)),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.lock().map(|_| f))
+            .map_err(|e| Error::from_callee(ctx, format!("unable to lock {}: {}", path, e)))?;
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let res = call_value(body, vec![], &callee, ctx);
+        let unlocked = file.unlock();
+        let res = res?;
+        unlocked
+            .map_err(|e| Error::from_callee(ctx, format!("unable to unlock {}: {}", path, e)))?;
+        Ok(res.1)
+    }
+}
+
+/// Replace contents of every file matching the glob pattern with the result of the function
+/// called with them. Files are written only if their contents changed, paths of changed files
+/// are returned. Options are name-value pairs:
+///
+///   - `gitignore`: whether to skip `.git` and files ignored by `.gitignore` files
+///
+/// ```text
+/// edit_files([C], ([C]) -> [C]) -> [[C]]
+/// edit_files([C], ([C]) -> [C], [[*]]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// edit_files('src/**/*.no', s -> join(split(s, 'foo'), 'bar')) -> [src/main.no, src/lib/foo.no]
+/// edit_files('**/*.no', s -> trim(s), [['gitignore', True]]) -> [src/main.no]
+/// ```
+///
+pub struct EditFiles;
+
+impl LibFunction for EditFiles {
+    fn name() -> String {
+        "edit_files".to_string()
+    }
+
+    fn doc() -> String {
+        "Replace contents of every file matching the glob pattern with the function result"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let string = Type::list(Type::Char);
+        Signature::variadic(
+            vec![
+                Param::new("pattern", string.clone()),
+                Param::new("edit", Type::function(vec![string.clone()], string.clone())),
+                Param::new("options", Type::Any),
+            ],
+            Type::list(string),
+        )
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
-        if !args.is_empty() {
-            Eprintln::call(args, ctx).ok();
+        let (pattern, edit, options) = match &args[..] {
+            [p, edit] => (p, edit, HashMap::new()),
+            [p, edit, o @ AstPair(_, Value::List { .. })] => (
+                p,
+                edit,
+                parse_options(o.1.clone(), &[Param::new("gitignore", Type::Bool)])
+                    .map_err(|e| Error::from_span(&o.0, &ctx.ast_context, e))?,
+            ),
+            _ => return Err(arg_error("([C], Fn) | ([C], Fn, [[*]])", args, ctx)),
+        };
+        let pattern = String::from_nois(pattern.1.clone())
+            .map_err(|e| Error::from_span(&pattern.0, &ctx.ast_context, e))?;
+        let gitignore = matches!(options.get("gitignore"), Some(Value::B(true)));
+        let paths = glob(&pattern, gitignore).map_err(|e| {
+            Error::from_callee(ctx, format!("unable to list files of {}: {}", pattern, e))
+        })?;
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let mut modified = vec![];
+        for path in paths {
+            let display = path.display().to_string();
+            let contents = fs::read_to_string(&path).map_err(|e| {
+                Error::from_callee(ctx, format!("unable to read {}: {}", display, e))
+            })?;
+            let arg = AstPair::from_span(&callee, contents.clone().to_nois());
+            let res = call_value(edit, vec![arg], &callee, ctx)?.1;
+            let edited = String::from_nois(res.clone()).map_err(|_| {
+                Error::from_callee(
+                    ctx,
+                    format!(
+                        "expected [C] contents of {}, found {}",
+                        display,
+                        res.value_type()
+                    ),
+                )
+            })?;
+            if edited != contents {
+                write_atomic(&path, edited.as_bytes()).map_err(|e| {
+                    Error::from_callee(ctx, format!("unable to write {}: {}", display, e))
+                })?;
+                modified.push(display);
+            }
         }
-        exit(1)
+        Ok(modified.to_nois())
+    }
+}
+
+/// Paths of files in the directory and its subdirectories, sorted. Options are name-value pairs:
+///
+///   - `glob`: pattern the path relative to the directory has to match, e.g. `**/*.no`
+///   - `gitignore`: whether to skip `.git` and files ignored by `.gitignore` files
+///
+/// ```text
+/// walk_dir([C]) -> [[C]]
+/// walk_dir([C], [[*]]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// walk_dir('src') -> [src/lib/foo.no, src/main.no]
+/// walk_dir('.', [['glob', '**/*.no'], ['gitignore', True]]) -> [src/lib/foo.no, src/main.no]
+/// ```
+///
+pub struct WalkDir;
+
+impl LibFunction for WalkDir {
+    fn name() -> String {
+        "walk_dir".to_string()
+    }
+
+    fn doc() -> String {
+        "Paths of files in the directory and its subdirectories, sorted".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("dir", Type::list(Type::Char)),
+                Param::new("options", Type::Any),
+            ],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (dir, options) = match &args[..] {
+            [d] => (d, HashMap::new()),
+            [d, o @ AstPair(_, Value::List { .. })] => (
+                d,
+                parse_options(o.1.clone(), &walk_dir_options())
+                    .map_err(|e| Error::from_span(&o.0, &ctx.ast_context, e))?,
+            ),
+            _ => return Err(arg_error("([C]) | ([C], [[*]])", args, ctx)),
+        };
+        let dir = String::from_nois(dir.1.clone())
+            .map_err(|e| Error::from_span(&dir.0, &ctx.ast_context, e))?;
+        let pattern = options
+            .get("glob")
+            .cloned()
+            .map(String::from_nois)
+            .transpose()
+            .map_err(|e| Error::from_span(&args[1].0, &ctx.ast_context, e))?;
+        let gitignore = matches!(options.get("gitignore"), Some(Value::B(true)));
+        let paths = walk_dir(Path::new(&dir), pattern.as_deref(), gitignore)
+            .map_err(|e| Error::from_callee(ctx, format!("unable to walk {}: {}", dir, e)))?;
+        Ok(paths
+            .into_iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .to_nois())
+    }
+}
+
+fn walk_dir_options() -> Vec<Param> {
+    vec![
+        Param::new("glob", Type::list(Type::Char)),
+        Param::new("gitignore", Type::Bool),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    #[test]
+    fn write_atomic_replaces_file() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target");
+        let path = dir.join("io_write_atomic_test.txt");
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "second");
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with(".io_write_atomic_test")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(write_atomic(&dir.join("missing/file.txt"), b"").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("io_write_atomic_permissions_test.sh");
+        fs::remove_file(&path).ok();
+        write_atomic(&path, b"first").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+        write_atomic(&path, b"second").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(read_to_string(&path).unwrap(), "second");
     }
 }
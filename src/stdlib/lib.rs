@@ -1,20 +1,100 @@
 use std::cell::RefMut;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 
+use colored::Colorize;
 use log::debug;
 
-use crate::ast::ast::{AstPair, Identifier};
+use crate::ast::ast::{AstPair, Identifier, Span};
+use crate::convert::FromNois;
 use crate::error::Error;
-use crate::interpret::context::{Context, Definition};
+use crate::interpret::context::{Context, Definition, Definitions, Scope};
 use crate::interpret::evaluate::Evaluate;
 use crate::interpret::value::Value;
 use crate::stdlib::*;
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 use crate::util::vec_to_string_paren;
 
 #[derive(Debug)]
 pub struct Package {
     pub name: String,
     pub definitions: HashMap<Identifier, Definition>,
+    pub functions: Vec<FunctionInfo>,
+}
+
+impl Package {
+    pub fn new(name: &str, functions: Vec<FunctionInfo>) -> Package {
+        let functions = functions
+            .into_iter()
+            .map(|f| FunctionInfo {
+                package: name.to_string(),
+                ..f
+            })
+            .collect::<Vec<_>>();
+        Package {
+            name: name.to_string(),
+            definitions: functions
+                .iter()
+                .map(|f| (Identifier(f.name.clone()), f.definition.clone()))
+                .collect(),
+            functions,
+        }
+    }
+}
+
+/// Machine readable description of a stdlib function, used by tooling
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub package: String,
+    pub name: String,
+    pub doc: String,
+    pub signature: fn(&mut dyn FnMut() -> Type) -> Signature,
+    pub definition: Definition,
+    pub deprecation: Option<Deprecation>,
+}
+
+/// Stdlib function scheduled for removal, calls to it are reported as warnings
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deprecation {
+    /// Interpreter version the function is deprecated since
+    pub since: String,
+    /// How to replace the function, e.g. "use `map` instead"
+    pub hint: String,
+}
+
+impl Deprecation {
+    pub fn message(&self, name: &str) -> String {
+        format!(
+            "'{}' is deprecated since {}: {}",
+            name, self.since, self.hint
+        )
+    }
+}
+
+impl FunctionInfo {
+    /// Name qualified with package name, e.g. `list.map`
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.package, self.name)
+    }
+
+    /// Qualified name with signature, e.g. `list.map(list: [*], f: (*) -> *) -> [*]`
+    pub fn declaration(&self) -> String {
+        format!(
+            "{}{}",
+            self.qualified_name(),
+            (self.signature)(&mut || Type::Any)
+        )
+    }
+
+    /// Reference entry, declaration followed by the doc line
+    pub fn reference(&self) -> String {
+        let mut reference = format!("{}\n    {}", self.declaration(), self.doc);
+        if let Some(d) = &self.deprecation {
+            reference.push_str(&format!("\n    Deprecated since {}: {}", d.since, d.hint));
+        }
+        reference
+    }
 }
 
 pub fn stdlib() -> Vec<Package> {
@@ -25,24 +105,211 @@ pub fn stdlib() -> Vec<Package> {
         list::package(),
         value::package(),
         option::package(),
+        function::package(),
+        string::package(),
+        math::package(),
+        deque::package(),
+        graph::package(),
+        seq::package(),
+        plot::package(),
+        image::package(),
+        random::package(),
+        time::package(),
+        scheduler::package(),
+        semver::package(),
+        url::package(),
+        http::package(),
+        net::package(),
+        process::package(),
+        checksum::package(),
+        #[cfg(feature = "desktop")]
+        desktop::package(),
+        build::package(),
     ]
+    .into_iter()
+    .chain(native_packages())
+    .collect()
+}
+
+/// Package implemented in a separate crate, e.g. bindings to a native library. Its functions are
+/// `LibFunction`s, same as the ones of builtin packages:
+///
+/// ```text
+/// struct Sqlite;
+///
+/// impl NativePackage for Sqlite {
+///     fn package() -> Package {
+///         Package::new("sqlite", vec![Open::info(), Query::info()])
+///     }
+/// }
+///
+/// register_package::<Sqlite>()?;
+/// ```
+///
+pub trait NativePackage {
+    fn package() -> Package;
+}
+
+static NATIVE_PACKAGES: Mutex<Vec<fn() -> Package>> = Mutex::new(Vec::new());
+static STDLIB: OnceLock<Definitions> = OnceLock::new();
+static REGISTRY: OnceLock<HashMap<String, FunctionInfo>> = OnceLock::new();
+
+/// Add native package to stdlib. Stdlib is shared by all contexts once initialized, so packages
+/// have to be registered before the first program is run, usually at the start of `main`.
+/// Fails if package defines a function that is already defined by another package.
+pub fn register_package<P: NativePackage>() -> Result<(), String> {
+    let package = P::package();
+    if STDLIB.get().is_some() || REGISTRY.get().is_some() {
+        return Err(format!(
+            "unable to register package '{}', stdlib is already initialized",
+            package.name
+        ));
+    }
+    let defined = stdlib()
+        .into_iter()
+        .flat_map(|p| p.functions)
+        .map(|f| (f.name.clone(), f))
+        .collect::<HashMap<_, _>>();
+    if let Some(f) = package
+        .functions
+        .iter()
+        .find_map(|f| defined.get(&f.name))
+    {
+        return Err(format!(
+            "unable to register package '{}', function '{}' is already defined by package '{}'",
+            package.name,
+            f.name,
+            f.package
+        ));
+    }
+    NATIVE_PACKAGES.lock().unwrap().push(P::package);
+    Ok(())
+}
+
+fn native_packages() -> Vec<Package> {
+    NATIVE_PACKAGES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|p| p())
+        .collect()
+}
+
+/// Definitions of all stdlib packages, initialized on first use
+pub fn shared_stdlib() -> Definitions {
+    STDLIB
+        .get_or_init(|| Arc::new(stdlib().into_iter().flat_map(|p| p.definitions).collect()))
+        .clone()
+}
+
+/// Definitions of stdlib packages that do not interact with the host: no output, files, network,
+/// processes or randomness. Used to evaluate untrusted expressions, see `embed::eval_expr`
+pub fn pure_stdlib() -> Definitions {
+    static PURE_STDLIB: OnceLock<Definitions> = OnceLock::new();
+    PURE_STDLIB
+        .get_or_init(|| {
+            let packages = vec![
+                binary_operator::package(),
+                unary_operator::package(),
+                list::package(),
+                value::package(),
+                option::package(),
+                function::package(),
+                string::package(),
+                math::package(),
+                deque::package(),
+                graph::package(),
+                seq::package(),
+                semver::package(),
+                url::package(),
+            ];
+            Arc::new(packages.into_iter().flat_map(|p| p.definitions).collect())
+        })
+        .clone()
+}
+
+/// Metadata of all stdlib functions, keyed by function name
+pub fn registry() -> &'static HashMap<String, FunctionInfo> {
+    REGISTRY.get_or_init(|| {
+        stdlib()
+            .into_iter()
+            .flat_map(|p| p.functions)
+            .map(|f| (f.name.clone(), f))
+            .collect()
+    })
+}
+
+pub fn function_info(name: &str) -> Option<&'static FunctionInfo> {
+    registry().get(name)
+}
+
+/// Functions whose name or qualified name starts with the query, sorted by qualified name.
+/// Used for reference lookup and name completion.
+pub fn find_functions(query: &str) -> Vec<&'static FunctionInfo> {
+    let mut found = registry()
+        .values()
+        .filter(|f| f.name.starts_with(query) || f.qualified_name().starts_with(query))
+        .collect::<Vec<_>>();
+    found.sort_by_key(|f| f.qualified_name());
+    found
 }
 
 pub trait LibFunction {
     fn name() -> String;
 
+    /// One-line description, shown by `nois doc`
+    fn doc() -> String;
+
+    /// Type signature, generic parameter types are created using `fresh`.
+    /// Arguments not accepted by the signature fail the call before it is made.
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature;
+
+    fn deprecation() -> Option<Deprecation> {
+        None
+    }
+
     // TODO: use patterns to validate call args
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error>;
 
     fn call_fn(
+        callee: &Span,
         args: Vec<AstPair<Value>>,
         ctx: &mut RefMut<Context>,
     ) -> Result<AstPair<Value>, Error> {
+        // function referenced outside of a call, e.g. eagerly evaluated top-level identifier,
+        // gets its own scope so that errors can point at the callee
+        let outside_call = ctx.scope_stack.last().unwrap().callee.is_none();
+        if outside_call {
+            ctx.scope_stack.push(
+                Scope::new(Self::name())
+                    .with_callee(Some(callee.clone()))
+                    .with_arguments(args.clone()),
+            );
+        }
+        let res = Self::call_scoped(args, ctx);
+        if outside_call {
+            ctx.scope_stack.pop();
+        }
+        res.map(|v| AstPair::from_span(callee, v))
+    }
+
+    fn call_scoped(args: Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let arguments: Vec<AstPair<Value>> = args
             .iter()
             .map(|a| a.eval(ctx, false))
             .collect::<Result<_, _>>()?;
 
+        if let Some(d) = Self::deprecation() {
+            if ctx.deprecation_warned.insert(Self::name()) {
+                let warning = Error::from_callee(ctx, d.message(&Self::name()));
+                eprintln!("{}", format!("{}", warning).yellow());
+            }
+        }
+        let signature = Self::signature(&mut || Type::Any);
+        let arguments = take_finite_seqs(&signature, arguments);
+        if !signature.accepts(&arguments) {
+            return Err(arg_error(&signature.params_string(), &arguments, ctx));
+        }
         let res = Self::call(&arguments, ctx);
         debug!(
             "stdlib function call {:?}, args: {:?}, result: {:?}",
@@ -50,36 +317,261 @@ pub trait LibFunction {
             &arguments,
             &res
         );
-
-        let scope = ctx.scope_stack.last().unwrap();
-        let callee = scope
-            .method_callee
-            .clone()
-            .map(|c| c.0)
-            .or(scope.callee.clone())
-            .expect("callee not found");
-        res.map(|v| AstPair::from_span(&callee, v))
+        res
     }
 
     fn definition() -> (Identifier, Definition) {
-        (
-            Identifier(Self::name()),
-            Definition::System(|args, ctx| Self::call_fn(args, ctx)),
-        )
+        (Identifier(Self::name()), Definition::System(Self::call_fn))
+    }
+
+    fn info() -> FunctionInfo {
+        FunctionInfo {
+            package: String::new(),
+            name: Self::name(),
+            doc: Self::doc(),
+            signature: Self::signature,
+            definition: Self::definition().1,
+            deprecation: Self::deprecation(),
+        }
     }
 }
 
+/// Replace finite sequences passed to list parameters with lists of their items, e.g. range in
+/// `(1..4).map(f)`
+fn take_finite_seqs(signature: &Signature, args: Vec<AstPair<Value>>) -> Vec<AstPair<Value>> {
+    args.into_iter()
+        .enumerate()
+        .map(|(i, a)| match (signature.param(i).map(|p| &p.ty), &a.1) {
+            (Some(Type::List(_)), Value::Seq(s)) => match s.items() {
+                Some(items) => a.map(|_| Value::list(items.clone())),
+                None => a,
+            },
+            _ => a,
+        })
+        .collect()
+}
+
+/// Error of arguments not matching the expected type of the called function.
+/// If the function is in stdlib registry, error points at the first argument rejected by its
+/// signature and mentions the signature.
 pub fn arg_error(
     expected_type: &str,
     args: &Vec<AstPair<Value>>,
     ctx: &mut RefMut<Context>,
 ) -> Error {
-    Error::from_callee(
-        ctx,
-        format!(
-            "expected {}, found {}",
-            expected_type,
-            vec_to_string_paren(args.into_iter().map(|l| l.1.value_type()).collect())
+    let name = ctx.scope_stack.last().unwrap().name.clone();
+    let info = function_info(&name);
+    let rejected = info.and_then(|i| {
+        let signature = (i.signature)(&mut || Type::Any);
+        let index = signature.rejected_arg(args)?;
+        Some((index, signature.param(index)?.ty.clone()))
+    });
+    match (info, rejected) {
+        (Some(_), Some((index, Type::List(_)))) if matches!(args[index].1, Value::Seq(_)) => {
+            Error::from_span(
+                &args[index].0,
+                &ctx.ast_context,
+                format!(
+                    "lazy sequence {} cannot be used as a list, take its items with 'take' or 'take_while'",
+                    args[index].1
+                ),
+            )
+        }
+        (Some(info), Some((index, ty))) => Error::from_span(
+            &args[index].0,
+            &ctx.ast_context,
+            format!(
+                "expected {}, found {} in argument {} of {}",
+                ty,
+                args[index].1.value_type(),
+                index + 1,
+                info.declaration()
+            ),
         ),
-    )
+        (info, _) => Error::from_callee(
+            ctx,
+            format!(
+                "expected {}, found {}{}",
+                expected_type,
+                vec_to_string_paren(args.iter().map(|l| l.1.value_type()).collect()),
+                info.map_or(String::new(), |i| format!(" in {}", i.declaration()))
+            ),
+        ),
+    }
+}
+
+/// Options argument given as name-value pairs, e.g. `[['read_timeout_ms', 500]]`. Option names
+/// and value types are checked against the schema of known options.
+pub fn parse_options(value: Value, schema: &[Param]) -> Result<HashMap<String, Value>, String> {
+    let options = HashMap::<String, Value>::from_nois(value)?;
+    for (name, v) in &options {
+        match schema.iter().find(|p| &p.name == name) {
+            Some(p) if p.ty.admits(v) => {}
+            Some(p) => {
+                return Err(format!(
+                    "option '{}' expects {}, found {}",
+                    name,
+                    p.ty,
+                    v.value_type()
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "unknown option '{}', expected one of: {}",
+                    name,
+                    schema
+                        .iter()
+                        .map(|p| p.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::ast::ast::{AstContext, Span};
+    use crate::ast::ast_parser::parse_block;
+    use crate::diagnostic::Diagnostic;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    struct Old;
+
+    impl LibFunction for Old {
+        fn name() -> String {
+            "old".to_string()
+        }
+
+        fn doc() -> String {
+            "Deprecated function".to_string()
+        }
+
+        fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+            Signature::fixed(vec![], Type::Unit)
+        }
+
+        fn deprecation() -> Option<Deprecation> {
+            Some(Deprecation {
+                since: "0.1".to_string(),
+                hint: "use `new` instead".to_string(),
+            })
+        }
+
+        fn call(_args: &Vec<AstPair<Value>>, _ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+            Ok(Value::Unit)
+        }
+    }
+
+    #[test]
+    fn deprecated_call_warns_once() {
+        let source = "main = -> {\n  old()\n  old()\n}\nmain()";
+        let a_ctx = AstContext::new(source);
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let stdlib = Arc::new(HashMap::from([Old::definition()]));
+        let ctx_cell = RefCell::new(Context::new(a_ctx, stdlib));
+        let ctx = &mut ctx_cell.borrow_mut();
+        assert_eq!(ast.eval(ctx, true).map(|a| a.1), Ok(Value::Unit));
+        assert_eq!(
+            ctx.deprecation_warned.iter().collect::<Vec<_>>(),
+            vec!["old"]
+        );
+        assert!(Old::info()
+            .reference()
+            .ends_with("Deprecated since 0.1: use `new` instead"));
+    }
+
+    #[test]
+    fn arg_error_points_at_argument() {
+        let error = |source: &str| {
+            let ast = NoisParser::parse_program(source)
+                .and_then(|parsed| parse_block(&parsed))
+                .unwrap();
+            let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+            let ctx = &mut ctx_cell.borrow_mut();
+            let error = ast.eval(ctx, true).unwrap_err();
+            let span = Diagnostic::from_error(&error, "runtime", "a.no")
+                .span
+                .unwrap();
+            (error.message(), source[span.start..span.end].to_string())
+        };
+        assert_eq!(
+            error("a = 1\nmain = -> map(a, b -> b)\nmain()"),
+            (
+                "expected [*], found I in argument 1 of list.map(list: [*], f: (*) -> *) -> [*]"
+                    .to_string(),
+                "a".to_string()
+            )
+        );
+        assert_eq!(
+            error("map([1])"),
+            (
+                "expected ([*], (*) -> *), found ([I]) in list.map(list: [*], f: (*) -> *) -> [*]"
+                    .to_string(),
+                "map([1])".to_string()
+            )
+        );
+        assert_eq!(
+            error("map(repeat(1), a -> a)"),
+            (
+                "lazy sequence <repeat> cannot be used as a list, take its items with 'take' or 'take_while'"
+                    .to_string(),
+                "repeat(1)".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn registry_describes_stdlib() {
+        let mut names = shared_stdlib()
+            .keys()
+            .map(|i| i.0.clone())
+            .collect::<Vec<_>>();
+        let mut registered = registry().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        registered.sort();
+        assert_eq!(names, registered);
+
+        assert_eq!(
+            find_functions("list.ma")
+                .iter()
+                .map(|f| f.reference())
+                .collect::<Vec<_>>(),
+            vec![
+                "list.map(list: [*], f: (*) -> *) -> [*]\n    Convert one list to another calling function on each item"
+            ]
+        );
+        assert_eq!(
+            find_functions("print")
+                .iter()
+                .map(|f| f.qualified_name())
+                .collect::<Vec<_>>(),
+            vec!["io.print_table", "io.println"]
+        );
+    }
+
+    #[test]
+    fn signature_accepts_args() {
+        let accepts = |name: &str, args: Vec<Value>| {
+            let args = args
+                .into_iter()
+                .map(|a| AstPair(Span { start: 0, end: 0 }, a))
+                .collect::<Vec<_>>();
+            (function_info(name).unwrap().signature)(&mut || Type::Any).accepts(&args)
+        };
+        assert!(accepts("range", vec![Value::I(1), Value::I(2)]));
+        assert!(!accepts("range", vec![Value::C('a')]));
+        assert!(accepts("assert", vec![Value::B(true)]));
+        assert!(!accepts("assert", vec![]));
+        assert!(!accepts("none", vec![Value::I(1)]));
+        assert!(accepts("println", vec![]));
+    }
 }
@@ -1,31 +1,50 @@
 use std::cell::RefMut;
-use std::collections::HashMap;
-
-use log::debug;
+use std::cmp::Ordering;
 
 use crate::ast::ast::{AstPair, Span};
 use crate::error::Error;
-use crate::interpret::context::{Context, Scope};
-use crate::interpret::evaluate::Evaluate;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
 use crate::interpret::value::Value;
 use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 
 pub fn package() -> Package {
-    Package {
-        name: "list".to_string(),
-        definitions: HashMap::from([Range::definition(), Map::definition(), Filter::definition()]),
-    }
+    Package::new(
+        "list",
+        vec![
+            Range::info(),
+            Map::info(),
+            Filter::info(),
+            Permutations::info(),
+            Combinations::info(),
+            Sort::info(),
+            SortWith::info(),
+            BinarySearch::info(),
+            Insort::info(),
+            IsSorted::info(),
+            DropWhile::info(),
+            Scan::info(),
+            Dedup::info(),
+            Intersperse::info(),
+        ],
+    )
 }
 
 /// Generate a list of integers in specified range
 ///
-///     range(I, I) -> [I]    from inclusive, to exclusive
-///     range(I)    -> [I]    to exclusive
+/// ```text
+/// range(I, I) -> [I]    from inclusive, to exclusive
+/// range(I)    -> [I]    to exclusive
+/// ```
 ///
 /// Examples:
 ///
-///     range(2) -> [0, 1]
-///     range(10, 15) -> [10, 11, 12, 13, 14]
+/// ```text
+/// range(2) -> [0, 1]
+/// range(10, 15) -> [10, 11, 12, 13, 14]
+/// ```
 ///
 pub struct Range;
 
@@ -34,6 +53,14 @@ impl LibFunction for Range {
         "range".to_string()
     }
 
+    fn doc() -> String {
+        "Generate a list of integers in specified range".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(vec![Param::new("bounds", Type::Int)], Type::list(Type::Int))
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let range = match &args.into_iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
             [Value::I(s)] => 0..*s,
@@ -50,11 +77,15 @@ impl LibFunction for Range {
 // TODO: element index as second argument
 /// Convert one list to another calling function on each item
 ///
-///     map([*], (*) -> *) -> [*]
+/// ```text
+/// map([*], (*) -> *) -> [*]
+/// ```
 ///
 /// Examples:
 ///
-///     map([1, 2, 3], e -> e + 1) -> [2, 3, 4]
+/// ```text
+/// map([1, 2, 3], e -> e + 1) -> [2, 3, 4]
+/// ```
 ///
 pub struct Map;
 
@@ -63,30 +94,30 @@ impl LibFunction for Map {
         "map".to_string()
     }
 
+    fn doc() -> String {
+        "Convert one list to another calling function on each item".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let (a, b) = (fresh(), fresh());
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("f", Type::function(vec![a], b.clone())),
+            ],
+            Type::list(b),
+        )
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let list = match &args.into_iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
-            [Value::List { items: l, .. }, Value::Fn(..)] => l.clone(),
+            [Value::List { items: l, .. }, Value::Fn(..) | Value::Native(..)] => l.clone(),
             _ => return Err(arg_error("([*], Fn)", args, ctx)),
         };
-        let callee: Option<Span> = ctx.scope_stack.last().unwrap().callee.clone();
-
         let res = list
             .into_iter()
-            .map(|li| {
-                ctx.scope_stack.push(
-                    Scope::new("<closure>".to_string())
-                        .with_callee(callee.clone())
-                        .with_arguments(vec![args[0].map(|_| li.clone())]),
-                );
-                debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
-
-                let next = args[1].eval(ctx, true).map_err(|e| e)?;
-
-                debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
-                ctx.scope_stack.pop();
-
-                Ok(next.1)
-            })
+            .enumerate()
+            .map(|(i, li)| call_item(&Map::name(), &args[1], i, vec![li], ctx))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Value::List {
@@ -99,11 +130,15 @@ impl LibFunction for Map {
 // TODO: element index as second argument
 /// Filter a list by predicate function
 ///
-///     filter([*], (*) -> B) -> [*]
+/// ```text
+/// filter([*], (*) -> B) -> [*]
+/// ```
 ///
 /// Examples:
 ///
-///     filter([1, 2, 3], e -> e != 2) -> [1, 3]
+/// ```text
+/// filter([1, 2, 3], e -> e != 2) -> [1, 3]
+/// ```
 ///
 pub struct Filter;
 
@@ -112,38 +147,36 @@ impl LibFunction for Filter {
         "filter".to_string()
     }
 
+    fn doc() -> String {
+        "Filter a list by predicate function".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("predicate", Type::function(vec![a.clone()], Type::Bool)),
+            ],
+            Type::list(a),
+        )
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let list = match &args.into_iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
-            [Value::List { items: l, .. }, Value::Fn(..)] => l.clone(),
+            [Value::List { items: l, .. }, Value::Fn(..) | Value::Native(..)] => l.clone(),
             _ => return Err(arg_error("([*], Fn)", args, ctx)),
         };
-        let callee: Option<Span> = ctx.scope_stack.last().unwrap().callee.clone();
-
         let res: Vec<Value> = list
             .into_iter()
-            .map(|li| {
-                ctx.scope_stack.push(
-                    Scope::new("<closure>".to_string())
-                        .with_callee(callee.clone())
-                        .with_arguments(vec![args[0].map(|_| li.clone())]),
-                );
-                debug!("push scope @{}", &ctx.scope_stack.last().unwrap().name);
-
-                let next = args[1].eval(ctx, true).map_err(|e| e)?;
-
-                debug!("pop scope @{}", &ctx.scope_stack.last().unwrap().name);
-                ctx.scope_stack.pop();
-
-                let b = match next.1 {
-                    Value::B(b) => Ok(b),
-                    v => Err(Error::from_callee(
-                        ctx,
-                        format!("expected B, found {}", v.value_type()),
-                    )),
-                }?;
-                Ok((li, b))
+            .enumerate()
+            .map(|(i, li)| {
+                Ok((
+                    li.clone(),
+                    test_item(&Filter::name(), &args[1], i, li, ctx)?,
+                ))
             })
-            .collect::<Result<Vec<_>, _>>()?
+            .collect::<Result<Vec<_>, Error>>()?
             .into_iter()
             .filter_map(|(i, f)| if f { Some(i) } else { None })
             .collect();
@@ -154,3 +187,747 @@ impl LibFunction for Filter {
         })
     }
 }
+
+/// All orderings of list items, in order of item positions
+///
+/// ```text
+/// permutations([*]) -> [[*]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// permutations([1, 2, 3]) -> [[1, 2, 3], [1, 3, 2], [2, 1, 3], [2, 3, 1], [3, 1, 2], [3, 2, 1]]
+/// permutations([]) -> [[]]
+/// ```
+///
+pub struct Permutations;
+
+impl LibFunction for Permutations {
+    fn name() -> String {
+        "permutations".to_string()
+    }
+
+    fn doc() -> String {
+        "All orderings of list items, in order of item positions".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::list(Type::list(a)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let list = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => items,
+            _ => return Err(arg_error("([*])", args, ctx)),
+        };
+        let mut indices = (0..list.len()).collect::<Vec<_>>();
+        let mut res = vec![select(list, &indices)];
+        while next_permutation(&mut indices) {
+            res.push(select(list, &indices));
+        }
+        Ok(Value::List {
+            items: res,
+            spread: false,
+        })
+    }
+}
+
+/// All ways to pick k list items keeping their order, in order of item positions
+///
+/// ```text
+/// combinations([*], I) -> [[*]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// combinations([1, 2, 3], 2) -> [[1, 2], [1, 3], [2, 3]]
+/// combinations([1, 2], 3) -> []
+/// ```
+///
+pub struct Combinations;
+
+impl LibFunction for Combinations {
+    fn name() -> String {
+        "combinations".to_string()
+    }
+
+    fn doc() -> String {
+        "All ways to pick k list items keeping their order".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("k", Type::Int),
+            ],
+            Type::list(Type::list(a)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, k) = match &args[..] {
+            [AstPair(_, Value::List { items, .. }), AstPair(_, Value::I(k))] => (items, *k),
+            _ => return Err(arg_error("([*], I)", args, ctx)),
+        };
+        let k = match usize::try_from(k) {
+            Ok(k) => k,
+            Err(_) => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!("expected non-negative combination size, found {}", k),
+                ))
+            }
+        };
+        let n = list.len();
+        if k > n {
+            return Ok(Value::List {
+                items: vec![],
+                spread: false,
+            });
+        }
+        let mut indices = (0..k).collect::<Vec<_>>();
+        let mut res = vec![];
+        loop {
+            res.push(select(list, &indices));
+            // rightmost index that can still be moved forward
+            let i = match (0..k).rev().find(|&i| indices[i] < n - k + i) {
+                Some(i) => i,
+                None => break,
+            };
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+        }
+        Ok(Value::List {
+            items: res,
+            spread: false,
+        })
+    }
+}
+
+/// Sort list in ascending order, keeping the order of equal items
+///
+/// ```text
+/// sort([*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// sort([3, 1, 2]) -> [1, 2, 3]
+/// sort(['b', 'a']) -> ['a', 'b']
+/// ```
+///
+pub struct Sort;
+
+impl LibFunction for Sort {
+    fn name() -> String {
+        "sort".to_string()
+    }
+
+    fn doc() -> String {
+        "Sort list in ascending order, keeping the order of equal items".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let list = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => items.clone(),
+            _ => return Err(arg_error("([*])", args, ctx)),
+        };
+        let sorted = merge_sort(list, &mut |a, b| Ok::<_, Error>(natural_order(a, b)))?;
+        Ok(Value::list(sorted))
+    }
+}
+
+/// Sort list using comparator function, keeping the order of equal items.
+/// Comparator returns negative integer if the first item goes first, positive if the second one
+/// goes first and zero if they are equal.
+///
+/// ```text
+/// sort_with([*], (*, *) -> I) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// sort_with([1, 3, 2], (a, b) -> b - a) -> [3, 2, 1]
+/// ```
+///
+pub struct SortWith;
+
+impl LibFunction for SortWith {
+    fn name() -> String {
+        "sort_with".to_string()
+    }
+
+    fn doc() -> String {
+        "Sort list using comparator function, keeping the order of equal items".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new(
+                    "comparator",
+                    Type::function(vec![a.clone(), a.clone()], Type::Int),
+                ),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let list = match &args[..] {
+            [AstPair(_, Value::List { items, .. }), AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+                items.clone()
+            }
+            _ => return Err(arg_error("([*], Fn)", args, ctx)),
+        };
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let sorted = merge_sort(list, &mut |a, b| {
+            let items = vec![args[0].map(|_| a.clone()), args[0].map(|_| b.clone())];
+            match call_value(&args[1], items, &callee, ctx)?.1 {
+                Value::I(i) => Ok(i.cmp(&0)),
+                v => Err(Error::from_callee(
+                    ctx,
+                    format!("expected comparator to return I, found {}", v.value_type()),
+                )),
+            }
+        })?;
+        Ok(Value::list(sorted))
+    }
+}
+
+/// Index of an item of the sorted list equal to the value, compared by key function if
+/// specified. If there are multiple equal items, the first one is found.
+///
+/// ```text
+/// binary_search([*], *, ((*) -> *)?) -> [I]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// binary_search([1, 3, 5], 3) -> [1]
+/// binary_search([1, 3, 5], 4) -> []
+/// binary_search([[1, 'a'], [2, 'b']], [2, 'z'], ([k, _]) -> k) -> [1]
+/// ```
+///
+pub struct BinarySearch;
+
+impl LibFunction for BinarySearch {
+    fn name() -> String {
+        "binary_search".to_string()
+    }
+
+    fn doc() -> String {
+        "Index of an item of the sorted list equal to the value".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        sorted_signature(fresh(), Type::list(Type::Int))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, value, key) = sorted_args(args, ctx)?;
+        let value = key.apply(value, ctx)?;
+        let i = partition_point(list, &key, ctx, |item| {
+            natural_order(item, &value) == Ordering::Less
+        })?;
+        let found = match list.get(i) {
+            Some(item) => key.apply(item, ctx)? == value,
+            None => false,
+        };
+        Ok(Value::list(if found {
+            vec![Value::I(i as i128)]
+        } else {
+            vec![]
+        }))
+    }
+}
+
+/// Insert value into the sorted list keeping it sorted, compared by key function if specified.
+/// Value is inserted after equal items.
+///
+/// ```text
+/// insort([*], *, ((*) -> *)?) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// insort([1, 3, 5], 4) -> [1, 3, 4, 5]
+/// ```
+///
+pub struct Insort;
+
+impl LibFunction for Insort {
+    fn name() -> String {
+        "insort".to_string()
+    }
+
+    fn doc() -> String {
+        "Insert value into the sorted list keeping it sorted".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        sorted_signature(a.clone(), Type::list(a))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, value, key) = sorted_args(args, ctx)?;
+        let k = key.apply(value, ctx)?;
+        let i = partition_point(list, &key, ctx, |item| {
+            natural_order(item, &k) != Ordering::Greater
+        })?;
+        let mut items = list.clone();
+        items.insert(i, value.clone());
+        Ok(Value::list(items))
+    }
+}
+
+/// Check whether every list item is not less than the previous one, compared by key function if
+/// specified
+///
+/// ```text
+/// is_sorted([*], ((*) -> *)?) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// is_sorted([1, 2, 2]) -> True
+/// is_sorted(['b', 'a']) -> False
+/// ```
+///
+pub struct IsSorted;
+
+impl LibFunction for IsSorted {
+    fn name() -> String {
+        "is_sorted".to_string()
+    }
+
+    fn doc() -> String {
+        "Check whether every list item is not less than the previous one".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::variadic(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("key", Type::function(vec![a], Type::Any)),
+            ],
+            Type::Bool,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, key) = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => (items, None),
+            [AstPair(_, Value::List { items, .. }), k @ AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+                (items, Some(k))
+            }
+            _ => return Err(arg_error("([*], Fn?)", args, ctx)),
+        };
+        let key = Key::new(key, ctx);
+        let keys = list
+            .iter()
+            .map(|i| key.apply(i, ctx))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::B(
+            keys.windows(2)
+                .all(|w| natural_order(&w[0], &w[1]) != Ordering::Greater),
+        ))
+    }
+}
+
+/// List without leading items satisfying the predicate
+///
+/// ```text
+/// drop_while([*], (*) -> B) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// drop_while([1, 1, 2, 1], a -> a == 1) -> [2, 1]
+/// ```
+///
+pub struct DropWhile;
+
+impl LibFunction for DropWhile {
+    fn name() -> String {
+        "drop_while".to_string()
+    }
+
+    fn doc() -> String {
+        "List without leading items satisfying the predicate".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("predicate", Type::function(vec![a.clone()], Type::Bool)),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, predicate) = match &args[..] {
+            [AstPair(_, Value::List { items, .. }), p @ AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+                (items, p)
+            }
+            _ => return Err(arg_error("([*], Fn)", args, ctx)),
+        };
+        let mut dropped = 0;
+        for (i, item) in list.iter().enumerate() {
+            if !test_item(&DropWhile::name(), predicate, i, item.clone(), ctx)? {
+                break;
+            }
+            dropped += 1;
+        }
+        Ok(Value::list(list[dropped..].to_vec()))
+    }
+}
+
+/// Fold list with accumulator function, returning every intermediate accumulator value
+///
+/// ```text
+/// scan([*], *, (*, *) -> *) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// scan([1, 2, 3], 0, (acc, a) -> acc + a) -> [1, 3, 6]
+/// ```
+///
+pub struct Scan;
+
+impl LibFunction for Scan {
+    fn name() -> String {
+        "scan".to_string()
+    }
+
+    fn doc() -> String {
+        "Fold list with accumulator function, returning every intermediate accumulator value"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let (a, b) = (fresh(), fresh());
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("init", b.clone()),
+                Param::new("f", Type::function(vec![b.clone(), a], b.clone())),
+            ],
+            Type::list(b),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, init, f) = match &args[..] {
+            [AstPair(_, Value::List { items, .. }), AstPair(_, init), f @ AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+                (items, init, f)
+            }
+            _ => return Err(arg_error("([*], *, Fn)", args, ctx)),
+        };
+        let mut acc = init.clone();
+        let mut res = vec![];
+        for (i, item) in list.iter().enumerate() {
+            acc = call_item(&Scan::name(), f, i, vec![acc, item.clone()], ctx)?;
+            res.push(acc.clone());
+        }
+        Ok(Value::list(res))
+    }
+}
+
+/// Remove consecutive duplicate items
+///
+/// ```text
+/// dedup([*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// dedup([1, 1, 2, 1]) -> [1, 2, 1]
+/// ```
+///
+pub struct Dedup;
+
+impl LibFunction for Dedup {
+    fn name() -> String {
+        "dedup".to_string()
+    }
+
+    fn doc() -> String {
+        "Remove consecutive duplicate items".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => {
+                let mut items = items.clone();
+                items.dedup();
+                Ok(Value::list(items))
+            }
+            _ => Err(arg_error("([*])", args, ctx)),
+        }
+    }
+}
+
+/// Put separator between every two adjacent list items
+///
+/// ```text
+/// intersperse([*], *) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// intersperse([1, 2, 3], 0) -> [1, 0, 2, 0, 3]
+/// ```
+///
+pub struct Intersperse;
+
+impl LibFunction for Intersperse {
+    fn name() -> String {
+        "intersperse".to_string()
+    }
+
+    fn doc() -> String {
+        "Put separator between every two adjacent list items".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("separator", a.clone()),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::List { items, .. }), AstPair(_, separator)] => {
+                let mut res = Vec::with_capacity((2 * items.len()).saturating_sub(1));
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        res.push(separator.clone());
+                    }
+                    res.push(item.clone());
+                }
+                Ok(Value::list(res))
+            }
+            _ => Err(arg_error("([*], *)", args, ctx)),
+        }
+    }
+}
+
+/// Call function with arguments made from the list item at the index. Error is wrapped into a
+/// cause pointing at the function argument, with the index in its location, e.g. `map[2]`
+fn call_item(
+    name: &str,
+    f: &AstPair<Value>,
+    index: usize,
+    args: Vec<Value>,
+    ctx: &mut RefMut<Context>,
+) -> Result<Value, Error> {
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let args = args
+        .into_iter()
+        .map(|a| AstPair::from_span(&callee, a))
+        .collect();
+    call_value(f, args, &callee, ctx)
+        .map(|r| r.1)
+        .map_err(|e| Error::new_cause(e, format!("{}[{}]", name, index), &f.0, &ctx.ast_context))
+}
+
+/// Call predicate with the list item at the index, failing if it returns not a boolean
+fn test_item(
+    name: &str,
+    predicate: &AstPair<Value>,
+    index: usize,
+    item: Value,
+    ctx: &mut RefMut<Context>,
+) -> Result<bool, Error> {
+    match call_item(name, predicate, index, vec![item], ctx)? {
+        Value::B(b) => Ok(b),
+        v => {
+            let error = Error::from_span(
+                &predicate.0,
+                &ctx.ast_context,
+                format!("expected B, found {}", v.value_type()),
+            );
+            Err(Error::new_cause(
+                error,
+                format!("{}[{}]", name, index),
+                &predicate.0,
+                &ctx.ast_context,
+            ))
+        }
+    }
+}
+
+/// Optional key function items are compared by
+struct Key<'a> {
+    function: Option<&'a AstPair<Value>>,
+    callee: Option<Span>,
+}
+
+impl<'a> Key<'a> {
+    fn new(function: Option<&'a AstPair<Value>>, ctx: &mut RefMut<Context>) -> Key<'a> {
+        Key {
+            function,
+            callee: ctx.scope_stack.last().unwrap().callee.clone(),
+        }
+    }
+
+    fn apply(&self, value: &Value, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match (self.function, &self.callee) {
+            (Some(f), Some(callee)) => {
+                let arg = AstPair::from_span(callee, value.clone());
+                Ok(call_value(f, vec![arg], callee, ctx)?.1)
+            }
+            _ => Ok(value.clone()),
+        }
+    }
+}
+
+fn sorted_signature(a: Type, ret: Type) -> Signature {
+    Signature::variadic(
+        vec![
+            Param::new("list", Type::list(a.clone())),
+            Param::new("value", a.clone()),
+            Param::new("key", Type::function(vec![a], Type::Any)),
+        ],
+        ret,
+    )
+}
+
+fn sorted_args<'a>(
+    args: &'a Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<(&'a Vec<Value>, &'a Value, Key<'a>), Error> {
+    let (list, value, key) = match &args[..] {
+        [AstPair(_, Value::List { items, .. }), AstPair(_, v)] => (items, v, None),
+        [AstPair(_, Value::List { items, .. }), AstPair(_, v), k @ AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+            (items, v, Some(k))
+        }
+        _ => return Err(arg_error("([*], *, Fn?)", args, ctx)),
+    };
+    Ok((list, value, Key::new(key, ctx)))
+}
+
+/// Index of the first item of the sorted list whose key does not satisfy the predicate, given
+/// that the predicate holds for a prefix of the list
+fn partition_point(
+    list: &[Value],
+    key: &Key,
+    ctx: &mut RefMut<Context>,
+    before: impl Fn(&Value) -> bool,
+) -> Result<usize, Error> {
+    let (mut low, mut high) = (0, list.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if before(&key.apply(&list[mid], ctx)?) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Ordering of values used by sorting, incomparable items, e.g. NaN, are treated as equal
+fn natural_order(a: &Value, b: &Value) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+/// Stable merge sort with fallible comparison, stops at the first error.
+/// Unlike `slice::sort_by`, does not require comparison to be a total order.
+fn merge_sort<E>(
+    mut items: Vec<Value>,
+    cmp: &mut impl FnMut(&Value, &Value) -> Result<Ordering, E>,
+) -> Result<Vec<Value>, E> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let right = merge_sort(items.split_off(items.len() / 2), cmp)?;
+    let left = merge_sort(items, cmp)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // left item goes first unless it is strictly greater, so that equal items keep order
+        if cmp(l, r)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn select(list: &[Value], indices: &[usize]) -> Value {
+    Value::List {
+        items: indices.iter().map(|i| list[*i].clone()).collect(),
+        spread: false,
+    }
+}
+
+/// Rearrange indices into the lexicographically next permutation, `false` if it is the last one
+fn next_permutation(indices: &mut [usize]) -> bool {
+    let i = match (1..indices.len())
+        .rev()
+        .find(|&i| indices[i - 1] < indices[i])
+    {
+        Some(i) => i,
+        None => return false,
+    };
+    let j = (i..indices.len())
+        .rev()
+        .find(|&j| indices[j] > indices[i - 1])
+        .unwrap();
+    indices.swap(i - 1, j);
+    indices[i..].reverse();
+    true
+}
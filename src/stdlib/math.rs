@@ -0,0 +1,778 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::AstPair;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "math",
+        vec![
+            IsNan::info(),
+            IsFinite::info(),
+            FloorDiv::info(),
+            CeilDiv::info(),
+            DivMod::info(),
+            RoundHalfEven::info(),
+            RoundHalfUp::info(),
+            Floor::info(),
+            Ceil::info(),
+            Gcd::info(),
+            Lcm::info(),
+            Factorial::info(),
+            Binomial::info(),
+            Xor::info(),
+        ],
+    )
+}
+
+fn float_arg(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<f64, Error> {
+    match &args[..] {
+        [AstPair(_, Value::F(f))] => Ok(*f),
+        _ => Err(arg_error("(F)", args, ctx)),
+    }
+}
+
+fn int_args(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<(i128, i128), Error> {
+    match &args[..] {
+        [AstPair(_, Value::I(_)), AstPair(_, Value::I(0))] => {
+            Err(Error::from_callee(ctx, "division by zero".to_string()))
+        }
+        [AstPair(_, Value::I(a)), AstPair(_, Value::I(b))] => Ok((*a, *b)),
+        _ => Err(arg_error("(I, I)", args, ctx)),
+    }
+}
+
+/// Signature of a function of two integers
+fn binary_int(ret: Type) -> Signature {
+    Signature::fixed(
+        vec![Param::new("a", Type::Int), Param::new("b", Type::Int)],
+        ret,
+    )
+}
+
+/// Quotient rounded towards negative infinity and remainder of the same sign as the divisor,
+/// `None` on overflow
+fn floor_div_mod(a: i128, b: i128) -> Option<(i128, i128)> {
+    let (q, r) = (a.checked_div(b)?, a.checked_rem(b)?);
+    if r != 0 && (r < 0) != (b < 0) {
+        Some((q - 1, r + b))
+    } else {
+        Some((q, r))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Rounding {
+    /// Nearest, ties to the even digit
+    HalfEven,
+    /// Nearest, ties away from zero
+    HalfUp,
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceil,
+}
+
+/// Round float to the number of decimal places, negative places round to tens, hundreds, etc.
+/// Rounding is done on the shortest decimal representation of the float, the one it is
+/// printed with, so that `2.675` is a tie even though the closest float is a bit lower.
+fn round_decimal(x: f64, places: i32, mode: Rounding) -> f64 {
+    if !x.is_finite() || x == 0.0 {
+        return x;
+    }
+    // shortest digits with exponent, e.g. `-2.675e0`
+    let repr = format!("{:e}", x.abs());
+    let (mantissa, exponent) = repr.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let point = exponent.parse::<i32>().unwrap() + 1;
+    // number of digits kept, negative if every digit is dropped along with leading zeros
+    let kept = point + places;
+    if kept >= digits.len() as i32 {
+        return x;
+    }
+    let (kept, dropped) = match usize::try_from(kept) {
+        Ok(k) => (&digits[..k], digits[k..].to_string()),
+        Err(_) => ("", "0".repeat(kept.unsigned_abs() as usize) + &digits),
+    };
+    let magnitude = kept.parse::<u64>().unwrap_or(0);
+    // shortest representation has no trailing zeros, so dropped digits are not all zeros
+    let first = dropped.as_bytes()[0];
+    let tie = dropped == "5";
+    let round_up = match mode {
+        Rounding::HalfEven => first > b'5' || (first == b'5' && (!tie || magnitude % 2 == 1)),
+        Rounding::HalfUp => first >= b'5',
+        Rounding::Floor => x < 0.0,
+        Rounding::Ceil => x > 0.0,
+    };
+    let magnitude = magnitude + u64::from(round_up);
+    let sign = if x < 0.0 { "-" } else { "" };
+    format!("{}{}e{}", sign, magnitude, -places)
+        .parse()
+        .unwrap()
+}
+
+fn round_args(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<(f64, i32), Error> {
+    match &args[..] {
+        [AstPair(_, Value::F(x)), p @ AstPair(_, Value::I(places))] => match i32::try_from(*places)
+        {
+            Ok(places) => Ok((*x, places)),
+            Err(_) => Err(Error::from_span(
+                &p.0,
+                &ctx.ast_context,
+                format!("{} decimal places are out of range", places),
+            )),
+        },
+        _ => Err(arg_error("(F, I)", args, ctx)),
+    }
+}
+
+fn round_signature() -> Signature {
+    Signature::fixed(
+        vec![
+            Param::new("value", Type::Float),
+            Param::new("places", Type::Int),
+        ],
+        Type::Float,
+    )
+}
+
+fn int_arg(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<i128, Error> {
+    match &args[..] {
+        [AstPair(_, Value::I(i))] => Ok(*i),
+        _ => Err(arg_error("(I)", args, ctx)),
+    }
+}
+
+fn overflow(ctx: &mut RefMut<Context>) -> Error {
+    Error::from_callee(ctx, "integer overflow".to_string())
+}
+
+/// Greatest common divisor of absolute values, `None` if it does not fit into `i128`
+fn gcd(a: i128, b: i128) -> Option<i128> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i128::try_from(a).ok()
+}
+
+fn float_predicate() -> Signature {
+    Signature::fixed(vec![Param::new("value", Type::Float)], Type::Bool)
+}
+
+/// Whether float is NaN, result of an undefined operation
+///
+/// ```text
+/// is_nan(F) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// is_nan(0.0 % 0.0) -> True
+/// is_nan(1.5) -> False
+/// ```
+///
+pub struct IsNan;
+
+impl LibFunction for IsNan {
+    fn name() -> String {
+        "is_nan".to_string()
+    }
+
+    fn doc() -> String {
+        "Whether float is NaN, result of an undefined operation".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        float_predicate()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(Value::B(float_arg(args, ctx)?.is_nan()))
+    }
+}
+
+/// Whether float is neither infinite nor NaN
+///
+/// ```text
+/// is_finite(F) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// is_finite(1.5) -> True
+/// is_finite('inf'.to(F)) -> False
+/// ```
+///
+pub struct IsFinite;
+
+impl LibFunction for IsFinite {
+    fn name() -> String {
+        "is_finite".to_string()
+    }
+
+    fn doc() -> String {
+        "Whether float is neither infinite nor NaN".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        float_predicate()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(Value::B(float_arg(args, ctx)?.is_finite()))
+    }
+}
+
+/// Integer division rounding towards negative infinity
+///
+/// ```text
+/// floor_div(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// floor_div(7, 2) -> 3
+/// floor_div(0 - 7, 2) -> -4
+/// ```
+///
+pub struct FloorDiv;
+
+impl LibFunction for FloorDiv {
+    fn name() -> String {
+        "floor_div".to_string()
+    }
+
+    fn doc() -> String {
+        "Integer division rounding towards negative infinity".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = int_args(args, ctx)?;
+        match floor_div_mod(a, b) {
+            Some((q, _)) => Ok(Value::I(q)),
+            None => Err(Error::from_callee(ctx, "integer overflow".to_string())),
+        }
+    }
+}
+
+/// Integer division rounding towards positive infinity
+///
+/// ```text
+/// ceil_div(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// ceil_div(7, 2) -> 4
+/// ceil_div(0 - 7, 2) -> -3
+/// ```
+///
+pub struct CeilDiv;
+
+impl LibFunction for CeilDiv {
+    fn name() -> String {
+        "ceil_div".to_string()
+    }
+
+    fn doc() -> String {
+        "Integer division rounding towards positive infinity".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = int_args(args, ctx)?;
+        match floor_div_mod(a, b) {
+            Some((q, 0)) => Ok(Value::I(q)),
+            Some((q, _)) => Ok(Value::I(q + 1)),
+            None => Err(Error::from_callee(ctx, "integer overflow".to_string())),
+        }
+    }
+}
+
+/// Result of `floor_div` and remainder of the same sign as the divisor, so that
+/// `a == q * b + r`. Differs from `%` taking the sign of the dividend
+///
+/// ```text
+/// div_mod(I, I) -> [I]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// div_mod(7, 2) -> [3, 1]
+/// div_mod(0 - 7, 2) -> [-4, 1]
+/// ```
+///
+pub struct DivMod;
+
+impl LibFunction for DivMod {
+    fn name() -> String {
+        "div_mod".to_string()
+    }
+
+    fn doc() -> String {
+        "Result of `floor_div` and remainder of the same sign as the divisor".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::list(Type::Int))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = int_args(args, ctx)?;
+        match floor_div_mod(a, b) {
+            Some((q, r)) => Ok(Value::list(vec![Value::I(q), Value::I(r)])),
+            None => Err(Error::from_callee(ctx, "integer overflow".to_string())),
+        }
+    }
+}
+
+/// Round float to the number of decimal places, ties to the even digit (banker's rounding)
+///
+/// ```text
+/// round_half_even(F, I) -> F
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// round_half_even(2.675, 2) -> 2.68
+/// round_half_even(2.665, 2) -> 2.66
+/// round_half_even(1250.0, 0 - 2) -> 1200.0
+/// ```
+///
+pub struct RoundHalfEven;
+
+impl LibFunction for RoundHalfEven {
+    fn name() -> String {
+        "round_half_even".to_string()
+    }
+
+    fn doc() -> String {
+        "Round float to the number of decimal places, ties to the even digit".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        round_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (x, places) = round_args(args, ctx)?;
+        Ok(Value::F(round_decimal(x, places, Rounding::HalfEven)))
+    }
+}
+
+/// Round float to the number of decimal places, ties away from zero
+///
+/// ```text
+/// round_half_up(F, I) -> F
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// round_half_up(2.665, 2) -> 2.67
+/// round_half_up(0.0 - 2.5, 0) -> -3.0
+/// ```
+///
+pub struct RoundHalfUp;
+
+impl LibFunction for RoundHalfUp {
+    fn name() -> String {
+        "round_half_up".to_string()
+    }
+
+    fn doc() -> String {
+        "Round float to the number of decimal places, ties away from zero".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        round_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (x, places) = round_args(args, ctx)?;
+        Ok(Value::F(round_decimal(x, places, Rounding::HalfUp)))
+    }
+}
+
+/// Round float to the number of decimal places towards negative infinity
+///
+/// ```text
+/// floor(F, I) -> F
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// floor(2.679, 2) -> 2.67
+/// floor(0.0 - 2.671, 2) -> -2.68
+/// ```
+///
+pub struct Floor;
+
+impl LibFunction for Floor {
+    fn name() -> String {
+        "floor".to_string()
+    }
+
+    fn doc() -> String {
+        "Round float to the number of decimal places towards negative infinity".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        round_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (x, places) = round_args(args, ctx)?;
+        Ok(Value::F(round_decimal(x, places, Rounding::Floor)))
+    }
+}
+
+/// Round float to the number of decimal places towards positive infinity
+///
+/// ```text
+/// ceil(F, I) -> F
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// ceil(2.671, 2) -> 2.68
+/// ceil(0.0 - 2.679, 2) -> -2.67
+/// ```
+///
+pub struct Ceil;
+
+impl LibFunction for Ceil {
+    fn name() -> String {
+        "ceil".to_string()
+    }
+
+    fn doc() -> String {
+        "Round float to the number of decimal places towards positive infinity".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        round_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (x, places) = round_args(args, ctx)?;
+        Ok(Value::F(round_decimal(x, places, Rounding::Ceil)))
+    }
+}
+
+/// Greatest common divisor, always non-negative
+///
+/// ```text
+/// gcd(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// gcd(12, 18) -> 6
+/// gcd(0, 5) -> 5
+/// ```
+///
+pub struct Gcd;
+
+impl LibFunction for Gcd {
+    fn name() -> String {
+        "gcd".to_string()
+    }
+
+    fn doc() -> String {
+        "Greatest common divisor, always non-negative".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::I(a)), AstPair(_, Value::I(b))] => match gcd(*a, *b) {
+                Some(g) => Ok(Value::I(g)),
+                None => Err(overflow(ctx)),
+            },
+            _ => Err(arg_error("(I, I)", args, ctx)),
+        }
+    }
+}
+
+/// Least common multiple, always non-negative, zero if any of the numbers is zero
+///
+/// ```text
+/// lcm(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// lcm(4, 6) -> 12
+/// ```
+///
+pub struct Lcm;
+
+impl LibFunction for Lcm {
+    fn name() -> String {
+        "lcm".to_string()
+    }
+
+    fn doc() -> String {
+        "Least common multiple, always non-negative".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = match &args[..] {
+            [AstPair(_, Value::I(a)), AstPair(_, Value::I(b))] => (*a, *b),
+            _ => return Err(arg_error("(I, I)", args, ctx)),
+        };
+        if a == 0 || b == 0 {
+            return Ok(Value::I(0));
+        }
+        gcd(a, b)
+            .and_then(|g| (a / g).checked_mul(b))
+            .and_then(|l| l.checked_abs())
+            .map(Value::I)
+            .ok_or_else(|| overflow(ctx))
+    }
+}
+
+/// Product of integers from 1 to n
+///
+/// ```text
+/// factorial(I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// factorial(5) -> 120
+/// factorial(0) -> 1
+/// ```
+///
+pub struct Factorial;
+
+impl LibFunction for Factorial {
+    fn name() -> String {
+        "factorial".to_string()
+    }
+
+    fn doc() -> String {
+        "Product of integers from 1 to n".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("n", Type::Int)], Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let n = int_arg(args, ctx)?;
+        if n < 0 {
+            return Err(Error::from_callee(
+                ctx,
+                format!("factorial of negative number {}", n),
+            ));
+        }
+        (1..=n)
+            .try_fold(1i128, |acc, i| acc.checked_mul(i))
+            .map(Value::I)
+            .ok_or_else(|| overflow(ctx))
+    }
+}
+
+/// Number of ways to choose k items out of n ignoring order, zero if k is out of `0..=n`
+///
+/// ```text
+/// binomial(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// binomial(5, 2) -> 10
+/// ```
+///
+pub struct Binomial;
+
+impl LibFunction for Binomial {
+    fn name() -> String {
+        "binomial".to_string()
+    }
+
+    fn doc() -> String {
+        "Number of ways to choose k items out of n ignoring order".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("n", Type::Int), Param::new("k", Type::Int)],
+            Type::Int,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (n, k) = match &args[..] {
+            [AstPair(_, Value::I(n)), AstPair(_, Value::I(k))] => (*n, *k),
+            _ => return Err(arg_error("(I, I)", args, ctx)),
+        };
+        if n < 0 {
+            return Err(Error::from_callee(
+                ctx,
+                format!("binomial of negative number {}", n),
+            ));
+        }
+        if k < 0 || k > n {
+            return Ok(Value::I(0));
+        }
+        // every partial product is a binomial coefficient itself, so division is exact
+        (0..k.min(n - k))
+            .try_fold(1i128, |acc, i| Some(acc.checked_mul(n - i)? / (i + 1)))
+            .map(Value::I)
+            .ok_or_else(|| overflow(ctx))
+    }
+}
+
+/// Bitwise exclusive OR of integers. Unlike other bitwise operations, it is a function rather
+/// than an operator, since `^` is already exponentiation
+///
+/// ```text
+/// xor(I, I) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// xor(6, 3) -> 5
+/// ```
+///
+pub struct Xor;
+
+impl LibFunction for Xor {
+    fn name() -> String {
+        "xor".to_string()
+    }
+
+    fn doc() -> String {
+        "Bitwise exclusive OR of integers, `^` is exponentiation, e.g. `xor(6, 3) -> 5`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        binary_int(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::I(a)), AstPair(_, Value::I(b))] => Ok(Value::I(a ^ b)),
+            _ => Err(arg_error("(I, I)", args, ctx)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_ties() {
+        for i in -2000i64..2000 {
+            let x: f64 = format!("{}.5", i).parse().unwrap();
+            // integer part of a negative tie is its upper neighbor, e.g. `-1` of `-1.5`
+            let (lower, upper) = if x < 0.0 { (i - 1, i) } else { (i, i + 1) };
+            let even = if lower % 2 == 0 { lower } else { upper };
+            let away = if x < 0.0 { lower } else { upper };
+            assert_eq!(
+                round_decimal(x, 0, Rounding::HalfEven),
+                even as f64,
+                "{}",
+                x
+            );
+            assert_eq!(round_decimal(x, 0, Rounding::HalfUp), away as f64, "{}", x);
+            assert_eq!(round_decimal(x, 0, Rounding::Floor), lower as f64, "{}", x);
+            assert_eq!(round_decimal(x, 0, Rounding::Ceil), upper as f64, "{}", x);
+        }
+    }
+
+    #[test]
+    fn round_cents() {
+        for cents in 0..10_000u64 {
+            let tie: f64 = format!("{}.{:02}5", cents / 100, cents % 100)
+                .parse()
+                .unwrap();
+            let cents_value = |c: u64| {
+                format!("{}.{:02}", c / 100, c % 100)
+                    .parse::<f64>()
+                    .unwrap()
+            };
+            let even = if cents % 2 == 0 { cents } else { cents + 1 };
+            assert_eq!(
+                round_decimal(tie, 2, Rounding::HalfEven),
+                cents_value(even),
+                "{}",
+                tie
+            );
+            assert_eq!(
+                round_decimal(tie, 2, Rounding::HalfUp),
+                cents_value(cents + 1),
+                "{}",
+                tie
+            );
+            assert_eq!(
+                round_decimal(-tie, 2, Rounding::HalfUp),
+                -cents_value(cents + 1),
+                "{}",
+                tie
+            );
+            assert_eq!(
+                round_decimal(tie, 2, Rounding::Floor),
+                cents_value(cents),
+                "{}",
+                tie
+            );
+            assert_eq!(
+                round_decimal(-tie, 2, Rounding::Floor),
+                -cents_value(cents + 1),
+                "{}",
+                tie
+            );
+        }
+    }
+
+    #[test]
+    fn round_places() {
+        assert_eq!(round_decimal(2.675, 2, Rounding::HalfEven), 2.68);
+        assert_eq!(round_decimal(2.4999, 0, Rounding::HalfUp), 2.0);
+        assert_eq!(round_decimal(1250.0, -2, Rounding::HalfEven), 1200.0);
+        assert_eq!(round_decimal(1350.0, -2, Rounding::HalfEven), 1400.0);
+        assert_eq!(round_decimal(0.001, 1, Rounding::Ceil), 0.1);
+        assert_eq!(round_decimal(0.001, 1, Rounding::HalfUp), 0.0);
+        assert_eq!(round_decimal(-0.001, 1, Rounding::Floor), -0.1);
+        assert_eq!(round_decimal(1.25, 5, Rounding::Floor), 1.25);
+        assert!(round_decimal(f64::NAN, 2, Rounding::HalfUp).is_nan());
+    }
+}
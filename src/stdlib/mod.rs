@@ -1,7 +1,27 @@
 pub mod binary_operator;
+pub mod build;
+pub mod checksum;
+pub mod deque;
+#[cfg(feature = "desktop")]
+pub mod desktop;
+pub mod function;
+pub mod graph;
+pub mod http;
+pub mod image;
 pub mod io;
 pub mod lib;
 pub mod list;
+pub mod math;
+pub mod net;
 pub mod option;
+pub mod plot;
+pub mod process;
+pub mod random;
+pub mod scheduler;
+pub mod semver;
+pub mod seq;
+pub mod string;
+pub mod time;
 pub mod unary_operator;
+pub mod url;
 pub mod value;
@@ -0,0 +1,190 @@
+use std::cell::RefMut;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::ast::ast::AstPair;
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, parse_options, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("net", vec![Resolve::info(), TcpCheck::info()])
+}
+
+/// Addresses of the host in resolver order, without duplicates
+fn resolve(host: &str) -> Vec<IpAddr> {
+    let mut ips: Vec<IpAddr> = vec![];
+    for addr in (host, 0).to_socket_addrs().into_iter().flatten() {
+        if !ips.contains(&addr.ip()) {
+            ips.push(addr.ip());
+        }
+    }
+    ips
+}
+
+/// IP addresses of the host, empty if it cannot be resolved
+///
+/// ```text
+/// resolve([C]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// resolve('localhost') -> ['127.0.0.1']
+/// resolve('no.such.host.invalid') -> []
+/// ```
+///
+pub struct Resolve;
+
+impl LibFunction for Resolve {
+    fn name() -> String {
+        "resolve".to_string()
+    }
+
+    fn doc() -> String {
+        "IP addresses of the host, empty if it cannot be resolved".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("host", Type::list(Type::Char))],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let host = match &args[..] {
+            [h] => String::from_nois(h.1.clone()).map_err(|_| arg_error("([C])", args, ctx))?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        Ok(resolve(&host)
+            .into_iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+            .to_nois())
+    }
+}
+
+/// Time in milliseconds to open TCP connection to the host port, as an option. Empty if the
+/// connection is refused or not opened within the timeout in milliseconds. Each address of the
+/// host is tried in turn. Timeout is given either as a number or as a `timeout_ms` option
+///
+/// ```text
+/// tcp_check([C], I, I) -> [F]
+/// tcp_check([C], I, [[*]]) -> [F]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// tcp_check('localhost', 8080, 500) -> [0.21]
+/// tcp_check('localhost', 1, 500) -> []
+/// tcp_check('localhost', 8080, [['timeout_ms', 500]]) -> [0.21]
+/// ```
+///
+pub struct TcpCheck;
+
+impl LibFunction for TcpCheck {
+    fn name() -> String {
+        "tcp_check".to_string()
+    }
+
+    fn doc() -> String {
+        "Time in milliseconds to open TCP connection to the host port, as an option".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("host", Type::list(Type::Char)),
+                Param::new("port", Type::Int),
+                Param::new("timeout", Type::Any),
+            ],
+            Type::list(Type::Float),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (host, port, timeout) = match &args[..] {
+            [h, p, t] => {
+                let host = String::from_nois(h.1.clone())
+                    .map_err(|_| arg_error("([C], I, I)", args, ctx))?;
+                let port = u16::from_nois(p.1.clone())
+                    .map_err(|e| Error::from_span(&p.0, &ctx.ast_context, e))?;
+                let ms = match &t.1 {
+                    Value::I(ms) => Value::I(*ms),
+                    o @ Value::List { .. } => {
+                        let schema = [Param::new("timeout_ms", Type::Int)];
+                        parse_options(o.clone(), &schema)
+                            .map_err(|e| Error::from_span(&t.0, &ctx.ast_context, e))?
+                            .remove("timeout_ms")
+                            .ok_or_else(|| {
+                                Error::from_span(
+                                    &t.0,
+                                    &ctx.ast_context,
+                                    "missing option 'timeout_ms'".to_string(),
+                                )
+                            })?
+                    }
+                    _ => return Err(arg_error("([C], I, I) | ([C], I, [[*]])", args, ctx)),
+                };
+                match ms {
+                    Value::I(ms) if ms > 0 => (host, port, Duration::from_millis(ms as u64)),
+                    ms => {
+                        return Err(Error::from_span(
+                            &t.0,
+                            &ctx.ast_context,
+                            format!("timeout must be positive, found {}", ms),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(arg_error("([C], I, I) | ([C], I, [[*]])", args, ctx)),
+        };
+        let latency = resolve(&host).into_iter().find_map(|ip| {
+            let start = Instant::now();
+            TcpStream::connect_timeout(&(ip, port).into(), timeout)
+                .ok()
+                .map(|_| start.elapsed().as_secs_f64() * 1000.)
+        });
+        Ok(latency.to_nois())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::net::TcpListener;
+
+    use crate::ast::ast::AstContext;
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    #[test]
+    fn tcp_check_open_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        for timeout in ["1000", "[['timeout_ms', 1000]]"] {
+            let source = format!("tcp_check('localhost', {}, {})", port, timeout);
+            let ast = NoisParser::parse_program(&source)
+                .and_then(|parsed| parse_block(&parsed))
+                .unwrap();
+            let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source.as_str())));
+            let ctx = &mut ctx_cell.borrow_mut();
+            let latency = ast.eval(ctx, true).map(|a| Option::<f64>::from_nois(a.1));
+            assert!(
+                matches!(latency, Ok(Ok(Some(ms))) if ms >= 0.),
+                "{:?}",
+                latency
+            );
+        }
+    }
+}
@@ -1,26 +1,28 @@
 use std::cell::RefMut;
-use std::collections::HashMap;
 
 use crate::ast::ast::AstPair;
 use crate::error::Error;
 use crate::interpret::context::Context;
 use crate::interpret::value::Value;
 use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 
 pub fn package() -> Package {
-    Package {
-        name: "option".to_string(),
-        definitions: HashMap::from([Some::definition(), None::definition()]),
-    }
+    Package::new("option", vec![Some::info(), None::info()])
 }
 
 /// Construct option type from a value
 ///
-///     some(*) -> [*]
+/// ```text
+/// some(*) -> [*]
+/// ```
 ///
 /// Examples:
 ///
-///     some(12) -> [12]
+/// ```text
+/// some(12) -> [12]
+/// ```
 ///
 pub struct Some;
 
@@ -29,6 +31,15 @@ impl LibFunction for Some {
         "some".to_string()
     }
 
+    fn doc() -> String {
+        "Construct option type from a value".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(vec![Param::new("value", a.clone())], Type::list(a))
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let arg = match &args.into_iter().map(|a| a.1.clone()).collect::<Vec<_>>()[..] {
             [a] => a.clone(),
@@ -43,11 +54,15 @@ impl LibFunction for Some {
 
 /// Construct empty option type
 ///
-///     none() -> [*]
+/// ```text
+/// none() -> [*]
+/// ```
 ///
 /// Examples:
 ///
-///     none() -> []
+/// ```text
+/// none() -> []
+/// ```
 ///
 pub struct None;
 
@@ -56,6 +71,14 @@ impl LibFunction for None {
         "none".to_string()
     }
 
+    fn doc() -> String {
+        "Construct empty option type".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::list(fresh()))
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         if !args.is_empty() {
             return Err(arg_error("()", args, ctx));
@@ -0,0 +1,139 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::AstPair;
+use crate::convert::ToNois;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::plot;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("plot", vec![Sparkline::info(), BarChart::info()])
+}
+
+/// Bar width of the largest value if not specified
+const BAR_CHART_WIDTH: usize = 40;
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::I(i) => Some(*i as f64),
+        Value::F(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn number_error(value: &Value, ctx: &mut RefMut<Context>) -> Error {
+    Error::from_callee(
+        ctx,
+        format!("expected I or F, found {}", value.value_type()),
+    )
+}
+
+/// Render numbers as a line of block chars of proportional height
+///
+/// ```text
+/// sparkline([I | F]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// sparkline([1, 2, 3]) -> '▁▅█'
+/// ```
+///
+pub struct Sparkline;
+
+impl LibFunction for Sparkline {
+    fn name() -> String {
+        "sparkline".to_string()
+    }
+
+    fn doc() -> String {
+        "Render numbers as a line of block chars of proportional height".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("values", Type::list(Type::Any))],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let values = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => items,
+            _ => return Err(arg_error("([I | F])", args, ctx)),
+        };
+        let values = values
+            .iter()
+            .map(|v| number(v).ok_or_else(|| number_error(v, ctx)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(plot::sparkline(&values).to_nois())
+    }
+}
+
+/// Render labeled values as a horizontal bar chart, one line per value.
+/// Bar of the largest value is 40 chars long unless the width is specified.
+///
+/// ```text
+/// bar_chart([[*]]) -> [C]
+/// bar_chart([[*]], I) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// bar_chart([['a', 1.5], ['bb', 3]], 4) -> 'a  │██ 1.5\nbb │████ 3'
+/// ```
+///
+pub struct BarChart;
+
+impl LibFunction for BarChart {
+    fn name() -> String {
+        "bar_chart".to_string()
+    }
+
+    fn doc() -> String {
+        "Render labeled values as a horizontal bar chart, one line per value".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::variadic(
+            vec![
+                Param::new("bars", Type::list(Type::list(Type::Any))),
+                Param::new("width", Type::Int),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (bars, width) = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => (items, BAR_CHART_WIDTH),
+            [AstPair(_, Value::List { items, .. }), AstPair(_, Value::I(w))] if *w >= 0 => {
+                (items, *w as usize)
+            }
+            _ => return Err(arg_error("([[*]], I?)", args, ctx)),
+        };
+        let bars = bars
+            .iter()
+            .map(|bar| match bar {
+                Value::List { items, .. } if items.len() == 2 => match number(&items[1]) {
+                    Some(v) if v >= 0. => Ok((items[0].to_string(), v)),
+                    _ => Err(Error::from_callee(
+                        ctx,
+                        format!("expected non-negative number, found {}", items[1]),
+                    )),
+                },
+                v => Err(Error::from_callee(
+                    ctx,
+                    format!("expected bar [label, value], found {}", v),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(plot::bar_chart(&bars, width).join("\n").to_nois())
+    }
+}
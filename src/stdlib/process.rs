@@ -0,0 +1,662 @@
+use std::cell::{RefCell, RefMut};
+use std::io::{copy, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::escape::shell_quote;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "process",
+        vec![
+            Spawn::info(),
+            WriteStdin::info(),
+            CloseStdin::info(),
+            ReadStdoutLine::info(),
+            PipeProcess::info(),
+            Wait::info(),
+            Kill::info(),
+            Sh::info(),
+            Run::info(),
+        ],
+    )
+}
+
+/// Child process started by `spawn`, stdin and stdout are piped to the script, stderr is
+/// inherited. Pipes are `None` once closed or connected to another process by
+/// `pipe_process`.
+#[derive(Debug)]
+pub struct Process {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Option<BufReader<ChildStdout>>,
+}
+
+const PROCESS_TYPE: &str = "Process";
+
+fn process_type() -> Type {
+    Type::Named(PROCESS_TYPE.to_string())
+}
+
+/// Process handle value, e.g. `Process(pid: 1234)`
+fn handle(pid: u32) -> Value {
+    Value::Struct {
+        name: PROCESS_TYPE.to_string(),
+        fields: vec![(Identifier::new("pid"), pid.to_nois())],
+    }
+}
+
+/// Process the handle argument refers to
+fn process(arg: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Result<Rc<RefCell<Process>>, Error> {
+    let pid = match &arg.1 {
+        Value::Struct { name, fields } if name == PROCESS_TYPE => fields
+            .iter()
+            .find(|(f, _)| f.0 == "pid")
+            .and_then(|(_, v)| u32::from_nois(v.clone()).ok()),
+        _ => None,
+    };
+    pid.and_then(|pid| ctx.processes.get(&pid).cloned())
+        .ok_or_else(|| {
+            Error::from_span(
+                &arg.0,
+                &ctx.ast_context,
+                format!(
+                    "expected running process started by spawn, found {}",
+                    arg.1
+                ),
+            )
+        })
+}
+
+/// Replace every `{}` of the template with the next argument quoted as a single shell word,
+/// `None` if the number of placeholders does not match the number of arguments
+fn interpolate(template: &str, args: &[String]) -> Option<String> {
+    let parts = template.split("{}").collect::<Vec<_>>();
+    if parts.len() != args.len() + 1 {
+        return None;
+    }
+    let mut res = parts[0].to_string();
+    for (arg, part) in args.iter().zip(&parts[1..]) {
+        res.push_str(&shell_quote(arg));
+        res.push_str(part);
+    }
+    Some(res)
+}
+
+/// Command running the command line in the platform shell
+fn shell_command(command_line: &str) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    command.arg(command_line);
+    command
+}
+
+fn io_error(ctx: &mut RefMut<Context>, e: std::io::Error) -> Error {
+    Error::from_callee(ctx, e.to_string())
+}
+
+/// Start the command with arguments, without waiting for it to exit. Command is not run
+/// through a shell. Handle is valid until the process is waited for or killed
+///
+/// ```text
+/// spawn([C], [[C]]) -> Process
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('cat', []) -> Process(pid: 1234)
+/// spawn('grep', ['-v', 'TODO']) -> Process(pid: 1235)
+/// ```
+///
+pub struct Spawn;
+
+impl LibFunction for Spawn {
+    fn name() -> String {
+        "spawn".to_string()
+    }
+
+    fn doc() -> String {
+        "Start the command with arguments, without waiting for it to exit".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("command", Type::list(Type::Char)),
+                Param::new("args", Type::list(Type::list(Type::Char))),
+            ],
+            process_type(),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (command, arguments) = match &args[..] {
+            [c, a] => match (
+                String::from_nois(c.1.clone()),
+                Vec::<String>::from_nois(a.1.clone()),
+            ) {
+                (Ok(c), Ok(a)) => (c, a),
+                _ => return Err(arg_error("([C], [[C]])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [[C]])", args, ctx)),
+        };
+        let mut child = Command::new(&command)
+            .args(&arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::from_callee(ctx, format!("unable to spawn {}: {}", command, e)))?;
+        let pid = child.id();
+        let process = Process {
+            stdin: child.stdin.take(),
+            stdout: child.stdout.take().map(BufReader::new),
+            child,
+        };
+        ctx.processes.insert(pid, Rc::new(RefCell::new(process)));
+        Ok(handle(pid))
+    }
+}
+
+/// Write text to stdin of the process
+///
+/// ```text
+/// write_stdin(Process, [C]) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('cat', []).write_stdin('line\n') -> ()
+/// ```
+///
+pub struct WriteStdin;
+
+impl LibFunction for WriteStdin {
+    fn name() -> String {
+        "write_stdin".to_string()
+    }
+
+    fn doc() -> String {
+        "Write text to stdin of the process".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("process", process_type()),
+                Param::new("text", Type::list(Type::Char)),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (p, text) = match &args[..] {
+            [p, t] => match String::from_nois(t.1.clone()) {
+                Ok(t) => (p, t),
+                Err(_) => return Err(arg_error("(Process, [C])", args, ctx)),
+            },
+            _ => return Err(arg_error("(Process, [C])", args, ctx)),
+        };
+        let process = process(p, ctx)?;
+        let mut process = process.borrow_mut();
+        let stdin = match &mut process.stdin {
+            Some(s) => s,
+            None => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!("stdin of {} is closed", p.1),
+                ))
+            }
+        };
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| io_error(ctx, e))?;
+        Ok(Value::Unit)
+    }
+}
+
+/// Close stdin of the process, signaling the end of its input
+///
+/// ```text
+/// close_stdin(Process) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('cat', []).close_stdin() -> ()
+/// ```
+///
+pub struct CloseStdin;
+
+impl LibFunction for CloseStdin {
+    fn name() -> String {
+        "close_stdin".to_string()
+    }
+
+    fn doc() -> String {
+        "Close stdin of the process, signaling the end of its input".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("process", process_type())], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let process = process(&args[0], ctx)?;
+        process.borrow_mut().stdin = None;
+        Ok(Value::Unit)
+    }
+}
+
+/// Next line of the process stdout without line terminator, as an option. Waits until the line
+/// is written, empty once stdout is closed. Only the requested line is read, so output of
+/// long-running processes can be consumed as it is produced.
+///
+/// ```text
+/// read_stdout_line(Process) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('echo', ['a']).read_stdout_line() -> ['a']
+/// ```
+///
+pub struct ReadStdoutLine;
+
+impl LibFunction for ReadStdoutLine {
+    fn name() -> String {
+        "read_stdout_line".to_string()
+    }
+
+    fn doc() -> String {
+        "Next line of the process stdout without line terminator, as an option".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("process", process_type())],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let process = process(&args[0], ctx)?;
+        let mut process = process.borrow_mut();
+        let stdout = match &mut process.stdout {
+            Some(s) => s,
+            None => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!("stdout of {} is piped to another process", args[0].1),
+                ))
+            }
+        };
+        let mut line = String::new();
+        let read = stdout.read_line(&mut line).map_err(|e| io_error(ctx, e))?;
+        if read == 0 {
+            return Ok(Option::<String>::None.to_nois());
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line).to_nois())
+    }
+}
+
+/// Connect stdout of the first process to stdin of the second one, like `a | b` in shell.
+/// Stdin of the second process is closed once the first one closes its stdout. Second process
+/// is returned, so that pipes can be chained.
+///
+/// ```text
+/// pipe_process(Process, Process) -> Process
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pipe_process(spawn('ls', []), spawn('sort', [])) -> Process(pid: 1235)
+/// spawn('ls', []).pipe_process(spawn('sort', [])).read_stdout_line() -> ['Cargo.toml']
+/// ```
+///
+pub struct PipeProcess;
+
+impl LibFunction for PipeProcess {
+    fn name() -> String {
+        "pipe_process".to_string()
+    }
+
+    fn doc() -> String {
+        "Connect stdout of the first process to stdin of the second one".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("from", process_type()),
+                Param::new("to", process_type()),
+            ],
+            process_type(),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (from, to) = match &args[..] {
+            [from, to] => (process(from, ctx)?, process(to, ctx)?),
+            _ => return Err(arg_error("(Process, Process)", args, ctx)),
+        };
+        if Rc::ptr_eq(&from, &to) {
+            return Err(Error::from_callee(
+                ctx,
+                "unable to pipe process into itself".to_string(),
+            ));
+        }
+        let stdout = from.borrow_mut().stdout.take();
+        let stdin = to.borrow_mut().stdin.take();
+        match (stdout, stdin) {
+            (Some(mut stdout), Some(mut stdin)) => {
+                // copying ends when stdout is closed, dropping stdin closes it
+                thread::spawn(move || copy(&mut stdout, &mut stdin));
+                Ok(args[1].1.clone())
+            }
+            (stdout, stdin) => {
+                from.borrow_mut().stdout = stdout;
+                to.borrow_mut().stdin = stdin;
+                Err(Error::from_callee(
+                    ctx,
+                    format!(
+                        "stdout of {} or stdin of {} is already closed or piped",
+                        args[0].1, args[1].1
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// Wait for the process to exit and return its exit code, `-1` if it was terminated by a
+/// signal. Stdin is closed first, so that processes reading it until the end can exit
+///
+/// ```text
+/// wait(Process) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('true', []).wait() -> 0
+/// ```
+///
+pub struct Wait;
+
+impl LibFunction for Wait {
+    fn name() -> String {
+        "wait".to_string()
+    }
+
+    fn doc() -> String {
+        "Wait for the process to exit and return its exit code".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("process", process_type())], Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let process = process(&args[0], ctx)?;
+        let mut process = process.borrow_mut();
+        process.stdin = None;
+        let status = process.child.wait().map_err(|e| io_error(ctx, e))?;
+        ctx.processes.remove(&process.child.id());
+        Ok(Value::I(status.code().unwrap_or(-1) as i128))
+    }
+}
+
+/// Terminate the process, does nothing if it has already exited
+///
+/// ```text
+/// kill(Process) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// spawn('sleep', ['10']).kill() -> ()
+/// ```
+///
+pub struct Kill;
+
+impl LibFunction for Kill {
+    fn name() -> String {
+        "kill".to_string()
+    }
+
+    fn doc() -> String {
+        "Terminate the process, does nothing if it has already exited".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("process", process_type())], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let process = process(&args[0], ctx)?;
+        let mut process = process.borrow_mut();
+        process.child.kill().map_err(|e| io_error(ctx, e))?;
+        // reap the process, so that it does not remain a zombie
+        process.child.wait().map_err(|e| io_error(ctx, e))?;
+        Ok(Value::Unit)
+    }
+}
+
+/// Command line with every `{}` of the template replaced by the next argument, quoted as a single
+/// shell word. Strings are quoted as is, other values as they are printed, so that arguments
+/// cannot inject shell syntax. Words are quoted for POSIX shells, so it fails on Windows, where
+/// `cmd` expands variables even in quoted arguments
+///
+/// ```text
+/// sh([C], [*]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// sh('ls {}', ['my dir']) -> ls 'my dir'
+/// sh('head -n {} {}', [5, 'a.txt']) -> head -n 5 a.txt
+/// sh('echo {}', ['$HOME; rm -rf /']) -> echo '$HOME; rm -rf /'
+/// ```
+///
+pub struct Sh;
+
+impl LibFunction for Sh {
+    fn name() -> String {
+        "sh".to_string()
+    }
+
+    fn doc() -> String {
+        "Command line with every `{}` of the template replaced by the next argument, quoted"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("template", Type::list(Type::Char)),
+                Param::new("args", Type::list(Type::Any)),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (template, values) = match &args[..] {
+            [t, AstPair(_, Value::List { items, .. })] => match String::from_nois(t.1.clone()) {
+                Ok(t) => (t, items),
+                Err(_) => return Err(arg_error("([C], [*])", args, ctx)),
+            },
+            _ => return Err(arg_error("([C], [*])", args, ctx)),
+        };
+        if cfg!(windows) {
+            return Err(Error::from_callee(
+                ctx,
+                "sh is not supported on Windows, use spawn with a list of arguments".to_string(),
+            ));
+        }
+        let words = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        match interpolate(&template, &words) {
+            Some(command_line) => Ok(command_line.to_nois()),
+            None => Err(Error::from_callee(
+                ctx,
+                format!(
+                    "expected {} arguments for placeholders of the template, found {}",
+                    template.matches("{}").count(),
+                    words.len()
+                ),
+            )),
+        }
+    }
+}
+
+/// Run the command line in the shell, `sh -c` or `cmd /C` on Windows, and return its stdout
+/// without trailing line terminator. Fails if the command exits with non-zero code, error
+/// message includes the code and captured stderr
+///
+/// ```text
+/// run([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// run('echo hello') -> hello
+/// run(sh('git log -1 --format=%s {}', [rev])) -> Fix typo
+/// run('false') -> error: command 'false' exited with code 1
+/// ```
+///
+pub struct Run;
+
+impl LibFunction for Run {
+    fn name() -> String {
+        "run".to_string()
+    }
+
+    fn doc() -> String {
+        "Run the command line in the shell and return its stdout, fail on non-zero exit code"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("command", Type::list(Type::Char))],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let command_line = match &args[..] {
+            [c] => String::from_nois(c.1.clone()).map_err(|_| arg_error("([C])", args, ctx))?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        let output = shell_command(&command_line)
+            .output()
+            .map_err(|e| Error::from_callee(ctx, format!("unable to run shell: {}", e)))?;
+        if !output.status.success() {
+            let code = match output.status.code() {
+                Some(c) => format!("exited with code {}", c),
+                None => "was terminated by a signal".to_string(),
+            };
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let mut message = format!("command {} {}", shell_quote(&command_line), code);
+            if !stderr.trim().is_empty() {
+                message.push_str(&format!(":\n{}", stderr.trim_end()));
+            }
+            return Err(Error::from_callee(ctx, message));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = stdout.strip_suffix('\n').unwrap_or(&stdout);
+        Ok(stdout.strip_suffix('\r').unwrap_or(stdout).to_nois())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::ast::ast::AstContext;
+    use crate::ast::ast_parser::parse_block;
+    use crate::interpret::evaluate::Evaluate;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    #[test]
+    fn interpolate_quoted() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            interpolate("cp {} {}", &args(&["a b", "c"])),
+            Some("cp 'a b' c".to_string())
+        );
+        assert_eq!(
+            interpolate("echo {}", &args(&["it's; ls"])),
+            Some(r#"echo 'it'"'"'s; ls'"#.to_string())
+        );
+        assert_eq!(interpolate("echo {}", &args(&[])), None);
+        assert_eq!(interpolate("echo", &args(&["a"])), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_failure_includes_stderr() {
+        let source = "run('echo out; echo oops >&2; exit 3')";
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+        let ctx = &mut ctx_cell.borrow_mut();
+        let error = ast.eval(ctx, true).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "command 'echo out; echo oops >&2; exit 3' exited with code 3:\noops"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn waited_process_is_forgotten() {
+        let source = "p = spawn('true', [])\n[wait(p), catch(-> wait(p), e -> e)]";
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        let ctx_cell = RefCell::new(Context::stdlib(AstContext::new(source)));
+        let ctx = &mut ctx_cell.borrow_mut();
+        let res = ast.eval(ctx, true).unwrap().1.to_string();
+        assert!(
+            res.starts_with("[0, expected running process started by spawn"),
+            "{}",
+            res
+        );
+        assert!(ctx.processes.is_empty());
+    }
+}
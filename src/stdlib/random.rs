@@ -0,0 +1,267 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::AstPair;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::random::Rng;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "random",
+        vec![
+            Seed::info(),
+            Random::info(),
+            Shuffle::info(),
+            Sample::info(),
+            WeightedChoice::info(),
+        ],
+    )
+}
+
+/// Seed random generator, making all following random results deterministic
+///
+/// ```text
+/// seed(I) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// seed(42) -> ()
+/// ```
+///
+pub struct Seed;
+
+impl LibFunction for Seed {
+    fn name() -> String {
+        "seed".to_string()
+    }
+
+    fn doc() -> String {
+        "Seed random generator, making all following random results deterministic".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("seed", Type::Int)], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let seed = match &args[..] {
+            [AstPair(_, Value::I(s))] => *s,
+            _ => return Err(arg_error("(I)", args, ctx)),
+        };
+        ctx.rng = Rng::new(seed as u64);
+        Ok(Value::Unit)
+    }
+}
+
+/// Random float in range [0, 1)
+///
+/// ```text
+/// random() -> F
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// random() -> 0.7462
+/// ```
+///
+pub struct Random;
+
+impl LibFunction for Random {
+    fn name() -> String {
+        "random".to_string()
+    }
+
+    fn doc() -> String {
+        "Random float in range [0, 1)".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Float)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        Ok(Value::F(ctx.rng.next_f64()))
+    }
+}
+
+/// List items in random order
+///
+/// ```text
+/// shuffle([*]) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// shuffle([1, 2, 3]) -> [3, 1, 2]
+/// ```
+///
+pub struct Shuffle;
+
+impl LibFunction for Shuffle {
+    fn name() -> String {
+        "shuffle".to_string()
+    }
+
+    fn doc() -> String {
+        "List items in random order".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let mut items = match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => items.clone(),
+            _ => return Err(arg_error("([*])", args, ctx)),
+        };
+        ctx.rng.shuffle(&mut items);
+        Ok(Value::list(items))
+    }
+}
+
+/// N distinct list items picked at random, in the order they were picked
+///
+/// ```text
+/// sample([*], I) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// sample([1, 2, 3, 4], 2) -> [4, 1]
+/// ```
+///
+pub struct Sample;
+
+impl LibFunction for Sample {
+    fn name() -> String {
+        "sample".to_string()
+    }
+
+    fn doc() -> String {
+        "N distinct list items picked at random".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("n", Type::Int),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, n) = match &args[..] {
+            [AstPair(_, Value::List { items, .. }), AstPair(_, Value::I(n))] => (items, *n),
+            _ => return Err(arg_error("([*], I)", args, ctx)),
+        };
+        let n = match usize::try_from(n) {
+            Ok(n) if n <= list.len() => n,
+            _ => {
+                return Err(Error::from_callee(
+                    ctx,
+                    format!(
+                        "expected sample size between 0 and {}, found {}",
+                        list.len(),
+                        n
+                    ),
+                ))
+            }
+        };
+        let indices = ctx.rng.sample(list.len(), n);
+        Ok(Value::list(
+            indices.into_iter().map(|i| list[i].clone()).collect(),
+        ))
+    }
+}
+
+/// List item picked at random with probability proportional to its weight
+///
+/// ```text
+/// weighted_choice([*], [I | F]) -> *
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// weighted_choice(['a', 'b'], [1, 3]) -> 'b'
+/// ```
+///
+pub struct WeightedChoice;
+
+impl LibFunction for WeightedChoice {
+    fn name() -> String {
+        "weighted_choice".to_string()
+    }
+
+    fn doc() -> String {
+        "List item picked at random with probability proportional to its weight".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(a.clone())),
+                Param::new("weights", Type::list(Type::Any)),
+            ],
+            a,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (list, weights) = match &args[..] {
+            [AstPair(_, Value::List { items: l, .. }), AstPair(_, Value::List { items: w, .. })] => {
+                (l, w)
+            }
+            _ => return Err(arg_error("([*], [I | F])", args, ctx)),
+        };
+        if list.len() != weights.len() {
+            return Err(Error::from_callee(
+                ctx,
+                format!(
+                    "expected a weight for each of {} items, found {} weights",
+                    list.len(),
+                    weights.len()
+                ),
+            ));
+        }
+        let weights = weights
+            .iter()
+            .map(|w| match w {
+                Value::I(i) if *i >= 0 => Ok(*i as f64),
+                Value::F(f) if *f >= 0. => Ok(*f),
+                _ => Err(Error::from_callee(
+                    ctx,
+                    format!("expected non-negative number weight, found {}", w),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match ctx.rng.weighted_index(&weights) {
+            Some(i) => Ok(list[i].clone()),
+            None => Err(Error::from_callee(
+                ctx,
+                "expected at least one positive weight".to_string(),
+            )),
+        }
+    }
+}
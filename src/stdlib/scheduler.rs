@@ -0,0 +1,261 @@
+use std::cell::RefMut;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::ast::ast::AstPair;
+use crate::cron::Cron;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "scheduler",
+        vec![
+            Every::info(),
+            Schedule::info(),
+            RunScheduler::info(),
+            StopScheduler::info(),
+        ],
+    )
+}
+
+/// Function called by `run_scheduler` when its trigger fires
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub f: AstPair<Value>,
+    pub trigger: Trigger,
+    pub next: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    Every(Duration),
+    Cron(Cron),
+}
+
+impl Trigger {
+    /// Time of the next call after the one at `last`, `None` if there are no more calls.
+    /// Calls missed because of long running jobs are skipped.
+    fn next(&self, last: SystemTime, now: SystemTime) -> Option<SystemTime> {
+        match self {
+            Trigger::Every(period) => {
+                let next = last + *period;
+                Some(if next < now { now + *period } else { next })
+            }
+            Trigger::Cron(cron) => {
+                let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                cron.next_after(secs)
+                    .map(|s| UNIX_EPOCH + Duration::from_secs(s))
+            }
+        }
+    }
+}
+
+fn add_job(trigger: Trigger, f: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Value {
+    let now = SystemTime::now();
+    let next = match &trigger {
+        Trigger::Every(period) => Some(now + *period),
+        Trigger::Cron(_) => trigger.next(now, now),
+    };
+    if let Some(next) = next {
+        ctx.scheduled.push(Job {
+            f: f.clone(),
+            trigger,
+            next,
+        });
+    }
+    Value::Unit
+}
+
+/// Call function every `ms` milliseconds once the scheduler is running
+///
+/// ```text
+/// every(I, () -> *) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// every(1000, -> println('tick'))
+/// ```
+///
+pub struct Every;
+
+impl LibFunction for Every {
+    fn name() -> String {
+        "every".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function every `ms` milliseconds once the scheduler is running".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("ms", Type::Int),
+                Param::new("f", Type::function(vec![], Type::Any)),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::I(ms)), f] if *ms > 0 => Ok(add_job(
+                Trigger::Every(Duration::from_millis(*ms as u64)),
+                f,
+                ctx,
+            )),
+            [AstPair(_, Value::I(ms)), _] => Err(Error::from_callee(
+                ctx,
+                format!("expected positive interval, found {}", ms),
+            )),
+            _ => Err(arg_error("(I, Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Call function at times matching the cron expression once the scheduler is running.
+/// Expression fields are minute, hour, day of month, month and day of week, times are in UTC
+///
+/// ```text
+/// schedule([C], () -> *) -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// schedule('*/15 9-17 * * 1-5', -> println('every 15 minutes during work hours'))
+/// ```
+///
+pub struct Schedule;
+
+impl LibFunction for Schedule {
+    fn name() -> String {
+        "schedule".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function at times matching the cron expression once the scheduler is running"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("cron", Type::list(Type::Char)),
+                Param::new("f", Type::function(vec![], Type::Any)),
+            ],
+            Type::Unit,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (expr, f) = match &args[..] {
+            [e @ AstPair(_, Value::List { .. }), f] => (e, f),
+            _ => return Err(arg_error("([C], Fn)", args, ctx)),
+        };
+        match Cron::parse(&expr.1.to_string()) {
+            Ok(cron) => Ok(add_job(Trigger::Cron(cron), f, ctx)),
+            Err(e) => Err(Error::from_span(&expr.0, &ctx.ast_context, e)),
+        }
+    }
+}
+
+/// Call scheduled functions when they are due, until there are none left or the scheduler is
+/// stopped. Error of a scheduled function stops the scheduler
+///
+/// ```text
+/// run_scheduler() -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// every(1000, -> println('tick'))
+/// run_scheduler()
+/// ```
+///
+pub struct RunScheduler;
+
+impl LibFunction for RunScheduler {
+    fn name() -> String {
+        "run_scheduler".to_string()
+    }
+
+    fn doc() -> String {
+        "Call scheduled functions when they are due, until there are none left or the scheduler is stopped"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        loop {
+            let (i, job) = match ctx.scheduled.iter().enumerate().min_by_key(|(_, j)| j.next) {
+                Some((i, j)) => (i, j.clone()),
+                None => return Ok(Value::Unit),
+            };
+            if let Ok(wait) = job.next.duration_since(SystemTime::now()) {
+                sleep(wait);
+            }
+            // rescheduled before the call, so that the job can stop the scheduler
+            match job.trigger.next(job.next, SystemTime::now()) {
+                Some(next) => ctx.scheduled[i].next = next,
+                None => {
+                    ctx.scheduled.remove(i);
+                }
+            }
+            call_value(&job.f, vec![], &callee, ctx)?;
+        }
+    }
+}
+
+/// Remove all scheduled functions, so that running scheduler returns
+///
+/// ```text
+/// stop_scheduler() -> ()
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// every(1000, -> stop_scheduler())
+/// run_scheduler()
+/// ```
+///
+pub struct StopScheduler;
+
+impl LibFunction for StopScheduler {
+    fn name() -> String {
+        "stop_scheduler".to_string()
+    }
+
+    fn doc() -> String {
+        "Remove all scheduled functions, so that running scheduler returns".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![], Type::Unit)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if !args.is_empty() {
+            return Err(arg_error("()", args, ctx));
+        }
+        ctx.scheduled.clear();
+        Ok(Value::Unit)
+    }
+}
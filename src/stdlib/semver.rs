@@ -0,0 +1,118 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::semver::SemVer;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("semver", vec![ParseSemver::info(), CompareSemver::info()])
+}
+
+fn semver_arg(arg: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Result<SemVer, Error> {
+    String::from_nois(arg.1.clone())
+        .and_then(|v| SemVer::parse(&v))
+        .map_err(|e| Error::from_span(&arg.0, &ctx.ast_context, e))
+}
+
+/// Parse semantic version `MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]`, optionally prefixed with `v`
+///
+/// ```text
+/// parse_semver([C]) -> SemVer
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// parse_semver('v1.2.3-rc.1') -> SemVer(major: 1, minor: 2, patch: 3, pre: rc.1, build: )
+/// ```
+///
+pub struct ParseSemver;
+
+impl LibFunction for ParseSemver {
+    fn name() -> String {
+        "parse_semver".to_string()
+    }
+
+    fn doc() -> String {
+        "Parse semantic version `MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("version", Type::list(Type::Char))],
+            Type::Named("SemVer".to_string()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let v = match &args[..] {
+            [a] => semver_arg(a, ctx)?,
+            _ => return Err(arg_error("([C])", args, ctx)),
+        };
+        let fields = vec![
+            ("major", Value::I(v.major as i128)),
+            ("minor", Value::I(v.minor as i128)),
+            ("patch", Value::I(v.patch as i128)),
+            ("pre", v.pre.to_nois()),
+            ("build", v.build.to_nois()),
+        ];
+        Ok(Value::Struct {
+            name: "SemVer".to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(f, v)| (Identifier::new(f), v))
+                .collect(),
+        })
+    }
+}
+
+/// Compare semantic versions by precedence, returning -1, 0 or 1 as a `sort_with` comparator.
+/// Pre-release versions precede the release, build metadata is ignored
+///
+/// ```text
+/// compare_semver([C], [C]) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// compare_semver('1.10.0', '1.9.0') -> 1
+/// compare_semver('1.0.0-rc.1', '1.0.0') -> -1
+/// sort_with(['1.10.0', '1.9.0'], (a, b) -> compare_semver(a, b)) -> ['1.9.0', '1.10.0']
+/// ```
+///
+pub struct CompareSemver;
+
+impl LibFunction for CompareSemver {
+    fn name() -> String {
+        "compare_semver".to_string()
+    }
+
+    fn doc() -> String {
+        "Compare semantic versions by precedence, returning -1, 0 or 1".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("a", Type::list(Type::Char)),
+                Param::new("b", Type::list(Type::Char)),
+            ],
+            Type::Int,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = match &args[..] {
+            [a, b] => (semver_arg(a, ctx)?, semver_arg(b, ctx)?),
+            _ => return Err(arg_error("([C], [C])", args, ctx)),
+        };
+        Ok(Value::I(a.precedence(&b) as i128))
+    }
+}
@@ -0,0 +1,281 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::AstPair;
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::seq::Seq;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "seq",
+        vec![
+            Iterate::info(),
+            Repeat::info(),
+            Cycle::info(),
+            Take::info(),
+            TakeWhile::info(),
+        ],
+    )
+}
+
+/// Pass items of the list or sequence to the function until it returns `false` or items end
+fn walk(
+    source: &AstPair<Value>,
+    ctx: &mut RefMut<Context>,
+    mut f: impl FnMut(&Value, &mut RefMut<Context>) -> Result<bool, Error>,
+) -> Result<(), Error> {
+    match &source.1 {
+        Value::List { items, .. } => {
+            for item in items {
+                if !f(item, ctx)? {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Value::Seq(s) => {
+            let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+            s.walk(&callee, ctx, f)
+        }
+        _ => unreachable!("checked by signature"),
+    }
+}
+
+/// Infinite sequence of repeated applications of the function to the initial value
+///
+/// ```text
+/// iterate(*, (*) -> *) -> Seq[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// iterate(1, a -> a + a).take(5) -> [1, 2, 4, 8, 16]
+/// ```
+///
+pub struct Iterate;
+
+impl LibFunction for Iterate {
+    fn name() -> String {
+        "iterate".to_string()
+    }
+
+    fn doc() -> String {
+        "Infinite sequence of repeated applications of the function to the initial value"
+            .to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("init", a.clone()),
+                Param::new("f", Type::function(vec![a.clone()], a.clone())),
+            ],
+            Type::seq(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, init), AstPair(_, f @ (Value::Fn(..) | Value::Native(..)))] => {
+                Ok(Value::Seq(Seq::Iterate {
+                    init: Box::new(init.clone()),
+                    f: Box::new(f.clone()),
+                }))
+            }
+            _ => Err(arg_error("(*, Fn)", args, ctx)),
+        }
+    }
+}
+
+/// Infinite sequence of the same value
+///
+/// ```text
+/// repeat(*) -> Seq[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// repeat(0).take(3) -> [0, 0, 0]
+/// ```
+///
+pub struct Repeat;
+
+impl LibFunction for Repeat {
+    fn name() -> String {
+        "repeat".to_string()
+    }
+
+    fn doc() -> String {
+        "Infinite sequence of the same value".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(vec![Param::new("value", a.clone())], Type::seq(a))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, v)] => Ok(Value::Seq(Seq::Repeat(Box::new(v.clone())))),
+            _ => Err(arg_error("(*)", args, ctx)),
+        }
+    }
+}
+
+/// Infinite sequence of list items repeated over and over again, empty if the list is empty
+///
+/// ```text
+/// cycle([*]) -> Seq[*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// cycle([1, 2]).take(5) -> [1, 2, 1, 2, 1]
+/// ```
+///
+pub struct Cycle;
+
+impl LibFunction for Cycle {
+    fn name() -> String {
+        "cycle".to_string()
+    }
+
+    fn doc() -> String {
+        "Infinite sequence of list items repeated over and over again".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![Param::new("list", Type::list(a.clone()))],
+            Type::seq(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::List { items, .. })] => Ok(Value::Seq(Seq::Cycle(items.clone()))),
+            _ => Err(arg_error("([*])", args, ctx)),
+        }
+    }
+}
+
+/// First n items of the list or sequence, fewer if there are not enough items
+///
+/// ```text
+/// take([*] | Seq[*], I) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// take([1, 2, 3], 2) -> [1, 2]
+/// take(repeat('a'), 2) -> ['a', 'a']
+/// ```
+///
+pub struct Take;
+
+impl LibFunction for Take {
+    fn name() -> String {
+        "take".to_string()
+    }
+
+    fn doc() -> String {
+        "First n items of the list or sequence".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("items", Type::Any), Param::new("n", Type::Int)],
+            Type::list(fresh()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (source, n) = match &args[..] {
+            [s @ AstPair(_, Value::List { .. } | Value::Seq(_)), AstPair(_, Value::I(n))] => {
+                (s, usize::try_from(*n).unwrap_or(0))
+            }
+            _ => return Err(arg_error("([*] | Seq, I)", args, ctx)),
+        };
+        let mut res = vec![];
+        if n > 0 {
+            walk(source, ctx, |item, _| {
+                res.push(item.clone());
+                Ok(res.len() < n)
+            })?;
+        }
+        Ok(Value::list(res))
+    }
+}
+
+/// Leading items of the list or sequence satisfying the predicate
+///
+/// ```text
+/// take_while([*] | Seq[*], (*) -> B) -> [*]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// take_while([1, 1, 2, 1], a -> a == 1) -> [1, 1]
+/// take_while(cycle([1, 1, 2]), a -> a == 1) -> [1, 1]
+/// ```
+///
+pub struct TakeWhile;
+
+impl LibFunction for TakeWhile {
+    fn name() -> String {
+        "take_while".to_string()
+    }
+
+    fn doc() -> String {
+        "Leading items of the list or sequence satisfying the predicate".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        let a = fresh();
+        Signature::fixed(
+            vec![
+                Param::new("items", Type::Any),
+                Param::new("predicate", Type::function(vec![a.clone()], Type::Bool)),
+            ],
+            Type::list(a),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (source, predicate) = match &args[..] {
+            [s @ AstPair(_, Value::List { .. } | Value::Seq(_)), p @ AstPair(_, Value::Fn(..) | Value::Native(..))] => {
+                (s, p)
+            }
+            _ => return Err(arg_error("([*] | Seq, Fn)", args, ctx)),
+        };
+        let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+        let mut res = vec![];
+        walk(source, ctx, |item, ctx| {
+            let arg = AstPair::from_span(&callee, item.clone());
+            match call_value(predicate, vec![arg], &callee, ctx)?.1 {
+                Value::B(true) => {
+                    res.push(item.clone());
+                    Ok(true)
+                }
+                Value::B(false) => Ok(false),
+                v => Err(Error::from_callee(
+                    ctx,
+                    format!("expected B, found {}", v.value_type()),
+                )),
+            }
+        })?;
+        Ok(Value::list(res))
+    }
+}
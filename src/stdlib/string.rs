@@ -0,0 +1,1383 @@
+use std::cell::RefMut;
+
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::ast::ast::AstPair;
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::escape;
+use crate::fuzzy;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new(
+        "string",
+        vec![
+            Escape::info(),
+            Unescape::info(),
+            Quote::info(),
+            ShellQuote::info(),
+            IndexOf::info(),
+            LastIndexOf::info(),
+            CountMatches::info(),
+            PadStart::info(),
+            PadEnd::info(),
+            Center::info(),
+            Truncate::info(),
+            SnakeCase::info(),
+            KebabCase::info(),
+            CamelCase::info(),
+            TitleCase::info(),
+            Capitalize::info(),
+            EditDistance::info(),
+            FuzzyMatch::info(),
+            Split::info(),
+            SplitN::info(),
+            SplitRegex::info(),
+            Join::info(),
+            Nfc::info(),
+            Nfd::info(),
+            Casefold::info(),
+            CompareIgnoreCase::info(),
+            HumanizeBytes::info(),
+            HumanizeDuration::info(),
+            Pluralize::info(),
+        ],
+    )
+}
+
+fn string_arg(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<String, Error> {
+    match &args[..] {
+        [a] => String::from_nois(a.1.clone()).map_err(|_| arg_error("([C])", args, ctx)),
+        _ => Err(arg_error("([C])", args, ctx)),
+    }
+}
+
+/// Argument converted into host value. Types are checked against the signature before the call,
+/// so conversion fails only for values out of range, e.g. negative width
+fn convert<T: FromNois>(arg: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Result<T, Error> {
+    T::from_nois(arg.1.clone()).map_err(|e| Error::from_span(&arg.0, &ctx.ast_context, e))
+}
+
+/// Index of the char at the byte index of the string
+fn char_index(string: &str, byte_index: usize) -> usize {
+    string[..byte_index].chars().count()
+}
+
+/// String with fill repeated on the left and right to the given number of chars
+fn pad(string: String, left: usize, right: usize, fill: &str) -> String {
+    let fill = |n| fill.chars().cycle().take(n);
+    fill(left)
+        .chain(string.chars())
+        .chain(fill(right))
+        .collect()
+}
+
+/// Words of an identifier or a phrase, e.g. `HTTPServer_port` -> `HTTP`, `Server`, `port`.
+/// Words are separated by non-alphanumeric chars and by case changes.
+fn words(string: &str) -> Vec<String> {
+    let chars = string.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && match prev {
+                Some(p) if p.is_lowercase() || p.is_numeric() => true,
+                // last capital of an acronym starts the next word, e.g. `HTTPServer`
+                Some(p) if p.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                _ => false,
+            };
+        if boundary {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    words.push(word);
+    words.retain(|w| !w.is_empty());
+    words
+}
+
+/// Word with the first char in uppercase and the rest in lowercase
+fn capitalize_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(|c| c.to_lowercase()))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// String and non-empty separator
+fn split_args(
+    args: &[AstPair<Value>],
+    ctx: &mut RefMut<Context>,
+) -> Result<(String, String), Error> {
+    let string: String = convert(&args[0], ctx)?;
+    let separator: String = convert(&args[1], ctx)?;
+    if separator.is_empty() {
+        return Err(Error::from_span(
+            &args[1].0,
+            &ctx.ast_context,
+            "separator must not be empty".to_string(),
+        ));
+    }
+    Ok((string, separator))
+}
+
+fn string_signature() -> Signature {
+    Signature::fixed(
+        vec![Param::new("string", Type::list(Type::Char))],
+        Type::list(Type::Char),
+    )
+}
+
+fn search_signature(ret: Type) -> Signature {
+    Signature::fixed(
+        vec![
+            Param::new("string", Type::list(Type::Char)),
+            Param::new("needle", Type::list(Type::Char)),
+        ],
+        ret,
+    )
+}
+
+fn pad_signature() -> Signature {
+    Signature::fixed(
+        vec![
+            Param::new("string", Type::list(Type::Char)),
+            Param::new("width", Type::Int),
+            Param::new("fill", Type::list(Type::Char)),
+        ],
+        Type::list(Type::Char),
+    )
+}
+
+fn search_args(
+    args: &Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<(String, String), Error> {
+    match &args[..] {
+        [s, n] => Ok((convert(s, ctx)?, convert(n, ctx)?)),
+        _ => Err(arg_error("([C], [C])", args, ctx)),
+    }
+}
+
+/// String, number of chars to pad it with to reach the width, and non-empty fill
+fn pad_args(
+    args: &Vec<AstPair<Value>>,
+    ctx: &mut RefMut<Context>,
+) -> Result<(String, usize, String), Error> {
+    match &args[..] {
+        [s, w, f] => {
+            let string: String = convert(s, ctx)?;
+            let width: usize = convert(w, ctx)?;
+            let fill: String = convert(f, ctx)?;
+            if fill.is_empty() {
+                return Err(Error::from_span(
+                    &f.0,
+                    &ctx.ast_context,
+                    "fill must not be empty".to_string(),
+                ));
+            }
+            let padding = width.saturating_sub(string.chars().count());
+            Ok((string, padding, fill))
+        }
+        _ => Err(arg_error("([C], I, [C])", args, ctx)),
+    }
+}
+
+/// Replace special characters with escape sequences, so string can be put inside of quotes
+///
+/// ```text
+/// escape([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// escape('a\nb') -> a\nb
+/// ```
+///
+pub struct Escape;
+
+impl LibFunction for Escape {
+    fn name() -> String {
+        "escape".to_string()
+    }
+
+    fn doc() -> String {
+        "Replace special characters with escape sequences, so string can be put inside of quotes"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(escape::escape(&string_arg(args, ctx)?).to_nois())
+    }
+}
+
+/// Replace escape sequences with characters they represent
+///
+/// ```text
+/// unescape([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// unescape('a\\nb') -> 'a\nb'
+/// ```
+///
+pub struct Unescape;
+
+impl LibFunction for Unescape {
+    fn name() -> String {
+        "unescape".to_string()
+    }
+
+    fn doc() -> String {
+        "Replace escape sequences with characters they represent".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        escape::unescape(&string_arg(args, ctx)?)
+            .map(|s| s.to_nois())
+            .map_err(|e| Error::from_callee(ctx, e.message))
+    }
+}
+
+/// Escape string and put it inside of double quotes
+///
+/// ```text
+/// quote([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// quote('say "hi"') -> "say \"hi\""
+/// ```
+///
+pub struct Quote;
+
+impl LibFunction for Quote {
+    fn name() -> String {
+        "quote".to_string()
+    }
+
+    fn doc() -> String {
+        "Escape string and put it inside of double quotes".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(format!("\"{}\"", escape::escape(&string_arg(args, ctx)?)).to_nois())
+    }
+}
+
+/// Quote string to be used as a single shell command argument
+///
+/// ```text
+/// shell_quote([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// shell_quote('file.txt') -> file.txt
+/// shell_quote('my file.txt') -> 'my file.txt'
+/// ```
+///
+pub struct ShellQuote;
+
+impl LibFunction for ShellQuote {
+    fn name() -> String {
+        "shell_quote".to_string()
+    }
+
+    fn doc() -> String {
+        "Quote string to be used as a single shell command argument".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(escape::shell_quote(&string_arg(args, ctx)?).to_nois())
+    }
+}
+
+/// Index of the first occurrence of the needle in the string
+///
+/// ```text
+/// index_of([C], [C]) -> [I]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// index_of('banana', 'an') -> [1]
+/// index_of('banana', 'x') -> []
+/// ```
+///
+pub struct IndexOf;
+
+impl LibFunction for IndexOf {
+    fn name() -> String {
+        "index_of".to_string()
+    }
+
+    fn doc() -> String {
+        "Index of the first occurrence of the needle in the string".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        search_signature(Type::list(Type::Int))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, needle) = search_args(args, ctx)?;
+        Ok(string
+            .find(&needle)
+            .map(|i| char_index(&string, i))
+            .to_nois())
+    }
+}
+
+/// Index of the last occurrence of the needle in the string
+///
+/// ```text
+/// last_index_of([C], [C]) -> [I]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// last_index_of('banana', 'an') -> [3]
+/// ```
+///
+pub struct LastIndexOf;
+
+impl LibFunction for LastIndexOf {
+    fn name() -> String {
+        "last_index_of".to_string()
+    }
+
+    fn doc() -> String {
+        "Index of the last occurrence of the needle in the string".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        search_signature(Type::list(Type::Int))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, needle) = search_args(args, ctx)?;
+        Ok(string
+            .rfind(&needle)
+            .map(|i| char_index(&string, i))
+            .to_nois())
+    }
+}
+
+/// Number of non-overlapping occurrences of the needle in the string
+///
+/// ```text
+/// count_matches([C], [C]) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// count_matches('banana', 'a') -> 3
+/// count_matches('aaa', 'aa') -> 1
+/// ```
+///
+pub struct CountMatches;
+
+impl LibFunction for CountMatches {
+    fn name() -> String {
+        "count_matches".to_string()
+    }
+
+    fn doc() -> String {
+        "Number of non-overlapping occurrences of the needle in the string".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        search_signature(Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, needle) = search_args(args, ctx)?;
+        if needle.is_empty() {
+            return Err(Error::from_span(
+                &args[1].0,
+                &ctx.ast_context,
+                "needle must not be empty".to_string(),
+            ));
+        }
+        Ok(string.matches(&needle).count().to_nois())
+    }
+}
+
+/// Pad string on the left with repeated fill to be at least of the width
+///
+/// ```text
+/// pad_start([C], I, [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pad_start('7', 3, '0') -> 007
+/// pad_start('7', 4, 'ab') -> aba7
+/// ```
+///
+pub struct PadStart;
+
+impl LibFunction for PadStart {
+    fn name() -> String {
+        "pad_start".to_string()
+    }
+
+    fn doc() -> String {
+        "Pad string on the left with repeated fill to be at least of the width".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        pad_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, padding, fill) = pad_args(args, ctx)?;
+        Ok(pad(string, padding, 0, &fill).to_nois())
+    }
+}
+
+/// Pad string on the right with repeated fill to be at least of the width
+///
+/// ```text
+/// pad_end([C], I, [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pad_end('ab', 4, '.') -> ab..
+/// ```
+///
+pub struct PadEnd;
+
+impl LibFunction for PadEnd {
+    fn name() -> String {
+        "pad_end".to_string()
+    }
+
+    fn doc() -> String {
+        "Pad string on the right with repeated fill to be at least of the width".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        pad_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, padding, fill) = pad_args(args, ctx)?;
+        Ok(pad(string, 0, padding, &fill).to_nois())
+    }
+}
+
+/// Pad string on both sides with repeated fill to be at least of the width,
+/// odd char of the padding goes to the right
+///
+/// ```text
+/// center([C], I, [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// center('ab', 5, '*') -> *ab**
+/// ```
+///
+pub struct Center;
+
+impl LibFunction for Center {
+    fn name() -> String {
+        "center".to_string()
+    }
+
+    fn doc() -> String {
+        "Pad string on both sides with repeated fill to be at least of the width".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        pad_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, padding, fill) = pad_args(args, ctx)?;
+        Ok(pad(string, padding / 2, padding - padding / 2, &fill).to_nois())
+    }
+}
+
+/// Shorten string longer than the width, ending it with ellipsis so that the result is of the
+/// width
+///
+/// ```text
+/// truncate([C], I, [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// truncate('Hello, World!', 8, '...') -> Hello...
+/// truncate('Hello', 8, '...') -> Hello
+/// ```
+///
+pub struct Truncate;
+
+impl LibFunction for Truncate {
+    fn name() -> String {
+        "truncate".to_string()
+    }
+
+    fn doc() -> String {
+        "Shorten string longer than the width, ending it with ellipsis".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("string", Type::list(Type::Char)),
+                Param::new("width", Type::Int),
+                Param::new("ellipsis", Type::list(Type::Char)),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, width, ellipsis): (String, usize, String) = match &args[..] {
+            [s, w, e] => (convert(s, ctx)?, convert(w, ctx)?, convert(e, ctx)?),
+            _ => return Err(arg_error("([C], I, [C])", args, ctx)),
+        };
+        if string.chars().count() <= width {
+            return Ok(string.to_nois());
+        }
+        let kept = width.saturating_sub(ellipsis.chars().count());
+        Ok(string
+            .chars()
+            .take(kept)
+            .chain(ellipsis.chars())
+            .take(width)
+            .collect::<String>()
+            .to_nois())
+    }
+}
+
+/// Convert string to lowercase words joined with underscore
+///
+/// ```text
+/// snake_case([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// snake_case('parseHTTPRequest') -> parse_http_request
+/// ```
+///
+pub struct SnakeCase;
+
+impl LibFunction for SnakeCase {
+    fn name() -> String {
+        "snake_case".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string to lowercase words joined with underscore".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(words(&string_arg(args, ctx)?)
+            .iter()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_")
+            .to_nois())
+    }
+}
+
+/// Convert string to lowercase words joined with hyphen
+///
+/// ```text
+/// kebab_case([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// kebab_case('Max file size') -> max-file-size
+/// ```
+///
+pub struct KebabCase;
+
+impl LibFunction for KebabCase {
+    fn name() -> String {
+        "kebab_case".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string to lowercase words joined with hyphen".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(words(&string_arg(args, ctx)?)
+            .iter()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_nois())
+    }
+}
+
+/// Convert string to joined words, all capitalized except the first one in lowercase
+///
+/// ```text
+/// camel_case([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// camel_case('max_file_size') -> maxFileSize
+/// ```
+///
+pub struct CamelCase;
+
+impl LibFunction for CamelCase {
+    fn name() -> String {
+        "camel_case".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string to joined words, all capitalized except the first one in lowercase"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(words(&string_arg(args, ctx)?)
+            .iter()
+            .enumerate()
+            .map(|(i, w)| match i {
+                0 => w.to_lowercase(),
+                _ => capitalize_word(w),
+            })
+            .collect::<String>()
+            .to_nois())
+    }
+}
+
+/// Convert string to capitalized words joined with space. Unlike other case conversions, words
+/// are separated only by non-alphanumeric chars, so that case of the rest of a word is ignored
+///
+/// ```text
+/// title_case([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// title_case('max_file_size') -> Max File Size
+/// title_case('hello wORLD') -> Hello World
+/// ```
+///
+pub struct TitleCase;
+
+impl LibFunction for TitleCase {
+    fn name() -> String {
+        "title_case".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string to capitalized words joined with space".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(string_arg(args, ctx)?
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(capitalize_word)
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_nois())
+    }
+}
+
+/// Convert the first char of string to uppercase, leaving the rest as is
+///
+/// ```text
+/// capitalize([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// capitalize('élan vital') -> Élan vital
+/// ```
+///
+pub struct Capitalize;
+
+impl LibFunction for Capitalize {
+    fn name() -> String {
+        "capitalize".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert the first char of string to uppercase, leaving the rest as is".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let string = string_arg(args, ctx)?;
+        let mut chars = string.chars();
+        Ok(chars
+            .next()
+            .map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+            .to_nois())
+    }
+}
+
+/// Minimal number of char insertions, deletions and substitutions turning one string into the
+/// other
+///
+/// ```text
+/// edit_distance([C], [C]) -> I
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// edit_distance('kitten', 'sitting') -> 3
+/// ```
+///
+pub struct EditDistance;
+
+impl LibFunction for EditDistance {
+    fn name() -> String {
+        "edit_distance".to_string()
+    }
+
+    fn doc() -> String {
+        "Minimal number of char insertions, deletions and substitutions turning one string into the other".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("a", Type::list(Type::Char)),
+                Param::new("b", Type::list(Type::Char)),
+            ],
+            Type::Int,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = search_args(args, ctx)?;
+        Ok(fuzzy::edit_distance(&a, &b).to_nois())
+    }
+}
+
+/// Score of pattern chars found in the string in the same order ignoring case, higher for
+/// consecutive chars and word starts
+///
+/// ```text
+/// fuzzy_match([C], [C]) -> [I]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// fuzzy_match('fooBar', 'fb') -> [8]
+/// fuzzy_match('fabric', 'fb') -> [5]
+/// fuzzy_match('foo', 'x') -> []
+/// ```
+///
+pub struct FuzzyMatch;
+
+impl LibFunction for FuzzyMatch {
+    fn name() -> String {
+        "fuzzy_match".to_string()
+    }
+
+    fn doc() -> String {
+        "Score of pattern chars found in the string in the same order ignoring case".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("string", Type::list(Type::Char)),
+                Param::new("pattern", Type::list(Type::Char)),
+            ],
+            Type::list(Type::Int),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, pattern) = search_args(args, ctx)?;
+        Ok(fuzzy::fuzzy_match(&string, &pattern).to_nois())
+    }
+}
+
+/// Split string into parts separated by the separator
+///
+/// ```text
+/// split([C], [C]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// split('a, b, c', ', ') -> ['a', 'b', 'c']
+/// split('a,,b', ',') -> ['a', '', 'b']
+/// ```
+///
+pub struct Split;
+
+impl LibFunction for Split {
+    fn name() -> String {
+        "split".to_string()
+    }
+
+    fn doc() -> String {
+        "Split string into parts separated by the separator".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("string", Type::list(Type::Char)),
+                Param::new("separator", Type::list(Type::Char)),
+            ],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if args.len() != 2 {
+            return Err(arg_error("([C], [C])", args, ctx));
+        }
+        let (string, separator) = split_args(args, ctx)?;
+        Ok(string.split(&separator).collect::<Vec<_>>().to_nois())
+    }
+}
+
+/// Split string into at most n parts separated by the separator, the last part contains the
+/// rest of the string
+///
+/// ```text
+/// split_n([C], [C], I) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// split_n('a=b=c', '=', 2) -> ['a', 'b=c']
+/// ```
+///
+pub struct SplitN;
+
+impl LibFunction for SplitN {
+    fn name() -> String {
+        "split_n".to_string()
+    }
+
+    fn doc() -> String {
+        "Split string into at most n parts separated by the separator".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("string", Type::list(Type::Char)),
+                Param::new("separator", Type::list(Type::Char)),
+                Param::new("n", Type::Int),
+            ],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        if args.len() != 3 {
+            return Err(arg_error("([C], [C], I)", args, ctx));
+        }
+        let (string, separator) = split_args(args, ctx)?;
+        let n: usize = convert(&args[2], ctx)?;
+        Ok(string.splitn(n, &separator).collect::<Vec<_>>().to_nois())
+    }
+}
+
+/// Split string into parts separated by matches of the regular expression
+///
+/// ```text
+/// split_regex([C], [C]) -> [[C]]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// split_regex('a1b22c', '[0-9]+') -> ['a', 'b', 'c']
+/// ```
+///
+pub struct SplitRegex;
+
+impl LibFunction for SplitRegex {
+    fn name() -> String {
+        "split_regex".to_string()
+    }
+
+    fn doc() -> String {
+        "Split string into parts separated by matches of the regular expression".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("string", Type::list(Type::Char)),
+                Param::new("pattern", Type::list(Type::Char)),
+            ],
+            Type::list(Type::list(Type::Char)),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (string, pattern) = search_args(args, ctx)?;
+        let regex = Regex::new(&pattern).map_err(|e| {
+            Error::from_span(
+                &args[1].0,
+                &ctx.ast_context,
+                format!("invalid regular expression: {}", e),
+            )
+        })?;
+        Ok(regex.split(&string).collect::<Vec<_>>().to_nois())
+    }
+}
+
+/// Join displayed list items into a string with the separator between them
+///
+/// ```text
+/// join([*], [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// join(['a', 'b'], ', ') -> 'a, b'
+/// join([1, 2.5, True], '') -> '12.5True'
+/// ```
+///
+pub struct Join;
+
+impl LibFunction for Join {
+    fn name() -> String {
+        "join".to_string()
+    }
+
+    fn doc() -> String {
+        "Join displayed list items into a string with the separator between them".to_string()
+    }
+
+    fn signature(fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("list", Type::list(fresh())),
+                Param::new("separator", Type::list(Type::Char)),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[..] {
+            [AstPair(_, Value::List { items, .. }), s] => {
+                let separator: String = convert(s, ctx)?;
+                let items = items.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+                Ok(items.join(&separator).to_nois())
+            }
+            _ => Err(arg_error("([*], [C])", args, ctx)),
+        }
+    }
+}
+
+/// Case-insensitive form of the string for comparisons. Unlike lowercase, it also folds chars
+/// with multi-char uppercase forms, e.g. `ß` is folded into `ss`
+fn casefold(string: &str) -> String {
+    string.to_uppercase().to_lowercase()
+}
+
+/// Convert string into Unicode normalization form C, composing chars with their combining marks
+///
+/// ```text
+/// nfc([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// nfc('e\u0301') -> 'é'
+/// ```
+///
+pub struct Nfc;
+
+impl LibFunction for Nfc {
+    fn name() -> String {
+        "nfc".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string into Unicode normalization form C, composing chars with their combining marks"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(string_arg(args, ctx)?.nfc().collect::<String>().to_nois())
+    }
+}
+
+/// Convert string into Unicode normalization form D, decomposing chars into base chars and
+/// combining marks
+///
+/// ```text
+/// nfd([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// nfd('é') -> 'e\u0301'
+/// ```
+///
+pub struct Nfd;
+
+impl LibFunction for Nfd {
+    fn name() -> String {
+        "nfd".to_string()
+    }
+
+    fn doc() -> String {
+        "Convert string into Unicode normalization form D, decomposing chars into base chars and combining marks"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(string_arg(args, ctx)?.nfd().collect::<String>().to_nois())
+    }
+}
+
+/// Fold case of the string for case-insensitive comparison, including chars that have no
+/// single-char lowercase form
+///
+/// ```text
+/// casefold([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// casefold('Straße') -> 'strasse'
+/// ```
+///
+pub struct Casefold;
+
+impl LibFunction for Casefold {
+    fn name() -> String {
+        "casefold".to_string()
+    }
+
+    fn doc() -> String {
+        "Fold case of the string for case-insensitive comparison".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        string_signature()
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(casefold(&string_arg(args, ctx)?).to_nois())
+    }
+}
+
+/// Check if strings are equal ignoring case and differences between composed and decomposed
+/// chars
+///
+/// ```text
+/// compare_ignore_case([C], [C]) -> B
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// compare_ignore_case('STRASSE', 'straße') -> True
+/// compare_ignore_case('É', 'e\u0301') -> True
+/// ```
+///
+pub struct CompareIgnoreCase;
+
+impl LibFunction for CompareIgnoreCase {
+    fn name() -> String {
+        "compare_ignore_case".to_string()
+    }
+
+    fn doc() -> String {
+        "Check if strings are equal ignoring case and differences between composed and decomposed chars"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("a", Type::list(Type::Char)),
+                Param::new("b", Type::list(Type::Char)),
+            ],
+            Type::Bool,
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (a, b) = search_args(args, ctx)?;
+        let normalize = |s: &str| {
+            casefold(&s.nfd().collect::<String>())
+                .nfc()
+                .collect::<String>()
+        };
+        Ok(Value::B(normalize(&a) == normalize(&b)))
+    }
+}
+
+/// Number with a single fraction digit, omitted if it is zero
+fn format_fraction(n: f64) -> String {
+    let s = format!("{:.1}", n);
+    s.strip_suffix(".0").map_or(s.clone(), |i| i.to_string())
+}
+
+/// Size in bytes in the largest binary unit keeping the value at least 1
+///
+/// ```text
+/// humanize_bytes(I) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// humanize_bytes(512) -> '512 B'
+/// humanize_bytes(1536) -> '1.5 KiB'
+/// humanize_bytes(1073741824) -> '1 GiB'
+/// ```
+///
+pub struct HumanizeBytes;
+
+impl LibFunction for HumanizeBytes {
+    fn name() -> String {
+        "humanize_bytes".to_string()
+    }
+
+    fn doc() -> String {
+        "Size in bytes in the largest binary unit keeping the value at least 1".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("n", Type::Int)], Type::list(Type::Char))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let n: u64 = match &args[..] {
+            [a] => convert(a, ctx)?,
+            _ => return Err(arg_error("(I)", args, ctx)),
+        };
+        const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        let mut size = n as f64;
+        let mut unit = 0;
+        while size >= 1024. && unit < UNITS.len() - 1 {
+            size /= 1024.;
+            unit += 1;
+        }
+        Ok(format!("{} {}", format_fraction(size), UNITS[unit]).to_nois())
+    }
+}
+
+/// Duration in milliseconds as days, hours, minutes and seconds, skipping zero units.
+/// Milliseconds are shown only for durations shorter than a second
+///
+/// ```text
+/// humanize_duration(I) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// humanize_duration(250) -> '250ms'
+/// humanize_duration(3725000) -> '1h 2m 5s'
+/// humanize_duration(90000000) -> '1d 1h'
+/// ```
+///
+pub struct HumanizeDuration;
+
+impl LibFunction for HumanizeDuration {
+    fn name() -> String {
+        "humanize_duration".to_string()
+    }
+
+    fn doc() -> String {
+        "Duration in milliseconds as days, hours, minutes and seconds, skipping zero units"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("ms", Type::Int)], Type::list(Type::Char))
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let ms = match &args[..] {
+            [AstPair(_, Value::I(ms))] => *ms,
+            _ => return Err(arg_error("(I)", args, ctx)),
+        };
+        let sign = if ms < 0 { "-" } else { "" };
+        let ms = ms.unsigned_abs();
+        if ms < 1000 {
+            return Ok(format!("{}{}ms", sign, ms).to_nois());
+        }
+        const UNITS: [(&str, u128); 4] = [
+            ("d", 24 * 60 * 60 * 1000),
+            ("h", 60 * 60 * 1000),
+            ("m", 60 * 1000),
+            ("s", 1000),
+        ];
+        let mut rest = ms;
+        let parts = UNITS
+            .iter()
+            .filter_map(|(unit, size)| {
+                let n = rest / size;
+                rest %= size;
+                (n > 0).then(|| format!("{}{}", n, unit))
+            })
+            .collect::<Vec<_>>();
+        Ok(format!("{}{}", sign, parts.join(" ")).to_nois())
+    }
+}
+
+/// Count followed by the English word, in plural form unless the count is 1
+///
+/// ```text
+/// pluralize(I, [C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// pluralize(1, 'file') -> '1 file'
+/// pluralize(3, 'match') -> '3 matches'
+/// pluralize(0, 'entry') -> '0 entries'
+/// ```
+///
+pub struct Pluralize;
+
+impl LibFunction for Pluralize {
+    fn name() -> String {
+        "pluralize".to_string()
+    }
+
+    fn doc() -> String {
+        "Count followed by the English word, in plural form unless the count is 1".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("n", Type::Int),
+                Param::new("word", Type::list(Type::Char)),
+            ],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (n, word): (i128, String) = match &args[..] {
+            [n, w] => (convert(n, ctx)?, convert(w, ctx)?),
+            _ => return Err(arg_error("(I, [C])", args, ctx)),
+        };
+        if n == 1 {
+            return Ok(format!("{} {}", n, word).to_nois());
+        }
+        let consonant_y = word.ends_with('y')
+            && !word
+                .chars()
+                .rev()
+                .nth(1)
+                .is_some_and(|c| "aeiou".contains(c.to_ascii_lowercase()));
+        let plural = if consonant_y {
+            format!("{}ies", &word[..word.len() - 1])
+        } else if ["s", "x", "z", "ch", "sh"]
+            .iter()
+            .any(|e| word.ends_with(e))
+        {
+            format!("{}es", word)
+        } else {
+            format!("{}s", word)
+        };
+        Ok(format!("{} {}", n, plural).to_nois())
+    }
+}
@@ -0,0 +1,133 @@
+use std::cell::RefMut;
+use std::time::Instant;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::evaluate::call_value;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+
+pub fn package() -> Package {
+    Package::new("time", vec![TimeIt::info(), Bench::info()])
+}
+
+/// Call function without arguments, measuring elapsed milliseconds with a monotonic clock
+fn timed_call(f: &AstPair<Value>, ctx: &mut RefMut<Context>) -> Result<(Value, f64), Error> {
+    let callee = ctx.scope_stack.last().unwrap().callee.clone().unwrap();
+    let start = Instant::now();
+    let res = call_value(f, vec![], &callee, ctx)?.1;
+    Ok((res, start.elapsed().as_secs_f64() * 1000.))
+}
+
+/// Call function and measure how long it takes, in milliseconds
+///
+/// ```text
+/// time_it(() -> *) -> [*, F]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// time_it(-> range(1000).map(i -> i + i)) -> [[0, 2, 4, ..], 0.52]
+/// ```
+///
+pub struct TimeIt;
+
+impl LibFunction for TimeIt {
+    fn name() -> String {
+        "time_it".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function and measure how long it takes, in milliseconds".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("f", Type::function(vec![], Type::Any))],
+            Type::list(Type::Any),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (res, elapsed) = match &args[..] {
+            [f] => timed_call(f, ctx)?,
+            _ => return Err(arg_error("(() -> *)", args, ctx)),
+        };
+        Ok(Value::list(vec![res, Value::F(elapsed)]))
+    }
+}
+
+/// Call function repeatedly and report min, mean and max call time, in milliseconds
+///
+/// ```text
+/// bench(() -> *, I) -> BenchStats
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// bench(-> range(1000).map(i -> i + i), 100) -> BenchStats(iterations: 100, min: 0.41, mean: 0.47, max: 0.93)
+/// ```
+///
+pub struct Bench;
+
+impl LibFunction for Bench {
+    fn name() -> String {
+        "bench".to_string()
+    }
+
+    fn doc() -> String {
+        "Call function repeatedly and report min, mean and max call time, in milliseconds"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("f", Type::function(vec![], Type::Any)),
+                Param::new("iterations", Type::Int),
+            ],
+            Type::Named("BenchStats".to_string()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let (f, iterations) = match &args[..] {
+            [f, AstPair(_, Value::I(n))] => (f, *n),
+            _ => return Err(arg_error("(() -> *, I)", args, ctx)),
+        };
+        if iterations < 1 {
+            return Err(Error::from_callee(
+                ctx,
+                format!(
+                    "expected positive number of iterations, found {}",
+                    iterations
+                ),
+            ));
+        }
+        let mut times = vec![];
+        for _ in 0..iterations {
+            times.push(timed_call(f, ctx)?.1);
+        }
+        let min = times.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = times.iter().copied().fold(0., f64::max);
+        let mean = times.iter().sum::<f64>() / times.len() as f64;
+        let fields = vec![
+            ("iterations", Value::I(iterations)),
+            ("min", Value::F(min)),
+            ("mean", Value::F(mean)),
+            ("max", Value::F(max)),
+        ];
+        Ok(Value::Struct {
+            name: "BenchStats".to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(f, v)| (Identifier::new(f), v))
+                .collect(),
+        })
+    }
+}
@@ -1,17 +1,15 @@
 use std::cell::RefMut;
-use std::collections::HashMap;
 
 use crate::ast::ast::{AstPair, UnaryOperator};
 use crate::error::Error;
 use crate::interpret::context::Context;
 use crate::interpret::value::Value;
 use crate::stdlib::lib::{LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
 
 pub fn package() -> Package {
-    Package {
-        name: "unary_operator".to_string(),
-        definitions: HashMap::from([Spread::definition()]),
-    }
+    Package::new("unary_operator", vec![Spread::info(), BitNot::info()])
 }
 
 pub struct Spread;
@@ -21,6 +19,15 @@ impl LibFunction for Spread {
         UnaryOperator::Spread.to_string()
     }
 
+    fn doc() -> String {
+        "Spread list or range items into the enclosing list or function arguments, e.g. `[0, ..l]`"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("list", Type::Any)], Type::list(Type::Any))
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let arg = &args[0];
         match &arg.1 {
@@ -37,6 +44,42 @@ impl LibFunction for Spread {
                     })
                 }
             }
+            Value::Seq(s) => match s.items() {
+                Some(items) => Ok(Value::List {
+                    items,
+                    spread: true,
+                }),
+                None => Err(Error::from_callee(
+                    ctx,
+                    format!("lazy sequence {} cannot be spread, take its items with 'take' or 'take_while'", s),
+                )),
+            },
+            a => Err(Error::from_callee(
+                ctx,
+                format!("incompatible operand: {}{}", Self::name(), a.value_type()),
+            )),
+        }
+    }
+}
+
+pub struct BitNot;
+
+impl LibFunction for BitNot {
+    fn name() -> String {
+        UnaryOperator::BitNot.to_string()
+    }
+
+    fn doc() -> String {
+        "Bitwise NOT of integer, e.g. `~5 -> -6`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(vec![Param::new("a", Type::Int)], Type::Int)
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        match &args[0].1 {
+            Value::I(i) => Ok(Value::I(!i)),
             a => Err(Error::from_callee(
                 ctx,
                 format!("incompatible operand: {}{}", Self::name(), a.value_type()),
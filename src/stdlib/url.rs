@@ -0,0 +1,246 @@
+use std::cell::RefMut;
+
+use crate::ast::ast::{AstPair, Identifier};
+use crate::convert::{FromNois, ToNois};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types::Type;
+use crate::url::{self, Url};
+
+pub fn package() -> Package {
+    Package::new(
+        "url",
+        vec![
+            ParseUrl::info(),
+            BuildUrl::info(),
+            UrlEncode::info(),
+            UrlDecode::info(),
+        ],
+    )
+}
+
+fn string_arg(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<String, Error> {
+    match &args[..] {
+        [a] => String::from_nois(a.1.clone()).map_err(|_| arg_error("([C])", args, ctx)),
+        _ => Err(arg_error("([C])", args, ctx)),
+    }
+}
+
+fn url_to_nois(url: Url) -> Value {
+    let query = url
+        .query
+        .into_iter()
+        .map(|(k, v)| vec![k, v])
+        .collect::<Vec<_>>();
+    let fields = vec![
+        ("scheme", url.scheme.to_nois()),
+        ("host", url.host.to_nois()),
+        ("port", url.port.to_nois()),
+        ("path", url.path.to_nois()),
+        ("query", query.to_nois()),
+        ("fragment", url.fragment.to_nois()),
+    ];
+    Value::Struct {
+        name: "Url".to_string(),
+        fields: fields
+            .into_iter()
+            .map(|(f, v)| (Identifier::new(f), v))
+            .collect(),
+    }
+}
+
+fn url_from_nois(value: &Value) -> Result<Url, String> {
+    let fields = match value {
+        Value::Struct { fields, .. } => fields,
+        v => return Err(format!("expected Url struct, found {}", v.value_type())),
+    };
+    fn field<T: FromNois + Default>(
+        fields: &[(Identifier, Value)],
+        name: &str,
+    ) -> Result<T, String> {
+        match fields.iter().find(|(f, _)| f.0 == name) {
+            Some((_, v)) => T::from_nois(v.clone()).map_err(|e| format!("url {}: {}", name, e)),
+            None => Ok(T::default()),
+        }
+    }
+    let query: Vec<Vec<String>> = field(fields, "query")?;
+    Ok(Url {
+        scheme: field(fields, "scheme")?,
+        host: field(fields, "host")?,
+        port: field(fields, "port")?,
+        path: field(fields, "path")?,
+        query: query
+            .into_iter()
+            .map(|p| match &p[..] {
+                [k, v] => Ok((k.clone(), v.clone())),
+                _ => Err(format!(
+                    "url query: expected key-value pair, found {} items",
+                    p.len()
+                )),
+            })
+            .collect::<Result<_, _>>()?,
+        fragment: field(fields, "fragment")?,
+    })
+}
+
+/// Split URL into scheme, host, optional port, path, decoded query parameters and optional
+/// fragment
+///
+/// ```text
+/// parse_url([C]) -> Url
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// parse_url('https://example.com:8080/search?q=nois#top') ->
+///     Url(scheme: https, host: example.com, port: [8080], path: /search, query: [[q, nois]], fragment: [top])
+/// ```
+///
+pub struct ParseUrl;
+
+impl LibFunction for ParseUrl {
+    fn name() -> String {
+        "parse_url".to_string()
+    }
+
+    fn doc() -> String {
+        "Split URL into scheme, host, optional port, path, decoded query parameters and optional fragment"
+            .to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("url", Type::list(Type::Char))],
+            Type::Named("Url".to_string()),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let s = string_arg(args, ctx)?;
+        match Url::parse(&s) {
+            Ok(url) => Ok(url_to_nois(url)),
+            Err(e) => Err(Error::from_span(&args[0].0, &ctx.ast_context, e)),
+        }
+    }
+}
+
+/// Build URL from the parts returned by `parse_url` or a `Url` struct with the same fields,
+/// encoding query parameters. Missing fields are empty
+///
+/// ```text
+/// build_url(Url) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// Url = #{scheme, host, path, query}
+/// build_url(Url('https', 'example.com', '/search', [['q', 'a b']])) -> 'https://example.com/search?q=a%20b'
+/// ```
+///
+pub struct BuildUrl;
+
+impl LibFunction for BuildUrl {
+    fn name() -> String {
+        "build_url".to_string()
+    }
+
+    fn doc() -> String {
+        "Build URL from the parts returned by `parse_url`, encoding query parameters".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("parts", Type::Named("Url".to_string()))],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let parts = match &args[..] {
+            [a] => a,
+            _ => return Err(arg_error("(Url)", args, ctx)),
+        };
+        match url_from_nois(&parts.1) {
+            Ok(url) => Ok(url.to_string().to_nois()),
+            Err(e) => Err(Error::from_span(&parts.0, &ctx.ast_context, e)),
+        }
+    }
+}
+
+/// Percent-encode all chars except letters, digits and `-._~`
+///
+/// ```text
+/// url_encode([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// url_encode('a b/é') -> 'a%20b%2F%C3%A9'
+/// ```
+///
+pub struct UrlEncode;
+
+impl LibFunction for UrlEncode {
+    fn name() -> String {
+        "url_encode".to_string()
+    }
+
+    fn doc() -> String {
+        "Percent-encode all chars except letters, digits and `-._~`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("string", Type::list(Type::Char))],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        Ok(url::encode(&string_arg(args, ctx)?).to_nois())
+    }
+}
+
+/// Decode percent-encoded string
+///
+/// ```text
+/// url_decode([C]) -> [C]
+/// ```
+///
+/// Examples:
+///
+/// ```text
+/// url_decode('a%20b%2F%C3%A9') -> 'a b/é'
+/// ```
+///
+pub struct UrlDecode;
+
+impl LibFunction for UrlDecode {
+    fn name() -> String {
+        "url_decode".to_string()
+    }
+
+    fn doc() -> String {
+        "Decode percent-encoded string".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("string", Type::list(Type::Char))],
+            Type::list(Type::Char),
+        )
+    }
+
+    fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
+        let s = string_arg(args, ctx)?;
+        url::decode(&s)
+            .map(|d| d.to_nois())
+            .map_err(|e| Error::from_callee(ctx, e))
+    }
+}
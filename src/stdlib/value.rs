@@ -1,17 +1,15 @@
 use std::cell::RefMut;
-use std::collections::HashMap;
 
 use crate::ast::ast::AstPair;
 use crate::error::Error;
 use crate::interpret::context::Context;
 use crate::interpret::value::Value;
 use crate::stdlib::lib::{arg_error, LibFunction, Package};
+use crate::typecheck::signature::{Param, Signature};
+use crate::typecheck::types;
 
 pub fn package() -> Package {
-    Package {
-        name: "value".to_string(),
-        definitions: HashMap::from([Type::definition(), To::definition()]),
-    }
+    Package::new("value", vec![Type::info(), To::info()])
 }
 
 pub struct Type;
@@ -21,6 +19,17 @@ impl LibFunction for Type {
         "type".to_string()
     }
 
+    fn doc() -> String {
+        "Type of the value, e.g. `type(1) -> I`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> types::Type) -> Signature {
+        Signature::fixed(
+            vec![Param::new("value", types::Type::Any)],
+            types::Type::Type,
+        )
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let a = args.into_iter().cloned().map(|a| a.1).collect::<Vec<_>>();
         let arg = match &a[..] {
@@ -38,6 +47,20 @@ impl LibFunction for To {
         "to".to_string()
     }
 
+    fn doc() -> String {
+        "Cast value to the type, e.g. `to(1, F) -> 1.0`".to_string()
+    }
+
+    fn signature(_fresh: &mut dyn FnMut() -> types::Type) -> Signature {
+        Signature::fixed(
+            vec![
+                Param::new("value", types::Type::Any),
+                Param::new("type", types::Type::Any),
+            ],
+            types::Type::Any,
+        )
+    }
+
     fn call(args: &Vec<AstPair<Value>>, ctx: &mut RefMut<Context>) -> Result<Value, Error> {
         let is_type_list = |l: &Vec<Value>| match l[..] {
             [Value::Type(..)] => true,
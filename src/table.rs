@@ -0,0 +1,141 @@
+/// Cell of a text table, numbers are aligned to the right
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cell {
+    pub text: String,
+    pub numeric: bool,
+}
+
+impl Cell {
+    pub fn new(text: &str, numeric: bool) -> Cell {
+        Cell {
+            text: text.to_string(),
+            numeric,
+        }
+    }
+}
+
+/// Lines of a table with columns aligned and separated by `|`, e.g.
+///
+/// ```text
+/// name  | age
+/// ------+----
+/// Alice |  30
+/// ```
+///
+/// Header and rule lines are omitted if there are no headers. Missing cells of short rows are
+/// left blank. If the table is wider than `max_width`, the widest columns are narrowed and their
+/// cells truncated with `…`.
+pub fn format_table(
+    headers: &[String],
+    rows: &[Vec<Cell>],
+    max_width: Option<usize>,
+) -> Vec<String> {
+    let columns = rows
+        .iter()
+        .map(|r| r.len())
+        .chain([headers.len()])
+        .max()
+        .unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for (i, h) in headers.iter().enumerate() {
+        widths[i] = h.chars().count();
+    }
+    for row in rows {
+        for (i, c) in row.iter().enumerate() {
+            widths[i] = widths[i].max(c.text.chars().count());
+        }
+    }
+    if let Some(max) = max_width {
+        const SEPARATOR: usize = " | ".len();
+        let width =
+            |ws: &Vec<usize>| ws.iter().sum::<usize>() + SEPARATOR * columns.saturating_sub(1);
+        while width(&widths) > max {
+            let widest = (0..columns).max_by_key(|i| (widths[*i], *i)).unwrap();
+            // column is not narrowed further than a char and ellipsis
+            if widths[widest] <= 2 {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+    }
+
+    let line = |cells: Vec<(String, bool)>| {
+        let cells = widths
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let (text, numeric) = cells.get(i).cloned().unwrap_or_default();
+                let text = truncate(&text, *w);
+                if numeric {
+                    format!("{:>w$}", text, w = w)
+                } else {
+                    format!("{:<w$}", text, w = w)
+                }
+            })
+            .collect::<Vec<_>>();
+        cells.join(" | ").trim_end().to_string()
+    };
+    let mut lines = vec![];
+    if !headers.is_empty() {
+        lines.push(line(headers.iter().map(|h| (h.clone(), false)).collect()));
+        lines.push(
+            widths
+                .iter()
+                .map(|w| "-".repeat(*w))
+                .collect::<Vec<_>>()
+                .join("-+-"),
+        );
+    }
+    for row in rows {
+        lines.push(line(
+            row.iter().map(|c| (c.text.clone(), c.numeric)).collect(),
+        ));
+    }
+    lines
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    text.chars()
+        .take(width.saturating_sub(1))
+        .chain(['…'])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[(&str, bool)]) -> Vec<Cell> {
+        cells.iter().map(|(t, n)| Cell::new(t, *n)).collect()
+    }
+
+    #[test]
+    fn align_columns() {
+        let headers = vec!["name".to_string(), "age".to_string()];
+        let rows = vec![
+            row(&[("Alice", false), ("30", true)]),
+            row(&[("Bob", false), ("4", true)]),
+            row(&[("Eve", false)]),
+        ];
+        assert_eq!(
+            format_table(&headers, &rows, None),
+            vec![
+                "name  | age",
+                "------+----",
+                "Alice |  30",
+                "Bob   |   4",
+                "Eve   |",
+            ]
+        );
+        assert_eq!(format_table(&[], &rows[..1], None), vec!["Alice | 30"]);
+    }
+
+    #[test]
+    fn truncate_to_width() {
+        let rows = vec![row(&[("a", false), ("long description", false)])];
+        assert_eq!(format_table(&[], &rows, Some(12)), vec!["a | long de…"]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::embed::{Runtime, Script};
+use crate::error::Error;
+use crate::interpret::context::Context;
+use crate::interpret::observer::EvalObserver;
+
+/// Top-level definitions starting with this prefix are considered tests, e.g. `testSum`
+pub const TEST_PREFIX: &str = "test";
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub error: Option<Error>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Call every test definition of the script without arguments, test fails if evaluation fails.
+/// Observers are notified of the evaluation of all tests.
+pub fn run_tests(
+    script: &Script,
+    observers: Vec<Rc<RefCell<dyn EvalObserver>>>,
+) -> Result<Vec<TestResult>, Error> {
+    let ctx = observers
+        .into_iter()
+        .fold(Context::stdlib(script.ast_context.clone()), |c, o| {
+            c.with_observer(o)
+        });
+    let runtime = Runtime::with_context(script, ctx)?;
+    Ok(runtime
+        .definitions()
+        .into_iter()
+        .filter(|n| n.starts_with(TEST_PREFIX))
+        .map(|name| {
+            let error = runtime.call(&name, vec![]).err();
+            TestResult { name, error }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::Path;
+
+    use crate::ast::ast::AstContext;
+    use crate::coverage::{to_lcov, Coverage};
+    use crate::stdlib::lib::stdlib;
+
+    use super::*;
+
+    /// Stdlib behavior is specified by nois tests in `tests/stdlib/<package>.no`
+    #[test]
+    fn stdlib_conformance() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/stdlib");
+        for package in stdlib() {
+            let path = dir.join(format!("{}.no", package.name));
+            let source =
+                read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            let script = Script::parse(&source).unwrap();
+            let results = run_tests(&script, vec![]).unwrap();
+            assert!(!results.is_empty(), "{}: no tests", path.display());
+            for r in results {
+                if let Some(e) = r.error {
+                    panic!("{}: {} failed\n{}", path.display(), r.name, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn run_tests_with_coverage() {
+        let source = r#"
+sign = a -> match a {
+    0 => 'zero',
+    _ => 'nonzero',
+}
+
+testZero = -> {
+    sign(0)
+}
+
+testFailing = -> {
+    assert(false)
+}
+"#;
+        let script = Script::parse(source).unwrap();
+        let coverage = Rc::new(RefCell::new(Coverage::default()));
+        let results = run_tests(&script, vec![coverage.clone()]).unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.name.as_str(), r.passed()))
+                .collect::<Vec<_>>(),
+            vec![("testFailing", false), ("testZero", true)]
+        );
+
+        let a_ctx = AstContext::new(source);
+        let report = coverage.borrow().report("a.no", &script.ast, &a_ctx);
+        assert_eq!(report.lines.get(&3), Some(&1));
+        assert_eq!(report.lines.get(&4), Some(&0));
+        assert_eq!(report.lines.get(&6), None);
+        assert_eq!(
+            report.to_text(),
+            "a.no: 85.7% (6/7 lines)\n  uncovered lines: 4"
+        );
+        assert!(to_lcov(&[report]).starts_with("TN:\nSF:a.no\nDA:2,1\nDA:3,1\nDA:4,0\n"));
+    }
+}
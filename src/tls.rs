@@ -0,0 +1,175 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+/// Client configuration verifying server certificates against the CA bundle, a PEM file with
+/// one or more certificates, or against the bundled Mozilla root certificates if not specified.
+/// Verification is skipped altogether if `verify` is false.
+pub fn client_config(verify: bool, ca_bundle: Option<&Path>) -> Result<Arc<ClientConfig>, String> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    let config = if verify {
+        builder.with_root_certificates(root_store(ca_bundle)?)
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+    };
+    Ok(Arc::new(config.with_no_client_auth()))
+}
+
+fn root_store(ca_bundle: Option<&Path>) -> Result<RootCertStore, String> {
+    let path = match ca_bundle {
+        Some(p) => p,
+        None => {
+            return Ok(RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+            })
+        }
+    };
+    let error =
+        |e: &dyn ToString| format!("invalid CA bundle {}: {}", path.display(), e.to_string());
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(path).map_err(|e| error(&e))? {
+        roots
+            .add(cert.map_err(|e| error(&e))?)
+            .map_err(|e| error(&e))?;
+    }
+    if roots.is_empty() {
+        return Err(error(&"no certificates found"));
+    }
+    Ok(roots)
+}
+
+/// Verifier accepting any server certificate. Handshake signatures are still checked, so that
+/// the connection is at least encrypted with the key of the presented certificate
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread;
+
+    use rustls::pki_types::PrivateKeyDer;
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+    use crate::http::{fetch, read_request, ClientOptions, Response};
+    use crate::url::Url;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/tls")
+            .join(name)
+    }
+
+    /// Serve the number of HTTPS connections with a self-signed certificate for `localhost`
+    fn serve(connections: usize) -> u16 {
+        let certs = CertificateDer::pem_file_iter(fixture("localhost.pem"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(fixture("localhost.key")).unwrap();
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .unwrap();
+        let config = Arc::new(config);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for tcp in listener.incoming().take(connections) {
+                let connection = ServerConnection::new(config.clone()).unwrap();
+                let mut stream = StreamOwned::new(connection, tcp.unwrap());
+                // handshake fails for clients rejecting the certificate
+                if let Ok(request) = read_request(&mut stream) {
+                    Response::text(200, &request.path).write(&mut stream).ok();
+                    stream.conn.send_close_notify();
+                    stream.flush().ok();
+                }
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn verify_certificates() {
+        let port = serve(3);
+        let url = Url::parse(&format!("https://localhost:{}/secure", port)).unwrap();
+        let get = |options: ClientOptions| fetch("GET", &url, &[], "", &options).map(|r| r.body);
+
+        let error = get(ClientOptions::default()).unwrap_err();
+        assert!(error.contains("UnknownIssuer"), "{}", error);
+        let trusted = ClientOptions {
+            ca_bundle: Some(fixture("localhost.pem")),
+            ..ClientOptions::default()
+        };
+        assert_eq!(get(trusted), Ok("/secure".to_string()));
+        let unverified = ClientOptions {
+            verify: false,
+            ..ClientOptions::default()
+        };
+        assert_eq!(get(unverified), Ok("/secure".to_string()));
+
+        let error = client_config(true, Some(&fixture("localhost.key"))).unwrap_err();
+        assert!(error.starts_with("invalid CA bundle"), "{}", error);
+    }
+}
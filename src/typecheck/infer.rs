@@ -0,0 +1,703 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::ast::{
+    Assignee, AstPair, BinaryOperator, Block, DestructureItem, DestructureList, Expression,
+    FunctionCall, FunctionInit, Identifier, MatchClause, Operand, PatternItem, Span, Statement,
+    UnaryOperator,
+};
+use crate::ast::visit::walk_statements;
+use crate::typecheck::signature::{stdlib_signature, Params};
+use crate::typecheck::types::Type;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeError {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Type with generalized type variables, instantiated with fresh variables on every use
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    ty: Type,
+}
+
+impl Scheme {
+    fn mono(ty: Type) -> Scheme {
+        Scheme { vars: vec![], ty }
+    }
+}
+
+/// Best-effort Hindley-Milner style inference.
+/// Only definite type errors are reported: whenever type cannot be inferred, it is `Any`,
+/// which is compatible with everything.
+#[derive(Debug, Default)]
+pub struct Inferer {
+    substitution: Vec<Option<Type>>,
+    scopes: Vec<HashMap<Identifier, Scheme>>,
+    pub errors: Vec<TypeError>,
+}
+
+impl Inferer {
+    pub fn infer_program(&mut self, ast: &AstPair<Block>) {
+        // top-level definitions can be referenced before they are defined
+        self.scopes.push(HashMap::new());
+        for s in &ast.1.statements {
+            if let Statement::Assignment {
+                assignee:
+                    AstPair(
+                        _,
+                        Assignee::Identifier {
+                            identifier,
+                            annotation,
+                        },
+                    ),
+                ..
+            } = &s.1
+            {
+                let t = annotation
+                    .as_ref()
+                    .map(|a| Type::from(&a.1))
+                    .unwrap_or(Type::Any);
+                self.bind(&identifier.1, Scheme::mono(t));
+            }
+        }
+        for s in &ast.1.statements {
+            self.infer_statement(s);
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.substitution.push(None);
+        Type::Var(self.substitution.len() - 1)
+    }
+
+    /// Replace resolved type variables with their types
+    pub fn resolve(&self, t: &Type) -> Type {
+        match t {
+            Type::Var(i) => match &self.substitution[*i] {
+                Some(s) => self.resolve(s),
+                None => t.clone(),
+            },
+            Type::List(i) => Type::list(self.resolve(i)),
+            Type::Deque(i) => Type::deque(self.resolve(i)),
+            Type::Seq(i) => Type::seq(self.resolve(i)),
+            Type::Fn(ps, r) => Type::function(
+                ps.iter().map(|p| self.resolve(p)).collect(),
+                self.resolve(r),
+            ),
+            _ => t.clone(),
+        }
+    }
+
+    /// Resolved type for error messages, unresolved variables are shown as `*`
+    fn display(&self, t: &Type) -> String {
+        fn erase(t: Type) -> Type {
+            match t {
+                Type::Var(_) => Type::Any,
+                Type::List(i) => Type::list(erase(*i)),
+                Type::Deque(i) => Type::deque(erase(*i)),
+                Type::Seq(i) => Type::seq(erase(*i)),
+                Type::Fn(ps, r) => Type::function(ps.into_iter().map(erase).collect(), erase(*r)),
+                _ => t,
+            }
+        }
+        erase(self.resolve(t)).to_string()
+    }
+
+    fn free_vars(&self, t: &Type, vars: &mut HashSet<usize>) {
+        match self.resolve(t) {
+            Type::Var(i) => {
+                vars.insert(i);
+            }
+            Type::List(i) | Type::Deque(i) | Type::Seq(i) => self.free_vars(&i, vars),
+            Type::Fn(ps, r) => {
+                ps.iter().for_each(|p| self.free_vars(p, vars));
+                self.free_vars(&r, vars);
+            }
+            _ => {}
+        }
+    }
+
+    fn occurs(&self, var: usize, t: &Type) -> bool {
+        let mut vars = HashSet::new();
+        self.free_vars(t, &mut vars);
+        vars.contains(&var)
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> bool {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        match (&a, &b) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Var(i), Type::Var(j)) if i == j => true,
+            (Type::Var(i), t) | (t, Type::Var(i)) => {
+                // recursive types are not supported, leave variable unresolved
+                if !self.occurs(*i, t) {
+                    self.substitution[*i] = Some(t.clone());
+                }
+                true
+            }
+            (Type::List(x), Type::List(y))
+            | (Type::Deque(x), Type::Deque(y))
+            | (Type::Seq(x), Type::Seq(y)) => self.unify(x, y),
+            // arguments are matched with parameters by position, extra ones are ignored
+            (Type::Fn(p1, r1), Type::Fn(p2, r2)) => {
+                let params = p1
+                    .iter()
+                    .zip(p2)
+                    .fold(true, |ok, (x, y)| self.unify(x, y) && ok);
+                self.unify(r1, r2) && params
+            }
+            _ => a == b,
+        }
+    }
+
+    /// Unify types, leaving substitution untouched if they are not compatible
+    fn try_unify(&mut self, a: &Type, b: &Type) -> bool {
+        let snapshot = self.substitution.clone();
+        let ok = self.unify(a, b);
+        if !ok {
+            self.substitution = snapshot;
+        }
+        ok
+    }
+
+    fn expect(&mut self, span: &Span, expected: &Type, found: &Type) {
+        // finite sequences, e.g. ranges, are taken as lists, infinite ones fail at runtime
+        let ok = match (self.resolve(expected), self.resolve(found)) {
+            (Type::List(e), Type::Seq(f)) => self.try_unify(&e, &f),
+            _ => self.try_unify(expected, found),
+        };
+        if !ok {
+            let message = format!(
+                "expected {}, found {}",
+                self.display(expected),
+                self.display(found)
+            );
+            self.error(span, message);
+        }
+    }
+
+    fn error(&mut self, span: &Span, message: String) {
+        self.errors.push(TypeError {
+            span: span.clone(),
+            message,
+        });
+    }
+
+    fn bind(&mut self, identifier: &Identifier, scheme: Scheme) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(identifier.clone(), scheme);
+    }
+
+    fn lookup(&mut self, identifier: &Identifier) -> Option<Type> {
+        let scheme = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|s| s.get(identifier))
+            .cloned()?;
+        let mapping = scheme
+            .vars
+            .iter()
+            .map(|v| (*v, self.fresh()))
+            .collect::<HashMap<_, _>>();
+        Some(instantiate(&self.resolve(&scheme.ty), &mapping))
+    }
+
+    fn generalize(&self, t: &Type) -> Scheme {
+        let ty = self.resolve(t);
+        let mut vars = HashSet::new();
+        self.free_vars(&ty, &mut vars);
+        let mut env_vars = HashSet::new();
+        for scheme in self.scopes.iter().flat_map(|s| s.values()) {
+            let mut vs = HashSet::new();
+            self.free_vars(&scheme.ty, &mut vs);
+            env_vars.extend(vs.into_iter().filter(|v| !scheme.vars.contains(v)));
+        }
+        Scheme {
+            vars: vars.difference(&env_vars).cloned().collect(),
+            ty,
+        }
+    }
+
+    fn infer_block(&mut self, block: &AstPair<Block>) -> Type {
+        self.scopes.push(HashMap::new());
+        let mut last = Type::Unit;
+        for s in &block.1.statements {
+            last = self.infer_statement(s);
+        }
+        self.scopes.pop();
+        last
+    }
+
+    fn infer_statement(&mut self, statement: &AstPair<Statement>) -> Type {
+        match &statement.1 {
+            Statement::Expression(e) => self.infer_expression(e, None),
+            Statement::Return(e) => {
+                if let Some(e) = e {
+                    self.infer_expression(e, None);
+                }
+                Type::Any
+            }
+            Statement::Assignment {
+                assignee,
+                expression,
+            } => {
+                self.infer_assignment(assignee, expression);
+                Type::Unit
+            }
+            // definitions of imported files are not known statically
+            Statement::Import(_) => Type::Unit,
+        }
+    }
+
+    fn infer_assignment(&mut self, assignee: &AstPair<Assignee>, expression: &AstPair<Expression>) {
+        if let (Assignee::Identifier { identifier, .. }, Expression::Operand(o)) =
+            (&assignee.1, &expression.1)
+        {
+            match &o.1 {
+                Operand::StructDefinition { fields } => {
+                    let constructor = Type::function(
+                        fields.iter().map(|_| Type::Any).collect(),
+                        Type::Named(identifier.1 .0.clone()),
+                    );
+                    self.bind(&identifier.1, Scheme::mono(constructor));
+                    return;
+                }
+                Operand::EnumDefinition { .. } => {
+                    self.bind(&identifier.1, Scheme::mono(Type::Type));
+                    return;
+                }
+                Operand::FunctionInit(_) if self.lookup(&identifier.1).is_none() => {
+                    // allow recursive references
+                    self.bind(&identifier.1, Scheme::mono(Type::Any));
+                }
+                _ => {}
+            }
+        }
+        let annotation = match &assignee.1 {
+            Assignee::Identifier {
+                annotation: Some(a),
+                ..
+            } => Some(Type::from(&a.1)),
+            _ => None,
+        };
+        let t = self.infer_expression(expression, annotation.as_ref());
+        let t = match annotation {
+            Some(a) => {
+                self.expect(&expression.0, &a, &t);
+                a
+            }
+            None => t,
+        };
+        self.bind_assignee(assignee, &t);
+    }
+
+    fn bind_assignee(&mut self, assignee: &AstPair<Assignee>, t: &Type) {
+        match &assignee.1 {
+            Assignee::Hole => {}
+            Assignee::Identifier { identifier, .. } => {
+                let scheme = self.generalize(t);
+                self.bind(&identifier.1, scheme)
+            }
+            Assignee::DestructureList(dl) => self.bind_destructure_list(dl, t),
+        }
+    }
+
+    fn bind_destructure_list(&mut self, list: &DestructureList, t: &Type) {
+        let item = match self.resolve(t) {
+            Type::List(i) => *i,
+            _ => Type::Any,
+        };
+        for i in &list.0 {
+            match &i.1 {
+                DestructureItem::Hole => {}
+                DestructureItem::Identifier { identifier, spread } => {
+                    let it = if *spread {
+                        Type::list(item.clone())
+                    } else {
+                        item.clone()
+                    };
+                    self.bind(&identifier.1, Scheme::mono(it))
+                }
+                DestructureItem::List(l) => self.bind_destructure_list(l, &item),
+            }
+        }
+    }
+
+    fn infer_expression(
+        &mut self,
+        expression: &AstPair<Expression>,
+        expected: Option<&Type>,
+    ) -> Type {
+        match &expression.1 {
+            Expression::Operand(o) => self.infer_operand(o, expected),
+            Expression::Group(e) => self.infer_expression(e, expected),
+            Expression::Unary { operator, operand } => {
+                let t = self.infer_expression(operand, None);
+                match operator.1 {
+                    UnaryOperator::Not => Type::Bool,
+                    UnaryOperator::BitNot => {
+                        let operand_type = self.resolve(&t);
+                        if !self.try_unify(&operand_type, &Type::Int) && operand_type.is_concrete() {
+                            let message =
+                                format!("incompatible operand: {}{}", operator.1, operand_type);
+                            self.error(&expression.0, message);
+                        }
+                        Type::Int
+                    }
+                    _ => t,
+                }
+            }
+            Expression::Binary {
+                left_operand,
+                operator,
+                right_operand,
+            } => self.infer_binary(expression, left_operand, &operator.1, right_operand),
+            Expression::MatchExpression {
+                condition,
+                match_clauses,
+            } => {
+                let ct = self.infer_expression(condition, None);
+                let mut res: Option<Type> = None;
+                for c in match_clauses {
+                    let t = self.infer_match_clause(c, &ct);
+                    res = Some(match res {
+                        Some(r) if self.try_unify(&r, &t) => r,
+                        Some(_) => Type::Any,
+                        None => t,
+                    });
+                }
+                res.unwrap_or(Type::Unit)
+            }
+            Expression::IfExpression {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                let ct = self.infer_expression(condition, None);
+                self.expect(&condition.0, &Type::Bool, &ct);
+                let then_type = self.infer_block(then_block);
+                let else_type = match else_block {
+                    Some(b) => self.infer_block(b),
+                    None => Type::Unit,
+                };
+                if self.try_unify(&then_type, &else_type) {
+                    then_type
+                } else {
+                    Type::Any
+                }
+            }
+        }
+    }
+
+    fn infer_match_clause(&mut self, clause: &AstPair<MatchClause>, condition: &Type) -> Type {
+        self.scopes.push(HashMap::new());
+        self.bind_pattern(&clause.1.pattern, condition);
+        let t = self.infer_block(&clause.1.block);
+        self.scopes.pop();
+        t
+    }
+
+    fn bind_pattern(&mut self, pattern: &AstPair<PatternItem>, t: &Type) {
+        match &pattern.1 {
+            PatternItem::Identifier { identifier, .. } => {
+                self.bind(&identifier.1, Scheme::mono(t.clone()))
+            }
+            PatternItem::StringConcat {
+                rest: Some(identifier),
+                ..
+            } => self.bind(&identifier.1, Scheme::mono(Type::list(Type::Char))),
+            PatternItem::PatternList(items) => {
+                let item = match self.resolve(t) {
+                    Type::List(i) => *i,
+                    _ => Type::Any,
+                };
+                for i in items {
+                    match &i.1 {
+                        PatternItem::Identifier {
+                            identifier,
+                            spread: true,
+                        } => self.bind(&identifier.1, Scheme::mono(Type::list(item.clone()))),
+                        _ => self.bind_pattern(i, &item),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn infer_operand(&mut self, operand: &AstPair<Operand>, expected: Option<&Type>) -> Type {
+        match &operand.1 {
+            Operand::Hole => Type::Any,
+            Operand::Integer(_) => Type::Int,
+            Operand::Float(_) => Type::Float,
+            Operand::Boolean(_) => Type::Bool,
+            Operand::String(_) => Type::list(Type::Char),
+            Operand::ValueType(_)
+            | Operand::StructDefinition { .. }
+            | Operand::EnumDefinition { .. } => Type::Type,
+            Operand::ListInit { items } => {
+                let expected_item = match expected.map(|e| self.resolve(e)) {
+                    Some(Type::List(i)) => Some(*i),
+                    _ => None,
+                };
+                let mut res: Option<Type> = None;
+                for i in items {
+                    let t = match &i.1 {
+                        Expression::Unary { operator, operand }
+                            if operator.1 == UnaryOperator::Spread =>
+                        {
+                            let spread = self.infer_expression(operand, None);
+                            match self.resolve(&spread) {
+                                Type::List(t) => *t,
+                                _ => Type::Any,
+                            }
+                        }
+                        _ => self.infer_expression(i, expected_item.as_ref()),
+                    };
+                    res = Some(match res {
+                        Some(r) if self.try_unify(&r, &t) => r,
+                        // lists can contain values of different types
+                        Some(_) => Type::Any,
+                        None => t,
+                    });
+                }
+                Type::list(res.unwrap_or_else(|| self.fresh()))
+            }
+            Operand::Identifier(i) => match self.lookup(&i.1) {
+                Some(t) => t,
+                None => {
+                    let mut fresh = || self.fresh();
+                    match stdlib_signature(&i.1 .0, &mut fresh) {
+                        Some(s) => match s.param_types() {
+                            Params::Fixed(ps) => Type::function(ps, s.ret),
+                            Params::Variadic(_) => Type::Any,
+                        },
+                        None => Type::Any,
+                    }
+                }
+            },
+            Operand::FunctionInit(fi) => self.infer_function(fi, expected),
+            Operand::FunctionCall(fc) => self.infer_call(&fc.identifier.1, None, fc),
+        }
+    }
+
+    fn infer_function(&mut self, function: &FunctionInit, expected: Option<&Type>) -> Type {
+        let expected_params = match expected.map(|e| self.resolve(e)) {
+            Some(Type::Fn(ps, _)) => ps,
+            _ => vec![],
+        };
+        self.scopes.push(HashMap::new());
+        let mut params = vec![];
+        for (i, p) in function.parameters.iter().enumerate() {
+            let t = match &p.1 {
+                Assignee::Identifier {
+                    annotation: Some(a),
+                    ..
+                } => Type::from(&a.1),
+                _ => expected_params
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| self.fresh()),
+            };
+            match &p.1 {
+                Assignee::Identifier { identifier, .. } => {
+                    self.bind(&identifier.1, Scheme::mono(t.clone()))
+                }
+                _ => self.bind_assignee(p, &t),
+            }
+            params.push(t);
+        }
+        let mut returns = false;
+        walk_statements(&function.block, &mut |s| {
+            returns |= matches!(s.1, Statement::Return(_))
+        });
+        let ret = self.infer_block(&function.block);
+        self.scopes.pop();
+        Type::function(params, if returns { Type::Any } else { ret })
+    }
+
+    fn infer_call(
+        &mut self,
+        name: &Identifier,
+        method_callee: Option<Type>,
+        call: &FunctionCall,
+    ) -> Type {
+        let arguments = &call.arguments;
+        let (params, ret) = match self.lookup(name).map(|t| self.resolve(&t)) {
+            Some(Type::Fn(ps, r)) => (Params::Fixed(ps), *r),
+            Some(Type::Var(v)) => {
+                let count = arguments.len() + method_callee.iter().count();
+                let ps = (0..count).map(|_| self.fresh()).collect::<Vec<_>>();
+                let r = self.fresh();
+                self.unify(&Type::Var(v), &Type::function(ps.clone(), r.clone()));
+                (Params::Fixed(ps), r)
+            }
+            Some(t) => (Params::Variadic(Type::Any), t),
+            None => {
+                let mut fresh = || self.fresh();
+                match stdlib_signature(&name.0, &mut fresh) {
+                    Some(s) => (s.param_types(), s.ret),
+                    None => (Params::Variadic(Type::Any), Type::Any),
+                }
+            }
+        };
+        let param = |i: usize| match &params {
+            Params::Fixed(ps) => ps.get(i).cloned().unwrap_or(Type::Any),
+            Params::Variadic(p) => p.clone(),
+        };
+        let offset = match method_callee {
+            Some(t) => {
+                // method callee span is checked where it is inferred
+                self.try_unify(&param(0), &t);
+                1
+            }
+            None => 0,
+        };
+        // positions of arguments after a spread one are unknown, e.g. `f(..args, b)`
+        let mut spread = false;
+        for (i, a) in arguments.iter().enumerate() {
+            match &a.1 {
+                Expression::Unary { operator, operand } if operator.1 == UnaryOperator::Spread => {
+                    self.infer_expression(operand, None);
+                    spread = true;
+                }
+                _ if spread => {
+                    self.infer_expression(a, None);
+                }
+                _ => {
+                    let p = param(i + offset);
+                    let t = self.infer_expression(a, Some(&p));
+                    self.expect(&a.0, &p, &t);
+                }
+            }
+        }
+        // function types do not keep parameter names, named arguments are checked when called
+        for (_, a) in &call.named_arguments {
+            self.infer_expression(a, None);
+        }
+        self.resolve(&ret)
+    }
+
+    fn infer_binary(
+        &mut self,
+        expression: &AstPair<Expression>,
+        left: &AstPair<Expression>,
+        operator: &BinaryOperator,
+        right: &AstPair<Expression>,
+    ) -> Type {
+        let lt = self.infer_expression(left, None);
+        if operator == &BinaryOperator::Accessor {
+            return match &right.1 {
+                Expression::Operand(o) => match &o.1 {
+                    Operand::FunctionCall(fc) => self.infer_call(&fc.identifier.1, Some(lt), fc),
+                    // struct field or enum value
+                    _ => Type::Any,
+                },
+                _ => Type::Any,
+            };
+        }
+        if operator == &BinaryOperator::Pipe {
+            return match &right.1 {
+                Expression::Operand(o) => match &o.1 {
+                    Operand::FunctionCall(fc) => self.infer_call(&fc.identifier.1, Some(lt), fc),
+                    Operand::Identifier(i) => self.infer_call(
+                        &i.1,
+                        Some(lt),
+                        &FunctionCall {
+                            identifier: i.clone(),
+                            arguments: vec![],
+                            named_arguments: vec![],
+                        },
+                    ),
+                    _ => Type::Any,
+                },
+                _ => Type::Any,
+            };
+        }
+        let rt = self.infer_expression(right, None);
+        let arithmetic = matches!(
+            operator,
+            BinaryOperator::Subtract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Exponent
+                | BinaryOperator::Remainder
+        );
+        // unknown operand of arithmetic operator is numeric, `+` also applies to lists
+        match (self.resolve(&lt), self.resolve(&rt)) {
+            (v @ Type::Var(_), t) | (t, v @ Type::Var(_)) if arithmetic && t.is_numeric() => {
+                self.unify(&v, &t);
+            }
+            _ => {}
+        }
+        let (l, r) = (self.resolve(&lt), self.resolve(&rt));
+        let numeric = |l: &Type, r: &Type| match (l, r) {
+            (Type::Int, Type::Int) => Some(Type::Int),
+            (l, r) if l.is_numeric() && r.is_numeric() => Some(Type::Float),
+            _ => None,
+        };
+        let res = match operator {
+            BinaryOperator::Add => match (&l, &r) {
+                (Type::List(a), Type::List(b)) => Some(Type::list(if self.try_unify(a, b) {
+                    *a.clone()
+                } else {
+                    Type::Any
+                })),
+                (Type::List(a), t) | (t, Type::List(a)) => {
+                    Some(Type::list(if self.try_unify(a, t) {
+                        *a.clone()
+                    } else {
+                        Type::Any
+                    }))
+                }
+                _ => numeric(&l, &r),
+            },
+            // true division, result is float even for integers
+            BinaryOperator::Divide => numeric(&l, &r).map(|_| Type::Float),
+            BinaryOperator::Subtract
+            | BinaryOperator::Multiply
+            | BinaryOperator::Exponent
+            | BinaryOperator::Remainder => numeric(&l, &r),
+            BinaryOperator::Range | BinaryOperator::RangeInclusive => {
+                let bounds = self.try_unify(&l, &Type::Int) && self.try_unify(&r, &Type::Int);
+                bounds.then(|| Type::seq(Type::Int))
+            }
+            BinaryOperator::BitAnd
+            | BinaryOperator::BitOr
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight => {
+                let ints = self.try_unify(&l, &Type::Int) && self.try_unify(&r, &Type::Int);
+                ints.then_some(Type::Int)
+            }
+            _ => return Type::Bool,
+        };
+        match res {
+            Some(t) => t,
+            None if l.is_concrete() && r.is_concrete() => {
+                let message = format!("incompatible operands: {} {} {}", l, operator, r);
+                self.error(&expression.0, message);
+                Type::Any
+            }
+            None => Type::Any,
+        }
+    }
+}
+
+fn instantiate(t: &Type, mapping: &HashMap<usize, Type>) -> Type {
+    match t {
+        Type::Var(v) => mapping.get(v).cloned().unwrap_or(Type::Var(*v)),
+        Type::List(i) => Type::list(instantiate(i, mapping)),
+        Type::Deque(i) => Type::deque(instantiate(i, mapping)),
+        Type::Seq(i) => Type::seq(instantiate(i, mapping)),
+        Type::Fn(ps, r) => Type::function(
+            ps.iter().map(|p| instantiate(p, mapping)).collect(),
+            instantiate(r, mapping),
+        ),
+        _ => t.clone(),
+    }
+}
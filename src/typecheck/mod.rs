@@ -0,0 +1,101 @@
+use crate::ast::ast::{AstPair, Block};
+use crate::typecheck::infer::{Inferer, TypeError};
+
+pub mod infer;
+pub mod signature;
+pub mod types;
+
+/// Infer types of the program, reporting definite type errors
+pub fn typecheck(ast: &AstPair<Block>) -> Vec<TypeError> {
+    let mut inferer = Inferer::default();
+    inferer.infer_program(ast);
+    inferer.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ast_parser::parse_block;
+    use crate::parser::NoisParser;
+
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        let ast = NoisParser::parse_program(source)
+            .and_then(|parsed| parse_block(&parsed))
+            .unwrap();
+        typecheck(&ast).into_iter().map(|e| e.message).collect()
+    }
+
+    #[test]
+    fn infer_polymorphic_function() {
+        assert!(
+            errors("id = a -> a\nmain = -> {\n  a = id(1) + 2\n  b = id('b') + 'c'\n}").is_empty()
+        );
+    }
+
+    #[test]
+    fn infer_operand_mismatch() {
+        assert_eq!(errors("a = 1 + True"), vec!["incompatible operands: I + B"]);
+        assert_eq!(
+            errors("f = (a: I) -> a\nmain = -> f(1.5)"),
+            vec!["expected I, found F"]
+        );
+        assert_eq!(
+            errors("dec = a -> a - 1\nmain = -> dec('a')"),
+            vec!["expected I, found [C]"]
+        );
+    }
+
+    #[test]
+    fn infer_callbacks() {
+        assert!(errors("main = -> map([1, 2], a -> a + 1)").is_empty());
+        assert_eq!(
+            errors("main = -> map([1, 2], a -> a - 'b')"),
+            vec!["incompatible operands: I - [C]"]
+        );
+    }
+
+    #[test]
+    fn infer_range() {
+        assert!(errors("main = -> map(1..=3, a -> a + 1)").is_empty());
+        assert_eq!(
+            errors("main = -> 1..'a'"),
+            vec!["incompatible operands: I .. [C]"]
+        );
+    }
+
+    #[test]
+    fn infer_pipe() {
+        assert!(errors("main = -> [1, 2] |> map(a -> a + 1) |> sort").is_empty());
+        assert_eq!(
+            errors("main = -> 'a' |> map(a -> a + 1) |> sort"),
+            errors("main = -> map('a', a -> a + 1)")
+        );
+    }
+
+    #[test]
+    fn infer_bitwise() {
+        assert!(errors("f = (a: I) -> a\nmain = -> f(1 << 2 | ~3)").is_empty());
+        assert_eq!(
+            errors("main = -> 1.5 & 1"),
+            vec!["incompatible operands: F & I"]
+        );
+        assert_eq!(errors("main = -> ~'a'"), vec!["incompatible operand: ~[C]"]);
+    }
+
+    #[test]
+    fn infer_spread_arguments() {
+        assert!(errors("add = (a, b) -> a + b\nmain = -> add(..[1, 2])").is_empty());
+        assert!(errors("f = (a: I, b: B) -> a\nmain = -> f(..[1], True)").is_empty());
+        assert_eq!(
+            errors("f = (a: I, b: B) -> a\nmain = -> f(True, ..[1])"),
+            vec!["expected I, found B"]
+        );
+    }
+
+    #[test]
+    fn infer_unknown_as_any() {
+        assert!(errors("main = -> {\n  a = unknown(1)\n  b = a + 1\n  c = a + 'c'\n}").is_empty());
+        assert!(errors("f = a -> a\nmain = -> [1, 'a', f]").is_empty());
+    }
+}
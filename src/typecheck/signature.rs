@@ -0,0 +1,121 @@
+use std::fmt::{Display, Formatter};
+
+use crate::ast::ast::AstPair;
+use crate::interpret::value::Value;
+use crate::stdlib::lib::function_info;
+use crate::typecheck::types::Type;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Params {
+    Fixed(Vec<Type>),
+    /// Any number of arguments of the same type
+    Variadic(Type),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+}
+
+impl Param {
+    pub fn new(name: &str, ty: Type) -> Param {
+        Param {
+            name: name.to_string(),
+            ty,
+        }
+    }
+}
+
+/// Type signature of a stdlib function
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+    pub params: Vec<Param>,
+    /// Whether the last parameter can be repeated any number of times, including zero
+    pub variadic: bool,
+    pub ret: Type,
+}
+
+impl Signature {
+    pub fn fixed(params: Vec<Param>, ret: Type) -> Signature {
+        Signature {
+            params,
+            variadic: false,
+            ret,
+        }
+    }
+
+    pub fn variadic(params: Vec<Param>, ret: Type) -> Signature {
+        Signature {
+            params,
+            variadic: true,
+            ret,
+        }
+    }
+
+    /// Parameter types as seen by type inference
+    pub fn param_types(&self) -> Params {
+        match (self.variadic, &self.params[..]) {
+            (true, [p]) => Params::Variadic(p.ty.clone()),
+            // leading parameters of variadic function are not tracked
+            (true, _) => Params::Variadic(Type::Any),
+            (false, ps) => Params::Fixed(ps.iter().map(|p| p.ty.clone()).collect()),
+        }
+    }
+
+    /// Parameter types in the format of argument errors, e.g. `([*], (*) -> *)`
+    pub fn params_string(&self) -> String {
+        let mut ps = self
+            .params
+            .iter()
+            .map(|p| p.ty.to_string())
+            .collect::<Vec<_>>();
+        if let (true, Some(last)) = (self.variadic, ps.last_mut()) {
+            last.push_str("...");
+        }
+        format!("({})", ps.join(", "))
+    }
+
+    /// Whether arguments match signature arity and parameter types, where type is known
+    pub fn accepts(&self, args: &[AstPair<Value>]) -> bool {
+        let arity = match self.variadic {
+            true => args.len() + 1 >= self.params.len(),
+            false => args.len() == self.params.len(),
+        };
+        arity && self.rejected_arg(args).is_none()
+    }
+
+    /// Parameter the argument at the index is passed to, `None` for extra arguments
+    pub fn param(&self, index: usize) -> Option<&Param> {
+        match self.variadic {
+            true => self.params.get(index).or(self.params.last()),
+            false => self.params.get(index),
+        }
+    }
+
+    /// Index of the first argument not admitted by the type of its parameter
+    pub fn rejected_arg(&self, args: &[AstPair<Value>]) -> Option<usize> {
+        args.iter()
+            .enumerate()
+            .position(|(i, a)| self.param(i).is_some_and(|p| !p.ty.admits(&a.1)))
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut ps = self
+            .params
+            .iter()
+            .map(|p| format!("{}: {}", p.name, p.ty))
+            .collect::<Vec<_>>();
+        if let (true, Some(last)) = (self.variadic, ps.last_mut()) {
+            last.push_str("...");
+        }
+        write!(f, "({}) -> {}", ps.join(", "), self.ret)
+    }
+}
+
+/// Signature of a stdlib function; generic parameters are instantiated using `fresh`
+pub fn stdlib_signature(name: &str, fresh: &mut dyn FnMut() -> Type) -> Option<Signature> {
+    function_info(name).map(|i| (i.signature)(fresh))
+}
@@ -0,0 +1,129 @@
+use std::fmt::{Display, Formatter};
+
+use crate::ast::ast::{TypeAnnotation, ValueType};
+use crate::interpret::value::Value;
+
+/// Static type of an expression.
+/// `Any` is used where type is unknown and is compatible with every other type.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Any,
+    Unit,
+    Int,
+    Float,
+    Char,
+    Bool,
+    /// Type of type values, e.g. `I` or `Point`
+    Type,
+    List(Box<Type>),
+    Deque(Box<Type>),
+    Seq(Box<Type>),
+    Fn(Vec<Type>, Box<Type>),
+    /// User struct or enum type
+    Named(String),
+    /// Type variable, resolved during inference
+    Var(usize),
+}
+
+impl Type {
+    pub fn list(item: Type) -> Type {
+        Type::List(Box::new(item))
+    }
+
+    pub fn deque(item: Type) -> Type {
+        Type::Deque(Box::new(item))
+    }
+
+    pub fn seq(item: Type) -> Type {
+        Type::Seq(Box::new(item))
+    }
+
+    pub fn function(params: Vec<Type>, ret: Type) -> Type {
+        Type::Fn(params, Box::new(ret))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+
+    /// Whether type is fully known, i.e. does not contain `Any` or type variables
+    pub fn is_concrete(&self) -> bool {
+        match self {
+            Type::Any | Type::Var(_) => false,
+            Type::List(i) | Type::Deque(i) | Type::Seq(i) => i.is_concrete(),
+            Type::Fn(ps, r) => ps.iter().all(|p| p.is_concrete()) && r.is_concrete(),
+            _ => true,
+        }
+    }
+
+    /// Whether runtime value can be of this type, unknown parts admit any value
+    pub fn admits(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Any | Type::Var(_), _) => true,
+            (Type::List(t), Value::List { items, .. }) => items.iter().all(|i| t.admits(i)),
+            (Type::Deque(t), Value::Deque(d)) => d.iter().all(|i| t.admits(i)),
+            // items are not known until the sequence is evaluated
+            (Type::Seq(_), Value::Seq(_)) => true,
+            (Type::Named(n), Value::Struct { name, .. } | Value::Enum { name, .. }) => n == name,
+            (Type::Unit, Value::Unit)
+            | (Type::Int, Value::I(_))
+            | (Type::Float, Value::F(_))
+            | (Type::Char, Value::C(_))
+            | (Type::Bool, Value::B(_))
+            | (Type::Type, Value::Type(_))
+            | (Type::Fn(..), Value::Fn(_) | Value::Native(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<&TypeAnnotation> for Type {
+    fn from(annotation: &TypeAnnotation) -> Self {
+        match annotation {
+            TypeAnnotation::Type(vt) => match vt {
+                ValueType::Unit => Type::Unit,
+                ValueType::Integer => Type::Int,
+                ValueType::Float => Type::Float,
+                ValueType::Char => Type::Char,
+                ValueType::Boolean => Type::Bool,
+                // annotation does not specify function signature
+                ValueType::Function => Type::function(vec![], Type::Any),
+                ValueType::Any => Type::Any,
+                ValueType::Type => Type::Type,
+                ValueType::Deque => Type::deque(Type::Any),
+                ValueType::Seq => Type::seq(Type::Any),
+                ValueType::Struct(n) | ValueType::Enum(n) => Type::Named(n.clone()),
+            },
+            TypeAnnotation::Named(i) => Type::Named(i.0.clone()),
+            TypeAnnotation::List(i) => Type::list(Type::from(&i.1)),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "*"),
+            Type::Unit => write!(f, "()"),
+            Type::Int => write!(f, "I"),
+            Type::Float => write!(f, "F"),
+            Type::Char => write!(f, "C"),
+            Type::Bool => write!(f, "B"),
+            Type::Type => write!(f, "T"),
+            Type::List(i) => write!(f, "[{}]", i),
+            Type::Deque(i) => write!(f, "{}[{}]", ValueType::Deque, i),
+            Type::Seq(i) => write!(f, "{}[{}]", ValueType::Seq, i),
+            // function of unknown signature, same as `Fn` annotation
+            Type::Fn(ps, r) if ps.is_empty() && **r == Type::Any => write!(f, "Fn"),
+            Type::Fn(ps, r) => {
+                let ps = ps.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                match **r {
+                    Type::Fn(..) => write!(f, "({}) -> ({})", ps.join(", "), r),
+                    _ => write!(f, "({}) -> {}", ps.join(", "), r),
+                }
+            }
+            Type::Named(n) => write!(f, "{}", n),
+            Type::Var(i) => write!(f, "?{}", i),
+        }
+    }
+}
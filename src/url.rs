@@ -0,0 +1,177 @@
+/// Absolute URL `scheme://host[:port][/path][?query][#fragment]`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Url {
+    pub scheme: String,
+    pub host: String,
+    pub port: Option<u16>,
+    /// Percent-encoded path, empty or starting with `/`
+    pub path: String,
+    /// Decoded query parameters in their order
+    pub query: Vec<(String, String)>,
+    pub fragment: Option<String>,
+}
+
+impl Url {
+    pub fn parse(url: &str) -> Result<Url, String> {
+        let (scheme, rest) = match url.split_once("://") {
+            Some((s, r))
+                if s.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && s.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) =>
+            {
+                (s, r)
+            }
+            _ => return Err(format!("invalid url '{}', expected scheme://host", url)),
+        };
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((r, f)) => (r, Some(decode(f)?)),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((r, q)) => (r, parse_query(q)?),
+            None => (rest, vec![]),
+        };
+        let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+        if authority.contains('@') {
+            return Err(format!("user info in url '{}' is not supported", url));
+        }
+        // port separator is the colon after IPv6 address brackets, e.g. `[::1]:8080`
+        let port_start = authority
+            .rfind(':')
+            .filter(|i| authority.rfind(']').is_none_or(|b| b < *i));
+        let (host, port) = match port_start {
+            Some(i) => match authority[i + 1..].parse::<u16>() {
+                Ok(p) => (&authority[..i], Some(p)),
+                Err(_) => return Err(format!("invalid port in url '{}'", url)),
+            },
+            None => (authority, None),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in url '{}'", url));
+        }
+        Ok(Url {
+            scheme: scheme.to_lowercase(),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            query,
+            fragment,
+        })
+    }
+}
+
+impl std::fmt::Display for Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        write!(f, "{}", self.path)?;
+        if !self.query.is_empty() {
+            write!(f, "?{}", encode_query(&self.query))?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", encode(fragment))?;
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encode all chars except unreserved ones: letters, digits and `-._~`
+pub fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Decode percent-encoded string, decoded bytes must form valid UTF-8
+pub fn decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut res = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("invalid percent-encoding in '{}'", s))?;
+            res.push(byte);
+            i += 3;
+        } else {
+            res.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(res).map_err(|_| format!("percent-encoded '{}' is not valid UTF-8", s))
+}
+
+/// Query parameters `a=1&b=2`, `+` is decoded as space as in HTML forms
+pub fn parse_query(query: &str) -> Result<Vec<(String, String)>, String> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            Ok((decode(&k.replace('+', " "))?, decode(&v.replace('+', " "))?))
+        })
+        .collect()
+}
+
+pub fn encode_query(query: &[(String, String)]) -> String {
+    query
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_build() {
+        let url = Url::parse("https://example.com:8080/a/b%20c?q=rust+lang&x=%C3%A9#top").unwrap();
+        assert_eq!(
+            url,
+            Url {
+                scheme: "https".to_string(),
+                host: "example.com".to_string(),
+                port: Some(8080),
+                path: "/a/b%20c".to_string(),
+                query: vec![
+                    ("q".to_string(), "rust lang".to_string()),
+                    ("x".to_string(), "é".to_string())
+                ],
+                fragment: Some("top".to_string()),
+            }
+        );
+        assert_eq!(
+            url.to_string(),
+            "https://example.com:8080/a/b%20c?q=rust%20lang&x=%C3%A9#top"
+        );
+        assert_eq!(Url::parse(&url.to_string()), Ok(url));
+
+        let ipv6 = Url::parse("http://[::1]:80").unwrap();
+        assert_eq!((ipv6.host.as_str(), ipv6.port), ("[::1]", Some(80)));
+        assert_eq!(
+            Url::parse("file:///tmp").unwrap_err(),
+            "missing host in url 'file:///tmp'"
+        );
+        assert!(Url::parse("example.com").is_err());
+        assert!(Url::parse("http://a:x").is_err());
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(encode("a b/é~"), "a%20b%2F%C3%A9~");
+        assert_eq!(decode("a%20b%2F%C3%A9~"), Ok("a b/é~".to_string()));
+        assert!(decode("%zz").is_err());
+        assert!(decode("%ff").is_err());
+    }
+}